@@ -1 +1,75 @@
-fn main() {}
+use std::collections::HashMap;
+use std::env;
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Exposes build details to `bckt version --verbose`: the enabled cargo
+/// features and the resolved versions of a few crates that shape the output.
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_ascii_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=BCKT_FEATURES={}", features.join(","));
+
+    let versions = dependency_versions().unwrap_or_else(|error| {
+        println!("cargo:warning=cannot read dependency versions from cargo metadata: {error}");
+        HashMap::new()
+    });
+    for (krate, key) in [
+        ("minijinja", "BCKT_MINIJINJA_VERSION"),
+        ("time", "BCKT_TIME_VERSION"),
+    ] {
+        let version = versions.get(krate).map_or("unknown", String::as_str);
+        println!("cargo:rustc-env={key}={version}");
+    }
+}
+
+/// Versions of bckt's direct dependencies, by crate name, as resolved by
+/// `cargo metadata` for this build.
+fn dependency_versions() -> Result<HashMap<String, String>, String> {
+    let cargo = env::var("CARGO").map_err(|error| error.to_string())?;
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").map_err(|error| error.to_string())?;
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--offline"])
+        .current_dir(manifest_dir)
+        .output()
+        .map_err(|error| error.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).map_err(|error| error.to_string())?;
+
+    let versions: HashMap<&str, &str> = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|package| Some((package["id"].as_str()?, package["version"].as_str()?)))
+        .collect();
+    let root = metadata["resolve"]["root"]
+        .as_str()
+        .ok_or("cargo metadata has no root package")?;
+    let node = metadata["resolve"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|node| node["id"].as_str() == Some(root))
+        .ok_or("cargo metadata does not resolve the root package")?;
+    Ok(node["deps"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|dep| {
+            let version = versions.get(dep["pkg"].as_str()?)?;
+            Some((dep["name"].as_str()?.to_string(), version.to_string()))
+        })
+        .collect())
+}
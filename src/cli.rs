@@ -1,4 +1,4 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(name = "bckt", version)]
@@ -11,6 +11,20 @@ or clean out generated artifacts before a fresh build."
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+    #[arg(
+        long,
+        global = true,
+        help = "Disable colored log labels",
+        long_help = "Print plain `[LABEL] message` lines. Colors are also disabled when NO_COLOR is set or output is not a terminal."
+    )]
+    pub no_color: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Print log lines as JSON objects",
+        long_help = "Print each log line as one JSON object with level, phase, message, and path fields, for CI logs and other tools. Warnings and errors still go to stderr."
+    )]
+    pub log_json: bool,
 }
 
 impl Cli {
@@ -43,11 +57,18 @@ The development server recompiles on edits so you can preview posts live while i
     Dev(DevArgs),
     #[command(
         about = "Remove generated files from html/",
-        long_about = "Delete the previously rendered html/ directory and the incremental cache (stored in .bckt).\n\
-The command recreates html/ so the next render starts from a clean slate.",
+        long_about = "Delete the previously rendered html/ directory and the incremental cache (stored in .bckt, or below cache_dir / BCKT_CACHE_DIR).\n\
+The command recreates html/ so the next render starts from a clean slate. It asks before deleting unless --yes is\n\
+given, and --dry-run lists what would go.",
         alias = "clear"
     )]
     Clean(CleanArgs),
+    #[command(
+        about = "Run the lint.command from bckt.yaml on changed posts",
+        long_about = "Run the linter configured under lint.command in bckt.yaml once for each post whose content changed since the\n\
+last render, and report the posts it rejected. The command fails when any linter run fails."
+    )]
+    Check(CheckArgs),
     #[command(
         about = "Inspect and switch between installed themes",
         long_about = "List the themes stored in themes/ or apply a different one to the current project.\n\
@@ -60,6 +81,51 @@ Applying a theme copies its templates and assets into place and updates bckt.yam
 Use this command from any subdirectory within the project to retrieve config values."
     )]
     Config(ConfigArgs),
+    #[command(
+        about = "List posts or find the source of a published file",
+        long_about = "Show where sources end up in html/ and where a published path comes from, using the same path rules as\n\
+`bckt render`. Nothing is written."
+    )]
+    Ls(LsArgs),
+    #[command(
+        about = "Render one post and print its HTML",
+        long_about = "Render the post with the given slug through its template and print the page to stdout, for debugging\n\
+templates. Nothing is written. With --context, print the JSON values the template receives instead."
+    )]
+    Preview(PreviewArgs),
+    #[command(
+        about = "Pack the site sources into a zip or tar.gz archive",
+        long_about = "Write bckt.yaml, posts/, pages/, templates/, and skel/ into one archive for backups or moving the\n\
+project. html/ and the cache are left out, and files are stored in path order without modification times,\n\
+so exporting unchanged sources gives the same bytes."
+    )]
+    Export(ExportArgs),
+    #[command(
+        about = "Edit the tags of every post",
+        long_about = "Change tags in the front matter of the post sources. Only the tags line or list is rewritten; the rest of\n\
+each file is left byte for byte."
+    )]
+    Tags(TagsArgs),
+    #[command(
+        about = "Run external commands when source files change",
+        long_about = "Watch the project and run the shell commands mapped to glob patterns under watch.commands in bckt.yaml.\n\
+Events are debounced, and with watch.rebuild enabled the site is re-rendered after the commands succeed."
+    )]
+    Watch(WatchArgs),
+    #[command(
+        about = "Print the bckt version",
+        long_about = "Print the bckt version. With --verbose, also list the enabled cargo features, the cache schema version,\n\
+and the versions of the template and date crates, which is useful when reporting bugs."
+    )]
+    Version(VersionArgs),
+    #[command(
+        hide = true,
+        about = "Render a generated site and print how long each render took",
+        long_about = "Generate a synthetic site with the given number of posts, tags, and attachments, then time a full render,\n\
+an unchanged incremental render, and an incremental render after editing one post. Meant for measuring\n\
+performance changes in bckt itself."
+    )]
+    Bench(BenchArgs),
 }
 
 #[derive(Args, Clone, Debug)]
@@ -147,10 +213,71 @@ pub struct RenderArgs {
     #[arg(
         short,
         long,
-        help = "Print progress information while rendering",
-        long_help = "Show which posts are rendered or skipped, along with timing breakdowns for each pipeline stage."
+        action = ArgAction::Count,
+        help = "Print progress information while rendering (-vv for more)",
+        long_help = "Show what each pipeline stage renders, writes, or copies. Repeat it (-vv) to also list posts, tags, and archives that were left unchanged."
     )]
-    pub verbose: bool,
+    pub verbose: u8,
+    #[arg(
+        short,
+        long,
+        help = "Suppress progress logs and the summary line",
+        long_help = "Keep stdout silent so scripted pipelines only see warnings and errors (printed to stderr). Overrides --verbose."
+    )]
+    pub quiet: bool,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration (for example a different base_url for previews). Switching profiles triggers a full rebuild."
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Skip the hooks.post_render commands",
+        long_help = "Render without running the commands listed under hooks.post_render in bckt.yaml, e.g. when debugging a CI build."
+    )]
+    pub no_hooks: bool,
+    #[arg(
+        long,
+        help = "Report what would be written or deleted without changing anything",
+        long_help = "Run discovery and change detection as usual, then list the files under html/ that would be written or deleted. Neither html/ nor the cache is modified, and post_render hooks are not run."
+    )]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Write a Chrome tracing file with the time spent in each render step",
+        long_help = "Record how long discovery, each rendered post, Markdown parsing, listings, feeds, the search index, pages, and static assets take, and write the spans to PATH as Chrome tracing JSON. Open it in chrome://tracing, https://ui.perfetto.dev, or speedscope to see a flame chart."
+    )]
+    pub trace: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["dry_run", "trace"],
+        help = "Keep rendering as sources change, without serving html/",
+        long_help = "After the first render, watch posts, pages, templates, skel/, bckt.yaml, and the active theme, and render again once changes settle for watch.debounce_ms. Unlike bckt dev no server is started. Ctrl+C lets a render in progress finish before exiting."
+    )]
+    pub watch: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Copy at most N files into html/ at once (defaults to the number of CPUs)",
+        long_help = "Limit how many attachments and static or theme assets are copied at the same time, overriding jobs in bckt.yaml. Lower it on network filesystems; --jobs 1 copies one file at a time, in order. Only copying runs in parallel: pages are always rendered one at a time."
+    )]
+    pub jobs: Option<usize>,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "Use URL as base_url for this render",
+        long_help = "Override base_url from bckt.yaml (and any profile) for this render, e.g. for preview deployments at their own address. Feeds, the sitemap, absolute links, and the base_url/base_path template values follow it, and switching URLs re-renders every page."
+    )]
+    pub base_url: Option<String>,
+    #[arg(
+        long,
+        help = "Print how many outputs the cache let this render skip",
+        long_help = "After rendering, print the cache hits and misses for posts, date archives, tag archives, pages, and the search index, with the hit ratio of each and overall. Archive and tag counts are per listing page. A render that had to rebuild everything says so."
+    )]
+    pub incremental_stats: bool,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -182,11 +309,79 @@ pub struct DevArgs {
     )]
     pub force: bool,
     #[arg(
+        short,
         long,
-        help = "Show verbose logs from the watcher and render pipeline",
+        action = ArgAction::Count,
+        help = "Show verbose logs from the watcher and render pipeline (-vv for more)",
         long_help = "Display the same detailed progress output as `render --verbose` while the dev server is running."
     )]
-    pub verbose: bool,
+    pub verbose: u8,
+    #[arg(
+        short,
+        long,
+        help = "Only print warnings and errors",
+        long_help = "Hide progress and status lines; warnings and errors are still printed to stderr. Overrides --verbose."
+    )]
+    pub quiet: bool,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml (defaults to dev if defined)",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration. When omitted, the dev server uses the `dev` profile if bckt.yaml defines one."
+    )]
+    pub profile: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct WatchArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        short,
+        long,
+        action = ArgAction::Count,
+        help = "Show verbose logs from the rebuilds triggered by watch (-vv for more)",
+        long_help = "Display the same detailed progress output as `render --verbose` when watch.rebuild triggers a render."
+    )]
+    pub verbose: u8,
+    #[arg(
+        short,
+        long,
+        help = "Only print warnings and errors",
+        long_help = "Hide progress and status lines; warnings and errors are still printed to stderr. Overrides --verbose."
+    )]
+    pub quiet: bool,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration for the watch rules and any rebuilds."
+    )]
+    pub profile: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct CheckArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration before deciding which posts changed."
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Report linter failures without failing the command",
+        long_help = "Print the linter output of rejected posts as warnings and exit successfully, e.g. while introducing a linter to an existing blog."
+    )]
+    pub lint_warn_only: bool,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -197,6 +392,31 @@ pub struct CleanArgs {
         long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
     )]
     pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Only remove cached state, keeping html/",
+        long_help = "Remove the project's cache directory (.bckt, or its directory below cache_dir / BCKT_CACHE_DIR) without touching html/. The next render performs a full rebuild."
+    )]
+    pub cache: bool,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration, so a cache_dir set by the profile is the one cleaned."
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "List what would be removed without deleting anything",
+        long_help = "Print every file and directory under html/ and the cache that clean would remove, and exit without deleting them."
+    )]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        short = 'y',
+        help = "Delete without asking for confirmation",
+        long_help = "Skip the confirmation prompt. Required when clean does not run in a terminal, e.g. in scripts and CI."
+    )]
+    pub yes: bool,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -282,6 +502,147 @@ pub struct ThemeDownloadArgs {
     pub force: bool,
 }
 
+#[derive(Args, Clone, Debug)]
+pub struct LsArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration, e.g. when it changes base_url or the content directories."
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Print JSON instead of a table",
+        long_help = "Print the result as JSON for scripts: an array of posts for `ls posts`, or an object with kind, output, and source for `ls output`."
+    )]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: LsSubcommand,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum LsSubcommand {
+    #[command(
+        about = "List every post with its permalink and source file",
+        long_about = "Print one row per post with its slug, date, type, tags, permalink, and source path, oldest first."
+    )]
+    Posts,
+    #[command(
+        about = "Find the source file of a published path",
+        long_about = "Resolve a path under html/ (or a URL on the site) back to the post, attachment, page, or static file that produces it."
+    )]
+    Output {
+        #[arg(help = "Site path or URL, e.g. /2024/01/02/hello/ or /about.html")]
+        path: String,
+    },
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct PreviewArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration, e.g. to preview with a local base_url."
+    )]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Print the template context as JSON instead of HTML",
+        long_help = "Print the `post` and `site` values the post template receives as JSON, to find out which variables a template can use."
+    )]
+    pub context: bool,
+    #[arg(help = "Slug of the post to render, as listed by `bckt ls posts`")]
+    pub slug: String,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ExportArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration, which can move the posts, pages, or static directories."
+    )]
+    pub profile: Option<String>,
+    #[arg(long, value_enum, default_value = "zip", help = "Archive format")]
+    pub format: ExportFormat,
+    #[arg(
+        long,
+        conflicts_with = "theme",
+        help = "Only export posts/ and pages/",
+        long_help = "Leave out bckt.yaml, templates/, and skel/, e.g. to move the writing into another project."
+    )]
+    pub content_only: bool,
+    #[arg(
+        long,
+        help = "Also export the active theme from themes/",
+        long_help = "Include themes/<theme>, where <theme> is the theme set in bckt.yaml, so the archive renders without installing it again."
+    )]
+    pub theme: bool,
+    #[arg(help = "Archive file to write")]
+    pub output: std::path::PathBuf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Zip,
+    #[value(name = "tar.gz")]
+    TarGz,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TagsArgs {
+    #[arg(
+        long,
+        help = "Project root directory (defaults to current directory)",
+        long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
+    )]
+    pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Overlay the named entry from profiles: in bckt.yaml",
+        long_help = "Apply the keys under profiles.<name> on top of the base configuration, which can move the posts directory."
+    )]
+    pub profile: Option<String>,
+    #[command(subcommand)]
+    pub command: TagsSubcommand,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum TagsSubcommand {
+    #[command(
+        about = "Rename a tag in every post that has it",
+        long_about = "Replace the tag <OLD>, matched case-insensitively, with <NEW> in the front matter of every post. A post\n\
+that already has <NEW> keeps a single copy. Tags lists too unusual to edit safely are reported, and no file is\n\
+written until all of them can be."
+    )]
+    Rename {
+        #[arg(help = "Tag to replace")]
+        old: String,
+        #[arg(help = "Tag to use instead")]
+        new: String,
+        #[arg(long, help = "List the posts that would change without writing them")]
+        dry_run: bool,
+    },
+}
+
 #[derive(Args, Clone, Debug)]
 pub struct ConfigArgs {
     #[arg(
@@ -290,6 +651,12 @@ pub struct ConfigArgs {
         long_help = "Specify the project root directory. Supports tilde expansion (e.g., ~/myblog). If not provided, uses the current working directory."
     )]
     pub root: Option<String>,
+    #[arg(
+        long,
+        help = "Resolve values through the named profile",
+        long_help = "Apply the keys under profiles.<name> before printing the requested value."
+    )]
+    pub profile: Option<String>,
     #[arg(long = "root-dir", help = "Get the project root directory path")]
     pub root_dir: bool,
     #[arg(long, help = "Get the base_url configuration value")]
@@ -310,4 +677,68 @@ pub struct ConfigArgs {
     pub search_asset_path: bool,
     #[arg(long, help = "Get the search.default_language configuration value")]
     pub search_default_language: bool,
+    #[command(subcommand)]
+    pub command: Option<ConfigSubcommand>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ConfigSubcommand {
+    #[command(
+        about = "Print every effective configuration value",
+        long_about = "Print each resolved key as `key = value`, with nested keys joined by dots. Values supplied by the\n\
+theme's defaults.yaml rather than bckt.yaml are marked (theme default)."
+    )]
+    List,
+    #[command(
+        about = "Check that the configuration agrees with the rest of the site",
+        long_about = "Report errors for a theme missing from themes/, post types or templates without a template file,\n\
+rss_tags entries no post uses, and a base_url ending in a directory bckt generates (tags/, a year, page/),\n\
+and warn about post languages missing from search.languages. Exits non-zero when there are errors.\n\
+bckt render runs the checks that do not need to read posts."
+    )]
+    Validate,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct VersionArgs {
+    #[arg(
+        long,
+        help = "Include build details for bug reports",
+        long_help = "Also print the enabled cargo features, the cache schema version, and the minijinja and time crate versions."
+    )]
+    pub verbose: bool,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct BenchArgs {
+    #[arg(
+        long,
+        default_value_t = 1000,
+        value_name = "N",
+        help = "Number of posts to generate"
+    )]
+    pub posts: usize,
+    #[arg(
+        long,
+        default_value_t = 3,
+        value_name = "N",
+        help = "Tags per post",
+        long_help = "Tags per post, drawn from a pool of one tag per 20 posts (at least 10)."
+    )]
+    pub tags: usize,
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_name = "N",
+        help = "Attachments per post",
+        long_help = "Attachments per post, 16 KB each."
+    )]
+    pub attachments: usize,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Generate the site in PATH and keep it",
+        long_help = "Generate the site in PATH, which must not exist yet, and leave it there afterwards, e.g. to render it again with --trace. By default a temporary directory is used and removed."
+    )]
+    pub dir: Option<String>,
 }
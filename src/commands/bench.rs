@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use tempfile::TempDir;
+use time::format_description::well_known::Rfc3339;
+use time::macros::datetime;
+
+use crate::cli::BenchArgs;
+use crate::logging::{Level, Logger};
+use crate::render::{BuildMode, RenderPlan, render_site};
+use crate::utils::expand_tilde;
+
+const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "base.html",
+        "<!doctype html><html><head><title>{% block title %}{{ config.title }}{% endblock %}</title></head><body>{% block content %}{% endblock %}</body></html>",
+    ),
+    (
+        "post.html",
+        "{% extends \"base.html\" %}{% block title %}{{ post.title }}{% endblock %}{% block content %}<article><h1>{{ post.title }}</h1><time>{{ post.date }}</time>{{ post.body | safe }}<ul>{% for tag in post.tag_links %}<li><a href=\"{{ base_path }}{{ tag.url }}\">{{ tag.name }}</a></li>{% endfor %}</ul></article>{% endblock %}",
+    ),
+    (
+        "index.html",
+        "{% extends \"base.html\" %}{% block content %}{% for post in posts %}<article><a href=\"{{ base_path }}{{ post.permalink }}\">{{ post.title }}</a>{{ post.body | safe }}</article>{% endfor %}<a href=\"{{ base_path }}{{ pagination.prev }}\">older</a>{% endblock %}",
+    ),
+    (
+        "tag.html",
+        "{% extends \"base.html\" %}{% block content %}<h1>{{ tag }}</h1>{% for post in posts %}<a href=\"{{ base_path }}{{ post.permalink }}\">{{ post.title }}</a><p>{{ post.excerpt }}</p>{% endfor %}{% endblock %}",
+    ),
+    (
+        "archive_year.html",
+        "{% extends \"base.html\" %}{% block content %}<h1>{{ year }}</h1>{% for post in posts %}<a href=\"{{ base_path }}{{ post.permalink }}\">{{ post.title }}</a>{% endfor %}{% endblock %}",
+    ),
+    (
+        "archive_month.html",
+        "{% extends \"base.html\" %}{% block content %}<h1>{{ year }}-{{ month }}</h1>{% for post in posts %}<a href=\"{{ base_path }}{{ post.permalink }}\">{{ post.title }}</a>{% endfor %}{% endblock %}",
+    ),
+    (
+        "rss.xml",
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><rss version=\"2.0\"><channel><title>{{ feed.title }}</title>{% for item in feed.items %}<item><title>{{ item.title }}</title><link>{{ base_url }}{{ item.permalink }}</link><description><![CDATA[{{ item.body | safe }}]]></description></item>{% endfor %}</channel></rss>",
+    ),
+];
+
+const PARAGRAPHS: &[&str] = &[
+    "Static sites trade a build step for pages that are **fast**, cheap to host, and easy to keep around for decades.",
+    "Each post lives in its own directory with its attachments, so moving or archiving one is a matter of copying a folder.",
+    "Incremental renders compare content hashes with the cache and only rewrite the pages whose inputs changed.",
+    "Listings are split into pages from the oldest post up, so a full page never changes when a new post arrives.",
+];
+
+const ATTACHMENT_BYTES: usize = 16 * 1024;
+
+pub fn run_bench_command(args: BenchArgs) -> Result<()> {
+    if args.posts == 0 {
+        bail!("--posts must be at least 1");
+    }
+    let (root, _temp) = match &args.dir {
+        Some(dir) => {
+            let root = PathBuf::from(expand_tilde(dir));
+            if root.exists() {
+                bail!("{} already exists", root.display());
+            }
+            (root, None)
+        }
+        None => {
+            let temp = TempDir::new().context("failed to create temporary directory")?;
+            (temp.path().to_path_buf(), Some(temp))
+        }
+    };
+
+    let started = Instant::now();
+    generate_site(&root, &args)?;
+    println!(
+        "generated {} posts ({} tag(s) and {} attachment(s) each) in {}: {}",
+        args.posts,
+        args.tags,
+        args.attachments,
+        root.display(),
+        format_millis(started.elapsed())
+    );
+
+    time_render(&root, "full render", BuildMode::Full)?;
+    time_render(&root, "unchanged render", BuildMode::Changed)?;
+    let edited = post_path(&root, args.posts / 2);
+    let mut source = fs::read_to_string(&edited)
+        .with_context(|| format!("failed to read {}", edited.display()))?;
+    source.push_str("\nAn edited paragraph.\n");
+    fs::write(&edited, source).with_context(|| format!("failed to write {}", edited.display()))?;
+    time_render(&root, "one post edited", BuildMode::Changed)?;
+    Ok(())
+}
+
+fn time_render(root: &Path, label: &str, mode: BuildMode) -> Result<()> {
+    let plan = RenderPlan {
+        mode,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let started = Instant::now();
+    let report = render_site(root, plan)?;
+    println!(
+        "{label}: {} ({} posts rendered, {} skipped)",
+        format_millis(started.elapsed()),
+        report.posts_rendered,
+        report.posts_skipped
+    );
+    Ok(())
+}
+
+fn format_millis(elapsed: Duration) -> String {
+    format!("{:.3} ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+fn post_path(root: &Path, index: usize) -> PathBuf {
+    root.join("posts")
+        .join(format!("post-{index:06}"))
+        .join("post.md")
+}
+
+/// Writes a project with `args.posts` posts, six hours apart, whose tags
+/// and bodies vary with their index so every run produces the same site.
+fn generate_site(root: &Path, args: &BenchArgs) -> Result<()> {
+    fs::create_dir_all(root.join("skel"))
+        .with_context(|| format!("failed to create {}", root.display()))?;
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: bckt bench\nbase_url: https://example.com\nhomepage_posts: 10\n",
+    )
+    .context("failed to write bckt.yaml")?;
+    for (name, contents) in TEMPLATES {
+        let path = root.join("templates").join(name);
+        fs::create_dir_all(path.parent().unwrap())
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        fs::write(&path, contents)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    fs::write(root.join("skel/style.css"), "body { margin: 0 auto; }\n")
+        .context("failed to write skel/style.css")?;
+
+    let tag_pool = (args.posts / 20).max(10);
+    let attachment: Vec<u8> = (0..ATTACHMENT_BYTES).map(|byte| byte as u8).collect();
+    let first = datetime!(2020-01-01 00:00 UTC);
+    for index in 0..args.posts {
+        let path = post_path(root, index);
+        let dir = path.parent().unwrap();
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+        let date = (first + time::Duration::hours(6 * index as i64))
+            .format(&Rfc3339)
+            .context("failed to format post date")?;
+        let tags: Vec<String> = (0..args.tags)
+            .map(|tag| format!("tag-{}", (index * 7 + tag * 13) % tag_pool))
+            .collect();
+        let attached: Vec<String> = (0..args.attachments)
+            .map(|number| format!("file-{number}.bin"))
+            .collect();
+        for name in &attached {
+            fs::write(dir.join(name), &attachment)
+                .with_context(|| format!("failed to write attachment {name}"))?;
+        }
+
+        let mut body = format!("## Section {index}\n\n");
+        for offset in 0..6 {
+            body.push_str(PARAGRAPHS[(index + offset) % PARAGRAPHS.len()]);
+            body.push_str("\n\n");
+        }
+        body.push_str("- one\n- two\n- three\n\n```rust\nfn main() {}\n```\n");
+        fs::write(
+            &path,
+            format!(
+                "---\ntitle: Post {index}\ndate: {date}\ntags: [{}]\nattached: [{}]\n---\n{body}",
+                tags.join(", "),
+                attached.join(", ")
+            ),
+        )
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::{Result, bail};
+
+use crate::cli::CheckArgs;
+use crate::config::{self, Config};
+use crate::logging::{Level, Logger};
+use crate::render::changed_post_files;
+use crate::utils::{resolve_root, shell_command, shell_quote};
+
+/// A post the linter rejected, with everything it printed.
+struct LintFailure {
+    path: PathBuf,
+    status: String,
+    output: String,
+}
+
+pub fn run_check_command(args: CheckArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    let profile = args.profile.as_deref();
+    let config = Config::load_with_profile(config::config_path(&root), profile)?;
+    let Some(command) = config.lint.command.as_deref() else {
+        bail!("no lint.command configured in bckt.yaml");
+    };
+
+    let log = Logger::default();
+    let files: Vec<PathBuf> = changed_post_files(&root, profile)?
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix(&root)
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect();
+    let failures = lint_files(&root, command, &files, config.lint.jobs);
+
+    let level = if args.lint_warn_only {
+        Level::Warn
+    } else {
+        Level::Error
+    };
+    for failure in &failures {
+        let mut message = format!("{}: linter {}", failure.path.display(), failure.status);
+        let output = failure.output.trim_end();
+        if !output.is_empty() {
+            message.push('\n');
+            message.push_str(output);
+        }
+        log.log(level, "LINT", message, Some(&failure.path));
+    }
+    log.info(
+        "LINT",
+        format!(
+            "Checked {} changed post(s), {} failed",
+            files.len(),
+            failures.len()
+        ),
+    );
+
+    if !failures.is_empty() && !args.lint_warn_only {
+        bail!("{} post(s) failed linting", failures.len());
+    }
+    Ok(())
+}
+
+/// Runs `command` for every file from `root`, at most `jobs` at a time, and
+/// returns the failures sorted by path.
+fn lint_files(root: &Path, command: &str, files: &[PathBuf], jobs: usize) -> Vec<LintFailure> {
+    let next = AtomicUsize::new(0);
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(files.len()) {
+            scope.spawn(|| {
+                while let Some(path) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Some(failure) = lint_file(root, command, path) {
+                        failures.lock().unwrap().push(failure);
+                    }
+                }
+            });
+        }
+    });
+
+    let mut failures = failures.into_inner().unwrap();
+    failures.sort_by(|a, b| a.path.cmp(&b.path));
+    failures
+}
+
+fn lint_file(root: &Path, command: &str, path: &Path) -> Option<LintFailure> {
+    let command = command.replace("{file}", &shell_quote(&path.to_string_lossy()));
+    let (status, output) = match shell_command(&command).current_dir(root).output() {
+        Ok(output) if output.status.success() => return None,
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            (format!("exited with {}", output.status), text)
+        }
+        Err(err) => (format!("could not be started: {err}"), String::new()),
+    };
+    Some(LintFailure {
+        path: path.to_path_buf(),
+        status,
+        output,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use crate::logging::capture;
+
+    /// A linter that rejects files containing "teh" and records every file
+    /// it was given.
+    const FAKE_LINTER: &str = "echo \"$1\" >> linted.txt\nif grep -n teh \"$1\"; then exit 1; fi\n";
+
+    fn write_post(root: &Path, slug: &str, body: &str) {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("post.md"),
+            format!("---\ndate: 2024-01-01T00:00:00Z\nslug: {slug}\n---\n{body}\n"),
+        )
+        .unwrap();
+    }
+
+    fn setup(jobs: usize) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("bckt.yaml"),
+            format!("lint:\n  command: sh lint.sh {{file}}\n  jobs: {jobs}\n"),
+        )
+        .unwrap();
+        fs::write(root.join("lint.sh"), FAKE_LINTER).unwrap();
+        write_post(root, "good", "All fine.");
+        write_post(root, "bad", "Fix teh typo.");
+        write_post(root, "also-good", "Nothing here.");
+        temp
+    }
+
+    fn args(root: &Path, lint_warn_only: bool) -> CheckArgs {
+        CheckArgs {
+            root: Some(root.to_string_lossy().into_owned()),
+            profile: None,
+            lint_warn_only,
+        }
+    }
+
+    #[test]
+    fn failing_posts_are_reported_with_their_output() {
+        let temp = setup(2);
+        let (result, records) = capture::records(|| run_check_command(args(temp.path(), false)));
+
+        let error = result.unwrap_err();
+        assert_eq!(error.to_string(), "1 post(s) failed linting");
+        let failures: Vec<_> = records
+            .iter()
+            .filter(|record| record.level == Level::Error)
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].path.as_deref(),
+            Some(Path::new("posts/bad/post.md"))
+        );
+        assert!(failures[0].message.contains("exited with exit status: 1"));
+        assert!(failures[0].message.contains("Fix teh typo."));
+        assert!(
+            records
+                .iter()
+                .any(|record| record.message == "Checked 3 changed post(s), 1 failed")
+        );
+
+        let mut linted: Vec<String> = fs::read_to_string(temp.path().join("linted.txt"))
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        linted.sort();
+        assert_eq!(
+            linted,
+            [
+                "posts/also-good/post.md",
+                "posts/bad/post.md",
+                "posts/good/post.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn warn_only_reports_without_failing() {
+        let temp = setup(1);
+        let (result, records) = capture::records(|| run_check_command(args(temp.path(), true)));
+
+        result.unwrap();
+        assert!(records.iter().any(|record| record.level == Level::Warn
+            && record.path.as_deref() == Some(Path::new("posts/bad/post.md"))));
+        assert!(!records.iter().any(|record| record.level == Level::Error));
+    }
+}
@@ -1,9 +1,14 @@
 use std::fs;
-use std::path::Path;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use walkdir::WalkDir;
 
 use crate::cli::CleanArgs;
+use crate::config::{self, Config};
+use crate::logging;
+use crate::render::cache_location;
 use crate::utils::resolve_root;
 
 fn ensure_directory(path: &Path) -> Result<()> {
@@ -33,7 +38,54 @@ fn remove_path(path: &Path) -> Result<bool> {
 pub fn run_clean_command(args: CleanArgs) -> Result<()> {
     let root = resolve_root(args.root.as_deref())?;
     let html = root.join("html");
-    let cache = root.join(".bckt");
+    let config =
+        match Config::load_with_profile(config::config_path(&root), args.profile.as_deref()) {
+            Ok(config) => config,
+            // Clearing the cache should work even when the config is broken.
+            Err(error) => {
+                logging::warn(
+                    "CONFIG",
+                    format!("{error:#}; cleaning the default cache location instead"),
+                );
+                Config::default()
+            }
+        };
+    let cache_dir = cache_location(&root, &config);
+    // The default cache lives in `.bckt`, which goes with it.
+    let local = root.join(".bckt");
+    let cache = if cache_dir.starts_with(&local) {
+        local
+    } else {
+        cache_dir
+    };
+
+    let targets: Vec<&Path> = if args.cache {
+        vec![&cache]
+    } else {
+        vec![&html, &cache]
+    };
+    if args.dry_run {
+        let removals = planned_removals(&targets)?;
+        if removals.is_empty() {
+            println!("Nothing to remove.");
+        }
+        for path in removals {
+            println!("Would remove {}", path.display());
+        }
+        return Ok(());
+    }
+    if !args.yes {
+        confirm_removal(&targets)?;
+    }
+
+    if args.cache {
+        if remove_path(&cache)? {
+            println!("Cleared cached state.");
+        } else {
+            println!("No cached state found.");
+        }
+        return Ok(());
+    }
 
     let removed_html = remove_path(&html)?;
     ensure_directory(&html)?;
@@ -49,3 +101,49 @@ pub fn run_clean_command(args: CleanArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Every file and directory under `targets` (and the targets themselves), in
+/// walk order, that clean would remove.
+fn planned_removals(targets: &[&Path]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for target in targets.iter().filter(|target| target.exists()) {
+        for entry in WalkDir::new(target).sort_by_file_name() {
+            let entry =
+                entry.with_context(|| format!("failed to enumerate {}", target.display()))?;
+            paths.push(entry.into_path());
+        }
+    }
+    Ok(paths)
+}
+
+fn confirm_removal(targets: &[&Path]) -> Result<()> {
+    let existing: Vec<String> = targets
+        .iter()
+        .filter(|target| target.exists())
+        .map(|target| target.display().to_string())
+        .collect();
+    if existing.is_empty() {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        bail!(
+            "refusing to remove {} without confirmation; pass --yes (or --dry-run to list the files)",
+            existing.join(" and ")
+        );
+    }
+
+    print!("Remove {}? [y/N]: ", existing.join(" and "));
+    io::stdout().flush().context("failed to flush stdout")?;
+
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("failed to read confirmation input")?;
+
+    let answer = input.trim().to_lowercase();
+    if matches!(answer.as_str(), "y" | "yes") {
+        Ok(())
+    } else {
+        bail!("clean aborted by user");
+    }
+}
@@ -1,7 +1,12 @@
+use std::path::Path;
+
 use anyhow::{Result, bail};
+use serde_json::Value as JsonValue;
 
-use crate::cli::ConfigArgs;
-use crate::config::{Config, find_project_root};
+use crate::cli::{ConfigArgs, ConfigSubcommand};
+use crate::config::{Config, config_path, find_project_root};
+use crate::logging::{Level, Logger};
+use crate::render::validate_site;
 use crate::utils::resolve_root;
 
 pub fn run_config_command(args: ConfigArgs) -> Result<()> {
@@ -15,9 +20,18 @@ pub fn run_config_command(args: ConfigArgs) -> Result<()> {
         return Ok(());
     }
 
+    if let Some(ConfigSubcommand::Validate) = args.command {
+        return validate(&root, args.profile.as_deref());
+    }
+
     // Load config
-    let config_path = root.join("bckt.yaml");
-    let config = Config::load(&config_path)?;
+    let config_path = config_path(&root);
+    let config = Config::load_with_profile(&config_path, args.profile.as_deref())?;
+
+    if let Some(ConfigSubcommand::List) = args.command {
+        print!("{}", list_config(&config)?);
+        return Ok(());
+    }
 
     // Count how many flags are set
     let flags_set = [
@@ -70,3 +84,78 @@ pub fn run_config_command(args: ConfigArgs) -> Result<()> {
 
     Ok(())
 }
+
+fn validate(root: &Path, profile: Option<&str>) -> Result<()> {
+    let log = Logger::default();
+    let issues = validate_site(root, profile)?;
+    for issue in &issues {
+        log.log(issue.level, "CONFIG", &issue.message, issue.path.as_deref());
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.level == Level::Error)
+        .count();
+    if errors > 0 {
+        bail!("{errors} error(s) and {} warning(s)", issues.len() - errors);
+    }
+    log.info("CONFIG", format!("No errors, {} warning(s)", issues.len()));
+    Ok(())
+}
+
+/// One `key = value` line per setting, with nested keys joined by dots and
+/// values from the theme's defaults marked.
+fn list_config(config: &Config) -> Result<String> {
+    let JsonValue::Object(values) = serde_json::to_value(config)? else {
+        bail!("config did not serialize to a mapping");
+    };
+    let mut output = String::new();
+    for (key, value) in &values {
+        push_lines(&mut output, key, value, config.theme_defaults.contains(key));
+    }
+    Ok(output)
+}
+
+fn push_lines(output: &mut String, key: &str, value: &JsonValue, from_theme: bool) {
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => {
+            for (child, value) in map {
+                push_lines(output, &format!("{key}.{child}"), value, from_theme);
+            }
+        }
+        _ => {
+            output.push_str(&format!("{key} = {value}"));
+            if from_theme {
+                output.push_str("  (theme default)");
+            }
+            output.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn list_marks_theme_defaults() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("themes/bckt3")).unwrap();
+        fs::write(
+            root.join("themes/bckt3/defaults.yaml"),
+            "accent_color: red\nsocial:\n  mastodon: null\n",
+        )
+        .unwrap();
+        fs::write(root.join("bckt.yaml"), "accent_color: blue\n").unwrap();
+        let config = Config::load(config_path(root)).unwrap();
+
+        let listing = list_config(&config).unwrap();
+        let lines: Vec<&str> = listing.lines().collect();
+        assert!(lines.contains(&"accent_color = \"blue\""));
+        assert!(lines.contains(&"social.mastodon = null  (theme default)"));
+        assert!(lines.contains(&"search.default_language = \"en\""));
+        assert!(lines.contains(&"theme = \"bckt3\""));
+    }
+}
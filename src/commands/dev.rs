@@ -1,28 +1,41 @@
 use std::fs;
 use std::net::ToSocketAddrs;
 use std::path::{Component, Path, PathBuf};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, mpsc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result, bail};
-use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use percent_encoding::percent_decode_str;
 use tiny_http::{Header, Response, Server, StatusCode};
 
 use crate::cli::DevArgs;
 use crate::config;
-use crate::render::{BuildMode, RenderPlan, render_site};
-use crate::utils::resolve_root;
+use crate::logging::{self, Logger};
+use crate::render::{BuildMode, RenderPlan, render_preview, render_site};
+use crate::urls::extract_base_path;
+use crate::utils::{expand_tilde, resolve_root};
 
+use super::site_watcher::SiteWatcher;
+
+const PREVIEW_ROUTE: &str = "/_preview";
 const LIVE_RELOAD_ID: &str = "__bckt_live_reload__";
 const LIVE_RELOAD_SNIPPET: &str = r#"<script id=\"__bckt_live_reload__\">(function(){if(window.__bcktLiveReload){return;}window.__bcktLiveReload=true;let last=0;async function poll(){try{const res=await fetch('/__bckt__/poll?since='+last+'&_='+(Date.now()),{cache:'no-store'});if(res.ok){const data=await res.json();if(typeof data.timestamp==='number'){last=data.timestamp;}if(data.reload){window.location.reload();return;}}}catch(e){}setTimeout(poll,1000);}poll();})();</script>"#;
 
 pub fn run_dev_command(args: DevArgs) -> Result<()> {
     let start_dir = resolve_root(args.root.as_deref())?;
     let root = config::find_project_root(&start_dir)?;
-    let config = config::Config::load(root.join("bckt.yaml"))?;
+    let base_config = config::Config::load(config::config_path(&root))?;
+    let profile = args
+        .profile
+        .clone()
+        .or_else(|| base_config.has_profile("dev").then(|| "dev".to_string()));
+    let config = config::Config::load_with_profile(config::config_path(&root), profile.as_deref())?;
+    let log = Logger::from_flags(args.verbose, args.quiet);
+    if let Some(name) = profile.as_deref() {
+        log.info("DEV", format!("Using config profile '{name}'"));
+    }
     let html_root = root.join("html");
     fs::create_dir_all(&html_root).context("failed to create html directory")?;
 
@@ -30,59 +43,44 @@ pub fn run_dev_command(args: DevArgs) -> Result<()> {
     let base_path = extract_base_path(&config.base_url);
 
     let initial_plan = RenderPlan {
-        posts: true,
-        static_assets: true,
         mode: if args.force {
             BuildMode::Full
         } else {
             BuildMode::Changed
         },
-        verbose: args.verbose,
+        log,
+        profile: profile.as_deref(),
+        ..RenderPlan::default()
     };
     render_site(&root, initial_plan).context("initial render before dev server failed")?;
 
     let latest_change = Arc::new(AtomicU64::new(now_timestamp()));
-    let (tx, rx) = mpsc::channel();
-
-    let watcher_tx = tx.clone();
-    let mut watcher = notify::recommended_watcher(move |event| match event {
-        Ok(_event) => {
-            let _ = watcher_tx.send(());
-        }
-        Err(err) => {
-            eprintln!("[bckt::dev] watcher error: {err}");
-        }
-    })?;
-
-    register_watch(&mut watcher, root.join("posts"))?;
-    register_watch(&mut watcher, root.join("templates"))?;
-    register_watch(&mut watcher, root.join("skel"))?;
-    register_watch_file(&mut watcher, root.join("bckt.yaml"))?;
+    let watcher = SiteWatcher::new(&root, &config, log, "DEV")?;
 
     let rebuild_root = root.clone();
-    let rebuild_verbose = args.verbose;
     let rebuild_mode = if args.force {
         BuildMode::Full
     } else {
         BuildMode::Changed
     };
     let rebuild_latest = Arc::clone(&latest_change);
+    let rebuild_profile = profile.clone();
 
+    // The server below keeps the process alive, so the watcher never stops.
     thread::spawn(move || {
-        while let Ok(()) = rx.recv() {
-            while rx.try_recv().is_ok() {}
+        watcher.run(&AtomicBool::new(false), |_| {
             let plan = RenderPlan {
-                posts: true,
-                static_assets: true,
                 mode: rebuild_mode,
-                verbose: rebuild_verbose,
+                log,
+                profile: rebuild_profile.as_deref(),
+                ..RenderPlan::default()
             };
             if let Err(error) = render_site(&rebuild_root, plan) {
-                eprintln!("[bckt::dev] render error: {error}");
-                continue;
+                log.error("DEV", format!("render error: {error}"));
+                return;
             }
             rebuild_latest.store(now_timestamp(), Ordering::SeqCst);
-        }
+        });
     });
 
     let address = format!("{}:{}", args.host, args.port);
@@ -102,7 +100,7 @@ pub fn run_dev_command(args: DevArgs) -> Result<()> {
             base_path
         )
     };
-    println!("bckt dev server running at {}", server_url);
+    log.info("DEV", format!("bckt dev server running at {server_url}"));
 
     let server = Server::http(listener_addr)
         .map_err(|err| anyhow::anyhow!("failed to start HTTP server: {err}"))?;
@@ -115,7 +113,21 @@ pub fn run_dev_command(args: DevArgs) -> Result<()> {
         if path == "/__bckt__/poll" {
             let response = handle_poll(query, &latest_change);
             if let Err(err) = request.respond(response) {
-                eprintln!("[bckt::dev] respond error: {err}");
+                log.error("DEV", format!("respond error: {err}"));
+            }
+            continue;
+        }
+
+        if path == PREVIEW_ROUTE {
+            let response = handle_preview(
+                &root,
+                profile.as_deref(),
+                &config.preview_paths,
+                query,
+                &latest_change,
+            );
+            if let Err(err) = request.respond(response) {
+                log.error("DEV", format!("respond error: {err}"));
             }
             continue;
         }
@@ -126,7 +138,7 @@ pub fn run_dev_command(args: DevArgs) -> Result<()> {
         } else if !base_path.is_empty() {
             // Request doesn't match base_path - return 404
             if let Err(err) = request.respond(not_found()) {
-                eprintln!("[bckt::dev] respond error: {err}");
+                log.error("DEV", format!("respond error: {err}"));
             }
             continue;
         } else {
@@ -141,31 +153,13 @@ pub fn run_dev_command(args: DevArgs) -> Result<()> {
 
         let response = serve_path(&html_root, stripped_path, range.as_deref(), &latest_change);
         if let Err(err) = request.respond(response) {
-            eprintln!("[bckt::dev] respond error: {err}");
+            log.error("DEV", format!("respond error: {err}"));
         }
     }
 
     Ok(())
 }
 
-fn register_watch(watcher: &mut RecommendedWatcher, path: PathBuf) -> Result<()> {
-    if path.exists() {
-        watcher
-            .watch(&path, RecursiveMode::Recursive)
-            .with_context(|| format!("failed to watch {}", path.display()))?;
-    }
-    Ok(())
-}
-
-fn register_watch_file(watcher: &mut RecommendedWatcher, path: PathBuf) -> Result<()> {
-    if path.exists() {
-        watcher
-            .watch(&path, RecursiveMode::NonRecursive)
-            .with_context(|| format!("failed to watch {}", path.display()))?;
-    }
-    Ok(())
-}
-
 fn serve_path(
     html_root: &Path,
     raw_path: &str,
@@ -256,7 +250,7 @@ fn serve_path(
             }
         }
         Err(err) => {
-            eprintln!("[bckt::dev] path resolution error: {err}");
+            logging::error("DEV", format!("path resolution error: {err}"));
             forbidden()
         }
     }
@@ -333,16 +327,81 @@ fn handle_poll(
 }
 
 fn parse_since(query: &str) -> Result<u64> {
+    Ok(query_value(query, "since")?.parse::<u64>()?)
+}
+
+/// Renders the markdown file named by `?path=` through `post.html` without
+/// writing anything, so notes can be previewed before they become posts.
+fn handle_preview(
+    root: &Path,
+    profile: Option<&str>,
+    preview_paths: &[String],
+    query: Option<&str>,
+    latest_change: &Arc<AtomicU64>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let Some(requested) = query.and_then(|q| query_value(q, "path").ok()) else {
+        return Response::from_string("missing ?path=").with_status_code(400);
+    };
+    let source = match resolve_preview_path(root, preview_paths, &requested) {
+        Ok(source) => source,
+        Err(err) => {
+            logging::error("DEV", format!("preview rejected: {err:#}"));
+            return forbidden();
+        }
+    };
+    match render_preview(root, profile, &source) {
+        Ok(rendered) => {
+            let mut response = Response::from_string(inject_live_reload(&rendered, latest_change));
+            add_header(&mut response, "Content-Type", "text/html; charset=utf-8");
+            add_header(&mut response, "Cache-Control", "no-store, max-age=0");
+            response
+        }
+        Err(err) => internal_error(format!("{err:#}")),
+    }
+}
+
+/// Resolves a preview request to an existing markdown file inside the project
+/// root or one of `preview_paths`. Symlinks and `..` are resolved first, so
+/// nothing outside those locations can be read.
+fn resolve_preview_path(root: &Path, preview_paths: &[String], requested: &str) -> Result<PathBuf> {
+    let is_markdown = Path::new(requested)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"));
+    if !is_markdown {
+        bail!("{requested} is not a markdown file");
+    }
+    let source = root
+        .join(expand_tilde(requested))
+        .canonicalize()
+        .with_context(|| format!("{requested} not found"))?;
+
+    let allowed = std::iter::once(root.to_path_buf())
+        .chain(
+            preview_paths
+                .iter()
+                .map(|entry| root.join(expand_tilde(entry))),
+        )
+        .filter_map(|path| path.canonicalize().ok())
+        .any(|allowed| source.starts_with(allowed));
+    if !allowed {
+        bail!(
+            "{} is outside the project and preview_paths",
+            source.display()
+        );
+    }
+    Ok(source)
+}
+
+fn query_value(query: &str, name: &str) -> Result<String> {
     for pair in query.split('&') {
         if let Some((key, value)) = pair.split_once('=')
-            && key == "since"
+            && key == name
         {
-            let decoded = percent_decode_str(value).decode_utf8()?;
-            let parsed = decoded.parse::<u64>()?;
-            return Ok(parsed);
+            let value = value.replace('+', " ");
+            return Ok(percent_decode_str(&value).decode_utf8()?.into_owned());
         }
     }
-    bail!("since not found")
+    bail!("{name} not found")
 }
 
 fn split_url(url: &str) -> (&str, Option<&str>) {
@@ -437,31 +496,10 @@ fn now_timestamp() -> u64 {
         .as_millis() as u64
 }
 
-fn extract_base_path(base_url: &str) -> String {
-    // Extract path component from base_url
-    // Examples:
-    //   "https://vrypan.net/blog/" -> "/blog"
-    //   "https://vrypan.net/" -> ""
-    //   "https://example.com" -> ""
-
-    if let Some(idx) = base_url.find("://") {
-        let after_scheme = &base_url[idx + 3..];
-        if let Some(slash_idx) = after_scheme.find('/') {
-            let path = &after_scheme[slash_idx..];
-            // Remove trailing slash
-            path.trim_end_matches('/').to_string()
-        } else {
-            String::new()
-        }
-    } else {
-        // No scheme, treat as path
-        base_url.trim_end_matches('/').to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logging::Level;
 
     #[test]
     fn injects_snippet_before_body() {
@@ -503,25 +541,119 @@ mod tests {
     }
 
     #[test]
-    fn extract_base_path_from_full_url() {
-        assert_eq!(extract_base_path("https://vrypan.net/blog/"), "/blog");
-        assert_eq!(extract_base_path("https://vrypan.net/blog"), "/blog");
-        assert_eq!(
-            extract_base_path("https://example.com/foo/bar/"),
-            "/foo/bar"
-        );
+    fn preview_paths_are_limited_to_project_and_allowlist() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().join("site");
+        let notes = temp.path().join("notes");
+        let secret = temp.path().join("secret");
+        for dir in [&root, &notes, &secret] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(root.join("draft.md"), "draft").unwrap();
+        fs::write(root.join("bckt.yaml"), "title: t\n").unwrap();
+        fs::write(notes.join("idea.md"), "idea").unwrap();
+        fs::write(secret.join("keys.md"), "keys").unwrap();
+        let allow = vec!["../notes".to_string()];
+
+        assert!(resolve_preview_path(&root, &allow, "draft.md").is_ok());
+        assert!(resolve_preview_path(&root, &allow, "../notes/idea.md").is_ok());
+        let absolute = notes.join("idea.md");
+        assert!(resolve_preview_path(&root, &allow, absolute.to_str().unwrap()).is_ok());
+
+        assert!(resolve_preview_path(&root, &allow, "../secret/keys.md").is_err());
+        assert!(resolve_preview_path(&root, &[], "../notes/idea.md").is_err());
+        let outside = secret.join("keys.md");
+        assert!(resolve_preview_path(&root, &allow, outside.to_str().unwrap()).is_err());
+        assert!(resolve_preview_path(&root, &allow, "bckt.yaml").is_err());
+        assert!(resolve_preview_path(&root, &allow, "missing.md").is_err());
     }
 
-    #[test]
-    fn extract_base_path_from_root_url() {
-        assert_eq!(extract_base_path("https://vrypan.net/"), "");
-        assert_eq!(extract_base_path("https://vrypan.net"), "");
-        assert_eq!(extract_base_path("http://example.com"), "");
+    fn is_canonical_dir_url(url: &str) -> bool {
+        url.starts_with('/')
+            && url.ends_with('/')
+            && !url.contains("//")
+            && url.bytes().all(|byte| {
+                byte.is_ascii_alphanumeric() || matches!(byte, b'/' | b'%' | b'.' | b'_' | b'-')
+            })
     }
 
     #[test]
-    fn extract_base_path_from_path_only() {
-        assert_eq!(extract_base_path("/blog/"), "/blog");
-        assert_eq!(extract_base_path("/blog"), "/blog");
+    fn emitted_listing_urls_are_canonical_and_served() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let templates = root.join("templates");
+        fs::create_dir_all(&templates).unwrap();
+        let listing = "<nav data-prev=\"{{ pagination.prev | safe }}\" data-next=\"{{ pagination.next | safe }}\"></nav>";
+        fs::write(
+            templates.join("base.html"),
+            "{% block content %}{% endblock %}",
+        )
+        .unwrap();
+        fs::write(templates.join("post.html"), "{{ post.body | safe }}").unwrap();
+        fs::write(templates.join("index.html"), listing).unwrap();
+        fs::write(templates.join("tag.html"), listing).unwrap();
+        for name in ["archive_year.html", "archive_month.html", "rss.xml"] {
+            fs::write(templates.join(name), "{{ year }}").unwrap();
+        }
+        fs::write(
+            root.join("bckt.yaml"),
+            "base_url: \"https://example.com/blog/\"\nhomepage_posts: 1\npaginate_tags: true\n",
+        )
+        .unwrap();
+        for (slug, month) in [("alpha", 1), ("beta", 2), ("gamma", 3)] {
+            let dir = root.join("posts").join(slug);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("post.md"),
+                format!(
+                    "---\ntitle: {slug}\nslug: {slug}\ndate: 2024-0{month}-01T00:00:00Z\ntags: [Rust Lang, {slug}]\n---\nBody"
+                ),
+            )
+            .unwrap();
+        }
+
+        render_site(
+            root,
+            RenderPlan {
+                static_assets: false,
+                log: Logger::new(Level::Warn),
+                ..RenderPlan::default()
+            },
+        )
+        .unwrap();
+
+        let html_root = root.join("html");
+        let sitemap = fs::read_to_string(html_root.join("sitemap.xml")).unwrap();
+        let mut urls: Vec<String> = sitemap
+            .split("<loc>")
+            .skip(1)
+            .map(|rest| {
+                let loc = &rest[..rest.find("</loc>").unwrap()];
+                loc.strip_prefix("https://example.com/blog")
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        for page in ["index.html", "page/1/index.html", "page/2/index.html"] {
+            let html = fs::read_to_string(html_root.join(page)).unwrap();
+            for attr in ["data-prev=\"", "data-next=\""] {
+                let rest = &html[html.find(attr).unwrap() + attr.len()..];
+                let url = &rest[..rest.find('"').unwrap()];
+                if !url.is_empty() {
+                    urls.push(url.to_string());
+                }
+            }
+        }
+
+        assert!(urls.iter().any(|url| url == "/page/2/"));
+        assert!(urls.iter().any(|url| url.starts_with("/tags/")));
+        for url in &urls {
+            assert!(is_canonical_dir_url(url), "non-canonical url {url}");
+            let (file, is_html) = resolve_path(&html_root, url).unwrap();
+            assert!(
+                is_html && file.is_file(),
+                "{url} does not resolve to a page"
+            );
+        }
     }
 }
@@ -0,0 +1,23 @@
+use anyhow::Result;
+
+use crate::cli::{ExportArgs, ExportFormat};
+use crate::config;
+use crate::render::{ArchiveFormat, ExportOptions, export_site};
+use crate::utils::resolve_root;
+
+pub fn run_export_command(args: ExportArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    let format = match args.format {
+        ExportFormat::Zip => ArchiveFormat::Zip,
+        ExportFormat::TarGz => ArchiveFormat::TarGz,
+    };
+    let options = ExportOptions {
+        format,
+        content_only: args.content_only,
+        include_theme: args.theme,
+    };
+    let count = export_site(&root, args.profile.as_deref(), &args.output, options)?;
+    println!("Exported {count} files to {}", args.output.display());
+    Ok(())
+}
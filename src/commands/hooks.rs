@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, bail};
+use walkdir::WalkDir;
+
+use crate::logging;
+use crate::render::RenderReport;
+use crate::utils::shell_command;
+
+/// Modification time and size of every file under the output directory, used
+/// to list what a render wrote.
+pub(super) struct OutputSnapshot {
+    files: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+impl OutputSnapshot {
+    pub(super) fn capture(html_root: &Path) -> Result<Self> {
+        let mut files = HashMap::new();
+        if !html_root.exists() {
+            return Ok(Self { files });
+        }
+        for entry in WalkDir::new(html_root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let metadata = entry
+                .metadata()
+                .with_context(|| format!("failed to inspect {}", entry.path().display()))?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.insert(entry.into_path(), (modified, metadata.len()));
+        }
+        Ok(Self { files })
+    }
+
+    /// Files that are new or were rewritten since the snapshot, relative to
+    /// `html_root`, `/`-separated and sorted.
+    pub(super) fn changed_files(&self, html_root: &Path) -> Result<Vec<String>> {
+        let after = Self::capture(html_root)?;
+        let mut changed: Vec<String> = after
+            .files
+            .into_iter()
+            .filter(|(path, stamp)| self.files.get(path) != Some(stamp))
+            .filter_map(|(path, _)| {
+                let relative = path.strip_prefix(html_root).ok()?;
+                let parts: Option<Vec<&str>> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_str())
+                    .collect();
+                parts.map(|parts| parts.join("/"))
+            })
+            .collect();
+        changed.sort();
+        Ok(changed)
+    }
+}
+
+/// What a finished render tells its hooks.
+pub(super) struct HookBuild<'a> {
+    pub(super) html_root: &'a Path,
+    /// The rendered `base_url`, including a `--base-url` override.
+    pub(super) base_url: &'a str,
+    pub(super) report: &'a RenderReport,
+    pub(super) changed: &'a [String],
+}
+
+/// Runs each `hooks.post_render` command from the project root. Every hook
+/// runs even if an earlier one fails; any failure makes the result an error.
+pub(super) fn run_post_render_hooks(
+    root: &Path,
+    commands: &[String],
+    build: HookBuild<'_>,
+) -> Result<()> {
+    let HookBuild {
+        html_root,
+        base_url,
+        report,
+        changed,
+    } = build;
+    let mut changed_list =
+        tempfile::NamedTempFile::new().context("failed to create changed files list")?;
+    for path in changed {
+        writeln!(changed_list, "{path}").context("failed to write changed files list")?;
+    }
+    changed_list
+        .flush()
+        .context("failed to write changed files list")?;
+
+    let mut failures = 0usize;
+    for command in commands {
+        let status = shell_command(command)
+            .current_dir(root)
+            .env("BCKT_OUTPUT_DIR", html_root)
+            .env("BCKT_BASE_URL", base_url)
+            .env("BCKT_POSTS_RENDERED", report.posts_rendered.to_string())
+            .env("BCKT_CHANGED_FILES", changed_list.path())
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                logging::error("HOOK", format!("`{command}` exited with {status}"));
+                failures += 1;
+            }
+            Err(err) => {
+                logging::error("HOOK", format!("failed to run `{command}`: {err}"));
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!("{failures} post_render hook(s) failed");
+    }
+    Ok(())
+}
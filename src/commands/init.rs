@@ -7,6 +7,7 @@ use anyhow::{Context, Result, anyhow};
 use walkdir::WalkDir;
 
 use crate::cli::InitArgs;
+use crate::logging;
 use crate::theme::{GithubReference, ThemeSource, download_theme};
 use crate::utils::resolve_root;
 
@@ -114,8 +115,11 @@ fn ensure_theme(theme_dir: &Path, args: &InitArgs) -> Result<()> {
         };
 
         if let Err(err) = download_theme(theme_dir, default_source.clone()) {
-            eprintln!(
-                "Warning: failed to download default theme tag {default_tag}: {err}. Falling back to main branch."
+            logging::warn(
+                "INIT",
+                format!(
+                    "failed to download default theme tag {default_tag}: {err}. Falling back to main branch."
+                ),
             );
             ThemeSource::Github {
                 owner: DEFAULT_THEME_OWNER.to_string(),
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+
+use crate::cli::{LsArgs, LsSubcommand};
+use crate::config;
+use crate::content::Post;
+use crate::render::{SiteSources, SourceFile, site_sources};
+use crate::utils::resolve_root;
+
+const POST_COLUMNS: [&str; 6] = ["SLUG", "DATE", "TYPE", "TAGS", "PERMALINK", "SOURCE"];
+
+/// One line of `bckt ls posts`.
+#[derive(Debug, Serialize)]
+struct PostRow {
+    slug: String,
+    date: String,
+    #[serde(rename = "type")]
+    post_type: Option<String>,
+    tags: Vec<String>,
+    permalink: String,
+    source: PathBuf,
+}
+
+pub fn run_ls_command(args: LsArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    let sources = site_sources(&root, args.profile.as_deref())?;
+
+    let output = match &args.command {
+        LsSubcommand::Posts => list_posts(&root, &sources, args.json)?,
+        LsSubcommand::Output { path } => find_output(&root, &sources, path, args.json)?,
+    };
+    println!("{output}");
+    Ok(())
+}
+
+fn list_posts(root: &Path, sources: &SiteSources, json: bool) -> Result<String> {
+    let mut posts: Vec<&Post> = sources.posts.iter().collect();
+    posts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.slug.cmp(&b.slug)));
+    let rows = posts
+        .into_iter()
+        .map(|post| post_row(root, post))
+        .collect::<Result<Vec<_>>>()?;
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&rows)?);
+    }
+    let cells: Vec<[String; 6]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.slug.clone(),
+                row.date.clone(),
+                row.post_type.clone().unwrap_or_else(|| "-".to_string()),
+                row.tags.join(","),
+                row.permalink.clone(),
+                row.source.display().to_string(),
+            ]
+        })
+        .collect();
+    Ok(format_table(POST_COLUMNS.map(str::to_string), &cells))
+}
+
+fn post_row(root: &Path, post: &Post) -> Result<PostRow> {
+    Ok(PostRow {
+        slug: post.slug.clone(),
+        date: post.date.format(&Rfc3339)?,
+        post_type: post.post_type.clone(),
+        tags: post.tags.clone(),
+        permalink: post.permalink.clone(),
+        source: relative_to(root, &post.content_path),
+    })
+}
+
+fn find_output(root: &Path, sources: &SiteSources, path: &str, json: bool) -> Result<String> {
+    let Some(file) = sources.resolve(path) else {
+        bail!(
+            "no source file produces {path}; it may be a generated listing or feed, or not exist"
+        );
+    };
+    let file = SourceFile {
+        source: relative_to(root, &file.source),
+        ..file.clone()
+    };
+    if json {
+        return Ok(serde_json::to_string_pretty(&file)?);
+    }
+    Ok(format!(
+        "{} {} <- {}",
+        file.kind.as_str(),
+        file.output,
+        file.source.display()
+    ))
+}
+
+fn relative_to(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Left-aligns every column to its widest cell, two spaces apart.
+fn format_table<const N: usize>(header: [String; N], rows: &[[String; N]]) -> String {
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    std::iter::once(&header)
+        .chain(rows)
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    use crate::render::SourceKind;
+
+    fn setup() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("bckt.yaml"),
+            "base_url: https://example.com/blog\n",
+        )
+        .unwrap();
+        let post_dir = root.join("posts/hello");
+        fs::create_dir_all(&post_dir).unwrap();
+        fs::write(
+            post_dir.join("post.md"),
+            "---\ndate: 2024-01-02T03:04:05Z\nslug: hello\ntype: note\ntags: [rust, web]\nattached: [cover.png]\n---\nHi.\n",
+        )
+        .unwrap();
+        fs::write(post_dir.join("cover.png"), "png").unwrap();
+        fs::create_dir_all(root.join("pages")).unwrap();
+        fs::write(root.join("pages/about.html"), "About").unwrap();
+        fs::create_dir_all(root.join("skel/css")).unwrap();
+        fs::write(root.join("skel/css/site.css"), "body {}").unwrap();
+        temp
+    }
+
+    #[test]
+    fn posts_list_permalink_and_source() {
+        let temp = setup();
+        let root = temp.path();
+        let sources = site_sources(root, None).unwrap();
+
+        let table = list_posts(root, &sources, false).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("SLUG   DATE"));
+        assert_eq!(
+            lines[1],
+            "hello  2024-01-02T03:04:05Z  note  rust,web  /2024/01/02/hello/  posts/hello/post.md"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&list_posts(root, &sources, true).unwrap()).unwrap();
+        assert_eq!(json[0]["permalink"], "/2024/01/02/hello/");
+        assert_eq!(json[0]["source"], "posts/hello/post.md");
+        assert_eq!(json[0]["type"], "note");
+    }
+
+    #[test]
+    fn outputs_resolve_to_their_source() {
+        let temp = setup();
+        let root = temp.path();
+        let sources = site_sources(root, None).unwrap();
+        let resolve = |path: &str| {
+            sources
+                .resolve(path)
+                .map(|file| (file.kind, relative_to(root, &file.source)))
+        };
+
+        let post = Some((SourceKind::Post, PathBuf::from("posts/hello/post.md")));
+        assert_eq!(resolve("/2024/01/02/hello/"), post);
+        assert_eq!(resolve("/blog/2024/01/02/hello"), post);
+        assert_eq!(
+            resolve("https://example.com/blog/2024/01/02/hello/#top"),
+            post
+        );
+        assert_eq!(
+            resolve("/2024/01/02/hello/cover.png"),
+            Some((
+                SourceKind::Attachment,
+                PathBuf::from("posts/hello/cover.png")
+            ))
+        );
+        assert_eq!(
+            resolve("/about.html"),
+            Some((SourceKind::Page, PathBuf::from("pages/about.html")))
+        );
+        assert_eq!(
+            resolve("/css/site.css?v=1"),
+            Some((SourceKind::Static, PathBuf::from("skel/css/site.css")))
+        );
+        assert_eq!(resolve("/"), None);
+
+        let error = find_output(root, &sources, "/rss.xml", false).unwrap_err();
+        assert!(error.to_string().contains("generated listing or feed"));
+        assert_eq!(
+            find_output(root, &sources, "/about.html", false).unwrap(),
+            "page about.html <- pages/about.html"
+        );
+    }
+}
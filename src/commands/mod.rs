@@ -1,9 +1,19 @@
+mod bench;
+mod check;
 mod clean;
 mod config;
 mod dev;
+mod export;
+mod hooks;
 mod init;
+mod ls;
+mod preview;
 mod render;
+mod site_watcher;
+mod tags;
 mod themes;
+mod version;
+mod watch;
 
 use anyhow::Result;
 
@@ -14,8 +24,16 @@ pub fn run(command: Command) -> Result<()> {
         Command::Init(args) => init::run_init_command(args),
         Command::Render(args) => render::run_render_command(args),
         Command::Dev(args) => dev::run_dev_command(args),
+        Command::Check(args) => check::run_check_command(args),
         Command::Clean(args) => clean::run_clean_command(args),
         Command::Themes(args) => themes::run_themes_command(args),
         Command::Config(args) => config::run_config_command(args),
+        Command::Ls(args) => ls::run_ls_command(args),
+        Command::Preview(args) => preview::run_preview_command(args),
+        Command::Export(args) => export::run_export_command(args),
+        Command::Tags(args) => tags::run_tags_command(args),
+        Command::Watch(args) => watch::run_watch_command(args),
+        Command::Version(args) => version::run_version_command(args),
+        Command::Bench(args) => bench::run_bench_command(args),
     }
 }
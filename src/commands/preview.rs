@@ -0,0 +1,19 @@
+use anyhow::Result;
+
+use crate::cli::PreviewArgs;
+use crate::config;
+use crate::render::{PostPreview, preview_post};
+use crate::utils::resolve_root;
+
+pub fn run_preview_command(args: PreviewArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    let output = if args.context {
+        PostPreview::Context
+    } else {
+        PostPreview::Html
+    };
+    let page = preview_post(&root, args.profile.as_deref(), &args.slug, output)?;
+    println!("{page}");
+    Ok(())
+}
@@ -1,20 +1,129 @@
-use anyhow::Result;
+use std::fs;
+
+use anyhow::{Context, Result};
 
 use crate::cli::RenderArgs;
-use crate::config;
-use crate::render::{BuildMode, RenderPlan, render_site};
+use crate::config::{self, Config};
+use crate::logging::Logger;
+use crate::render::{BuildMode, CacheCounts, CacheStats, RenderPlan, render_site_with_config};
+use crate::timing;
 use crate::utils::resolve_root;
 
+use super::hooks::{HookBuild, OutputSnapshot, run_post_render_hooks};
+use super::site_watcher::{SiteWatcher, stop_on_interrupt};
+
 pub fn run_render_command(args: RenderArgs) -> Result<()> {
+    if args.watch {
+        return render_on_changes(&args);
+    }
+    let Some(trace_path) = args.trace.as_deref() else {
+        return render(&args);
+    };
+    let (result, spans) = timing::record(|| render(&args));
+    let trace = timing::chrome_trace(&spans).context("failed to serialize trace")?;
+    fs::write(trace_path, trace).with_context(|| format!("failed to write trace {trace_path}"))?;
+    result
+}
+
+fn render_on_changes(args: &RenderArgs) -> Result<()> {
+    let stop = stop_on_interrupt();
+    render(args)?;
+
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    let config = Config::load_with_profile(config::config_path(&root), args.profile.as_deref())?;
+    let log = Logger::from_flags(args.verbose, args.quiet);
+    let watcher = SiteWatcher::new(&root, &config, log, "WATCH")?;
+    log.info("WATCH", "Watching for changes; press Ctrl+C to stop");
+
+    watcher.run(stop, |_| {
+        if let Err(error) = render(args) {
+            log.error("WATCH", format!("render error: {error}"));
+        }
+    });
+    Ok(())
+}
+
+fn render(args: &RenderArgs) -> Result<()> {
     let start_dir = resolve_root(args.root.as_deref())?;
     let root = config::find_project_root(&start_dir)?;
     let plan = determine_plan(args);
-    render_site(&root, plan)
+
+    // Loaded once, so hooks see the same values as the render.
+    let mut config = Config::load_with_profile(config::config_path(&root), plan.profile)?;
+    if let Some(base_url) = plan.base_url {
+        config.override_base_url(base_url)?;
+    }
+    let hooks = if args.no_hooks || args.dry_run {
+        Vec::new()
+    } else {
+        config.hooks.post_render.clone()
+    };
+    if hooks.is_empty() {
+        let report = render_site_with_config(&root, config, plan)?;
+        if args.incremental_stats {
+            print_cache_stats(&report.cache);
+        }
+        return Ok(());
+    }
+
+    let html_root = root.join("html");
+    let before = OutputSnapshot::capture(&html_root)?;
+    let base_url = config.base_url.clone();
+    let report = render_site_with_config(&root, config, plan)?;
+    if args.incremental_stats {
+        print_cache_stats(&report.cache);
+    }
+    let changed = before.changed_files(&html_root)?;
+    let build = HookBuild {
+        html_root: &html_root,
+        base_url: &base_url,
+        report: &report,
+        changed: &changed,
+    };
+    run_post_render_hooks(&root, &hooks, build)
+}
+
+fn print_cache_stats(stats: &CacheStats) {
+    for line in cache_stats_lines(stats) {
+        println!("{line}");
+    }
 }
 
-fn determine_plan(args: RenderArgs) -> RenderPlan {
+/// One line per category, then the overall ratio, e.g.
+/// `posts     12 hits, 1 miss (92.3%)`.
+fn cache_stats_lines(stats: &CacheStats) -> Vec<String> {
+    fn line(name: &str, counts: CacheCounts) -> String {
+        let ratio = counts
+            .hit_ratio()
+            .map_or("-".to_string(), |ratio| format!("{:.1}%", ratio * 100.0));
+        format!(
+            "{name:<9} {} hit{}, {} miss{} ({ratio})",
+            counts.hits,
+            if counts.hits == 1 { "" } else { "s" },
+            counts.misses,
+            if counts.misses == 1 { "" } else { "es" },
+        )
+    }
+
+    let mut lines = Vec::new();
+    if !stats.incremental {
+        lines.push("Full rebuild: the cache was not used".to_string());
+    }
+    lines.extend(
+        stats
+            .categories()
+            .into_iter()
+            .map(|(name, counts)| line(name, counts)),
+    );
+    lines.push(line("overall", stats.overall()));
+    lines
+}
+
+fn determine_plan(args: &RenderArgs) -> RenderPlan<'_> {
     let posts = args.posts;
     let static_assets = args.static_assets;
+    let log = Logger::from_flags(args.verbose, args.quiet);
     let mode = if args.force {
         BuildMode::Full
     } else {
@@ -26,13 +135,21 @@ fn determine_plan(args: RenderArgs) -> RenderPlan {
             posts: true,
             static_assets: true,
             mode,
-            verbose: args.verbose,
+            log,
+            profile: args.profile.as_deref(),
+            dry_run: args.dry_run,
+            jobs: args.jobs,
+            base_url: args.base_url.as_deref(),
         },
         _ => RenderPlan {
             posts,
             static_assets,
             mode,
-            verbose: args.verbose,
+            log,
+            profile: args.profile.as_deref(),
+            dry_run: args.dry_run,
+            jobs: args.jobs,
+            base_url: args.base_url.as_deref(),
         },
     }
 }
@@ -40,58 +157,164 @@ fn determine_plan(args: RenderArgs) -> RenderPlan {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logging::Level;
 
     #[test]
     fn plan_defaults_to_both_when_flags_missing() {
-        let plan = determine_plan(RenderArgs {
+        let args = RenderArgs {
             root: None,
             posts: false,
             static_assets: false,
             force: false,
-            verbose: false,
-        });
+            verbose: 0,
+            quiet: false,
+            profile: None,
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
         assert!(plan.posts);
         assert!(plan.static_assets);
         assert!(matches!(plan.mode, BuildMode::Changed));
-        assert!(!plan.verbose);
+        assert!(!plan.log.enabled(Level::Debug));
     }
 
     #[test]
     fn plan_respects_individual_flags() {
-        let plan = determine_plan(RenderArgs {
+        let args = RenderArgs {
             root: None,
             posts: true,
             static_assets: false,
             force: false,
-            verbose: false,
-        });
+            verbose: 0,
+            quiet: false,
+            profile: None,
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
         assert!(plan.posts);
         assert!(!plan.static_assets);
         assert!(matches!(plan.mode, BuildMode::Changed));
-        assert!(!plan.verbose);
+        assert!(!plan.log.enabled(Level::Debug));
 
-        let plan = determine_plan(RenderArgs {
+        let args = RenderArgs {
             root: None,
             posts: false,
             static_assets: true,
             force: false,
-            verbose: true,
-        });
+            verbose: 1,
+            quiet: false,
+            profile: None,
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
         assert!(!plan.posts);
         assert!(plan.static_assets);
         assert!(matches!(plan.mode, BuildMode::Changed));
-        assert!(plan.verbose);
+        assert!(plan.log.enabled(Level::Debug));
     }
 
     #[test]
     fn force_overrides_changed_mode() {
-        let plan = determine_plan(RenderArgs {
+        let args = RenderArgs {
             root: None,
             posts: false,
             static_assets: false,
             force: true,
-            verbose: false,
-        });
+            verbose: 0,
+            quiet: false,
+            profile: None,
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
         assert!(matches!(plan.mode, BuildMode::Full));
     }
+
+    #[test]
+    fn quiet_disables_verbose_output() {
+        let args = RenderArgs {
+            root: None,
+            posts: false,
+            static_assets: false,
+            force: false,
+            verbose: 1,
+            quiet: true,
+            profile: None,
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
+        assert!(!plan.log.enabled(Level::Info));
+        assert!(plan.log.enabled(Level::Warn));
+    }
+
+    #[test]
+    fn profile_is_forwarded_to_plan() {
+        let args = RenderArgs {
+            root: None,
+            posts: false,
+            static_assets: false,
+            force: false,
+            verbose: 0,
+            quiet: false,
+            profile: Some("dev".to_string()),
+            no_hooks: false,
+            dry_run: false,
+            trace: None,
+            watch: false,
+            jobs: None,
+            base_url: None,
+            incremental_stats: false,
+        };
+        let plan = determine_plan(&args);
+        assert_eq!(plan.profile, Some("dev"));
+    }
+
+    #[test]
+    fn cache_stats_report_each_category_and_overall() {
+        let stats = CacheStats {
+            incremental: true,
+            posts: CacheCounts { hits: 3, misses: 1 },
+            pages: CacheCounts { hits: 0, misses: 1 },
+            ..CacheStats::default()
+        };
+        let lines = cache_stats_lines(&stats);
+        assert_eq!(lines[0], "posts     3 hits, 1 miss (75.0%)");
+        assert_eq!(lines[1], "archives  0 hits, 0 misses (-)");
+        assert_eq!(lines[5], "overall   3 hits, 2 misses (60.0%)");
+
+        let full = CacheStats::default();
+        assert_eq!(
+            cache_stats_lines(&full)[0],
+            "Full rebuild: the cache was not used"
+        );
+    }
 }
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::config::{self, Config};
+use crate::logging::Logger;
+
+/// How often an idle watcher checks whether it was asked to stop.
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Watches a site for `bckt dev`, `bckt render --watch`, and `bckt watch`,
+/// and hands each settled batch of changed paths to a rebuild.
+pub(super) struct SiteWatcher {
+    events: Receiver<Vec<PathBuf>>,
+    debounce: Duration,
+    _watcher: RecommendedWatcher,
+}
+
+impl SiteWatcher {
+    /// Watches the sources of a site: posts, pages, templates, `skel/`,
+    /// `bckt.yaml`, and the active theme.
+    pub(super) fn new(
+        root: &Path,
+        config: &Config,
+        log: Logger,
+        scope: &'static str,
+    ) -> Result<Self> {
+        let (mut watcher, events) = start_watcher(log, scope)?;
+
+        register_watch(&mut watcher, config.posts_path(root))?;
+        register_watch(&mut watcher, config.pages_path(root))?;
+        register_watch(&mut watcher, config.templates_path(root))?;
+        register_watch(&mut watcher, config.static_path(root))?;
+        register_watch_file(&mut watcher, config::config_path(root))?;
+        if let Some(theme_dir) = config.theme_path(root) {
+            register_watch(&mut watcher, theme_dir)?;
+        }
+
+        Ok(Self {
+            events,
+            debounce: Duration::from_millis(config.watch.debounce_ms),
+            _watcher: watcher,
+        })
+    }
+
+    /// Watches everything under `root`, for rules that match arbitrary files.
+    pub(super) fn whole_project(
+        root: &Path,
+        config: &Config,
+        log: Logger,
+        scope: &'static str,
+    ) -> Result<Self> {
+        let (mut watcher, events) = start_watcher(log, scope)?;
+        register_watch(&mut watcher, root.to_path_buf())?;
+
+        Ok(Self {
+            events,
+            debounce: Duration::from_millis(config.watch.debounce_ms),
+            _watcher: watcher,
+        })
+    }
+
+    /// Calls `rebuild` with the changed paths once per settled batch of
+    /// changes until `stop` is set. A rebuild in progress when that happens is
+    /// finished first.
+    pub(super) fn run(&self, stop: &AtomicBool, rebuild: impl FnMut(Vec<PathBuf>)) {
+        rebuild_on_changes(&self.events, self.debounce, stop, rebuild);
+    }
+}
+
+fn start_watcher(
+    log: Logger,
+    scope: &'static str,
+) -> Result<(RecommendedWatcher, Receiver<Vec<PathBuf>>)> {
+    let (tx, events) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |event| match event {
+        Ok(notify::Event { paths, .. }) => {
+            let _ = tx.send(paths);
+        }
+        Err(err) => {
+            log.error(scope, format!("watcher error: {err}"));
+        }
+    })?;
+    Ok((watcher, events))
+}
+
+/// Makes the first Ctrl-C set the returned flag instead of ending the process,
+/// so a render can finish writing the cache. A second Ctrl-C ends it at once.
+pub(super) fn stop_on_interrupt() -> &'static AtomicBool {
+    #[cfg(unix)]
+    // SAFETY: the handler only stores to an atomic, which is signal safe.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESETHAND;
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+    &INTERRUPTED
+}
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+fn rebuild_on_changes(
+    events: &Receiver<Vec<PathBuf>>,
+    debounce: Duration,
+    stop: &AtomicBool,
+    mut rebuild: impl FnMut(Vec<PathBuf>),
+) {
+    while let Some(changed) = next_batch(events, debounce, stop) {
+        rebuild(changed);
+    }
+}
+
+/// Waits for a change, then for `debounce` to pass without another one, and
+/// returns every path changed meanwhile. Returns `None` once `stop` is set or
+/// the watcher is gone.
+fn next_batch(
+    events: &Receiver<Vec<PathBuf>>,
+    debounce: Duration,
+    stop: &AtomicBool,
+) -> Option<Vec<PathBuf>> {
+    let mut changed = loop {
+        if stop.load(Ordering::SeqCst) {
+            return None;
+        }
+        match events.recv_timeout(STOP_POLL) {
+            Ok(paths) => break paths,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    };
+    loop {
+        match events.recv_timeout(debounce) {
+            Ok(paths) => changed.extend(paths),
+            Err(RecvTimeoutError::Timeout) => break,
+            Err(RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+    (!stop.load(Ordering::SeqCst)).then_some(changed)
+}
+
+fn register_watch(watcher: &mut RecommendedWatcher, path: PathBuf) -> Result<()> {
+    if path.exists() {
+        watcher
+            .watch(&path, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn register_watch_file(watcher: &mut RecommendedWatcher, path: PathBuf) -> Result<()> {
+    if path.exists() {
+        watcher
+            .watch(&path, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::Level;
+    use crate::render::{BuildMode, RenderPlan, render_site};
+    use std::fs;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn bursts_of_changes_rebuild_once_after_the_quiet_period() {
+        let (tx, events) = mpsc::channel();
+        let stop = AtomicBool::new(false);
+        let debounce = Duration::from_millis(150);
+
+        let sender = thread::spawn(move || {
+            for index in 0..5 {
+                tx.send(vec![PathBuf::from(format!("{index}.md"))]).unwrap();
+                thread::sleep(Duration::from_millis(30));
+            }
+            thread::sleep(Duration::from_millis(400));
+            tx.send(vec![PathBuf::from("late.md")]).unwrap();
+            thread::sleep(Duration::from_millis(400));
+        });
+
+        let started = Instant::now();
+        let mut rebuilds = Vec::new();
+        rebuild_on_changes(&events, debounce, &stop, |changed| {
+            rebuilds.push((started.elapsed(), changed.len()))
+        });
+        sender.join().unwrap();
+
+        assert_eq!(rebuilds.len(), 2, "rebuilds at {rebuilds:?}");
+        // The last event of the burst arrives after 120ms.
+        assert!(rebuilds[0].0 >= Duration::from_millis(120) + debounce);
+        assert_eq!(rebuilds[0].1, 5);
+        assert_eq!(rebuilds[1].1, 1);
+    }
+
+    #[test]
+    fn stopping_during_a_rebuild_finishes_it_and_flushes_the_cache() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        let templates = root.join("templates");
+        fs::create_dir_all(&templates).unwrap();
+        for name in [
+            "base.html",
+            "post.html",
+            "index.html",
+            "tag.html",
+            "archive_year.html",
+            "archive_month.html",
+            "rss.xml",
+        ] {
+            fs::write(templates.join(name), "{{ config.title }}").unwrap();
+        }
+        fs::write(root.join("bckt.yaml"), "title: Test\n").unwrap();
+        fs::create_dir_all(root.join("posts/hello")).unwrap();
+        fs::write(
+            root.join("posts/hello/post.md"),
+            "---\ntitle: Hello\ndate: 2024-01-01T00:00:00Z\n---\nHi\n",
+        )
+        .unwrap();
+        let plan = || RenderPlan {
+            mode: BuildMode::Changed,
+            log: Logger::new(Level::Warn),
+            ..RenderPlan::default()
+        };
+
+        let (tx, events) = mpsc::channel();
+        tx.send(vec![root.join("posts/hello/post.md")]).unwrap();
+        let stop = AtomicBool::new(false);
+        let mut rendered = 0;
+        rebuild_on_changes(&events, Duration::from_millis(10), &stop, |_| {
+            // Ctrl-C arrives while the render is running.
+            stop.store(true, Ordering::SeqCst);
+            rendered += render_site(root, plan()).unwrap().posts_rendered;
+            // Changes made meanwhile are left for the next run.
+            tx.send(Vec::new()).unwrap();
+        });
+        assert_eq!(rendered, 1);
+
+        let report = render_site(root, plan()).unwrap();
+        assert_eq!(report.posts_rendered, 0);
+        assert_eq!(report.posts_skipped, 1);
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::{TagsArgs, TagsSubcommand};
+use crate::config;
+use crate::content::rename_tag;
+use crate::render::site_sources;
+use crate::utils::resolve_root;
+
+pub fn run_tags_command(args: TagsArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+
+    match args.command {
+        TagsSubcommand::Rename { old, new, dry_run } => {
+            let changed = rename_in_posts(&root, args.profile.as_deref(), &old, &new, dry_run)?;
+            let verb = if dry_run { "Would update" } else { "Updated" };
+            for path in &changed {
+                println!("{verb} {}", path.display());
+            }
+            if changed.is_empty() {
+                println!("No posts are tagged '{old}'");
+            } else if !dry_run {
+                println!(
+                    "Renamed '{old}' to '{new}' in {} post{}",
+                    changed.len(),
+                    if changed.len() == 1 { "" } else { "s" }
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites every post source tagged `old`, returning the changed files
+/// relative to `root`. Nothing is written when any of them fails, or with
+/// `dry_run`.
+fn rename_in_posts(
+    root: &Path,
+    profile: Option<&str>,
+    old: &str,
+    new: &str,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>> {
+    let sources = site_sources(root, profile)?;
+    let mut paths: Vec<&Path> = sources
+        .posts
+        .iter()
+        .map(|post| post.content_path.as_path())
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut rewrites = Vec::new();
+    for path in paths {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let rewritten = rename_tag(&raw, old, new)
+            .with_context(|| format!("{}: failed to rename tag '{old}'", path.display()))?;
+        if let Some(rewritten) = rewritten {
+            rewrites.push((path, rewritten));
+        }
+    }
+
+    if !dry_run {
+        for (path, rewritten) in &rewrites {
+            fs::write(path, rewritten)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(rewrites
+        .into_iter()
+        .map(|(path, _)| {
+            path.strip_prefix(root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.to_path_buf())
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_post(root: &Path, slug: &str, front: &str) -> PathBuf {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("post.md");
+        fs::write(
+            &path,
+            format!(
+                "---\ntitle: {slug}\ndate: 2024-01-02T03:04:05Z\n{front}---\nAbout rust-lang.\n"
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn renames_tag_in_tagged_posts_only() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("bckt.yaml"), "title: Test\n").unwrap();
+        let flow = write_post(root, "flow", "tags: [Rust-Lang, web]\n");
+        let block = write_post(root, "block", "tags:\n  - rust-lang\n");
+        let other = write_post(root, "other", "tags: [web]\n");
+        let other_before = fs::read(&other).unwrap();
+
+        let preview = rename_in_posts(root, None, "rust-lang", "rust", true).unwrap();
+        assert_eq!(
+            preview,
+            [
+                PathBuf::from("posts/block/post.md"),
+                PathBuf::from("posts/flow/post.md")
+            ]
+        );
+        assert!(
+            fs::read_to_string(&flow)
+                .unwrap()
+                .contains("[Rust-Lang, web]")
+        );
+
+        let changed = rename_in_posts(root, None, "rust-lang", "rust", false).unwrap();
+        assert_eq!(changed, preview);
+        assert_eq!(
+            fs::read_to_string(&flow).unwrap(),
+            "---\ntitle: flow\ndate: 2024-01-02T03:04:05Z\ntags: [rust, web]\n---\nAbout rust-lang.\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&block).unwrap(),
+            "---\ntitle: block\ndate: 2024-01-02T03:04:05Z\ntags:\n  - rust\n---\nAbout rust-lang.\n"
+        );
+        assert_eq!(fs::read(&other).unwrap(), other_before);
+    }
+}
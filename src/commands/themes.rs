@@ -6,7 +6,7 @@ use anyhow::{Context, Result, bail};
 use walkdir::WalkDir;
 
 use crate::cli::{ThemeDownloadArgs, ThemesArgs, ThemesSubcommand};
-use crate::config::Config;
+use crate::config::{self, Config};
 use crate::theme::{GithubReference, ThemeSource, download_theme};
 use crate::utils::resolve_root;
 
@@ -48,7 +48,7 @@ fn list_themes(root: &Path) -> Result<()> {
 
     names.sort_unstable();
 
-    let config_path = root.join("bckt.yaml");
+    let config_path = config::config_path(root);
     let active = Config::load(&config_path)
         .ok()
         .and_then(|config| config.theme);
@@ -73,7 +73,7 @@ fn use_theme(root: &Path, name: &str, force: bool) -> Result<()> {
     confirm_overwrite(root, force)?;
     apply_theme(&theme_root, root)?;
 
-    let config_path = root.join("bckt.yaml");
+    let config_path = config::config_path(root);
     let mut config = Config::load(&config_path)?;
     config.theme = Some(name.to_string());
     config.save(&config_path)?;
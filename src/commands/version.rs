@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+use crate::cli::VersionArgs;
+use crate::render::CACHE_SCHEMA_VERSION;
+
+pub fn run_version_command(args: VersionArgs) -> Result<()> {
+    print!("{}", version_report(args.verbose));
+    Ok(())
+}
+
+fn version_report(verbose: bool) -> String {
+    let mut report = format!("bckt {}\n", env!("CARGO_PKG_VERSION"));
+    if verbose {
+        let features = env!("BCKT_FEATURES");
+        let features = if features.is_empty() {
+            "none"
+        } else {
+            features
+        };
+        report.push_str(&format!("features: {features}\n"));
+        report.push_str(&format!("cache schema: {CACHE_SCHEMA_VERSION}\n"));
+        report.push_str(&format!("minijinja: {}\n", env!("BCKT_MINIJINJA_VERSION")));
+        report.push_str(&format!("time: {}\n", env!("BCKT_TIME_VERSION")));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_report_is_a_single_line() {
+        let report = version_report(false);
+        assert_eq!(report, format!("bckt {}\n", env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn verbose_report_lists_build_details() {
+        let report = version_report(true);
+        assert!(report.contains(&format!("cache schema: {CACHE_SCHEMA_VERSION}\n")));
+        assert!(report.contains("features: "));
+        for krate in ["minijinja", "time"] {
+            let version = report
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("{krate}: ")))
+                .unwrap();
+            let parts: Vec<&str> = version.split('.').collect();
+            assert!(
+                parts.len() >= 3 && parts[..3].iter().all(|part| part.parse::<u64>().is_ok()),
+                "{krate}: {version}"
+            );
+        }
+    }
+}
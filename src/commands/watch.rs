@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::WatchArgs;
+use crate::config::{self, WatchConfig};
+use crate::logging::Logger;
+use crate::render::{BuildMode, RenderPlan, render_site};
+use crate::utils::{glob_matches, resolve_root, shell_command};
+
+use super::site_watcher::{SiteWatcher, stop_on_interrupt};
+
+// Generated output and cache state; changes here come from bckt itself.
+const IGNORED_DIRS: &[&str] = &["html", ".bckt", ".git"];
+
+pub fn run_watch_command(args: WatchArgs) -> Result<()> {
+    let start_dir = resolve_root(args.root.as_deref())?;
+    let root = config::find_project_root(&start_dir)?;
+    // notify reports canonical paths, so match against the canonical root.
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {}", root.display()))?;
+    let config =
+        config::Config::load_with_profile(config::config_path(&root), args.profile.as_deref())?;
+    if config.watch.commands.is_empty() {
+        bail!("no watch.commands configured in bckt.yaml");
+    }
+    let log = Logger::from_flags(args.verbose, args.quiet);
+    let stop = stop_on_interrupt();
+    let watcher = SiteWatcher::whole_project(&root, &config, log, "WATCH")?;
+
+    log.info(
+        "WATCH",
+        format!(
+            "bckt watch running with {} rule(s); press Ctrl+C to stop",
+            config.watch.commands.len()
+        ),
+    );
+
+    watcher.run(stop, |changed| {
+        if !run_matching_commands(&root, &config.watch, &changed, log) || !config.watch.rebuild {
+            return;
+        }
+
+        let plan = RenderPlan {
+            mode: BuildMode::Changed,
+            log,
+            profile: args.profile.as_deref(),
+            ..RenderPlan::default()
+        };
+        if let Err(error) = render_site(&root, plan) {
+            log.error("WATCH", format!("render error: {error}"));
+        }
+    });
+
+    Ok(())
+}
+
+/// Runs every command whose pattern matches one of the changed paths, once per
+/// batch. Returns true when at least one command ran and all of them succeeded.
+fn run_matching_commands(
+    root: &Path,
+    watch: &WatchConfig,
+    changed: &[PathBuf],
+    log: Logger,
+) -> bool {
+    let relative: Vec<String> = changed
+        .iter()
+        .filter_map(|path| relative_path(root, path))
+        .collect();
+
+    let mut ran = false;
+    let mut succeeded = true;
+    for (pattern, command) in &watch.commands {
+        let Some(matched) = relative.iter().find(|path| glob_matches(pattern, path)) else {
+            continue;
+        };
+        ran = true;
+        log.info("WATCH", format!("{matched} changed; running `{command}`"));
+        match shell_command(command).current_dir(root).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                log.error("WATCH", format!("`{command}` exited with {status}"));
+                succeeded = false;
+            }
+            Err(err) => {
+                log.error("WATCH", format!("failed to run `{command}`: {err}"));
+                succeeded = false;
+            }
+        }
+    }
+
+    ran && succeeded
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        parts.push(component.as_os_str().to_str()?);
+    }
+    let first = parts.first()?;
+    if IGNORED_DIRS.contains(first) {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn generated_directories_are_ignored() {
+        let root = Path::new("/site");
+        assert_eq!(
+            relative_path(root, Path::new("/site/styles/a.css")).as_deref(),
+            Some("styles/a.css")
+        );
+        assert_eq!(
+            relative_path(root, Path::new("/site/html/index.html")),
+            None
+        );
+        assert_eq!(relative_path(root, Path::new("/elsewhere/a.css")), None);
+    }
+
+    #[test]
+    fn matching_change_runs_mapped_command() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("styles")).unwrap();
+        let changed = root.join("styles/site.css");
+        fs::write(&changed, "body{}").unwrap();
+
+        let watch = WatchConfig {
+            commands: BTreeMap::from([
+                (
+                    "styles/**/*.css".to_string(),
+                    "echo built > sentinel.txt".to_string(),
+                ),
+                (
+                    "scripts/*.js".to_string(),
+                    "echo wrong > other.txt".to_string(),
+                ),
+            ]),
+            ..WatchConfig::default()
+        };
+
+        assert!(run_matching_commands(
+            root,
+            &watch,
+            &[changed],
+            Logger::default()
+        ));
+        assert!(root.join("sentinel.txt").exists());
+        assert!(!root.join("other.txt").exists());
+    }
+
+    #[test]
+    fn unmatched_change_runs_nothing() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let watch = WatchConfig {
+            commands: BTreeMap::from([(
+                "styles/*.css".to_string(),
+                "echo built > sentinel.txt".to_string(),
+            )]),
+            ..WatchConfig::default()
+        };
+
+        assert!(!run_matching_commands(
+            root,
+            &watch,
+            &[root.join("posts/a/post.md")],
+            Logger::default()
+        ));
+        assert!(!root.join("sentinel.txt").exists());
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::{Result, bail};
+use serde_yaml::Value;
+
+use super::profile::PROFILES_KEY;
+
+/// Replaces `${NAME}` and `${NAME:-default}` in every string value with the
+/// result of `lookup`. `$${` produces a literal `${`. The `profiles` block is
+/// skipped because only the selected profile, already merged, is used.
+pub(super) fn interpolate_env(
+    value: &mut Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    if let Value::Mapping(map) = value {
+        for (key, item) in map.iter_mut() {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            if key == PROFILES_KEY {
+                continue;
+            }
+            walk(item, key.to_string(), lookup)?;
+        }
+        Ok(())
+    } else {
+        walk(value, String::new(), lookup)
+    }
+}
+
+fn walk(value: &mut Value, path: String, lookup: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    match value {
+        Value::String(text) if text.contains('$') => {
+            *text = expand(text, &path, lookup)?;
+        }
+        Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                walk(item, format!("{path}[{index}]"), lookup)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = match key {
+                    Value::String(key) => key.clone(),
+                    other => serde_yaml::to_string(other)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                };
+                walk(item, format!("{path}.{key}"), lookup)?;
+            }
+        }
+        Value::Tagged(tagged) => walk(&mut tagged.value, path, lookup)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(text: &str, path: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+            continue;
+        }
+        let Some(body) = tail.strip_prefix("${") else {
+            output.push('$');
+            rest = &tail[1..];
+            continue;
+        };
+        let Some(end) = body.find('}') else {
+            bail!("unterminated ${{...}} in {path}");
+        };
+        let expression = &body[..end];
+        let (name, default) = match expression.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expression, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            bail!("invalid environment variable name '{name}' in {path}");
+        }
+        // Like the shell, only `:-` treats an empty value as unset.
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => output.push_str(default),
+            (Some(value), _) => output.push_str(&value),
+            (None, Some(default)) => output.push_str(default),
+            (None, None) => bail!("environment variable {name} is not set (used by {path})"),
+        }
+        rest = &body[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "SITE_URL" => Some("https://prod.example.com".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn interpolate(source: &str) -> Result<Value> {
+        let mut value: Value = serde_yaml::from_str(source).unwrap();
+        interpolate_env(&mut value, &lookup)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn replaces_set_variables_and_defaults() {
+        let value = interpolate(
+            "base_url: ${SITE_URL}\nanalytics:\n  key: \"${ANALYTICS_KEY:-none}\"\n  empty: ${EMPTY:-fallback}\nnote: cost $5 and $${literal}\n",
+        )
+        .unwrap();
+        assert_eq!(value["base_url"].as_str(), Some("https://prod.example.com"));
+        assert_eq!(value["analytics"]["key"].as_str(), Some("none"));
+        assert_eq!(value["analytics"]["empty"].as_str(), Some("fallback"));
+        assert_eq!(value["note"].as_str(), Some("cost $5 and ${literal}"));
+    }
+
+    #[test]
+    fn empty_variables_without_default_expand_to_nothing() {
+        let value = interpolate("title: \"Notes${EMPTY}\"\n").unwrap();
+        assert_eq!(value["title"].as_str(), Some("Notes"));
+    }
+
+    #[test]
+    fn missing_variable_names_key_path() {
+        let error = interpolate("search:\n  languages:\n    - id: ${LANG_ID}\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "environment variable LANG_ID is not set (used by search.languages[0].id)"
+        );
+    }
+
+    #[test]
+    fn profiles_are_left_untouched() {
+        let value = interpolate("profiles:\n  prod:\n    base_url: ${PROD_ONLY}\n").unwrap();
+        assert_eq!(
+            value["profiles"]["prod"]["base_url"].as_str(),
+            Some("${PROD_ONLY}")
+        );
+    }
+}
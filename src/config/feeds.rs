@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct FeedsConfig {
+    /// MIME patterns (`audio/*`, `video/mp4`) of attachments published as
+    /// `<enclosure>` elements.
+    pub enclosure_types: Vec<String>,
+    /// Emit an enclosure for every matching attachment instead of the first.
+    pub multiple_enclosures: bool,
+    /// `<description>` of each tag feed, keyed by tag; the feed title when
+    /// missing.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_descriptions: BTreeMap<String, String>,
+    /// What `item.body` holds in every feed.
+    pub content: FeedContent,
+    /// `content` of individual tag feeds, keyed by tag.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_content: BTreeMap<String, FeedContent>,
+    /// Also write `/updates.xml`, listing the posts whose `updated` date is
+    /// later than their `date`, most recently updated first.
+    pub updates: bool,
+    /// `<language>` of every feed; `search.default_language` when missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// The body of each feed item, usually placed in `<content:encoded>`.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedContent {
+    /// The whole post.
+    #[default]
+    Full,
+    /// Only the excerpt, so readers visit the site for the rest.
+    Excerpt,
+}
+
+impl Default for FeedsConfig {
+    fn default() -> Self {
+        Self {
+            enclosure_types: vec!["audio/*".to_string(), "video/*".to_string()],
+            multiple_enclosures: false,
+            tag_descriptions: BTreeMap::new(),
+            content: FeedContent::Full,
+            tag_content: BTreeMap::new(),
+            updates: false,
+            language: None,
+        }
+    }
+}
+
+impl FeedsConfig {
+    pub fn is_enclosure_type(&self, mime_type: &str) -> bool {
+        self.enclosure_types
+            .iter()
+            .any(|pattern| match pattern.strip_suffix("/*") {
+                Some(top) => mime_type
+                    .split_once('/')
+                    .is_some_and(|(kind, _)| kind.eq_ignore_ascii_case(top)),
+                None => pattern.eq_ignore_ascii_case(mime_type),
+            })
+    }
+}
+
+/// iTunes channel metadata added to the feeds when a `podcast:` block exists.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PodcastConfig {
+    pub author: Option<String>,
+    /// Cover art; site-relative paths are resolved against `base_url`.
+    pub image: Option<String>,
+    pub category: Option<String>,
+}
+
+pub fn validate_feeds_config(config: &FeedsConfig, origin: &Path) -> Result<()> {
+    for pattern in &config.enclosure_types {
+        let valid = pattern
+            .split_once('/')
+            .is_some_and(|(kind, sub)| !kind.is_empty() && !sub.is_empty());
+        if !valid {
+            bail!(
+                "{}: feeds.enclosure_types entry '{}' must look like type/subtype or type/*",
+                origin.display(),
+                pattern
+            );
+        }
+    }
+    if config
+        .language
+        .as_deref()
+        .is_some_and(|language| language.trim().is_empty())
+    {
+        bail!("{}: feeds.language must not be empty", origin.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_enclosures_cover_audio_and_video() {
+        let config = FeedsConfig::default();
+        assert!(config.is_enclosure_type("audio/mpeg"));
+        assert!(config.is_enclosure_type("video/mp4"));
+        assert!(!config.is_enclosure_type("image/png"));
+    }
+
+    #[test]
+    fn exact_patterns_match_whole_type() {
+        let config: FeedsConfig =
+            serde_yaml::from_str("enclosure_types: [application/pdf]\n").unwrap();
+        assert!(config.is_enclosure_type("application/pdf"));
+        assert!(!config.is_enclosure_type("audio/mpeg"));
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        let config: FeedsConfig = serde_yaml::from_str("enclosure_types: [audio]\n").unwrap();
+        let error = validate_feeds_config(&config, Path::new("bckt.yaml")).unwrap_err();
+        assert!(error.to_string().contains("'audio'"));
+    }
+}
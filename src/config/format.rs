@@ -0,0 +1,78 @@
+use std::path::Path;
+
+#[cfg(not(feature = "toml"))]
+use anyhow::bail;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+
+use super::Config;
+
+/// Whether `path` names a TOML config (`bckt.toml`) rather than YAML.
+pub(super) fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+pub(super) fn format_name(path: &Path) -> &'static str {
+    if is_toml(path) { "TOML" } else { "YAML" }
+}
+
+/// Parses a config file into the YAML value tree that profiles and
+/// environment interpolation work on, whatever its format.
+pub(super) fn parse_config(path: &Path, raw: &str) -> Result<Value> {
+    if is_toml(path) {
+        return parse_toml(path, raw);
+    }
+    serde_yaml::from_str(raw).with_context(|| format!("{}: invalid YAML", path.display()))
+}
+
+pub(super) fn serialize_config(path: &Path, config: &Config) -> Result<String> {
+    if is_toml(path) {
+        return serialize_toml(path, config);
+    }
+    Ok(serde_yaml::to_string(config)?)
+}
+
+#[cfg(feature = "toml")]
+fn parse_toml(path: &Path, raw: &str) -> Result<Value> {
+    let table: toml::Table =
+        toml::from_str(raw).with_context(|| format!("{}: invalid TOML", path.display()))?;
+    Ok(toml_to_yaml(toml::Value::Table(table)))
+}
+
+#[cfg(not(feature = "toml"))]
+fn parse_toml(path: &Path, _raw: &str) -> Result<Value> {
+    bail!(
+        "{}: this bckt was built without the toml feature",
+        path.display()
+    )
+}
+
+#[cfg(feature = "toml")]
+fn serialize_toml(path: &Path, config: &Config) -> Result<String> {
+    toml::to_string(config).with_context(|| format!("failed to encode {}", path.display()))
+}
+
+#[cfg(not(feature = "toml"))]
+fn serialize_toml(path: &Path, _config: &Config) -> Result<String> {
+    parse_toml(path, "").map(|_| String::new())
+}
+
+/// TOML dates and times become strings, the form the YAML config uses.
+#[cfg(feature = "toml")]
+fn toml_to_yaml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => Value::Number(number.into()),
+        toml::Value::Float(number) => Value::Number(number.into()),
+        toml::Value::Boolean(flag) => Value::Bool(flag),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Sequence(items.into_iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(table) => Value::Mapping(
+            table
+                .into_iter()
+                .map(|(key, value)| (Value::String(key), toml_to_yaml(value)))
+                .collect(),
+        ),
+    }
+}
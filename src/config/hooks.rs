@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Shell commands run, in order, after `bckt render` succeeds.
+    pub post_render: Vec<String>,
+}
+
+pub fn validate_hooks_config(config: &HooksConfig, origin: &Path) -> Result<()> {
+    if config
+        .post_render
+        .iter()
+        .any(|command| command.trim().is_empty())
+    {
+        bail!(
+            "{}: hooks.post_render entries must not be empty",
+            origin.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_post_render_list() {
+        let config: HooksConfig =
+            serde_yaml::from_str("post_render:\n  - ./scripts/notify.sh\n").unwrap();
+        assert_eq!(config.post_render, vec!["./scripts/notify.sh"]);
+        assert!(validate_hooks_config(&config, Path::new("bckt.yaml")).is_ok());
+    }
+
+    #[test]
+    fn rejects_blank_command() {
+        let config: HooksConfig = serde_yaml::from_str("post_render: [\" \"]\n").unwrap();
+        assert!(validate_hooks_config(&config, Path::new("bckt.yaml")).is_err());
+    }
+}
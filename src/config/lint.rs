@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Shell command run by `bckt check` for each changed post; `{file}` is
+    /// replaced with the post's source path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Number of linter processes allowed to run at once.
+    pub jobs: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            jobs: 4,
+        }
+    }
+}
+
+pub fn validate_lint_config(config: &LintConfig, origin: &Path) -> Result<()> {
+    if let Some(command) = &config.command
+        && !command.contains("{file}")
+    {
+        bail!(
+            "{}: lint.command must contain a {{file}} placeholder",
+            origin.display()
+        );
+    }
+    if config.jobs == 0 {
+        bail!("{}: lint.jobs must be at least 1", origin.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_command_and_defaults_jobs() {
+        let config: LintConfig =
+            serde_yaml::from_str("command: vale --output=line {file}\n").unwrap();
+        assert_eq!(config.command.as_deref(), Some("vale --output=line {file}"));
+        assert_eq!(config.jobs, 4);
+        validate_lint_config(&config, Path::new("bckt.yaml")).unwrap();
+    }
+
+    #[test]
+    fn rejects_command_without_placeholder() {
+        let config: LintConfig = serde_yaml::from_str("command: vale posts\n").unwrap();
+        let error = validate_lint_config(&config, Path::new("bckt.yaml")).unwrap_err();
+        assert!(error.to_string().contains("{file} placeholder"));
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    pub smart_punctuation: bool,
+    pub footnotes: bool,
+    pub tables: bool,
+    pub tasklists: bool,
+    pub strikethrough: bool,
+    /// Add `rel="nofollow noopener"` and `target="_blank"` to links that
+    /// leave the site's host.
+    pub external_nofollow: bool,
+    /// Pass HTML written in Markdown through as it is; when off it is
+    /// escaped and shows up as text.
+    pub allow_raw_html: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            smart_punctuation: false,
+            footnotes: true,
+            tables: true,
+            tasklists: true,
+            strikethrough: true,
+            external_nofollow: false,
+            allow_raw_html: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_previous_behaviour() {
+        let config = MarkdownConfig::default();
+        assert!(!config.smart_punctuation);
+        assert!(config.footnotes);
+        assert!(config.tables);
+        assert!(config.tasklists);
+        assert!(config.strikethrough);
+        assert!(!config.external_nofollow);
+        assert!(config.allow_raw_html);
+    }
+
+    #[test]
+    fn partial_section_keeps_defaults() {
+        let config: MarkdownConfig = serde_yaml::from_str("smart_punctuation: true\n").unwrap();
+        assert!(config.smart_punctuation);
+        assert!(config.footnotes);
+    }
+}
@@ -1,10 +1,30 @@
 mod date_format;
+mod env;
+mod feeds;
+mod format;
+mod hooks;
+mod lint;
+mod markdown;
 mod model;
+mod pages;
+mod postprocess;
+mod profile;
 mod project;
 mod search;
+mod size;
+mod theme;
 mod timezone;
+mod watch;
 
 // Re-export public items
-pub use model::Config;
-pub use project::find_project_root;
-pub use search::{SearchConfig, SearchLanguageConfig};
+pub use feeds::{FeedContent, FeedsConfig, PodcastConfig};
+pub use hooks::HooksConfig;
+pub use lint::LintConfig;
+pub use markdown::MarkdownConfig;
+pub use model::{ArchiveLevels, Config, HomepageSort, TagSlugCollisions};
+pub use pages::PagesConfig;
+pub use postprocess::{PostprocessConfig, Replacement};
+pub use project::{config_path, find_project_root};
+pub use search::{SearchConfig, SearchField, SearchFormat, SearchLanguageConfig};
+pub use theme::theme_defaults_path;
+pub use watch::WatchConfig;
@@ -1,102 +1,636 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use time::UtcOffset;
 use url::Url;
 
+use crate::content::parse_date_field;
+
 use super::date_format::parse_format;
+use super::env::interpolate_env;
+use super::feeds::{FeedsConfig, PodcastConfig, validate_feeds_config};
+use super::format::{format_name, parse_config, serialize_config};
+use super::hooks::{HooksConfig, validate_hooks_config};
+use super::lint::{LintConfig, validate_lint_config};
+use super::markdown::MarkdownConfig;
+use super::pages::{PagesConfig, validate_pages_config};
+use super::postprocess::{PostprocessConfig, validate_postprocess_config};
+use super::profile::apply_profile;
 use super::search::{SearchConfig, validate_search_config};
+use super::size::{deserialize_optional_size, deserialize_size};
+use super::theme::{merge_theme_defaults, theme_dir};
 use super::timezone::parse_timezone;
+use super::watch::{WatchConfig, validate_watch_config};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 #[serde(default)]
 pub struct Config {
     pub title: Option<String>,
+    /// Site description, used for the feed `<description>`; the title when
+    /// unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub base_url: String,
     pub homepage_posts: usize,
+    /// Order of the posts on the homepage and its numbered pages.
+    pub homepage_sort: HomepageSort,
+    /// Pass every post to `index.html` as `all_posts`; off by default because
+    /// the list grows with the site.
+    pub homepage_all_posts: bool,
+    /// Give listing pages (the homepage, tags, and archives) each post's full
+    /// `body`. Turn it off when the templates only show excerpts, to keep a
+    /// copy of every body out of each listing a post appears in.
+    pub listing_full_content: bool,
+    /// Write `html/posts.json`, a listing of every post for external tools.
+    pub posts_json: bool,
+    /// Write `html/manifest.json`, the blake3 hash and size of every output
+    /// file, e.g. to purge changed files from a CDN.
+    pub output_manifest: bool,
+    /// Accept posts without a `date`; they get `undated_date` or the file's
+    /// modification time instead.
+    pub allow_undated: bool,
+    pub undated_date: Option<String>,
+    /// Also load each `.md`/`.html` file directly under `posts/` as a post,
+    /// taking the date and slug from a `YYYY-MM-DD-slug` file name.
+    pub flat_posts: bool,
+    /// Follow symlinked directories below `posts/` when discovering posts.
+    /// A link back to one of its own parents is skipped.
+    pub follow_symlinks: bool,
+    /// Keep rendered post bodies in the cache instead of in memory, and load
+    /// each one back only while writing a page that shows it. Lowers peak
+    /// memory on very large sites at the cost of slower renders.
+    pub low_memory: bool,
+    /// Most files copied into `html/` at once; unset uses the number of CPUs.
+    /// Lower it on network filesystems, or set 1 to copy one file at a time.
+    /// Pages are rendered one at a time whatever the value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    /// Permissions for every file written below `html/` on Unix, as an
+    /// octal string such as `"644"`; unset leaves them to the umask.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_output_mode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub output_mode: Option<String>,
+    /// Run post bodies through an allowlist HTML sanitizer, for sites that
+    /// publish HTML they did not write.
+    pub sanitize_html: bool,
+    /// Run the body of every feed item through the same sanitizer, so feeds
+    /// never carry scripts even when posts do.
+    pub rss_sanitize: bool,
+    /// Remove EXIF, XMP, and similar metadata (such as GPS positions) from
+    /// JPEG, PNG, and WebP attachments when copying them; posts can override
+    /// it with `strip_exif` in their front matter.
+    pub strip_exif: bool,
+    /// Attachments larger than this many bytes produce a warning when copied.
+    #[serde(deserialize_with = "deserialize_size")]
+    pub max_attachment_size: u64,
+    /// Attachments larger than this many bytes fail the render.
+    #[serde(
+        deserialize_with = "deserialize_optional_size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub attachment_size_limit: Option<u64>,
+    /// Files larger than this many bytes cannot be embedded with the
+    /// `inline_file` and `inline_svg` template functions.
+    #[serde(deserialize_with = "deserialize_size")]
+    pub max_inline_size: u64,
     pub date_format: String,
+    /// Marks the end of a post's excerpt in Markdown and HTML bodies; the
+    /// text before it becomes the excerpt. Empty turns the marker off.
+    pub excerpt_separator: String,
+    /// Site author, reported as `author_name` in oEmbed files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Write an `oembed.json` next to every post for link previews.
+    pub oembed: bool,
     pub paginate_tags: bool,
+    /// Posts per tag page when `paginate_tags` is on; unset uses
+    /// `homepage_posts`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags_per_page: Option<usize>,
+    /// Which date archives are written: `year`, `year_month`, or
+    /// `year_month_day`.
+    pub archive_levels: ArchiveLevels,
+    /// Posts per date archive page; unset keeps each archive on a single
+    /// page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_per_page: Option<usize>,
+    /// Where numbered listing page `{n}` is written, relative to its listing:
+    /// `/page/{n}/` gives `/page/2/` and `/tags/rust/page/2/`, while
+    /// `/page/{n}.html` writes plain files.
+    pub pagination_path: String,
+    /// Maps alias tags to the canonical tag they are merged into, matched
+    /// case-insensitively.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_aliases: BTreeMap<String, String>,
+    /// Also replace aliases in `post.tags` instead of only merging tag pages.
+    pub tag_aliases_rewrite: bool,
+    /// What to do when different tags have the same slug, like `C++` and `C#`.
+    pub tag_slug_collisions: TagSlugCollisions,
+    /// Archive slug shared by tags without ASCII letters or digits; empty
+    /// leaves those tags without an archive.
+    pub untagged_slug: String,
+    /// Slug of a tag archive listing the posts without tags; unset writes
+    /// none. Set it to `untagged_slug` to list them with the slugless tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub untagged_posts_slug: Option<String>,
+    /// Transliterate non-ASCII slugs to ASCII (`Δοκιμή` becomes `dokime`)
+    /// instead of dropping those characters.
+    pub slug_transliterate: bool,
+    /// Files or directories outside the project that `bckt dev` may render
+    /// through `/_preview`, relative to the project root or `~`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preview_paths: Vec<String>,
     pub default_timezone: String,
+    /// Reject dates without an offset instead of assuming `default_timezone`.
+    pub require_explicit_timezone: bool,
     pub theme: Option<String>,
+    pub posts_dir: String,
+    pub pages_dir: String,
+    pub static_dir: String,
+    /// Globs of files and directories in `static_dir` and the theme's assets
+    /// that are neither copied nor hashed, on top of hidden entries; `!glob`
+    /// publishes matches again.
+    pub static_ignore: Vec<String>,
+    pub templates_dir: String,
+    /// Templates allowed to render a blank page; any other template that does
+    /// fails the render.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_empty_output: Vec<String>,
+    /// Shared parent directory for incremental caches, relative to the
+    /// project root or `~`; each project gets its own directory below it.
+    /// Unset keeps the cache in `.bckt/cache`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<String>,
     #[serde(default)]
     pub search: SearchConfig,
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    #[serde(default)]
+    pub pages: PagesConfig,
+    #[serde(default)]
+    pub postprocess: PostprocessConfig,
+    #[serde(default)]
+    pub watch: WatchConfig,
+    #[serde(default)]
+    pub feeds: FeedsConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub podcast: Option<PodcastConfig>,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, JsonValue>,
     #[serde(flatten)]
     pub extra: serde_json::Map<String, JsonValue>,
+    /// Keys of `extra` filled in from the theme's `defaults.yaml`; they are
+    /// left out when the config is saved.
+    #[serde(skip)]
+    pub theme_defaults: BTreeSet<String>,
+    /// Slugs other than their own given to colliding tags by
+    /// `tag_slug_collisions: disambiguate`, keyed by lowercased tag. Filled
+    /// in from the posts during a render.
+    #[serde(skip)]
+    pub tag_slugs: BTreeMap<String, String>,
+    /// The name shown for each slug several spellings of tags share. Filled in
+    /// from the posts during a render.
+    #[serde(skip)]
+    pub tag_names: BTreeMap<String, String>,
+}
+
+/// Order of the homepage listing. Heavier posts come first either way.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HomepageSort {
+    /// Newest first.
+    #[default]
+    DateDesc,
+    /// Oldest first.
+    DateAsc,
+    /// Alphabetically by title, or by slug for untitled posts.
+    Title,
+}
+
+/// How tags whose names differ but slug the same are published.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagSlugCollisions {
+    /// One archive, named after the most used tag (then the first
+    /// alphabetically), with a warning.
+    #[default]
+    Merge,
+    /// The most used tag keeps the slug, the others get `-1`, `-2`, ...
+    /// appended, with a warning.
+    Disambiguate,
+    /// Fail the render.
+    Error,
+}
+
+/// The date archives written below `html/`, from coarsest to finest.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveLevels {
+    /// `/YYYY/` only.
+    Year,
+    /// `/YYYY/` and `/YYYY/MM/`.
+    #[default]
+    YearMonth,
+    /// `/YYYY/`, `/YYYY/MM/`, and `/YYYY/MM/DD/`.
+    YearMonthDay,
+}
+
+impl ArchiveLevels {
+    pub fn months(self) -> bool {
+        self != Self::Year
+    }
+
+    pub fn days(self) -> bool {
+        self == Self::YearMonthDay
+    }
 }
 
 impl Config {
     pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_with_profile(path, None)
+    }
+
+    /// Loads the config and, when `profile` is given, overlays `profiles.<name>`
+    /// onto the top-level keys before validation.
+    pub fn load_with_profile(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self> {
+        Self::load_with_env(path, profile, &|name| std::env::var(name).ok())
+    }
+
+    /// Like `load_with_profile`, resolving `${NAME}` references with `lookup`
+    /// instead of the process environment.
+    pub fn load_with_env(
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
         let path = path.as_ref();
         if !path.exists() {
+            if let Some(name) = profile {
+                bail!("{}: profile '{}' is not defined", path.display(), name);
+            }
             return Ok(Self::default());
         }
 
         let raw = fs::read_to_string(path)
             .with_context(|| format!("failed to read config file {}", path.display()))?;
-        let config: Config =
-            serde_yaml::from_str(&raw).with_context(|| invalid_yaml_message(path))?;
+        let mut value = parse_config(path, &raw)?;
+        if let Some(name) = profile {
+            value = apply_profile(value, name).with_context(|| format!("{}", path.display()))?;
+        }
+        interpolate_env(&mut value, lookup).map_err(|err| anyhow!("{}: {err}", path.display()))?;
+        let mut config: Config = if value.is_null() {
+            Config::default()
+        } else {
+            serde_yaml::from_value(value)
+                .with_context(|| format!("{}: invalid {}", path.display(), format_name(path)))?
+        };
+        let root = path.parent().unwrap_or(Path::new("."));
+        config.theme_defaults =
+            merge_theme_defaults(root, config.theme.as_deref(), &mut config.extra)?;
         config.validate(path)?;
         Ok(config)
     }
 
+    /// Replaces `base_url` for one run, such as `bckt render --base-url`,
+    /// checked like the configured value.
+    pub fn override_base_url(&mut self, base_url: &str) -> Result<()> {
+        validate_url(base_url, "--base-url")?;
+        self.base_url = base_url.to_string();
+        Ok(())
+    }
+
+    /// `output_mode` as permission bits.
+    pub fn output_file_mode(&self) -> Option<u32> {
+        self.output_mode.as_deref().and_then(parse_file_mode)
+    }
+
+    pub fn has_profile(&self, name: &str) -> bool {
+        self.profiles.contains_key(name)
+    }
+
     pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
-        let yaml = serde_yaml::to_string(self)?;
-        fs::write(path, yaml)
+        let mut saved = self.clone();
+        for key in &self.theme_defaults {
+            saved.extra.remove(key);
+        }
+        let contents = serialize_config(path, &saved)?;
+        fs::write(path, contents)
             .with_context(|| format!("failed to write config file {}", path.display()))?;
         Ok(())
     }
 
     pub fn validate(&self, origin: &Path) -> Result<()> {
-        validate_url(&self.base_url, origin)?;
+        validate_url(&self.base_url, origin.display())?;
         if self.homepage_posts == 0 {
             bail!(
                 "{}: homepage_posts must be greater than zero",
                 origin.display()
             );
         }
+        if self.tags_per_page == Some(0) {
+            bail!(
+                "{}: tags_per_page must be greater than zero",
+                origin.display()
+            );
+        }
+        if self.archive_per_page == Some(0) {
+            bail!(
+                "{}: archive_per_page must be greater than zero",
+                origin.display()
+            );
+        }
+        validate_pagination_path(&self.pagination_path, origin)?;
         validate_format(&self.date_format, origin)?;
         validate_timezone(&self.default_timezone, origin)?;
+        if let Some(value) = self.undated_date.as_deref() {
+            parse_date_field("undated_date", value, self, origin)?;
+        }
         validate_search_config(&self.search, origin)?;
+        validate_watch_config(&self.watch, origin)?;
+        validate_pages_config(&self.pages, origin)?;
+        validate_postprocess_config(&self.postprocess, origin)?;
+        validate_feeds_config(&self.feeds, origin)?;
+        validate_hooks_config(&self.hooks, origin)?;
+        validate_lint_config(&self.lint, origin)?;
+        validate_tag_aliases(&self.tag_aliases, origin)?;
+        if self.jobs == Some(0) {
+            bail!("{}: jobs must be at least 1", origin.display());
+        }
+        if let Some(mode) = &self.output_mode
+            && parse_file_mode(mode).is_none()
+        {
+            bail!(
+                "{}: output_mode must be an octal file mode such as \"644\", not '{}'",
+                origin.display(),
+                mode
+            );
+        }
+        if let Some(limit) = self.attachment_size_limit
+            && limit < self.max_attachment_size
+        {
+            bail!(
+                "{}: attachment_size_limit must not be smaller than max_attachment_size",
+                origin.display()
+            );
+        }
+        for (key, value) in [
+            ("posts_dir", &self.posts_dir),
+            ("pages_dir", &self.pages_dir),
+            ("static_dir", &self.static_dir),
+            ("templates_dir", &self.templates_dir),
+        ] {
+            if value.trim().is_empty() {
+                bail!("{}: {} must not be empty", origin.display(), key);
+            }
+        }
+        if self
+            .static_ignore
+            .iter()
+            .any(|pattern| pattern.trim_start_matches('!').trim().is_empty())
+        {
+            bail!(
+                "{}: static_ignore patterns must not be empty",
+                origin.display()
+            );
+        }
+        if !self.untagged_slug.is_empty() && !is_valid_slug(&self.untagged_slug) {
+            bail!(
+                "{}: untagged_slug must be lowercase letters, digits, and single dashes, or empty",
+                origin.display()
+            );
+        }
+        if let Some(slug) = &self.untagged_posts_slug
+            && !is_valid_slug(slug)
+        {
+            bail!(
+                "{}: untagged_posts_slug must be lowercase letters, digits, and single dashes",
+                origin.display()
+            );
+        }
+        if self
+            .cache_dir
+            .as_deref()
+            .is_some_and(|dir| dir.trim().is_empty())
+        {
+            bail!("{}: cache_dir must not be empty", origin.display());
+        }
         Ok(())
     }
 
+    pub fn posts_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.posts_dir)
+    }
+
+    pub fn pages_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.pages_dir)
+    }
+
+    pub fn static_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.static_dir)
+    }
+
+    pub fn templates_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.templates_dir)
+    }
+
+    /// The directory of the active theme, if one is set.
+    pub fn theme_path(&self, root: &Path) -> Option<PathBuf> {
+        self.theme
+            .as_deref()
+            .and_then(|theme| theme_dir(root, theme))
+    }
+
     pub fn default_offset(&self) -> Result<UtcOffset> {
         parse_timezone(&self.default_timezone)
     }
+
+    /// The tag `tag` is merged into through `tag_aliases`, or `tag` itself.
+    pub fn canonical_tag<'a>(&'a self, tag: &'a str) -> &'a str {
+        let trimmed = tag.trim();
+        self.tag_aliases
+            .iter()
+            .find(|(alias, _)| alias.trim().eq_ignore_ascii_case(trimmed))
+            .map_or(trimmed, |(_, canonical)| canonical.trim())
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             title: None,
+            description: None,
             base_url: "https://example.com".to_string(),
             homepage_posts: 5,
+            homepage_sort: HomepageSort::default(),
+            homepage_all_posts: false,
+            listing_full_content: true,
+            posts_json: false,
+            output_manifest: false,
+            allow_undated: false,
+            undated_date: None,
+            flat_posts: false,
+            follow_symlinks: false,
+            low_memory: false,
+            jobs: None,
+            output_mode: None,
+            sanitize_html: false,
+            rss_sanitize: false,
+            strip_exif: false,
+            max_attachment_size: 100_000_000,
+            attachment_size_limit: None,
+            max_inline_size: 100_000,
             date_format: "[year]-[month]-[day]".to_string(),
+            excerpt_separator: "<!-- more -->".to_string(),
+            author: None,
+            oembed: false,
             paginate_tags: true,
+            tags_per_page: None,
+            archive_levels: ArchiveLevels::default(),
+            archive_per_page: None,
+            pagination_path: "/page/{n}/".to_string(),
+            tag_aliases: BTreeMap::new(),
+            tag_aliases_rewrite: false,
+            tag_slug_collisions: TagSlugCollisions::default(),
+            untagged_slug: "untagged".to_string(),
+            untagged_posts_slug: None,
+            slug_transliterate: true,
+            preview_paths: Vec::new(),
             default_timezone: "+00:00".to_string(),
+            require_explicit_timezone: false,
             theme: Some("bckt3".to_string()),
+            posts_dir: "posts".to_string(),
+            pages_dir: "pages".to_string(),
+            static_dir: "skel".to_string(),
+            static_ignore: Vec::new(),
+            templates_dir: "templates".to_string(),
+            allow_empty_output: Vec::new(),
+            cache_dir: None,
             search: SearchConfig::default(),
+            markdown: MarkdownConfig::default(),
+            pages: PagesConfig::default(),
+            postprocess: PostprocessConfig::default(),
+            watch: WatchConfig::default(),
+            feeds: FeedsConfig::default(),
+            podcast: None,
+            hooks: HooksConfig::default(),
+            lint: LintConfig::default(),
+            profiles: BTreeMap::new(),
             extra: serde_json::Map::new(),
+            theme_defaults: BTreeSet::new(),
+            tag_slugs: BTreeMap::new(),
+            tag_names: BTreeMap::new(),
         }
     }
 }
 
-fn invalid_yaml_message(path: &Path) -> String {
-    format!("{}: invalid YAML", path.display())
+fn parse_file_mode(value: &str) -> Option<u32> {
+    let digits = value.trim();
+    let digits = digits.strip_prefix("0o").unwrap_or(digits);
+    if digits.is_empty() || !digits.bytes().all(|digit| matches!(digit, b'0'..=b'7')) {
+        return None;
+    }
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+}
+
+/// Takes `output_mode: 644` written without quotes as the string `"644"`.
+fn deserialize_output_mode<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawMode {
+        Text(String),
+        Number(u64),
+    }
+
+    Ok(
+        Option::<RawMode>::deserialize(deserializer)?.map(|raw| match raw {
+            RawMode::Text(text) => text,
+            RawMode::Number(number) => number.to_string(),
+        }),
+    )
 }
 
-fn validate_url(value: &str, origin: &Path) -> Result<()> {
+/// `origin` names where the value came from: the config file, or a flag.
+fn validate_url(value: &str, origin: impl fmt::Display) -> Result<()> {
     if value.trim().is_empty() {
-        bail!("{}: base_url must not be empty", origin.display());
+        bail!("{origin}: base_url must not be empty");
     }
-    let url = Url::parse(value)
-        .with_context(|| format!("{}: base_url must be an absolute URL", origin.display()))?;
+    let url =
+        Url::parse(value).with_context(|| format!("{origin}: base_url must be an absolute URL"))?;
     if !matches!(url.scheme(), "http" | "https") {
-        bail!("{}: base_url must use http or https", origin.display());
+        bail!("{origin}: base_url must use http or https");
+    }
+    Ok(())
+}
+
+/// Numbered pages need a directory of their own below the listing, so
+/// cleanup can tell them apart from everything else, and must end in either
+/// a directory or a file name.
+fn validate_pagination_path(value: &str, origin: &Path) -> Result<()> {
+    let Some((dir, rest)) = value
+        .strip_prefix('/')
+        .and_then(|path| path.split_once("{n}"))
+    else {
+        bail!(
+            "{}: pagination_path must start with '/' and contain {{n}}",
+            origin.display()
+        );
+    };
+    if rest.contains("{n}") {
+        bail!(
+            "{}: pagination_path must contain {{n}} only once",
+            origin.display()
+        );
+    }
+    let literal = value.replacen("{n}", "", 1);
+    if !literal
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/-_.~".contains(c))
+    {
+        bail!(
+            "{}: pagination_path '{}' may only use letters, digits, '/', '-', '_', '.' and '~' besides {{n}}",
+            origin.display(),
+            value
+        );
+    }
+    let Some((parent, _)) = dir.rsplit_once('/') else {
+        bail!(
+            "{}: pagination_path must put {{n}} below a directory, e.g. /page/{{n}}/",
+            origin.display()
+        );
+    };
+    if parent
+        .split('/')
+        .any(|segment| matches!(segment, "" | "." | ".."))
+    {
+        bail!(
+            "{}: pagination_path '{}' has an empty, '.' or '..' segment",
+            origin.display(),
+            value
+        );
+    }
+    if rest != "/" && (rest.is_empty() || rest.contains('/')) {
+        bail!(
+            "{}: pagination_path must end with '/' or a file extension after {{n}}, e.g. /page/{{n}}.html",
+            origin.display()
+        );
     }
     Ok(())
 }
@@ -111,6 +645,42 @@ fn validate_format(value: &str, origin: &Path) -> Result<()> {
     Ok(())
 }
 
+fn validate_tag_aliases(aliases: &BTreeMap<String, String>, origin: &Path) -> Result<()> {
+    for (alias, canonical) in aliases {
+        if alias.trim().is_empty() || canonical.trim().is_empty() {
+            bail!(
+                "{}: tag_aliases entries must not be empty",
+                origin.display()
+            );
+        }
+        // `Rust: rust` only fixes the spelling; anything else is a chain.
+        let chained = aliases.iter().any(|(other, target)| {
+            other.trim().eq_ignore_ascii_case(canonical.trim())
+                && !target.trim().eq_ignore_ascii_case(other.trim())
+        });
+        if chained {
+            bail!(
+                "{}: tag_aliases maps '{}' to '{}', which is itself an alias",
+                origin.display(),
+                alias,
+                canonical
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether `slug` is what a tag slugs to: lowercase ASCII letters and digits
+/// in dash-separated runs.
+fn is_valid_slug(slug: &str) -> bool {
+    slug.split('-').all(|part| {
+        !part.is_empty()
+            && part
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+    })
+}
+
 fn validate_timezone(value: &str, origin: &Path) -> Result<()> {
     parse_timezone(value).with_context(|| {
         format!(
@@ -135,6 +705,22 @@ mod tests {
         assert_eq!(config, Config::default());
     }
 
+    #[test]
+    fn missing_environment_variable_fails_load() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "base_url: ${BCKT_TEST_UNSET_BASE_URL}\n").unwrap();
+
+        let error = Config::load(&path).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}: environment variable BCKT_TEST_UNSET_BASE_URL is not set (used by base_url)",
+                path.display()
+            )
+        );
+    }
+
     #[test]
     fn load_valid_config() {
         let dir = TempDir::new().unwrap();
@@ -144,6 +730,7 @@ mod tests {
             r#"title: "Bucket"
 base_url: "https://example.com/blog"
 homepage_posts: 8
+homepage_sort: title
 paginate_tags: false
 default_timezone: "+05:30"
 "#,
@@ -154,12 +741,104 @@ default_timezone: "+05:30"
         assert_eq!(config.title.as_deref(), Some("Bucket"));
         assert_eq!(config.base_url, "https://example.com/blog");
         assert_eq!(config.homepage_posts, 8);
+        assert_eq!(config.homepage_sort, HomepageSort::Title);
+        assert!(!config.homepage_all_posts);
         assert_eq!(config.date_format, "[year]-[month]-[day]");
         assert!(!config.paginate_tags);
         assert_eq!(config.default_timezone, "+05:30");
         assert_eq!(config.theme.as_deref(), Some("bckt3"));
     }
 
+    #[test]
+    fn validates_pagination_path() {
+        let origin = Path::new("bckt.yaml");
+        for valid in ["/page/{n}/", "/page/{n}.html", "/archive/p{n}/"] {
+            validate_pagination_path(valid, origin).unwrap();
+        }
+        for (invalid, message) in [
+            ("/page/n/", "contain {n}"),
+            ("page/{n}/", "start with '/'"),
+            ("/{n}/", "below a directory"),
+            ("/page/{n}", "end with '/' or a file extension"),
+            ("/page/{n}/{n}/", "only once"),
+            ("/page/{n}/index.html", "end with '/' or a file extension"),
+            ("/../{n}/", "'..' segment"),
+            ("/my page/{n}/", "may only use"),
+        ] {
+            let error = validate_pagination_path(invalid, origin).unwrap_err();
+            assert!(error.to_string().contains(message), "{invalid}: {error}");
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_config_loads_like_yaml() {
+        let dir = TempDir::new().unwrap();
+        let yaml = dir.path().join("bckt.yaml");
+        fs::write(
+            &yaml,
+            "title: Notes\nhomepage_posts: 8\nundated_date: 2024-01-01\nsearch:\n  default_language: en\nextra:\n  links: [a, b]\n",
+        )
+        .unwrap();
+        let toml = dir.path().join("bckt.toml");
+        fs::write(
+            &toml,
+            "title = \"Notes\"\nhomepage_posts = 8\nundated_date = 2024-01-01\n\n[search]\ndefault_language = \"en\"\n\n[extra]\nlinks = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+
+        let from_toml = Config::load(&toml).unwrap();
+        assert_eq!(from_toml.homepage_posts, 8);
+        assert_eq!(from_toml, Config::load(&yaml).unwrap());
+
+        from_toml.save(&toml).unwrap();
+        assert!(
+            fs::read_to_string(&toml)
+                .unwrap()
+                .contains("homepage_posts = 8")
+        );
+        assert_eq!(Config::load(&toml).unwrap(), from_toml);
+    }
+
+    #[test]
+    fn content_directories_resolve_against_root() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "posts_dir: content\nstatic_dir: static\n").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        let root = Path::new("/site");
+        assert_eq!(config.posts_path(root), PathBuf::from("/site/content"));
+        assert_eq!(config.static_path(root), PathBuf::from("/site/static"));
+        assert_eq!(config.pages_path(root), PathBuf::from("/site/pages"));
+        assert_eq!(
+            config.templates_path(root),
+            PathBuf::from("/site/templates")
+        );
+    }
+
+    #[test]
+    fn reject_empty_content_directory() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "posts_dir: \"\"\n").unwrap();
+
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("posts_dir must not be empty"));
+    }
+
+    #[test]
+    fn reject_invalid_untagged_slug() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "untagged_slug: \"Misc Posts\"\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("untagged_slug must be lowercase"));
+
+        fs::write(&path, "untagged_slug: \"\"\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().untagged_slug, "");
+    }
+
     #[test]
     fn save_round_trips_config() {
         let dir = TempDir::new().unwrap();
@@ -210,6 +889,89 @@ homepage_posts: 0
         assert!(format!("{error}").contains("homepage_posts must be greater than zero"));
     }
 
+    #[test]
+    fn output_mode_must_be_octal() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("bckt.yaml");
+        fs::write(&path, "output_mode: 644\n").unwrap();
+        assert_eq!(Config::load(&path).unwrap().output_file_mode(), Some(0o644));
+        fs::write(&path, "output_mode: \"0o2755\"\n").unwrap();
+        assert_eq!(
+            Config::load(&path).unwrap().output_file_mode(),
+            Some(0o2755)
+        );
+        fs::write(&path, "output_mode: \"rw-r--r--\"\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("output_mode must be an octal file mode"));
+        fs::write(&path, "output_mode: \"680\"\n").unwrap();
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn reject_zero_listing_page_sizes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "tags_per_page: 0\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("tags_per_page must be greater than zero"));
+
+        fs::write(&path, "archive_per_page: 0\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("archive_per_page must be greater than zero"));
+
+        fs::write(&path, "tags_per_page: 3\narchive_per_page: 10\n").unwrap();
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.tags_per_page, Some(3));
+        assert_eq!(config.archive_per_page, Some(10));
+    }
+
+    #[test]
+    fn attachment_sizes_accept_units() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(
+            &path,
+            "max_attachment_size: 50MB\nattachment_size_limit: 1 GiB\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.max_attachment_size, 50_000_000);
+        assert_eq!(config.attachment_size_limit, Some(1 << 30));
+
+        fs::write(
+            &path,
+            "max_attachment_size: 2000\nattachment_size_limit: 1000\n",
+        )
+        .unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("attachment_size_limit must not be smaller"));
+    }
+
+    #[test]
+    fn tag_aliases_resolve_case_insensitively() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(
+            &path,
+            "tag_aliases:\n  rustlang: rust\n  Rust-Lang: rust\n  Rust: rust\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.canonical_tag("RustLang"), "rust");
+        assert_eq!(config.canonical_tag("rust-lang"), "rust");
+        assert_eq!(config.canonical_tag("go"), "go");
+
+        fs::write(
+            &path,
+            "tag_aliases:\n  rustlang: rust-lang\n  rust-lang: rust\n",
+        )
+        .unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(format!("{error}").contains("which is itself an alias"));
+    }
+
     #[test]
     fn reject_invalid_date_format() {
         let dir = TempDir::new().unwrap();
@@ -257,4 +1019,143 @@ default_timezone: "Mars/Station"
         let error = Config::load(&path).unwrap_err();
         assert!(format!("{error}").contains("default_timezone"));
     }
+
+    #[test]
+    fn load_markdown_section() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(
+            &path,
+            r#"base_url: "https://example.com"
+markdown:
+  smart_punctuation: true
+  tables: false
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert!(config.markdown.smart_punctuation);
+        assert!(!config.markdown.tables);
+        assert!(config.markdown.footnotes);
+    }
+
+    #[test]
+    fn profile_overlay_takes_precedence() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(
+            &path,
+            r#"base_url: "https://example.com"
+theme: bckt3
+profiles:
+  dev:
+    base_url: "http://localhost:8000"
+  staging:
+    base_url: "https://staging.example.com"
+    theme: plain
+"#,
+        )
+        .unwrap();
+
+        let base = Config::load(&path).unwrap();
+        assert_eq!(base.base_url, "https://example.com");
+        assert!(base.has_profile("dev"));
+
+        let dev = Config::load_with_profile(&path, Some("dev")).unwrap();
+        assert_eq!(dev.base_url, "http://localhost:8000");
+        assert_eq!(dev.theme.as_deref(), Some("bckt3"));
+
+        let staging = Config::load_with_profile(&path, Some("staging")).unwrap();
+        assert_eq!(staging.base_url, "https://staging.example.com");
+        assert_eq!(staging.theme.as_deref(), Some("plain"));
+
+        let error = Config::load_with_profile(&path, Some("prod")).unwrap_err();
+        assert!(format!("{error:#}").contains("profile 'prod'"));
+    }
+
+    #[test]
+    fn profile_values_are_validated() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(
+            &path,
+            r#"base_url: "https://example.com"
+profiles:
+  broken:
+    base_url: "ftp://example.com"
+"#,
+        )
+        .unwrap();
+
+        let error = Config::load_with_profile(&path, Some("broken")).unwrap_err();
+        assert!(format!("{error}").contains("base_url must use http or https"));
+    }
+
+    #[test]
+    fn rejects_invalid_undated_date() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        fs::write(&path, "undated_date: last tuesday\n").unwrap();
+
+        let error = format!("{:#}", Config::load(&path).unwrap_err());
+        assert!(error.contains(&path.display().to_string()), "{error}");
+        assert!(error.contains("undated_date must be RFC3339"), "{error}");
+
+        fs::write(&path, "undated_date: 2024-01-01\n").unwrap();
+        Config::load(&path).unwrap();
+    }
+
+    #[test]
+    fn theme_path_requires_a_plain_directory_name() {
+        let root = Path::new("/site");
+        let mut config = Config {
+            theme: None,
+            ..Config::default()
+        };
+        assert_eq!(config.theme_path(root), None);
+
+        config.theme = Some("plain".to_string());
+        assert_eq!(
+            config.theme_path(root),
+            Some(PathBuf::from("/site/themes/plain"))
+        );
+
+        config.theme = Some("../elsewhere".to_string());
+        assert_eq!(config.theme_path(root), None);
+    }
+
+    #[test]
+    fn theme_defaults_fill_in_unset_keys() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bckt.yaml");
+        let theme_dir = dir.path().join("themes/plain");
+        fs::create_dir_all(&theme_dir).unwrap();
+        fs::write(
+            theme_dir.join("defaults.yaml"),
+            "accent_color: red\nshow_avatar: true\n",
+        )
+        .unwrap();
+        fs::write(&path, "theme: plain\naccent_color: blue\n").unwrap();
+
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.extra["accent_color"], "blue");
+        assert_eq!(config.extra["show_avatar"], true);
+        assert_eq!(
+            config.theme_defaults,
+            BTreeSet::from(["show_avatar".to_string()])
+        );
+
+        config.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("accent_color: blue"));
+        assert!(!saved.contains("show_avatar"));
+
+        fs::write(theme_dir.join("defaults.yaml"), "homepage_posts: 3\n").unwrap();
+        let error = Config::load(&path).unwrap_err();
+        assert!(
+            format!("{error}").contains("'homepage_posts' is a bckt setting"),
+            "{error}"
+        );
+    }
 }
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PagesConfig {
+    /// Glob patterns (relative to `pages/`) of HTML files copied verbatim
+    /// instead of being rendered as templates.
+    pub raw: Vec<String>,
+}
+
+impl PagesConfig {
+    pub fn is_raw(&self, relative: &str) -> bool {
+        self.raw
+            .iter()
+            .any(|pattern| crate::utils::glob_matches(pattern, relative))
+    }
+}
+
+pub fn validate_pages_config(config: &PagesConfig, origin: &Path) -> Result<()> {
+    if config.raw.iter().any(|pattern| pattern.trim().is_empty()) {
+        bail!("{}: pages.raw patterns must not be empty", origin.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_patterns_match_relative_paths() {
+        let config: PagesConfig =
+            serde_yaml::from_str("raw: [\"widgets/**\", \"embed.html\"]\n").unwrap();
+        assert!(config.is_raw("widgets/clock.html"));
+        assert!(config.is_raw("widgets/deep/clock.html"));
+        assert!(config.is_raw("embed.html"));
+        assert!(!config.is_raw("about/index.html"));
+    }
+
+    #[test]
+    fn rejects_blank_pattern() {
+        let config: PagesConfig = serde_yaml::from_str("raw: [\" \"]\n").unwrap();
+        let error = validate_pages_config(&config, Path::new("bckt.yaml")).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("pages.raw patterns must not be empty")
+        );
+    }
+}
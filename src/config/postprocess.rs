@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+/// Rewrites applied to every post body after it is rendered to HTML.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PostprocessConfig {
+    /// Open links to other hosts in a new tab (`target="_blank"`).
+    pub external_links_new_tab: bool,
+    /// `rel` tokens merged into links to other hosts, e.g. `noopener`.
+    pub external_links_rel: Vec<String>,
+    /// Literal text replacements, applied in order outside `<pre>` and
+    /// `<code>`.
+    pub replacements: Vec<Replacement>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Replacement {
+    pub from: String,
+    pub to: String,
+}
+
+pub fn validate_postprocess_config(config: &PostprocessConfig, origin: &Path) -> Result<()> {
+    if let Some(token) = config
+        .external_links_rel
+        .iter()
+        .find(|token| token.is_empty() || token.contains(char::is_whitespace))
+    {
+        bail!(
+            "{}: postprocess.external_links_rel entries must be single words, got '{}'",
+            origin.display(),
+            token
+        );
+    }
+    if config
+        .replacements
+        .iter()
+        .any(|replacement| replacement.from.is_empty())
+    {
+        bail!(
+            "{}: postprocess.replacements entries need a non-empty 'from'",
+            origin.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_replacement_pairs() {
+        let config: PostprocessConfig = serde_yaml::from_str(
+            "external_links_rel: [noopener]\nreplacements:\n  - from: \"(c)\"\n    to: \"©\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.external_links_rel, ["noopener"]);
+        assert_eq!(
+            config.replacements,
+            [Replacement {
+                from: "(c)".to_string(),
+                to: "©".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_blank_replacement_and_spaced_rel() {
+        let origin = Path::new("bckt.yaml");
+        let config: PostprocessConfig =
+            serde_yaml::from_str("replacements:\n  - from: \"\"\n    to: x\n").unwrap();
+        let error = validate_postprocess_config(&config, origin).unwrap_err();
+        assert!(error.to_string().contains("non-empty 'from'"));
+
+        let config: PostprocessConfig =
+            serde_yaml::from_str("external_links_rel: [\"no opener\"]\n").unwrap();
+        let error = validate_postprocess_config(&config, origin).unwrap_err();
+        assert!(error.to_string().contains("single words"));
+    }
+}
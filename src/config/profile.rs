@@ -0,0 +1,79 @@
+use anyhow::{Context, Result, bail};
+use serde_yaml::{Mapping, Value};
+
+pub(super) const PROFILES_KEY: &str = "profiles";
+
+/// Overlays the keys of `profiles.<name>` onto the top-level configuration.
+/// Nested mappings are merged key by key; any other value replaces the base.
+pub(super) fn apply_profile(mut base: Value, name: &str) -> Result<Value> {
+    let overlay = base
+        .get(PROFILES_KEY)
+        .and_then(|profiles| profiles.get(name))
+        .cloned()
+        .with_context(|| format!("profile '{name}' is not defined under profiles"))?;
+
+    let overlay = match overlay {
+        Value::Mapping(map) => map,
+        Value::Null => Mapping::new(),
+        _ => bail!("profile '{name}' must be a mapping of config keys"),
+    };
+
+    if overlay.contains_key(PROFILES_KEY) {
+        bail!("profile '{name}' cannot define nested profiles");
+    }
+
+    let Value::Mapping(target) = &mut base else {
+        bail!("config must be a mapping to apply profile '{name}'");
+    };
+    merge_mapping(target, overlay);
+    Ok(base)
+}
+
+fn merge_mapping(target: &mut Mapping, overlay: Mapping) {
+    for (key, value) in overlay {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Mapping(existing)), Value::Mapping(incoming)) => {
+                merge_mapping(existing, incoming);
+            }
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(source: &str) -> Value {
+        serde_yaml::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn profile_keys_override_base_values() {
+        let base = yaml(
+            "base_url: https://example.com\ntitle: Site\nprofiles:\n  dev:\n    base_url: http://localhost:8000\n",
+        );
+        let merged = apply_profile(base, "dev").unwrap();
+        assert_eq!(merged["base_url"].as_str(), Some("http://localhost:8000"));
+        assert_eq!(merged["title"].as_str(), Some("Site"));
+    }
+
+    #[test]
+    fn nested_sections_merge_per_key() {
+        let base = yaml(
+            "search:\n  asset_path: a.json\n  default_language: en\nprofiles:\n  dev:\n    search:\n      asset_path: b.json\n",
+        );
+        let merged = apply_profile(base, "dev").unwrap();
+        assert_eq!(merged["search"]["asset_path"].as_str(), Some("b.json"));
+        assert_eq!(merged["search"]["default_language"].as_str(), Some("en"));
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let base = yaml("title: Site\n");
+        let error = apply_profile(base, "prod").unwrap_err();
+        assert!(error.to_string().contains("profile 'prod'"));
+    }
+}
@@ -2,18 +2,52 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Result, bail};
 
+use crate::logging;
+
+/// Config file names recognised in a project root, preferred first.
+const CONFIG_FILES: &[&str] = &[
+    "bckt.yaml",
+    #[cfg(feature = "toml")]
+    "bckt.toml",
+];
+
+/// The config file of the project at `root`: `bckt.yaml`, or `bckt.toml` when
+/// only that one exists. A missing config still resolves to `bckt.yaml`,
+/// which loads as the defaults.
+pub fn config_path(root: &Path) -> PathBuf {
+    let mut found = CONFIG_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| path.exists());
+    let Some(path) = found.next() else {
+        return root.join(CONFIG_FILES[0]);
+    };
+    if let Some(ignored) = found.next() {
+        logging::warn(
+            "CONFIG",
+            format!(
+                "Found both {} and {}; using {}",
+                path.display(),
+                ignored.display(),
+                path.display()
+            ),
+        );
+    }
+    path
+}
+
 pub fn find_project_root(start: impl AsRef<Path>) -> Result<PathBuf> {
     let mut current = start.as_ref().to_path_buf();
 
     loop {
-        let candidate = current.join("bckt.yaml");
-        if candidate.exists() {
+        if CONFIG_FILES.iter().any(|name| current.join(name).exists()) {
             return Ok(current);
         }
 
         if !current.pop() {
             bail!(
-                "could not locate bckt.yaml starting from {}",
+                "could not locate {} starting from {}",
+                CONFIG_FILES.join(" or "),
                 start.as_ref().display()
             );
         }
@@ -44,4 +78,22 @@ mod tests {
         let error = find_project_root(dir.path()).unwrap_err();
         assert!(error.to_string().contains("could not locate bckt.yaml"));
     }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_config_marks_the_root_and_yaml_wins() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path();
+        let nested = project.join("posts");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(project.join("bckt.toml"), "title = \"test\"\n").unwrap();
+
+        assert_eq!(find_project_root(&nested).unwrap(), project);
+        assert_eq!(config_path(project), project.join("bckt.toml"));
+
+        fs::write(project.join("bckt.yaml"), "title: test\n").unwrap();
+        let (path, records) = logging::capture::records(|| config_path(project));
+        assert_eq!(path, project.join("bckt.yaml"));
+        assert!(records[0].message.contains("Found both"));
+    }
 }
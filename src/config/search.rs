@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use std::path::Path;
 
 use anyhow::{Result, bail};
+use isolang::Language;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -13,6 +14,75 @@ pub struct SearchConfig {
     pub languages: Vec<SearchLanguageConfig>,
     #[serde(default)]
     pub payload_fields: Vec<String>,
+    #[serde(default)]
+    pub format: SearchFormat,
+    /// Words with fewer characters are left out of each document's
+    /// `content`; words in CJK scripts are always kept.
+    pub min_token_length: usize,
+    /// Text fields written for each document.
+    pub fields: Vec<SearchField>,
+}
+
+/// A text field of the documents in the search index. Without `body`, each
+/// document lists the distinct words of its body as `terms` instead, so
+/// body words stay searchable at a fraction of the size.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    Title,
+    Excerpt,
+    Tags,
+    Body,
+}
+
+impl SearchField {
+    pub const ALL: [SearchField; 4] = [Self::Title, Self::Excerpt, Self::Tags, Self::Body];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Excerpt => "excerpt",
+            Self::Tags => "tags",
+            Self::Body => "body",
+        }
+    }
+}
+
+/// Encoding of the published search index.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SearchFormat {
+    /// Plain JSON at `asset_path`.
+    #[default]
+    Json,
+    /// Gzip-compressed JSON at `asset_path` + `.gz`.
+    JsonGz,
+    /// MessagePack at `asset_path` with its extension replaced by `.msgpack`.
+    Msgpack,
+}
+
+impl SearchFormat {
+    pub const ALL: [SearchFormat; 3] = [Self::Json, Self::JsonGz, Self::Msgpack];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::JsonGz => "json-gz",
+            Self::Msgpack => "msgpack",
+        }
+    }
+
+    /// The published file name for an index configured at `asset_path`.
+    pub fn asset_path(self, asset_path: &str) -> String {
+        match self {
+            Self::Json => asset_path.to_string(),
+            Self::JsonGz => format!("{asset_path}.gz"),
+            Self::Msgpack => {
+                let stem = asset_path.strip_suffix(".json").unwrap_or(asset_path);
+                format!("{stem}.msgpack")
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -20,8 +90,19 @@ pub struct SearchLanguageConfig {
     pub id: String,
     #[serde(default)]
     pub name: Option<String>,
-    #[serde(default)]
-    pub stopwords: Vec<String>,
+    /// Words left out of the index; `None` uses the built-in list for `id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopwords: Option<Vec<String>>,
+}
+
+impl SearchLanguageConfig {
+    /// The configured stopwords, or the built-in ones when none are set.
+    pub fn stopwords(&self) -> Vec<String> {
+        match &self.stopwords {
+            Some(words) => words.clone(),
+            None => default_stopwords(&self.id),
+        }
+    }
 }
 
 impl Default for SearchConfig {
@@ -31,6 +112,9 @@ impl Default for SearchConfig {
             default_language: "en".to_string(),
             languages: default_search_languages(),
             payload_fields: Vec::new(),
+            format: SearchFormat::default(),
+            min_token_length: 2,
+            fields: SearchField::ALL.to_vec(),
         }
     }
 }
@@ -81,6 +165,22 @@ pub fn validate_search_config(config: &SearchConfig, origin: &Path) -> Result<()
         );
     }
 
+    if config.fields.is_empty() {
+        bail!(
+            "{}: search.fields must list at least one field",
+            origin.display()
+        );
+    }
+    for (index, field) in config.fields.iter().enumerate() {
+        if config.fields[..index].contains(field) {
+            bail!(
+                "{}: duplicate entry '{}' in search.fields",
+                origin.display(),
+                field.as_str()
+            );
+        }
+    }
+
     let mut payload_seen = HashSet::new();
     for field in &config.payload_fields {
         let trimmed = field.trim();
@@ -121,81 +221,127 @@ fn default_search_languages() -> Vec<SearchLanguageConfig> {
         SearchLanguageConfig {
             id: "en".to_string(),
             name: Some("English".to_string()),
-            stopwords: default_english_stopwords(),
+            stopwords: None,
         },
         SearchLanguageConfig {
             id: "el".to_string(),
             name: Some("Greek".to_string()),
-            stopwords: default_greek_stopwords(),
+            stopwords: None,
         },
     ]
 }
 
-fn default_english_stopwords() -> Vec<String> {
-    vec![
-        "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "in",
-        "is", "it", "of", "on", "or", "that", "the", "to", "was", "were", "will", "with", "you",
-        "your", "about", "into", "more", "can", "do", "just", "like", "not", "only", "out", "some",
-        "than", "then", "there", "this", "up", "what", "when", "who", "why",
-    ]
-    .into_iter()
-    .map(|word| word.to_string())
-    .collect()
+/// The built-in stopwords for a language id such as `fr`, `fr-CA`, or
+/// `fra`; empty for languages without a list.
+pub fn default_stopwords(id: &str) -> Vec<String> {
+    let primary = id
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let code = match primary.len() {
+        3 => Language::from_639_3(&primary)
+            .and_then(|language| language.to_639_1())
+            .unwrap_or_default(),
+        _ => primary.as_str(),
+    };
+    let words: &[&str] = match code {
+        "en" => ENGLISH_STOPWORDS,
+        "el" => GREEK_STOPWORDS,
+        "fr" => FRENCH_STOPWORDS,
+        "de" => GERMAN_STOPWORDS,
+        "es" => SPANISH_STOPWORDS,
+        "it" => ITALIAN_STOPWORDS,
+        _ => &[],
+    };
+    words.iter().map(|word| word.to_string()).collect()
 }
 
-fn default_greek_stopwords() -> Vec<String> {
-    vec![
-        "και",
-        "να",
-        "σε",
-        "το",
-        "η",
-        "ο",
-        "οι",
-        "τα",
-        "για",
-        "με",
-        "που",
-        "ως",
-        "από",
-        "αυτο",
-        "αυτά",
-        "αυτή",
-        "αυτό",
-        "αυτές",
-        "αυτοί",
-        "αυτών",
-        "είναι",
-        "στο",
-        "στη",
-        "στην",
-        "στον",
-        "τους",
-        "τις",
-        "των",
-        "μια",
-        "μιας",
-        "μιαν",
-        "μου",
-        "σου",
-        "του",
-        "της",
-        "μας",
-        "σας",
-        "αν",
-        "θα",
-        "δε",
-        "δεν",
-        "πως",
-        "ότι",
-        "όπως",
-        "όταν",
-        "όσο",
-    ]
-    .into_iter()
-    .map(|word| word.to_string())
-    .collect()
-}
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "in",
+    "is", "it", "of", "on", "or", "that", "the", "to", "was", "were", "will", "with", "you",
+    "your", "about", "into", "more", "can", "do", "just", "like", "not", "only", "out", "some",
+    "than", "then", "there", "this", "up", "what", "when", "who", "why",
+];
+
+const GREEK_STOPWORDS: &[&str] = &[
+    "και",
+    "να",
+    "σε",
+    "το",
+    "η",
+    "ο",
+    "οι",
+    "τα",
+    "για",
+    "με",
+    "που",
+    "ως",
+    "από",
+    "αυτο",
+    "αυτά",
+    "αυτή",
+    "αυτό",
+    "αυτές",
+    "αυτοί",
+    "αυτών",
+    "είναι",
+    "στο",
+    "στη",
+    "στην",
+    "στον",
+    "τους",
+    "τις",
+    "των",
+    "μια",
+    "μιας",
+    "μιαν",
+    "μου",
+    "σου",
+    "του",
+    "της",
+    "μας",
+    "σας",
+    "αν",
+    "θα",
+    "δε",
+    "δεν",
+    "πως",
+    "ότι",
+    "όπως",
+    "όταν",
+    "όσο",
+];
+
+const FRENCH_STOPWORDS: &[&str] = &[
+    "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "et", "eux", "il",
+    "ils", "je", "la", "le", "les", "leur", "lui", "ma", "mais", "me", "mes", "moi", "mon", "ne",
+    "nos", "notre", "nous", "on", "ou", "par", "pas", "pour", "qu", "que", "qui", "sa", "se",
+    "ses", "son", "sur", "ta", "te", "tes", "toi", "ton", "tu", "un", "une", "vos", "votre",
+    "vous", "est", "sont", "été", "être", "avoir", "cette", "comme", "plus",
+];
+
+const GERMAN_STOPWORDS: &[&str] = &[
+    "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "das", "dass", "dem",
+    "den", "der", "des", "die", "doch", "du", "ein", "eine", "einem", "einen", "einer", "eines",
+    "er", "es", "für", "hat", "ich", "ihr", "im", "in", "ist", "mit", "nach", "nicht", "noch",
+    "nur", "oder", "sich", "sie", "sind", "so", "über", "um", "und", "uns", "von", "vor", "war",
+    "was", "wenn", "wie", "wir", "wird", "zu", "zum", "zur",
+];
+
+const SPANISH_STOPWORDS: &[&str] = &[
+    "a", "al", "como", "con", "de", "del", "el", "ella", "en", "es", "esta", "este", "fue", "ha",
+    "la", "las", "le", "les", "lo", "los", "me", "mi", "más", "no", "nos", "o", "para", "pero",
+    "por", "que", "se", "si", "sin", "su", "sus", "sí", "también", "te", "tu", "un", "una", "unos",
+    "y", "ya", "yo", "son", "está", "hay", "muy", "entre",
+];
+
+const ITALIAN_STOPWORDS: &[&str] = &[
+    "a", "al", "alla", "anche", "che", "chi", "ci", "come", "con", "da", "dal", "dei", "del",
+    "della", "di", "e", "è", "gli", "ha", "i", "il", "in", "io", "la", "le", "lo", "ma", "mi",
+    "ne", "nel", "nella", "non", "o", "per", "più", "quella", "quello", "questa", "questo", "se",
+    "si", "sono", "su", "sua", "suo", "ti", "tra", "tu", "un", "una", "uno",
+];
 
 #[cfg(test)]
 mod tests {
@@ -214,6 +360,46 @@ mod tests {
         assert!(ids.contains(&"en"));
         assert!(ids.contains(&"el"));
         assert!(config.payload_fields.is_empty());
+        assert_eq!(config.min_token_length, 2);
+    }
+
+    #[test]
+    fn configured_languages_get_builtin_stopwords() {
+        let config: SearchConfig = serde_yaml::from_str(
+            "default_language: fr\nlanguages:\n  - id: fr\n  - id: de\n    stopwords: []\n  - id: ita\n",
+        )
+        .unwrap();
+        let french = config.languages[0].stopwords();
+        assert!(!french.is_empty());
+        assert!(french.contains(&"les".to_string()));
+        assert!(config.languages[1].stopwords().is_empty());
+        assert!(
+            config.languages[2]
+                .stopwords()
+                .contains(&"della".to_string())
+        );
+        assert_eq!(default_stopwords("es-MX"), default_stopwords("es"));
+        assert!(default_stopwords("xx").is_empty());
+    }
+
+    #[test]
+    fn parses_and_validates_fields() {
+        let config: SearchConfig = serde_yaml::from_str("fields: [title, excerpt]\n").unwrap();
+        assert_eq!(config.fields, [SearchField::Title, SearchField::Excerpt]);
+        assert_eq!(SearchConfig::default().fields, SearchField::ALL);
+        assert!(serde_yaml::from_str::<SearchConfig>("fields: [summary]\n").is_err());
+
+        let config: SearchConfig = serde_yaml::from_str("fields: [body, body]\n").unwrap();
+        let error = validate_search_config(&config, Path::new("config.yml")).unwrap_err();
+        assert!(error.to_string().contains("duplicate entry 'body'"));
+    }
+
+    #[test]
+    fn parses_search_format() {
+        let config: SearchConfig = serde_yaml::from_str("format: json-gz\n").unwrap();
+        assert_eq!(config.format, SearchFormat::JsonGz);
+        assert_eq!(SearchConfig::default().format, SearchFormat::Json);
+        assert!(serde_yaml::from_str::<SearchConfig>("format: xml\n").is_err());
     }
 
     #[test]
@@ -222,7 +408,7 @@ mod tests {
         config.languages.push(SearchLanguageConfig {
             id: "en".to_string(),
             name: None,
-            stopwords: Vec::new(),
+            stopwords: None,
         });
 
         let error = validate_search_config(&config, Path::new("config.yml")).unwrap_err();
@@ -0,0 +1,74 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use super::model::Config;
+
+/// File in a theme directory listing defaults for the custom keys of
+/// `bckt.yaml`, e.g. `accent_color: "#c33"`.
+const THEME_DEFAULTS_FILE: &str = "defaults.yaml";
+
+/// The directory of `theme` in the project at `root`, or `None` when the
+/// theme name is not a plain directory name.
+pub(super) fn theme_dir(root: &Path, theme: &str) -> Option<PathBuf> {
+    let mut components = Path::new(theme).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Some(root.join("themes").join(theme)),
+        _ => None,
+    }
+}
+
+/// The defaults file of `theme` in the project at `root`, or `None` when the
+/// theme name is not a plain directory name.
+pub fn theme_defaults_path(root: &Path, theme: &str) -> Option<PathBuf> {
+    theme_dir(root, theme).map(|dir| dir.join(THEME_DEFAULTS_FILE))
+}
+
+/// Adds the keys of the theme's defaults file that `extra` lacks, and returns
+/// their names. Keys set in `bckt.yaml` keep their value.
+pub(super) fn merge_theme_defaults(
+    root: &Path,
+    theme: Option<&str>,
+    extra: &mut JsonMap<String, JsonValue>,
+) -> Result<BTreeSet<String>> {
+    let Some(path) = theme.and_then(|theme| theme_defaults_path(root, theme)) else {
+        return Ok(BTreeSet::new());
+    };
+    if !path.is_file() {
+        return Ok(BTreeSet::new());
+    }
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read theme defaults {}", path.display()))?;
+    let defaults = match serde_yaml::from_str::<JsonValue>(&raw)
+        .with_context(|| format!("{}: invalid YAML", path.display()))?
+    {
+        JsonValue::Object(defaults) => defaults,
+        JsonValue::Null => return Ok(BTreeSet::new()),
+        _ => bail!("{}: expected a mapping of keys to values", path.display()),
+    };
+
+    // Keys that deserialize into a bckt setting rather than `extra` are not
+    // the theme's to set.
+    let parsed: Config = serde_json::from_value(JsonValue::Object(defaults.clone()))
+        .with_context(|| format!("{}: invalid theme defaults", path.display()))?;
+    if let Some(key) = defaults.keys().find(|key| !parsed.extra.contains_key(*key)) {
+        bail!(
+            "{}: '{}' is a bckt setting; theme defaults may only set custom keys",
+            path.display(),
+            key
+        );
+    }
+
+    let mut merged = BTreeSet::new();
+    for (key, value) in defaults {
+        if !extra.contains_key(&key) {
+            extra.insert(key.clone(), value);
+            merged.insert(key);
+        }
+    }
+    Ok(merged)
+}
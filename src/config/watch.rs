@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Quiet period collected into one batch before commands run.
+    pub debounce_ms: u64,
+    /// Render the site after a batch whose commands all succeeded.
+    pub rebuild: bool,
+    /// Glob pattern (relative to the project root) mapped to a shell command.
+    pub commands: BTreeMap<String, String>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 300,
+            rebuild: false,
+            commands: BTreeMap::new(),
+        }
+    }
+}
+
+pub fn validate_watch_config(config: &WatchConfig, origin: &Path) -> Result<()> {
+    for (pattern, command) in &config.commands {
+        if pattern.trim().is_empty() {
+            bail!(
+                "{}: watch.commands patterns must not be empty",
+                origin.display()
+            );
+        }
+        if command.trim().is_empty() {
+            bail!(
+                "{}: watch.commands entry '{}' must have a command",
+                origin.display(),
+                pattern
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_command_map() {
+        let config: WatchConfig = serde_yaml::from_str(
+            "rebuild: true\ncommands:\n  \"styles/**/*.css\": npx tailwindcss -o skel/site.css\n",
+        )
+        .unwrap();
+        assert!(config.rebuild);
+        assert_eq!(config.debounce_ms, 300);
+        assert_eq!(
+            config.commands.get("styles/**/*.css").map(String::as_str),
+            Some("npx tailwindcss -o skel/site.css")
+        );
+    }
+
+    #[test]
+    fn rejects_blank_command() {
+        let config: WatchConfig = serde_yaml::from_str("commands:\n  \"*.css\": \"\"\n").unwrap();
+        let error = validate_watch_config(&config, Path::new("bckt.yaml")).unwrap_err();
+        assert!(error.to_string().contains("'*.css' must have a command"));
+    }
+}
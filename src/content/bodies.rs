@@ -0,0 +1,39 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::Post;
+
+/// The parts of a post a [`BodyStore`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyPart {
+    /// `body_html`.
+    Html,
+    /// `search_text`, for the search index, word counts, and descriptions.
+    SearchText,
+}
+
+impl BodyPart {
+    pub const ALL: [BodyPart; 2] = [BodyPart::Html, BodyPart::SearchText];
+}
+
+/// Holds rendered post bodies outside of [`Post`] for `low_memory` builds.
+/// Discovery hands it each body and its search text as soon as the post is
+/// parsed and leaves both empty; renders fetch one back only while writing
+/// something that uses it.
+pub trait BodyStore {
+    fn put(&self, content_path: &Path, part: BodyPart, text: &str) -> Result<()>;
+    fn get(&self, content_path: &Path, part: BodyPart) -> Result<String>;
+}
+
+/// Moves the body and search text of `post` into `bodies`, when there is a
+/// store.
+pub(super) fn stash_body(mut post: Post, bodies: Option<&dyn BodyStore>) -> Result<Post> {
+    if let Some(bodies) = bodies {
+        bodies.put(&post.content_path, BodyPart::Html, &post.body_html)?;
+        bodies.put(&post.content_path, BodyPart::SearchText, &post.search_text)?;
+        post.body_html = String::new();
+        post.search_text = String::new();
+    }
+    Ok(post)
+}
@@ -0,0 +1,39 @@
+use isolang::Language;
+use whatlang::detect;
+
+/// Guesses the language of a post from its plain text, as an ISO 639 code.
+/// Discovery takes one so renders can reuse earlier guesses.
+pub trait LanguageDetector {
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+/// Runs whatlang on every call.
+pub struct Whatlang;
+
+impl LanguageDetector for Whatlang {
+    fn detect(&self, text: &str) -> Option<String> {
+        guess_language(text)
+    }
+}
+
+fn guess_language(body_text: &str) -> Option<String> {
+    let trimmed = body_text.trim();
+    if trimmed.chars().count() < 24 {
+        return None;
+    }
+
+    let info = detect(trimmed)?;
+    if !info.is_reliable() {
+        return None;
+    }
+
+    let iso3 = info.lang().code();
+    if let Some(lang) = Language::from_639_3(iso3) {
+        if let Some(code) = lang.to_639_1() {
+            return Some(code.to_lowercase());
+        }
+        return Some(lang.to_639_3().to_lowercase());
+    }
+
+    Some(iso3.to_lowercase())
+}
@@ -0,0 +1,376 @@
+use std::collections::BTreeMap;
+
+use url::Url;
+
+/// Merges `rel` tokens into any existing `rel` and, with `new_tab`, adds
+/// `target="_blank"` to every `<a>` whose `href` points at another host.
+pub(super) fn mark_external_links(
+    html: &str,
+    site_host: &str,
+    rel: &[&str],
+    new_tab: bool,
+) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = find_tag(rest, "a") {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let Some(end) = tag_end(tail) else {
+            rest = tail;
+            break;
+        };
+        let tag = &tail[..=end];
+        match rewrite_anchor(tag, site_host, rel, new_tab) {
+            Some(rewritten) => output.push_str(&rewritten),
+            None => output.push_str(tag),
+        }
+        rest = &tail[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Offset of the first `<name` tag in `html`, matched case-insensitively.
+fn find_tag(html: &str, name: &str) -> Option<usize> {
+    let bytes = html.as_bytes();
+    let mut offset = 0;
+    while let Some(found) = html[offset..].find('<') {
+        let start = offset + found;
+        let name_end = start + 1 + name.len();
+        let is_tag = bytes
+            .get(start + 1..name_end)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()))
+            && bytes
+                .get(name_end)
+                .is_some_and(|byte| byte.is_ascii_whitespace() || matches!(*byte, b'>' | b'/'));
+        if is_tag {
+            return Some(start);
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+pub(super) fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+struct Attribute<'a> {
+    name: &'a str,
+    value: Option<String>,
+}
+
+fn rewrite_anchor(tag: &str, site_host: &str, rel: &[&str], new_tab: bool) -> Option<String> {
+    let mut attributes = parse_attributes(&tag[2..tag.len() - 1]);
+    let href = attributes
+        .iter()
+        .find(|attr| attr.name.eq_ignore_ascii_case("href"))?
+        .value
+        .as_deref()?;
+    if !is_external(href, site_host) {
+        return None;
+    }
+
+    match attributes
+        .iter_mut()
+        .find(|attr| attr.name.eq_ignore_ascii_case("rel"))
+    {
+        Some(existing) => {
+            let mut tokens: Vec<String> = existing
+                .value
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect();
+            merge_tokens(&mut tokens, rel);
+            existing.value = Some(tokens.join(" "));
+        }
+        None if !rel.is_empty() => {
+            let mut tokens = Vec::new();
+            merge_tokens(&mut tokens, rel);
+            attributes.push(Attribute {
+                name: "rel",
+                value: Some(tokens.join(" ")),
+            });
+        }
+        None => {}
+    }
+    if new_tab
+        && !attributes
+            .iter()
+            .any(|attr| attr.name.eq_ignore_ascii_case("target"))
+    {
+        attributes.push(Attribute {
+            name: "target",
+            value: Some("_blank".to_string()),
+        });
+    }
+
+    Some(write_tag("a", attributes))
+}
+
+fn write_tag(name: &str, attributes: Vec<Attribute<'_>>) -> String {
+    let mut tag = format!("<{name}");
+    for attr in attributes {
+        tag.push(' ');
+        tag.push_str(attr.name);
+        if let Some(value) = attr.value {
+            let quote = if value.contains('"') { '\'' } else { '"' };
+            tag.push('=');
+            tag.push(quote);
+            tag.push_str(&value);
+            tag.push(quote);
+        }
+    }
+    tag.push('>');
+    tag
+}
+
+/// Gives each `<img>` without a non-empty `alt` the text `alts` holds for its
+/// `src`, keyed by attachment path.
+pub(super) fn inject_image_alts(html: &str, alts: &BTreeMap<String, String>) -> String {
+    if alts.is_empty() {
+        return html.to_string();
+    }
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = find_tag(rest, "img") {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        let Some(end) = tag_end(tail) else {
+            rest = tail;
+            break;
+        };
+        let tag = &tail[..=end];
+        let mut attributes = parse_attributes(&tag[4..tag.len() - 1]);
+        let alt = image_src(&attributes).and_then(|src| alts.get(attachment_key(&src)));
+        match alt {
+            Some(alt) if !has_alt(&attributes) => {
+                attributes.retain(|attr| !attr.name.eq_ignore_ascii_case("alt"));
+                attributes.push(Attribute {
+                    name: "alt",
+                    value: Some(escape_attribute(alt)),
+                });
+                output.push_str(&write_tag("img", attributes));
+            }
+            _ => output.push_str(tag),
+        }
+        rest = &tail[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// The `src` of every `<img>` in `html` without a non-empty `alt`, in order.
+pub fn images_without_alt(html: &str) -> Vec<String> {
+    let mut missing = Vec::new();
+    let mut rest = html;
+    while let Some(start) = find_tag(rest, "img") {
+        let tail = &rest[start..];
+        let Some(end) = tag_end(tail) else {
+            break;
+        };
+        let attributes = parse_attributes(&tail[4..end]);
+        if !has_alt(&attributes) {
+            missing.push(image_src(&attributes).unwrap_or_default());
+        }
+        rest = &tail[end + 1..];
+    }
+    missing
+}
+
+/// A path from `attachments_meta` or an image `src`, compared without a
+/// leading `./`.
+pub(super) fn attachment_key(path: &str) -> &str {
+    let path = path.trim();
+    path.strip_prefix("./").unwrap_or(path)
+}
+
+fn image_src(attributes: &[Attribute<'_>]) -> Option<String> {
+    attributes
+        .iter()
+        .find(|attr| attr.name.eq_ignore_ascii_case("src"))?
+        .value
+        .clone()
+}
+
+fn has_alt(attributes: &[Attribute<'_>]) -> bool {
+    attributes.iter().any(|attr| {
+        attr.name.eq_ignore_ascii_case("alt")
+            && attr
+                .value
+                .as_deref()
+                .is_some_and(|value| !value.trim().is_empty())
+    })
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn merge_tokens(tokens: &mut Vec<String>, extra: &[&str]) {
+    for token in extra {
+        if !tokens
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(token))
+        {
+            tokens.push(token.to_string());
+        }
+    }
+}
+
+fn parse_attributes(source: &str) -> Vec<Attribute<'_>> {
+    let mut attributes = Vec::new();
+    let bytes = source.as_bytes();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        while index < bytes.len() && (bytes[index].is_ascii_whitespace() || bytes[index] == b'/') {
+            index += 1;
+        }
+        let name_start = index;
+        while index < bytes.len() && !bytes[index].is_ascii_whitespace() && bytes[index] != b'=' {
+            index += 1;
+        }
+        if name_start == index {
+            break;
+        }
+        let name = &source[name_start..index];
+
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+        if bytes.get(index) != Some(&b'=') {
+            attributes.push(Attribute { name, value: None });
+            continue;
+        }
+        index += 1;
+        while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+            index += 1;
+        }
+
+        let value = match bytes.get(index) {
+            Some(&quote @ (b'"' | b'\'')) => {
+                let start = index + 1;
+                let end = source[start..]
+                    .find(quote as char)
+                    .map_or(source.len(), |offset| start + offset);
+                index = (end + 1).min(source.len());
+                &source[start..end]
+            }
+            _ => {
+                let start = index;
+                while index < bytes.len() && !bytes[index].is_ascii_whitespace() {
+                    index += 1;
+                }
+                &source[start..index]
+            }
+        };
+        attributes.push(Attribute {
+            name,
+            value: Some(value.to_string()),
+        });
+    }
+
+    attributes
+}
+
+fn is_external(href: &str, site_host: &str) -> bool {
+    let href = href.trim();
+    let absolute = if href.starts_with("//") {
+        format!("https:{href}")
+    } else {
+        href.to_string()
+    };
+    match Url::parse(&absolute) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => url
+            .host_str()
+            .is_some_and(|host| !host.eq_ignore_ascii_case(site_host)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOFOLLOW: &[&str] = &["nofollow", "noopener"];
+
+    #[test]
+    fn external_links_gain_rel_and_target() {
+        let html = r#"<p><a href="https://other.org/page">Out</a> and <a href="/2024/01/01/post/">in</a> and <a href="https://example.com/about/">home</a> and <a href="cover.jpg">file</a></p>"#;
+        assert_eq!(
+            mark_external_links(html, "example.com", NOFOLLOW, true),
+            r#"<p><a href="https://other.org/page" rel="nofollow noopener" target="_blank">Out</a> and <a href="/2024/01/01/post/">in</a> and <a href="https://example.com/about/">home</a> and <a href="cover.jpg">file</a></p>"#
+        );
+    }
+
+    #[test]
+    fn existing_rel_and_target_are_preserved() {
+        let html =
+            r#"<a class='x' rel="sponsored NOFOLLOW" target=_self href=//cdn.other.net/x>x</a>"#;
+        assert_eq!(
+            mark_external_links(html, "example.com", NOFOLLOW, true),
+            r#"<a class="x" rel="sponsored NOFOLLOW noopener" target="_self" href="//cdn.other.net/x">x</a>"#
+        );
+    }
+
+    #[test]
+    fn non_anchor_tags_and_schemes_are_ignored() {
+        let html = r#"<abbr title="x">A</abbr><a href="mailto:me@other.org">mail</a><area href="https://other.org">"#;
+        assert_eq!(
+            mark_external_links(html, "example.com", NOFOLLOW, true),
+            html
+        );
+    }
+
+    #[test]
+    fn rel_and_target_are_independent() {
+        let html = r#"<a href="https://other.org/">x</a>"#;
+        assert_eq!(
+            mark_external_links(html, "example.com", &["noopener", "NOOPENER"], false),
+            r#"<a href="https://other.org/" rel="noopener">x</a>"#
+        );
+        assert_eq!(
+            mark_external_links(html, "example.com", &[], true),
+            r#"<a href="https://other.org/" target="_blank">x</a>"#
+        );
+    }
+
+    #[test]
+    fn image_alts_come_from_attachment_text() {
+        let alts = BTreeMap::from([
+            ("images/pic.png".to_string(), "A \"red\" barn".to_string()),
+            ("kept.png".to_string(), "unused".to_string()),
+        ]);
+        let html = r#"<p><img src="./images/pic.png" alt="" /><img src="kept.png" alt="Mine"><img src="other.png"></p>"#;
+        assert_eq!(
+            inject_image_alts(html, &alts),
+            r#"<p><img src="./images/pic.png" alt="A &quot;red&quot; barn"><img src="kept.png" alt="Mine"><img src="other.png"></p>"#
+        );
+    }
+
+    #[test]
+    fn images_without_alt_are_listed() {
+        let html = r#"<img src="a.png" alt="A"><IMG SRC="b.png" alt=" "><img src="c.png"><image src="d.png"><img alt="E">"#;
+        assert_eq!(images_without_alt(html), ["b.png", "c.png"]);
+    }
+}
@@ -1,34 +1,84 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
+use deunicode::deunicode;
 use serde::Deserialize;
 use serde_json::{Map as JsonMap, Value as JsonValue};
 use serde_yaml::Mapping;
 use time::format_description::{self, well_known::Rfc3339};
-use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
 use walkdir::WalkDir;
 
 use crate::config::Config;
 use crate::markdown::{MarkdownRender, render_markdown};
+use crate::timing;
+use crate::utils::glob_matches;
 use isolang::Language;
-use whatlang::detect;
+use shortcodes::ShortcodePost;
+
+mod bodies;
+mod language;
+mod links;
+mod postprocess;
+mod refs;
+mod retag;
+mod sanitize;
+mod shortcodes;
+
+pub use bodies::{BodyPart, BodyStore};
+pub use language::{LanguageDetector, Whatlang};
+pub use links::images_without_alt;
+pub use refs::{PostRef, contains_refs, resolve_page_refs};
+pub use retag::rename_tag;
+pub use sanitize::sanitize_html;
+pub use shortcodes::Shortcodes;
 
 const MAIN_EXTENSIONS: &[&str] = &["md", "html"];
+/// Marks a directory whose every `*.md` file is a separate post.
+const BULK_MARKER: &str = "_bulk.yaml";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Post {
     pub title: Option<String>,
     pub slug: String,
     pub date: OffsetDateTime,
+    pub updated: Option<OffsetDateTime>,
     pub tags: Vec<String>,
     pub post_type: Option<String>,
+    /// Template named in the front matter, used instead of `post.html` or
+    /// `post-<type>.html`.
+    pub template: Option<String>,
     pub abstract_text: Option<String>,
     pub attached: Vec<PathBuf>,
+    pub attached_private: Vec<PathBuf>,
+    /// Alt text and captions from `attachments_meta`, keyed by attachment
+    /// path without a leading `./`.
+    pub attachments_meta: BTreeMap<String, AttachmentText>,
+    /// The `jsonld:` front matter mapping; it has an `@type`.
+    pub jsonld: Option<JsonMap<String, JsonValue>>,
+    /// Front matter override of the `strip_exif` setting.
+    pub strip_exif: Option<bool>,
+    pub featured: bool,
+    /// Listing order ahead of `date`: heavier posts are listed first.
+    pub weight: i64,
+    /// The front matter had no `date`; `date` is synthetic.
+    pub undated: bool,
+    pub classes: Vec<String>,
+    /// Names of the shortcodes expanded in the body, sorted.
+    pub shortcodes: Vec<String>,
+    /// `{{post:...}}` references in the body, in order.
+    pub post_refs: Vec<PostRef>,
+    /// Content files of the posts `post_refs` resolved to, sorted.
+    pub linked_posts: Vec<PathBuf>,
     pub body_html: String,
     pub excerpt: String,
     pub language: String,
+    /// Text direction for `dir`: `rtl`, `ltr`, or `auto`.
+    pub dir: String,
     pub search_text: String,
     pub source_dir: PathBuf,
     pub content_path: PathBuf,
@@ -36,35 +86,72 @@ pub struct Post {
     pub extra: JsonMap<String, JsonValue>,
 }
 
+/// Text describing one attachment, from the `attachments_meta` front matter
+/// map.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AttachmentText {
+    pub alt: Option<String>,
+    pub caption: Option<String>,
+}
+
 #[derive(Debug, Default, Deserialize)]
 #[serde(default)]
 struct FrontMatter {
     pub title: Option<String>,
     pub slug: Option<String>,
     pub date: Option<String>,
+    pub updated: Option<String>,
     #[serde(deserialize_with = "deserialize_string_or_list")]
     pub tags: Vec<String>,
     #[serde(rename = "type")]
     pub post_type: Option<String>,
+    pub template: Option<String>,
     #[serde(rename = "abstract")]
     pub abstract_text: Option<String>,
     pub language: Option<String>,
+    pub dir: Option<String>,
     #[serde(deserialize_with = "deserialize_path_list")]
     pub attached: Vec<PathBuf>,
+    #[serde(deserialize_with = "deserialize_path_list")]
+    pub attached_private: Vec<PathBuf>,
+    pub attachments_meta: BTreeMap<String, AttachmentText>,
+    pub jsonld: Option<Mapping>,
+    pub strip_exif: Option<bool>,
+    pub featured: bool,
+    pub weight: i64,
+    #[serde(deserialize_with = "deserialize_string_or_list")]
+    pub classes: Vec<String>,
     #[serde(flatten)]
     pub extra: Mapping,
 }
 
 pub fn discover_posts(root: impl AsRef<Path>, config: &Config) -> Result<Vec<Post>> {
+    discover_posts_with_shortcodes(root, config, &Shortcodes::builtin(config)?, &Whatlang, None)
+}
+
+/// Loads every post below `root`, oldest first. With `bodies`, each rendered
+/// body goes to the store instead of staying in `body_html`.
+pub fn discover_posts_with_shortcodes(
+    root: impl AsRef<Path>,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    detector: &dyn LanguageDetector,
+    bodies: Option<&dyn BodyStore>,
+) -> Result<Vec<Post>> {
     let root = root.as_ref();
     if !root.exists() {
         bail!("posts directory {} does not exist", root.display());
     }
 
     let mut posts = Vec::new();
+    if config.flat_posts {
+        posts.extend(load_flat_posts(root, config, shortcodes, detector, bodies)?);
+    }
 
-    for entry in WalkDir::new(root)
+    let mut walker = WalkDir::new(root)
         .min_depth(1)
+        .follow_links(config.follow_symlinks)
         .into_iter()
         .filter_entry(|e| {
             // Skip directories that contain .bcktignore file
@@ -73,14 +160,30 @@ pub fn discover_posts(root: impl AsRef<Path>, config: &Config) -> Result<Vec<Pos
             } else {
                 true
             }
-        })
-    {
-        let entry = entry?;
+        });
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            // A symlink to one of its own parents; walkdir does not enter it.
+            Err(err) if err.loop_ancestor().is_some() => continue,
+            entry => entry?,
+        };
         if !entry.file_type().is_dir() {
             continue;
         }
-        match load_post(entry.path(), config)? {
-            Some(post) => posts.push(post),
+        if entry.path().join(BULK_MARKER).is_file() {
+            posts.extend(load_bulk_posts(
+                entry.path(),
+                config,
+                shortcodes,
+                detector,
+                bodies,
+            )?);
+            // Subdirectories of a bulk directory hold attachments, not posts.
+            walker.skip_current_dir();
+            continue;
+        }
+        match load_post(entry.path(), config, shortcodes, detector)? {
+            Some(post) => posts.push(bodies::stash_body(post, bodies)?),
             None => continue,
         }
     }
@@ -89,10 +192,16 @@ pub fn discover_posts(root: impl AsRef<Path>, config: &Config) -> Result<Vec<Pos
         std::cmp::Ordering::Equal => left.slug.cmp(&right.slug),
         other => other,
     });
+    refs::resolve_post_refs(&mut posts, config, bodies)?;
     Ok(posts)
 }
 
-fn load_post(dir: &Path, config: &Config) -> Result<Option<Post>> {
+fn load_post(
+    dir: &Path,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    detector: &dyn LanguageDetector,
+) -> Result<Option<Post>> {
     let mut main_files = Vec::new();
     for entry in
         fs::read_dir(dir).with_context(|| format!("failed to enumerate {}", dir.display()))?
@@ -107,6 +216,20 @@ fn load_post(dir: &Path, config: &Config) -> Result<Option<Post>> {
         return Ok(None);
     }
 
+    // An index.md/index.html is the main file; other siblings are parts.
+    let index_files: Vec<PathBuf> = main_files
+        .iter()
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.eq_ignore_ascii_case("index"))
+        })
+        .cloned()
+        .collect();
+    if !index_files.is_empty() {
+        main_files = index_files;
+    }
+
     if main_files.len() > 1 {
         bail!(
             "{}: expected exactly one main content file, found {}",
@@ -116,30 +239,265 @@ fn load_post(dir: &Path, config: &Config) -> Result<Option<Post>> {
     }
 
     let content_path = main_files.remove(0);
+    load_post_file(
+        dir,
+        content_path,
+        dir.file_name(),
+        None,
+        config,
+        shortcodes,
+        detector,
+    )
+    .map(Some)
+}
+
+/// Loads each `*.md` file in a `_bulk.yaml` directory as its own post, with
+/// the slug defaulting to the file stem and attachments relative to `dir`.
+fn load_bulk_posts(
+    dir: &Path,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    detector: &dyn LanguageDetector,
+    bodies: Option<&dyn BodyStore>,
+) -> Result<Vec<Post>> {
+    let mut files = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed to enumerate {}", dir.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let stem = path.file_stem().map(OsStr::to_os_string);
+            let post = load_post_file(
+                dir,
+                path,
+                stem.as_deref(),
+                None,
+                config,
+                shortcodes,
+                detector,
+            )?;
+            bodies::stash_body(post, bodies)
+        })
+        .collect()
+}
+
+/// Loads each `.md`/`.html` file directly in `posts/` as a post when
+/// `flat_posts` is on. A `YYYY-MM-DD-` file name prefix supplies the date and
+/// the rest of the stem the slug, unless the front matter sets them. Flat
+/// posts share one directory, so they cannot have attachments.
+fn load_flat_posts(
+    root: &Path,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    detector: &dyn LanguageDetector,
+    bodies: Option<&dyn BodyStore>,
+) -> Result<Vec<Post>> {
+    let mut files = Vec::new();
+    for entry in
+        fs::read_dir(root).with_context(|| format!("failed to enumerate {}", root.display()))?
+    {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && is_main_file(&entry.path()) {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let (date, slug) = split_dated_stem(&stem);
+            let post = load_post_file(
+                root,
+                path.clone(),
+                Some(OsStr::new(slug)),
+                date,
+                config,
+                shortcodes,
+                detector,
+            )?;
+            if !post.attached.is_empty() || !post.attached_private.is_empty() {
+                bail!(
+                    "{}: flat posts cannot have attachments; move the post into its own directory",
+                    path.display()
+                );
+            }
+            bodies::stash_body(post, bodies)
+        })
+        .collect()
+}
+
+/// Splits a `YYYY-MM-DD-slug` file stem into its date and slug.
+fn split_dated_stem(stem: &str) -> (Option<&str>, &str) {
+    let bytes = stem.as_bytes();
+    let dated = bytes.len() > 11
+        && bytes[10] == b'-'
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+    if dated {
+        (Some(&stem[..10]), &stem[11..])
+    } else {
+        (None, stem)
+    }
+}
+
+fn load_post_file(
+    dir: &Path,
+    content_path: PathBuf,
+    default_slug: Option<&OsStr>,
+    default_date: Option<&str>,
+    config: &Config,
+    shortcodes: &Shortcodes,
+    detector: &dyn LanguageDetector,
+) -> Result<Post> {
     let raw = fs::read_to_string(&content_path)
         .with_context(|| format!("failed to read {}", content_path.display()))?;
-    let (front, body) = parse_front_matter(&raw).with_context(|| {
+    let (mut front, body, body_line) = parse_front_matter(&raw).with_context(|| {
         format!(
             "{}: missing or invalid front matter",
             content_path.display()
         )
     })?;
+    normalize_front_matter(&mut front, &content_path)?;
 
     let date_str = front
         .date
-        .as_ref()
-        .with_context(|| format!("{}: date is required", content_path.display()))?;
-    let date = parse_post_date(date_str, config, &content_path)?;
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .or(default_date);
+    let undated = date_str.is_none();
+    let date = match date_str {
+        Some(value) => parse_post_date(value, config, &content_path)?,
+        None if config.allow_undated => synthetic_date(&content_path, config)?,
+        None => bail!("{}: date is required", content_path.display()),
+    };
+    let updated = front
+        .updated
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| parse_date_field("updated", value, config, &content_path))
+        .transpose()?;
+    if let Some(updated) = updated
+        && !undated
+        && updated < date
+    {
+        bail!(
+            "{}: updated must not be earlier than date",
+            content_path.display()
+        );
+    }
 
-    let slug = determine_slug(dir, front.slug.as_deref())?;
+    let slug = determine_slug(
+        &content_path,
+        default_slug,
+        front.slug.as_deref(),
+        config.slug_transliterate,
+    )?;
     let permalink = build_permalink(&date, &slug);
 
-    let (body_html, excerpt) = render_body(&content_path, &body)?;
+    let (body, post_refs) = refs::extract_post_refs(&body, &content_path, body_line)?;
+    let expansion = shortcodes.expand(
+        &body,
+        &content_path,
+        body_line,
+        &ShortcodePost {
+            title: front.title.as_deref(),
+            slug: &slug,
+            permalink: &permalink,
+            date_iso: date
+                .format(&Rfc3339)
+                .context("failed to format RFC3339 date")?,
+        },
+    )?;
+    let attachments_meta: BTreeMap<String, AttachmentText> = front
+        .attachments_meta
+        .into_iter()
+        .map(|(path, text)| (links::attachment_key(&path).to_string(), text))
+        .collect();
+    let alts: BTreeMap<String, String> = attachments_meta
+        .iter()
+        .filter_map(|(path, text)| Some((path.clone(), text.alt.clone()?)))
+        .collect();
+
+    let (body_html, excerpt) = render_body(&content_path, &expansion.text, config)?;
+    let mut body_html = links::inject_image_alts(&body_html, &alts);
+    if config.sanitize_html {
+        body_html = sanitize::sanitize_html(&body_html);
+    }
+    let body_html = postprocess::postprocess_body(body_html, config);
     let plain_text = to_plain_text(&body_html);
 
     let post_type = normalize_post_type(front.post_type.as_deref(), &content_path)?;
 
-    let language = determine_language(front.language.as_deref(), &plain_text, config);
+    let attached = expand_attachment_globs(dir, &content_path, front.attached, "attached")?;
+    let attached_private = expand_attachment_globs(
+        dir,
+        &content_path,
+        front.attached_private,
+        "attached_private",
+    )?;
+    if let Some(shared) = attached_private.iter().find(|path| attached.contains(path)) {
+        bail!(
+            "{}: {} cannot be listed in both attached and attached_private",
+            content_path.display(),
+            shared.display()
+        );
+    }
+
+    // Accept HTML-style "a b" lists as well as YAML lists and "a, b".
+    let classes: Vec<String> = front
+        .classes
+        .iter()
+        .flat_map(|class| class.split_whitespace())
+        .map(str::to_string)
+        .collect();
+    if let Some(invalid) = classes.iter().find(|class| {
+        !class
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    }) {
+        bail!(
+            "{}: class '{}' may only contain letters, digits, '-' or '_'",
+            content_path.display(),
+            invalid
+        );
+    }
+
+    let language = determine_language(front.language.as_deref(), &plain_text, config, detector);
+    let direction = match front.dir.as_deref().map(str::trim) {
+        None | Some("") => text_direction(&language).to_string(),
+        Some(direction @ ("ltr" | "rtl" | "auto")) => direction.to_string(),
+        Some(other) => bail!(
+            "{}: dir must be ltr, rtl, or auto, not '{}'",
+            content_path.display(),
+            other
+        ),
+    };
 
     let extras = mapping_to_json_map(&front.extra).with_context(|| {
         format!(
@@ -148,17 +506,46 @@ fn load_post(dir: &Path, config: &Config) -> Result<Option<Post>> {
         )
     })?;
 
+    let jsonld = front
+        .jsonld
+        .as_ref()
+        .map(|mapping| parse_jsonld(mapping, &content_path))
+        .transpose()?;
+
+    let tags = if config.tag_aliases_rewrite {
+        rewrite_tag_aliases(front.tags, config)
+    } else {
+        front.tags
+    };
+
     let post = Post {
         title: front.title,
         slug,
         date,
-        tags: front.tags,
+        updated,
+        tags,
         post_type,
+        template: front
+            .template
+            .map(|template| template.trim().to_string())
+            .filter(|template| !template.is_empty()),
         abstract_text: front.abstract_text,
-        attached: front.attached,
+        attached,
+        attached_private,
+        attachments_meta,
+        jsonld,
+        strip_exif: front.strip_exif,
+        featured: front.featured,
+        weight: front.weight,
+        undated,
+        classes,
+        shortcodes: expansion.used.into_iter().collect(),
+        post_refs,
+        linked_posts: Vec::new(),
         body_html,
         excerpt,
         language,
+        dir: direction,
         search_text: plain_text,
         source_dir: dir.to_path_buf(),
         content_path,
@@ -166,7 +553,70 @@ fn load_post(dir: &Path, config: &Config) -> Result<Option<Post>> {
         extra: extras,
     };
 
-    Ok(Some(post))
+    Ok(post)
+}
+
+/// Replaces each entry of `field` containing `*` or `?` with the files under
+/// `dir` it matches (`**` spans directories), sorted; other entries are kept
+/// as written.
+fn expand_attachment_globs(
+    dir: &Path,
+    content_path: &Path,
+    entries: Vec<PathBuf>,
+    field: &str,
+) -> Result<Vec<PathBuf>> {
+    let mut expanded: Vec<PathBuf> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Some(pattern) = entry.to_str().filter(|value| value.contains(['*', '?'])) else {
+            if !expanded.contains(&entry) {
+                expanded.push(entry);
+            }
+            continue;
+        };
+        let mut matches = Vec::new();
+        for file in WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+            let file = file.with_context(|| format!("failed to enumerate {}", dir.display()))?;
+            if !file.file_type().is_file() || file.path() == content_path {
+                continue;
+            }
+            let relative = file.path().strip_prefix(dir).unwrap_or(file.path());
+            let Some(relative) = relative.to_str() else {
+                continue;
+            };
+            let relative = relative.replace(std::path::MAIN_SEPARATOR, "/");
+            if glob_matches(pattern, &relative) {
+                matches.push(PathBuf::from(relative));
+            }
+        }
+        if matches.is_empty() {
+            bail!(
+                "{}: {field} pattern '{pattern}' matches no files in {}",
+                content_path.display(),
+                dir.display()
+            );
+        }
+        for path in matches {
+            if !expanded.contains(&path) {
+                expanded.push(path);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Replaces aliased tags with their canonical names, dropping duplicates.
+fn rewrite_tag_aliases(tags: Vec<String>, config: &Config) -> Vec<String> {
+    let mut rewritten: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in &tags {
+        let canonical = config.canonical_tag(tag);
+        if !rewritten
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(canonical))
+        {
+            rewritten.push(canonical.to_string());
+        }
+    }
+    rewritten
 }
 
 fn normalize_post_type(value: Option<&str>, origin: &Path) -> Result<Option<String>> {
@@ -194,22 +644,50 @@ fn normalize_post_type(value: Option<&str>, origin: &Path) -> Result<Option<Stri
     Ok(Some(normalized))
 }
 
+/// Date for a post without one: `undated_date` when configured, otherwise the
+/// content file's modification time.
+fn synthetic_date(content_path: &Path, config: &Config) -> Result<OffsetDateTime> {
+    if let Some(value) = config.undated_date.as_deref() {
+        return parse_date_field("undated_date", value, config, content_path);
+    }
+    let modified = fs::metadata(content_path)
+        .and_then(|metadata| metadata.modified())
+        .with_context(|| {
+            format!(
+                "{}: failed to read modification time",
+                content_path.display()
+            )
+        })?;
+    Ok(OffsetDateTime::from(modified))
+}
+
 fn parse_post_date(date_str: &str, config: &Config, origin: &Path) -> Result<OffsetDateTime> {
+    parse_date_field("date", date_str, config, origin)
+}
+
+pub(crate) fn parse_date_field(
+    field: &str,
+    date_str: &str,
+    config: &Config,
+    origin: &Path,
+) -> Result<OffsetDateTime> {
     if let Ok(datetime) = OffsetDateTime::parse(date_str, &Rfc3339) {
         return Ok(datetime);
     }
 
+    // A bare date means midnight in `default_timezone`.
+    let date_format =
+        format_description::parse("[year]-[month]-[day]").expect("static date format to parse");
+    if let Ok(date) = Date::parse(date_str, &date_format) {
+        let offset = naive_date_offset(field, date_str, config, origin)?;
+        return Ok(PrimitiveDateTime::new(date, Time::MIDNIGHT).assume_offset(offset));
+    }
+
     let naive_format = format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]")
         .expect("static datetime format to parse");
 
     if let Ok(datetime) = PrimitiveDateTime::parse(date_str, &naive_format) {
-        let offset = config.default_offset().with_context(|| {
-            format!(
-                "{}: default_timezone '{}' is invalid",
-                origin.display(),
-                config.default_timezone
-            )
-        })?;
+        let offset = naive_date_offset(field, date_str, config, origin)?;
         return Ok(datetime.assume_offset(offset));
     }
 
@@ -221,12 +699,45 @@ fn parse_post_date(date_str: &str, config: &Config, origin: &Path) -> Result<Off
     }
 
     bail!(
-        "{}: date must be RFC3339, 'YYYY-MM-DD HH:MM:SS', or 'YYYY-MM-DD HH:MM:SS ±HHMM/±HH:MM'",
-        origin.display()
+        "{}: {} must be RFC3339, 'YYYY-MM-DD', 'YYYY-MM-DD HH:MM:SS', or 'YYYY-MM-DD HH:MM:SS ±HHMM/±HH:MM'",
+        origin.display(),
+        field
     )
 }
 
-fn determine_language(value: Option<&str>, body_text: &str, config: &Config) -> String {
+/// Offset assumed for a date written without one, unless
+/// `require_explicit_timezone` rejects it.
+fn naive_date_offset(
+    field: &str,
+    date_str: &str,
+    config: &Config,
+    origin: &Path,
+) -> Result<UtcOffset> {
+    if config.require_explicit_timezone {
+        bail!(
+            "{}: {} '{}' has no timezone offset and require_explicit_timezone is set",
+            origin.display(),
+            field,
+            date_str
+        );
+    }
+    config.default_offset().with_context(|| {
+        format!(
+            "{}: default_timezone '{}' is invalid",
+            origin.display(),
+            config.default_timezone
+        )
+    })
+}
+
+/// An explicit front matter language that maps to a known one is used as is;
+/// only otherwise is `detector` asked to guess from the body.
+fn determine_language(
+    value: Option<&str>,
+    body_text: &str,
+    config: &Config,
+    detector: &dyn LanguageDetector,
+) -> String {
     let languages = language_lookup(config);
 
     if let Some(explicit) = value
@@ -235,7 +746,7 @@ fn determine_language(value: Option<&str>, body_text: &str, config: &Config) ->
         return tag;
     }
 
-    if let Some(guessed) = guess_language(body_text)
+    if let Some(guessed) = detector.detect(body_text)
         && let Some(tag) = canonical_language(&guessed, &languages)
     {
         return tag;
@@ -245,6 +756,23 @@ fn determine_language(value: Option<&str>, body_text: &str, config: &Config) ->
         .unwrap_or_else(|| sanitize_language(&config.search.default_language))
 }
 
+/// Languages written right to left, by ISO 639-1 and 639-3 code.
+const RTL_LANGUAGES: &[&str] = &[
+    "ar", "ara", "he", "heb", "fa", "fas", "per", "ur", "urd", "yi", "yid", "ps", "pus", "sd",
+    "snd", "ug", "uig", "dv", "div", "ckb",
+];
+
+/// `rtl` for languages written right to left, going by the primary subtag,
+/// otherwise `ltr`.
+pub fn text_direction(language: &str) -> &'static str {
+    let primary = language.split('-').next().unwrap_or(language);
+    if RTL_LANGUAGES.contains(&primary.to_ascii_lowercase().as_str()) {
+        "rtl"
+    } else {
+        "ltr"
+    }
+}
+
 fn language_lookup(config: &Config) -> BTreeMap<String, String> {
     let mut map = BTreeMap::new();
     for entry in &config.search.languages {
@@ -304,28 +832,6 @@ fn sanitize_language(value: &str) -> String {
     value.trim().replace('_', "-").to_ascii_lowercase()
 }
 
-fn guess_language(body_text: &str) -> Option<String> {
-    let trimmed = body_text.trim();
-    if trimmed.chars().count() < 24 {
-        return None;
-    }
-
-    let info = detect(trimmed)?;
-    if !info.is_reliable() {
-        return None;
-    }
-
-    let iso3 = info.lang().code();
-    if let Some(lang) = Language::from_639_3(iso3) {
-        if let Some(code) = lang.to_639_1() {
-            return Some(code.to_lowercase());
-        }
-        return Some(lang.to_639_3().to_lowercase());
-    }
-
-    Some(iso3.to_lowercase())
-}
-
 fn to_plain_text(html: &str) -> String {
     let mut result = String::with_capacity(html.len());
     let mut in_tag = false;
@@ -399,18 +905,27 @@ fn parse_offset_str(value: &str) -> Result<UtcOffset> {
     bail!("offset '{}' is invalid", value)
 }
 
-fn determine_slug(dir: &Path, provided: Option<&str>) -> Result<String> {
+fn determine_slug(
+    origin: &Path,
+    default: Option<&OsStr>,
+    provided: Option<&str>,
+    transliterate: bool,
+) -> Result<String> {
     let raw = if let Some(value) = provided {
         value
     } else {
-        dir.file_name()
+        default
             .and_then(|value| value.to_str())
-            .with_context(|| format!("{}: directory name not valid utf-8", dir.display()))?
+            .with_context(|| format!("{}: post name not valid utf-8", origin.display()))?
     };
 
-    let candidate = slugify(raw);
+    let candidate = if transliterate {
+        slugify(&deunicode(raw))
+    } else {
+        slugify(raw)
+    };
     if candidate.is_empty() {
-        bail!("{}: slug cannot be empty", dir.display());
+        bail!("{}: slug cannot be empty", origin.display());
     }
     Ok(candidate)
 }
@@ -425,7 +940,9 @@ fn is_main_file(path: &Path) -> bool {
     }
 }
 
-fn parse_front_matter(raw: &str) -> Result<(FrontMatter, String)> {
+/// Splits front matter from the body, also returning the 1-based line number
+/// the body starts on so later errors can point into the file.
+fn parse_front_matter(raw: &str) -> Result<(FrontMatter, String, usize)> {
     let mut lines = raw.lines();
     match lines.next() {
         Some(line) if line.trim() == "---" => {}
@@ -441,11 +958,13 @@ fn parse_front_matter(raw: &str) -> Result<(FrontMatter, String)> {
             } else {
                 serde_yaml::from_str(&yaml)?
             };
+            let mut body_line = yaml_lines.len() + 3;
             let mut body = lines.collect::<Vec<_>>().join("\n");
             if body.starts_with('\n') {
                 body.remove(0);
+                body_line += 1;
             }
-            return Ok((front, body));
+            return Ok((front, body, body_line));
         }
         yaml_lines.push(line);
     }
@@ -453,6 +972,43 @@ fn parse_front_matter(raw: &str) -> Result<(FrontMatter, String)> {
     bail!("front matter not terminated with ---")
 }
 
+/// Trims `title`, `abstract`, `slug`, and tags, and joins the lines of a
+/// multi-line title or abstract with single spaces. `title: ""` marks an
+/// untitled post, but a title of only whitespace is an error.
+fn normalize_front_matter(front: &mut FrontMatter, content_path: &Path) -> Result<()> {
+    fn single_line(text: &str) -> String {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    if let Some(title) = front.title.take().filter(|title| !title.is_empty()) {
+        let title = single_line(&title);
+        if title.is_empty() {
+            bail!("{}: title is blank", content_path.display());
+        }
+        front.title = Some(title);
+    }
+    front.abstract_text = front
+        .abstract_text
+        .take()
+        .map(|text| single_line(&text))
+        .filter(|text| !text.is_empty());
+    front.slug = front
+        .slug
+        .take()
+        .map(|slug| slug.trim().to_string())
+        .filter(|slug| !slug.is_empty());
+    front.tags = std::mem::take(&mut front.tags)
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    Ok(())
+}
+
 fn slugify(value: &str) -> String {
     let mut slug = String::new();
     let mut previous_dash = false;
@@ -539,6 +1095,30 @@ fn mapping_to_json_map(mapping: &Mapping) -> Result<JsonMap<String, JsonValue>>
     Ok(map)
 }
 
+/// Checks the `jsonld:` mapping: it needs an `@type` naming a schema.org
+/// type, or a list of them.
+fn parse_jsonld(mapping: &Mapping, content_path: &Path) -> Result<JsonMap<String, JsonValue>> {
+    let jsonld = mapping_to_json_map(mapping)
+        .with_context(|| format!("{}: invalid jsonld", content_path.display()))?;
+    let valid_type = match jsonld.get("@type") {
+        Some(JsonValue::String(name)) => !name.trim().is_empty(),
+        Some(JsonValue::Array(names)) => {
+            !names.is_empty()
+                && names
+                    .iter()
+                    .all(|name| name.as_str().is_some_and(|name| !name.trim().is_empty()))
+        }
+        _ => false,
+    };
+    if !valid_type {
+        bail!(
+            "{}: jsonld needs an @type, e.g. Event or Recipe",
+            content_path.display()
+        );
+    }
+    Ok(jsonld)
+}
+
 fn build_permalink(date: &OffsetDateTime, slug: &str) -> String {
     format!(
         "/{:04}/{:02}/{:02}/{slug}/",
@@ -548,15 +1128,36 @@ fn build_permalink(date: &OffsetDateTime, slug: &str) -> String {
     )
 }
 
-fn render_body(path: &Path, body: &str) -> Result<(String, String)> {
+/// Renders the body and picks its excerpt: the text before
+/// `excerpt_separator` when the body has one, otherwise the first paragraph
+/// (Markdown) or the start of the text (HTML). Markdown bodies lose the
+/// separator, which would show as text when raw HTML is escaped.
+fn render_body(path: &Path, body: &str, config: &Config) -> Result<(String, String)> {
+    let separator = config.excerpt_separator.as_str();
+    let split = (!separator.is_empty())
+        .then(|| body.split_once(separator))
+        .flatten();
+    let head = split.map(|(head, _)| head);
     match path.extension().and_then(|ext| ext.to_str()) {
         Some(ext) if ext.eq_ignore_ascii_case("md") => {
-            let MarkdownRender { html, excerpt } = render_markdown(body);
+            let _span = timing::span(|| format!("markdown {}", path.display()));
+            let source = match split {
+                Some((head, tail)) => Cow::Owned(format!("{head}{tail}")),
+                None => Cow::Borrowed(body),
+            };
+            let MarkdownRender { html, excerpt } = render_markdown(&source, &config.markdown);
+            let excerpt = match head {
+                Some(head) => to_plain_text(&render_markdown(head, &config.markdown).html),
+                None => excerpt,
+            };
             Ok((html, excerpt))
         }
         Some(ext) if ext.eq_ignore_ascii_case("html") => {
             let clean = body.trim().to_string();
-            let excerpt = excerpt_from_html(&clean);
+            let excerpt = match head {
+                Some(head) => html_text(head),
+                None => excerpt_from_html(&clean),
+            };
             Ok((clean, excerpt))
         }
         _ => bail!("{}: unsupported content extension", path.display()),
@@ -565,20 +1166,7 @@ fn render_body(path: &Path, body: &str) -> Result<(String, String)> {
 
 fn excerpt_from_html(html: &str) -> String {
     const LIMIT: usize = 280;
-    let mut plain = String::with_capacity(html.len());
-    let mut in_tag = false;
-    for ch in html.chars() {
-        match ch {
-            '<' => in_tag = true,
-            '>' => {
-                in_tag = false;
-                plain.push(' ');
-            }
-            _ if !in_tag => plain.push(ch),
-            _ => {}
-        }
-    }
-    let text = plain.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = html_text(html);
     if text.is_empty() {
         return String::new();
     }
@@ -598,5 +1186,23 @@ fn excerpt_from_html(html: &str) -> String {
     excerpt.trim().to_string()
 }
 
+/// The text of `html`, with each tag read as a space.
+fn html_text(html: &str) -> String {
+    let mut plain = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            _ if !in_tag => plain.push(ch),
+            _ => {}
+        }
+    }
+    plain.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 #[cfg(test)]
 mod tests;
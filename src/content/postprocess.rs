@@ -0,0 +1,171 @@
+use std::borrow::Cow;
+
+use url::Url;
+
+use crate::config::{Config, Replacement};
+
+use super::links::{mark_external_links, tag_end};
+
+const NOFOLLOW_REL: &[&str] = &["nofollow", "noopener"];
+
+/// Elements whose text is left alone by `postprocess.replacements`.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "code", "script", "style"];
+
+/// Applies `markdown.external_nofollow` and the `postprocess:` section to a
+/// rendered post body.
+pub(super) fn postprocess_body(html: String, config: &Config) -> String {
+    let settings = &config.postprocess;
+    let mut rel: Vec<&str> = settings
+        .external_links_rel
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let mut new_tab = settings.external_links_new_tab;
+    if config.markdown.external_nofollow {
+        rel.extend_from_slice(NOFOLLOW_REL);
+        new_tab = true;
+    }
+
+    let mut html = html;
+    if (new_tab || !rel.is_empty())
+        && let Some(host) = Url::parse(&config.base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+    {
+        html = mark_external_links(&html, &host, &rel, new_tab);
+    }
+    if !settings.replacements.is_empty() {
+        html = replace_text(&html, &settings.replacements);
+    }
+    html
+}
+
+/// Runs the literal replacements over text nodes only, so tags, attributes,
+/// comments, and everything inside `<pre>`, `<code>`, `<script>`, and
+/// `<style>` are copied unchanged.
+fn replace_text(html: &str, replacements: &[Replacement]) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut verbatim_depth = 0usize;
+
+    while let Some(start) = rest.find('<') {
+        push_text(
+            &mut output,
+            &rest[..start],
+            replacements,
+            verbatim_depth > 0,
+        );
+        let tail = &rest[start..];
+        let end = if tail.starts_with("<!--") {
+            tail.find("-->").map(|offset| offset + 2)
+        } else {
+            tag_end(tail)
+        };
+        let Some(end) = end else {
+            output.push_str(tail);
+            return output;
+        };
+        let tag = &tail[..=end];
+        if let Some((name, closing)) = tag_name(tag)
+            && VERBATIM_ELEMENTS
+                .iter()
+                .any(|element| name.eq_ignore_ascii_case(element))
+        {
+            if closing {
+                verbatim_depth = verbatim_depth.saturating_sub(1);
+            } else if !tag.ends_with("/>") {
+                verbatim_depth += 1;
+            }
+        }
+        output.push_str(tag);
+        rest = &tail[end + 1..];
+    }
+
+    push_text(&mut output, rest, replacements, verbatim_depth > 0);
+    output
+}
+
+/// The element name of an opening or closing tag, and whether it closes.
+fn tag_name(tag: &str) -> Option<(&str, bool)> {
+    let inner = tag.strip_prefix('<')?;
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let end = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    (end > 0).then(|| (&inner[..end], closing))
+}
+
+fn push_text(output: &mut String, text: &str, replacements: &[Replacement], verbatim: bool) {
+    if verbatim {
+        output.push_str(text);
+        return;
+    }
+    let mut text = Cow::Borrowed(text);
+    for replacement in replacements {
+        if text.contains(&replacement.from) {
+            text = Cow::Owned(text.replace(&replacement.from, &replacement.to));
+        }
+    }
+    output.push_str(&text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(from: &str, to: &str) -> Replacement {
+        Replacement {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn code_blocks_and_attributes_are_left_alone() {
+        let html = "<p title=\"(c)\">(c) 2024 <code>(c)</code></p>\n<pre><code class=\"language-rust\">let c = \"(c)\";\n</code></pre>\n<!-- (c) --><p>Done (c)</p>";
+        assert_eq!(
+            replace_text(html, &[replacement("(c)", "©")]),
+            "<p title=\"(c)\">© 2024 <code>(c)</code></p>\n<pre><code class=\"language-rust\">let c = \"(c)\";\n</code></pre>\n<!-- (c) --><p>Done ©</p>"
+        );
+    }
+
+    #[test]
+    fn replacements_apply_in_order() {
+        let html = "<p>Bonjour !</p><script>if (a !== b) {}</script>";
+        assert_eq!(
+            replace_text(
+                html,
+                &[
+                    replacement(" !", "\u{a0}!"),
+                    replacement("Bonjour", "Salut")
+                ]
+            ),
+            "<p>Salut\u{a0}!</p><script>if (a !== b) {}</script>"
+        );
+    }
+
+    #[test]
+    fn external_links_follow_postprocess_settings() {
+        let mut config = Config {
+            base_url: "https://example.com".to_string(),
+            ..Config::default()
+        };
+        config.postprocess.external_links_new_tab = true;
+        config.postprocess.external_links_rel = vec!["noopener".to_string()];
+        let html = "<p><a href=\"https://other.org/\">out</a> <a href=\"/about/\">in</a></p>";
+        assert_eq!(
+            postprocess_body(html.to_string(), &config),
+            "<p><a href=\"https://other.org/\" rel=\"noopener\" target=\"_blank\">out</a> <a href=\"/about/\">in</a></p>"
+        );
+
+        config.postprocess.external_links_new_tab = false;
+        config.markdown.external_nofollow = true;
+        assert!(
+            postprocess_body(html.to_string(), &config)
+                .contains("rel=\"noopener nofollow\" target=\"_blank\"")
+        );
+    }
+}
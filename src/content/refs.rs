@@ -0,0 +1,259 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use crate::config::Config;
+use crate::urls::with_base_path;
+
+use super::{BodyPart, BodyStore, Post};
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const PREFIX: &str = "post:";
+// Stands in for a reference until every post's permalink is known. Letters
+// and digits only, so Markdown, sanitizing, and postprocessing keep it as is.
+const PLACEHOLDER: &str = "BCKTPOSTREF";
+
+/// A `{{post:target}}` reference in a post body, where `target` is a slug or
+/// a dated `YYYY/MM/DD/slug` permalink path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostRef {
+    pub target: String,
+    /// Line of the reference in the content file.
+    pub line: usize,
+}
+
+/// Swaps every reference in a post body for a placeholder that
+/// [`resolve_post_refs`] later replaces with the target's URL.
+pub(super) fn extract_post_refs(
+    body: &str,
+    origin: &Path,
+    first_line: usize,
+) -> Result<(String, Vec<PostRef>)> {
+    let mut refs = Vec::new();
+    let text = replace_refs(body, origin, first_line, |target, line| {
+        refs.push(PostRef {
+            target: target.to_string(),
+            line,
+        });
+        Ok(placeholder(refs.len() - 1))
+    })?;
+    Ok((text, refs))
+}
+
+/// Fills in the URLs of the posts each post references and records their
+/// content files in `linked_posts`. Bodies kept in `bodies` are updated there.
+pub(super) fn resolve_post_refs(
+    posts: &mut [Post],
+    config: &Config,
+    bodies: Option<&dyn BodyStore>,
+) -> Result<()> {
+    let index = PostIndex::new(posts);
+    for post in posts.iter_mut().filter(|post| !post.post_refs.is_empty()) {
+        let mut linked = BTreeSet::new();
+        let mut urls = Vec::with_capacity(post.post_refs.len());
+        for reference in &post.post_refs {
+            let location = format!("{}:{}", post.content_path.display(), reference.line);
+            let target = index.resolve(&reference.target, &location)?;
+            linked.insert(target.content_path.clone());
+            urls.push(with_base_path(&config.base_url, &target.permalink));
+        }
+        match bodies {
+            Some(bodies) => {
+                for part in BodyPart::ALL {
+                    let text = bodies.get(&post.content_path, part)?;
+                    bodies.put(&post.content_path, part, &fill_placeholders(&text, &urls))?;
+                }
+            }
+            None => {
+                post.body_html = fill_placeholders(&post.body_html, &urls);
+                post.search_text = fill_placeholders(&post.search_text, &urls);
+            }
+        }
+        post.excerpt = fill_placeholders(&post.excerpt, &urls);
+        post.linked_posts = linked.into_iter().collect();
+    }
+    Ok(())
+}
+
+/// Replaces the references in a page's source with the targets' URLs.
+pub fn resolve_page_refs(
+    source: &str,
+    origin: &Path,
+    posts: &[Post],
+    config: &Config,
+) -> Result<String> {
+    if !contains_refs(source) {
+        return Ok(source.to_string());
+    }
+    let index = PostIndex::new(posts);
+    replace_refs(source, origin, 1, |target, line| {
+        let location = format!("{}:{}", origin.display(), line);
+        let post = index.resolve(target, &location)?;
+        Ok(with_base_path(&config.base_url, &post.permalink))
+    })
+}
+
+/// Whether `source` has at least one `{{post:...}}` reference.
+pub fn contains_refs(source: &str) -> bool {
+    let mut rest = source;
+    while let Some(found) = rest.find(OPEN) {
+        rest = &rest[found + OPEN.len()..];
+        if rest.trim_start().starts_with(PREFIX) {
+            return true;
+        }
+    }
+    false
+}
+
+fn replace_refs(
+    source: &str,
+    origin: &Path,
+    first_line: usize,
+    mut replace: impl FnMut(&str, usize) -> Result<String>,
+) -> Result<String> {
+    let mut text = String::with_capacity(source.len());
+    let mut cursor = 0;
+
+    while let Some(found) = source[cursor..].find(OPEN) {
+        let start = cursor + found;
+        let inner_start = start + OPEN.len();
+        let Some(target) = source[inner_start..].trim_start().strip_prefix(PREFIX) else {
+            text.push_str(&source[cursor..inner_start]);
+            cursor = inner_start;
+            continue;
+        };
+        text.push_str(&source[cursor..start]);
+
+        let line = first_line + source[..start].matches('\n').count();
+        let Some(length) = target.find(CLOSE) else {
+            bail!(
+                "{}:{line}: post reference is missing its closing }}}}",
+                origin.display()
+            );
+        };
+        let name = target[..length].trim();
+        if name.is_empty() {
+            bail!("{}:{line}: post reference needs a slug", origin.display());
+        }
+
+        text.push_str(&replace(name, line)?);
+        cursor = source.len() - target.len() + length + CLOSE.len();
+    }
+
+    text.push_str(&source[cursor..]);
+    Ok(text)
+}
+
+fn placeholder(index: usize) -> String {
+    format!("{PLACEHOLDER}{index}Z")
+}
+
+fn fill_placeholders(text: &str, urls: &[String]) -> String {
+    if !text.contains(PLACEHOLDER) {
+        return text.to_string();
+    }
+    urls.iter()
+        .enumerate()
+        .fold(text.to_string(), |text, (index, url)| {
+            text.replace(&placeholder(index), url)
+        })
+}
+
+struct IndexedPost {
+    slug: String,
+    permalink: String,
+    content_path: PathBuf,
+}
+
+struct PostIndex {
+    posts: Vec<IndexedPost>,
+}
+
+impl PostIndex {
+    fn new(posts: &[Post]) -> Self {
+        Self {
+            posts: posts
+                .iter()
+                .map(|post| IndexedPost {
+                    slug: post.slug.clone(),
+                    permalink: post.permalink.clone(),
+                    content_path: post.content_path.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The post `target` names, by slug or by its permalink path.
+    fn resolve(&self, target: &str, location: &str) -> Result<&IndexedPost> {
+        let path = target.trim_matches('/');
+        let matches: Vec<&IndexedPost> = self
+            .posts
+            .iter()
+            .filter(|post| post.slug == target || post.permalink.trim_matches('/') == path)
+            .collect();
+        match matches.as_slice() {
+            [] => bail!("{location}: post:{target} does not match any post"),
+            [post] => Ok(post),
+            [first, ..] => {
+                let permalinks: Vec<&str> =
+                    matches.iter().map(|post| post.permalink.as_str()).collect();
+                bail!(
+                    "{location}: post:{target} matches several posts ({}); use a dated reference such as post:{}",
+                    permalinks.join(", "),
+                    first.permalink.trim_matches('/')
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_become_placeholders_with_their_lines() {
+        let (text, refs) = extract_post_refs(
+            "See [it]({{post:hello}}).\n\n{{< youtube x >}} and {{ post: 2024/01/02/other }}\n",
+            Path::new("post.md"),
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "See [it](BCKTPOSTREF0Z).\n\n{{< youtube x >}} and BCKTPOSTREF1Z\n"
+        );
+        assert_eq!(
+            refs,
+            vec![
+                PostRef {
+                    target: "hello".to_string(),
+                    line: 4
+                },
+                PostRef {
+                    target: "2024/01/02/other".to_string(),
+                    line: 6
+                },
+            ]
+        );
+        assert_eq!(
+            fill_placeholders(&text, &["/a/".to_string(), "/b/".to_string()]),
+            "See [it](/a/).\n\n{{< youtube x >}} and /b/\n"
+        );
+    }
+
+    #[test]
+    fn malformed_references_report_their_location() {
+        let error = extract_post_refs("one\n{{post:hello", Path::new("post.md"), 3).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "post.md:4: post reference is missing its closing }}"
+        );
+        let error = extract_post_refs("{{post: }}", Path::new("post.md"), 1).unwrap_err();
+        assert_eq!(error.to_string(), "post.md:1: post reference needs a slug");
+        assert!(!contains_refs("{{ site.title }} {{< note >}}"));
+        assert!(contains_refs("{{ site.title }} {{ post:hello }}"));
+    }
+}
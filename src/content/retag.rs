@@ -0,0 +1,234 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Result, bail};
+use serde_yaml::Value as YamlValue;
+
+use super::parse_front_matter;
+
+/// Replaces the tag `old`, matched case-insensitively, with `new` in the
+/// front matter of the post source `raw`, leaving every other byte as it
+/// was. Returns `None` when the post lacks the tag; a post that already has
+/// `new` keeps a single copy.
+pub fn rename_tag(raw: &str, old: &str, new: &str) -> Result<Option<String>> {
+    let new = new.trim();
+    if new.is_empty() {
+        bail!("the new tag name is empty");
+    }
+    let (front, _, _) = parse_front_matter(raw)?;
+    if !front.tags.iter().any(|tag| same_tag(tag, old)) {
+        return Ok(None);
+    }
+
+    // The front matter parsed, so it opens with `---` and is closed by one.
+    let lines: Vec<&str> = raw.split_inclusive('\n').collect();
+    let end = lines
+        .iter()
+        .skip(1)
+        .position(|line| line.trim() == "---")
+        .map_or(lines.len(), |index| index + 1);
+    let Some(key) = (1..end).find(|&index| lines[index].starts_with("tags:")) else {
+        bail!("tags are not set by a top-level `tags:` key; edit them by hand");
+    };
+
+    let mut output: Vec<String> = lines[..key].iter().map(|line| line.to_string()).collect();
+    let (value, eol) = split_eol(&lines[key]["tags:".len()..]);
+    let mut next = key + 1;
+    if value.trim().is_empty() {
+        // A block list, one `- tag` per line.
+        output.push(lines[key].to_string());
+        let mut items = Vec::new();
+        while next < end {
+            let (line, eol) = split_eol(lines[next]);
+            let indent = line.len() - line.trim_start().len();
+            let Some(item) = line[indent..].strip_prefix('-') else {
+                break;
+            };
+            items.push((&line[..=indent], item, eol));
+            next += 1;
+        }
+        let renamed = rename_items(
+            &items.iter().map(|item| item.1).collect::<Vec<_>>(),
+            old,
+            new,
+        );
+        for ((prefix, _, eol), item) in items.iter().zip(renamed) {
+            if let Some(item) = item {
+                output.push(format!("{prefix}{item}{eol}"));
+            }
+        }
+    } else {
+        let lead = &value[..value.len() - value.trim_start().len()];
+        let trimmed = value.trim();
+        let (inner, open, close) = match trimmed
+            .strip_prefix('[')
+            .and_then(|inner| inner.strip_suffix(']'))
+        {
+            Some(inner) => (inner, "[", "]"),
+            None => (trimmed, "", ""),
+        };
+        let items: Vec<&str> = inner.split(',').collect();
+        let renamed = rename_items(&items, old, new);
+        let list = if renamed.iter().all(Option::is_some) {
+            renamed.into_iter().flatten().collect::<Vec<_>>().join(",")
+        } else {
+            renamed
+                .iter()
+                .flatten()
+                .map(|item| item.trim())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        output.push(format!("tags:{lead}{open}{list}{close}{eol}"));
+    }
+    output.extend(lines[next..].iter().map(|line| line.to_string()));
+    let rewritten = output.concat();
+
+    // Quoted commas, comments, and other YAML this does not follow are
+    // caught here rather than written out.
+    let expected = unique_tags(front.tags.iter().map(|tag| {
+        if same_tag(tag, old) {
+            new
+        } else {
+            tag.as_str()
+        }
+    }));
+    let matches = parse_front_matter(&rewritten)
+        .is_ok_and(|(after, _, _)| unique_tags(after.tags.iter().map(String::as_str)) == expected);
+    if !matches {
+        bail!("cannot rewrite this tags list automatically; edit it by hand");
+    }
+    Ok(Some(rewritten))
+}
+
+fn same_tag(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+fn unique_tags<'a>(tags: impl Iterator<Item = &'a str>) -> BTreeSet<String> {
+    tags.map(|tag| tag.trim().to_lowercase()).collect()
+}
+
+/// Splits the line ending off `line`.
+fn split_eol(line: &str) -> (&str, &str) {
+    let content = line.trim_end_matches(['\r', '\n']);
+    (content, &line[content.len()..])
+}
+
+/// Renames the matching list items, keeping their spacing and quotes. Only
+/// the first match is kept, and none when another item is already `new`.
+fn rename_items(items: &[&str], old: &str, new: &str) -> Vec<Option<String>> {
+    let tags: Vec<String> = items.iter().map(|item| unquote(item.trim())).collect();
+    let has_new = tags
+        .iter()
+        .any(|tag| !same_tag(tag, old) && same_tag(tag, new));
+    let mut renamed = false;
+    items
+        .iter()
+        .zip(&tags)
+        .map(|(item, tag)| {
+            if !same_tag(tag, old) {
+                return Some(item.to_string());
+            }
+            if has_new || renamed {
+                return None;
+            }
+            renamed = true;
+            Some(replace_scalar(item, new))
+        })
+        .collect()
+}
+
+fn unquote(scalar: &str) -> String {
+    if let Some(inner) = scalar
+        .strip_prefix('\'')
+        .and_then(|inner| inner.strip_suffix('\''))
+    {
+        inner.replace("''", "'")
+    } else if let Some(inner) = scalar
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+    {
+        inner.replace("\\\"", "\"").replace("\\\\", "\\")
+    } else {
+        scalar.to_string()
+    }
+}
+
+fn replace_scalar(item: &str, new: &str) -> String {
+    let start = item.len() - item.trim_start().len();
+    let end = start + item.trim().len();
+    let current = &item[start..end];
+    let scalar = if current.starts_with('\'') {
+        format!("'{}'", new.replace('\'', "''"))
+    } else if current.starts_with('"') || !is_plain(new) {
+        format!("\"{}\"", new.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        new.to_string()
+    };
+    format!("{}{scalar}{}", &item[..start], &item[end..])
+}
+
+/// Whether `tag` reads back as the same string without quotes, also inside
+/// a `[...]` list.
+fn is_plain(tag: &str) -> bool {
+    !tag.contains([',', '[', ']', '{', '}', '#', ':', '"', '\''])
+        && matches!(
+            serde_yaml::from_str::<YamlValue>(tag),
+            Ok(YamlValue::String(parsed)) if parsed == tag
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_flow_lists_and_block_lists() {
+        let flow = "---\ntitle: A\ntags: [Rust-Lang, web]\n---\nrust-lang stays\n";
+        assert_eq!(
+            rename_tag(flow, "rust-lang", "rust").unwrap().unwrap(),
+            "---\ntitle: A\ntags: [rust, web]\n---\nrust-lang stays\n"
+        );
+
+        let block = "---\r\ntags:\r\n  - web\r\n  - 'rust-lang'\r\ndate: 2024-01-01\r\n---\r\nBody";
+        assert_eq!(
+            rename_tag(block, "Rust-Lang", "rust: the language")
+                .unwrap()
+                .unwrap(),
+            "---\r\ntags:\r\n  - web\r\n  - 'rust: the language'\r\ndate: 2024-01-01\r\n---\r\nBody"
+        );
+
+        let csv = "---\ntags: rust-lang, web\n---\n";
+        assert_eq!(
+            rename_tag(csv, "rust-lang", "systems").unwrap().unwrap(),
+            "---\ntags: systems, web\n---\n"
+        );
+        assert!(rename_tag(csv, "rust-lang", "2024").is_err());
+    }
+
+    #[test]
+    fn keeps_one_copy_when_the_new_tag_exists() {
+        let raw = "---\ntags: [rust-lang, web, Rust]\n---\n";
+        assert_eq!(
+            rename_tag(raw, "rust-lang", "rust").unwrap().unwrap(),
+            "---\ntags: [web, Rust]\n---\n"
+        );
+
+        let block = "---\ntags:\n- rust\n- rust-lang\n---\n";
+        assert_eq!(
+            rename_tag(block, "rust-lang", "rust").unwrap().unwrap(),
+            "---\ntags:\n- rust\n---\n"
+        );
+    }
+
+    #[test]
+    fn leaves_untagged_posts_and_refuses_unusual_yaml() {
+        assert!(
+            rename_tag("---\ntags: [web]\n---\n", "rust", "go")
+                .unwrap()
+                .is_none()
+        );
+        let error = rename_tag("---\ntags: [\"a, b\", rust]\n---\n", "a, b", "c").unwrap_err();
+        assert!(error.to_string().contains("edit it by hand"));
+    }
+}
@@ -0,0 +1,45 @@
+use ammonia::Builder;
+
+/// Strips scripts, event handlers, and other markup outside an allowlist from
+/// a post body when `sanitize_html` is on, and from feed items when
+/// `rss_sanitize` is. Relative `src`/`href` values are
+/// kept so attachments still resolve, and the classes and ids that Markdown
+/// rendering emits (code languages, alerts, footnotes) survive.
+pub fn sanitize_html(html: &str) -> String {
+    Builder::default()
+        .link_rel(None)
+        .add_generic_attributes(["class", "id"])
+        .add_generic_attribute_prefixes(["data-"])
+        .add_tags(["input", "video", "audio", "source", "iframe"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("video", ["src", "controls", "poster", "width", "height"])
+        .add_tag_attributes("audio", ["src", "controls"])
+        .add_tag_attributes("source", ["src", "type"])
+        .add_tag_attributes(
+            "iframe",
+            [
+                "src",
+                "title",
+                "width",
+                "height",
+                "allow",
+                "allowfullscreen",
+            ],
+        )
+        .clean(html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_handlers_and_keeps_markdown_markup() {
+        let html = "<p onclick=\"steal()\"><img src=\"cover.jpg\" onerror=\"x()\"><a href=\"#fn-1\" id=\"fnref-1\" data-footnote-ref>1</a></p><pre><code class=\"language-rust\">fn main() {}</code></pre><a href=\"javascript:alert(1)\">x</a>";
+        assert_eq!(
+            sanitize_html(html),
+            "<p><img src=\"cover.jpg\"><a href=\"#fn-1\" id=\"fnref-1\" data-footnote-ref=\"\">1</a></p><pre><code class=\"language-rust\">fn main() {}</code></pre><a>x</a>"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use minijinja::Environment;
+use minijinja::value::Value;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+
+const OPEN: &str = "{{<";
+const CLOSE: &str = ">}}";
+
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "youtube",
+        r#"{% set video = id if id is defined else args[0] %}<div class="video video--youtube"><iframe src="https://www.youtube-nocookie.com/embed/{{ video }}" title="{{ title | default("YouTube video") }}" loading="lazy" allow="accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture" allowfullscreen></iframe></div>"#,
+    ),
+    (
+        "figure",
+        r#"{% set source = src if src is defined else args[0] %}<figure><img src="{{ source }}" alt="{{ alt | default("") }}">{% if caption is defined %}<figcaption>{{ caption }}</figcaption>{% endif %}</figure>"#,
+    ),
+];
+
+/// Shortcode templates available to posts: the built-in set plus any
+/// `templates/shortcodes/<name>.html` files, which take precedence.
+pub struct Shortcodes {
+    env: Environment<'static>,
+    digests: BTreeMap<String, String>,
+}
+
+/// Post fields exposed to shortcode templates as `post`.
+#[derive(Serialize)]
+pub(super) struct ShortcodePost<'a> {
+    pub(super) title: Option<&'a str>,
+    pub(super) slug: &'a str,
+    pub(super) permalink: &'a str,
+    pub(super) date_iso: String,
+}
+
+pub(super) struct Expansion {
+    pub(super) text: String,
+    pub(super) used: BTreeSet<String>,
+}
+
+impl Shortcodes {
+    pub fn builtin(config: &Config) -> Result<Self> {
+        let mut env = crate::template::environment(config)?;
+        let mut digests = BTreeMap::new();
+        for (name, source) in BUILTIN {
+            let template_name = Box::leak(format!("{name}.html").into_boxed_str());
+            env.add_template(template_name, source)
+                .with_context(|| format!("failed to register built-in shortcode {name}"))?;
+            digests.insert(name.to_string(), digest(source));
+        }
+        Ok(Self { env, digests })
+    }
+
+    pub fn load(config: &Config, dir: &Path) -> Result<Self> {
+        let mut shortcodes = Self::builtin(config)?;
+        if !dir.exists() {
+            return Ok(shortcodes);
+        }
+
+        for entry in WalkDir::new(dir).min_depth(1).max_depth(1) {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || !path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("html"))
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("failed to read shortcode {}", path.display()))?;
+            shortcodes.digests.insert(name.to_string(), digest(&source));
+            let template_name = Box::leak(format!("{name}.html").into_boxed_str());
+            let template_source = Box::leak(source.into_boxed_str());
+            shortcodes
+                .env
+                .add_template(template_name, template_source)
+                .with_context(|| format!("failed to register shortcode {}", path.display()))?;
+        }
+
+        Ok(shortcodes)
+    }
+
+    /// Content hash of the named shortcode template, used to invalidate the
+    /// posts that reference it.
+    pub fn digest(&self, name: &str) -> Option<&str> {
+        self.digests.get(name).map(String::as_str)
+    }
+
+    /// Replaces every `{{< name args >}}` in `source` with its rendered
+    /// template. `{{</* ... */>}}` is emitted literally as `{{< ... >}}`.
+    pub(super) fn expand(
+        &self,
+        source: &str,
+        origin: &Path,
+        first_line: usize,
+        post: &ShortcodePost<'_>,
+    ) -> Result<Expansion> {
+        let mut text = String::with_capacity(source.len());
+        let mut used = BTreeSet::new();
+        let mut cursor = 0;
+
+        while let Some(found) = source[cursor..].find(OPEN) {
+            let start = cursor + found;
+            text.push_str(&source[cursor..start]);
+            let line = first_line + source[..start].matches('\n').count();
+            let location = format!("{}:{}", origin.display(), line);
+
+            let inner_start = start + OPEN.len();
+            let Some(length) = source[inner_start..].find(CLOSE) else {
+                bail!("{location}: shortcode is missing its closing >}}}}");
+            };
+            let inner = &source[inner_start..inner_start + length];
+            cursor = inner_start + length + CLOSE.len();
+
+            if let Some(literal) = inner
+                .trim()
+                .strip_prefix("/*")
+                .and_then(|rest| rest.strip_suffix("*/"))
+            {
+                text.push_str(OPEN);
+                text.push_str(literal);
+                text.push_str(CLOSE);
+                continue;
+            }
+
+            let invocation = parse_invocation(inner).map_err(|err| anyhow!("{location}: {err}"))?;
+            let template = self
+                .env
+                .get_template(&format!("{}.html", invocation.name))
+                .map_err(|_| anyhow!("{location}: unknown shortcode '{}'", invocation.name))?;
+
+            let mut context: BTreeMap<String, Value> = invocation
+                .named
+                .into_iter()
+                .map(|(key, value)| (key, Value::from(value)))
+                .collect();
+            context.insert("args".to_string(), Value::from(invocation.positional));
+            context.insert("post".to_string(), Value::from_serialize(post));
+
+            let rendered = template.render(Value::from(context)).map_err(|err| {
+                anyhow!(
+                    "{location}: failed to render shortcode '{}': {err}",
+                    invocation.name
+                )
+            })?;
+            text.push_str(rendered.trim());
+            used.insert(invocation.name);
+        }
+
+        text.push_str(&source[cursor..]);
+        Ok(Expansion { text, used })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Invocation {
+    name: String,
+    named: BTreeMap<String, String>,
+    positional: Vec<String>,
+}
+
+/// Parses `name key="value" bare 'quoted'` into a name, named and positional
+/// arguments.
+fn parse_invocation(inner: &str) -> Result<Invocation> {
+    let mut chars = inner.trim().chars().peekable();
+
+    let name: String = std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+    if name.is_empty() {
+        bail!("shortcode name is missing");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        bail!("invalid shortcode name '{name}'");
+    }
+
+    let mut named = BTreeMap::new();
+    let mut positional = Vec::new();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+
+        if first == '"' || first == '\'' {
+            positional.push(read_quoted(&mut chars)?);
+            continue;
+        }
+
+        let token: String =
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '=')).collect();
+        if chars.next_if_eq(&'=').is_some() {
+            let value = match chars.peek() {
+                Some('"') | Some('\'') => read_quoted(&mut chars)?,
+                _ => std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect(),
+            };
+            if token.is_empty() {
+                bail!("argument name is missing before '='");
+            }
+            named.insert(token, value);
+        } else {
+            positional.push(token);
+        }
+    }
+
+    Ok(Invocation {
+        name,
+        named,
+        positional,
+    })
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String> {
+    let quote = chars.next().expect("caller peeked a quote");
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => value.push(escaped),
+                None => break,
+            },
+            c if c == quote => return Ok(value),
+            c => value.push(c),
+        }
+    }
+    bail!("unterminated {quote} in shortcode arguments")
+}
+
+fn digest(source: &str) -> String {
+    blake3::hash(source.as_bytes()).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn post() -> ShortcodePost<'static> {
+        ShortcodePost {
+            title: Some("Example"),
+            slug: "example",
+            permalink: "/2024/01/01/example/",
+            date_iso: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_named_and_positional_arguments() {
+        let invocation =
+            parse_invocation(r#" figure cover.jpg caption="A \"quoted\" caption" alt='Alt' wide "#)
+                .unwrap();
+        assert_eq!(invocation.name, "figure");
+        assert_eq!(invocation.positional, vec!["cover.jpg", "wide"]);
+        assert_eq!(
+            invocation.named.get("caption").map(String::as_str),
+            Some(r#"A "quoted" caption"#)
+        );
+        assert_eq!(invocation.named.get("alt").map(String::as_str), Some("Alt"));
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        let error = parse_invocation(r#"figure caption="open"#).unwrap_err();
+        assert!(error.to_string().contains("unterminated"));
+    }
+
+    #[test]
+    fn expands_builtin_youtube() {
+        let shortcodes = Shortcodes::builtin(&Config::default()).unwrap();
+        let expansion = shortcodes
+            .expand(
+                "Intro\n\n{{< youtube dQw4w9WgXcQ >}}\n",
+                Path::new("post.md"),
+                1,
+                &post(),
+            )
+            .unwrap();
+        assert!(
+            expansion
+                .text
+                .contains("https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ")
+        );
+        assert!(expansion.text.starts_with("Intro\n\n<div"));
+        assert_eq!(
+            expansion.used.into_iter().collect::<Vec<_>>(),
+            vec!["youtube"]
+        );
+    }
+
+    #[test]
+    fn unknown_shortcode_names_file_and_line() {
+        let shortcodes = Shortcodes::builtin(&Config::default()).unwrap();
+        let error = shortcodes
+            .expand(
+                "one\ntwo\n{{< vimeo 123 >}}\n",
+                Path::new("posts/a/post.md"),
+                5,
+                &post(),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "posts/a/post.md:7: unknown shortcode 'vimeo'"
+        );
+    }
+
+    #[test]
+    fn escaped_shortcodes_are_literal() {
+        let shortcodes = Shortcodes::builtin(&Config::default()).unwrap();
+        let expansion = shortcodes
+            .expand("{{</* youtube id */>}}", Path::new("post.md"), 1, &post())
+            .unwrap();
+        assert_eq!(expansion.text, "{{< youtube id >}}");
+        assert!(expansion.used.is_empty());
+    }
+
+    #[test]
+    fn project_templates_override_builtins() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("youtube.html"),
+            "<lite-youtube videoid=\"{{ args[0] }}\" data-post=\"{{ post.slug }}\"></lite-youtube>",
+        )
+        .unwrap();
+
+        let shortcodes = Shortcodes::load(&Config::default(), temp.path()).unwrap();
+        let expansion = shortcodes
+            .expand("{{< youtube abc >}}", Path::new("post.md"), 1, &post())
+            .unwrap();
+        assert_eq!(
+            expansion.text,
+            "<lite-youtube videoid=\"abc\" data-post=\"example\"></lite-youtube>"
+        );
+        assert_ne!(
+            shortcodes.digest("youtube"),
+            Shortcodes::builtin(&Config::default())
+                .unwrap()
+                .digest("youtube")
+        );
+    }
+}
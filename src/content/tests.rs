@@ -1,5 +1,5 @@
 use super::*;
-use crate::config::Config;
+use crate::config::{Config, Replacement};
 use std::path::PathBuf;
 use tempfile::TempDir;
 use time::UtcOffset;
@@ -42,6 +42,36 @@ fn prefer_slug_from_front_matter() {
     assert_eq!(posts[0].slug, "custom-slug");
 }
 
+#[test]
+fn greek_slug_is_transliterated() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    fs::create_dir_all(root.join("Δοκιμή")).unwrap();
+    fs::write(
+        root.join("Δοκιμή/post.md"),
+        "---\ndate: 2024-03-04T00:00:00Z\n---\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("greek")).unwrap();
+    fs::write(
+        root.join("greek/post.md"),
+        "---\ndate: 2024-03-05T00:00:00Z\nslug: Καλημέρα κόσμε\n---\n",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    let mut slugs: Vec<_> = discover_posts(&root, &config)
+        .unwrap()
+        .into_iter()
+        .map(|post| post.slug)
+        .collect();
+    slugs.sort();
+    assert_eq!(slugs, vec!["dokime", "kalemera-kosme"]);
+
+    config.slug_transliterate = false;
+    assert!(discover_posts(&root, &config).is_err());
+}
+
 #[test]
 fn parse_full_front_matter_payload() {
     let dir = TempDir::new().unwrap();
@@ -95,6 +125,261 @@ fn reject_duplicate_main_files() {
     assert!(format!("{error}").contains("expected exactly one"));
 }
 
+#[test]
+fn index_file_wins_over_sibling_parts() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/with-parts");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("index.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nMain body",
+    )
+    .unwrap();
+    fs::write(root.join("notes.md"), "Scratch notes without front matter").unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].content_path, root.join("index.md"));
+    assert_eq!(posts[0].slug, "with-parts");
+    assert_eq!(posts[0].excerpt, "Main body");
+}
+
+#[test]
+fn reject_index_md_and_index_html_together() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/both");
+    fs::create_dir_all(&root).unwrap();
+    for name in ["index.md", "index.html"] {
+        fs::write(root.join(name), "---\ndate: 2024-01-01T00:00:00Z\n---\n").unwrap();
+    }
+
+    let config = Config::default();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(format!("{error}").contains("expected exactly one"));
+}
+
+#[test]
+fn external_links_get_nofollow_when_enabled() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/links");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\n[out](https://other.org/) [home](https://example.com/about/) [rel](/tags/rust/)",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert!(!posts[0].body_html.contains("nofollow"));
+
+    config.markdown.external_nofollow = true;
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    let body = &posts[0].body_html;
+    assert!(body.contains(
+        "<a href=\"https://other.org/\" rel=\"nofollow noopener\" target=\"_blank\">out</a>"
+    ));
+    assert!(body.contains("<a href=\"https://example.com/about/\">home</a>"));
+    assert!(body.contains("<a href=\"/tags/rust/\">rel</a>"));
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_symlinks_discovers_linked_post_directories() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    let elsewhere = dir.path().join("elsewhere");
+    fs::create_dir_all(&root).unwrap();
+    fs::create_dir_all(elsewhere.join("linked")).unwrap();
+    fs::write(
+        elsewhere.join("linked/post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nLinked",
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(&elsewhere, root.join("external")).unwrap();
+    // A link back up the tree must not send discovery in circles.
+    std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+
+    let mut config = Config::default();
+    assert!(discover_posts(&root, &config).unwrap().is_empty());
+
+    config.follow_symlinks = true;
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].slug, "linked");
+    assert_eq!(posts[0].body_html.trim(), "<p>Linked</p>");
+}
+
+#[test]
+fn flat_posts_take_date_and_slug_from_file_name() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    fs::create_dir_all(root.join("nested")).unwrap();
+    fs::write(
+        root.join("2024-05-06-hello-flat.md"),
+        "---\ntitle: Flat\n---\nFlat body",
+    )
+    .unwrap();
+    fs::write(
+        root.join("nested/post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nNested",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].slug, "nested");
+
+    config.flat_posts = true;
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts.len(), 2);
+    let flat = &posts[1];
+    assert_eq!(flat.slug, "hello-flat");
+    assert_eq!(
+        flat.date,
+        OffsetDateTime::parse("2024-05-06T00:00:00Z", &Rfc3339).unwrap()
+    );
+    assert_eq!(flat.permalink, "/2024/05/06/hello-flat/");
+    assert!(!flat.undated);
+
+    fs::write(
+        root.join("2024-05-07-with-image.md"),
+        "---\nattached: [cover.jpg]\n---\nBody",
+    )
+    .unwrap();
+    let error = discover_posts(&root, &config).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("flat posts cannot have attachments"),
+        "{error:#}"
+    );
+}
+
+#[test]
+fn sanitize_html_strips_scripts_when_enabled() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/guest");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("cover.jpg"), "jpeg").unwrap();
+    fs::write(
+        root.join("post.html"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [cover.jpg]\n---\n<p>Hi<script>alert(1)</script></p><img src=\"cover.jpg\" onerror=\"alert(2)\">",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert!(posts[0].body_html.contains("<script>alert(1)</script>"));
+
+    config.sanitize_html = true;
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    let body = &posts[0].body_html;
+    assert!(!body.contains("script"), "{body}");
+    assert!(!body.contains("onerror"), "{body}");
+    assert!(body.contains("<img src=\"cover.jpg\">"), "{body}");
+}
+
+#[test]
+fn postprocess_replacements_skip_code_blocks() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/typography");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nCopyright (c) me, see `(c)`.\n\n```\n(c) stays\n```\n",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    config.postprocess.replacements = vec![Replacement {
+        from: "(c)".to_string(),
+        to: "©".to_string(),
+    }];
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    let body = &posts[0].body_html;
+    assert!(
+        body.contains("Copyright © me, see <code>(c)</code>."),
+        "{body}"
+    );
+    assert!(
+        body.contains("<pre><code>(c) stays\n</code></pre>"),
+        "{body}"
+    );
+}
+
+#[test]
+fn undated_posts_require_opt_in_and_sort_by_mtime() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    let mtimes = [
+        ("older", "2021-06-01T00:00:00Z"),
+        ("newer", "2023-06-01T00:00:00Z"),
+    ];
+    for (slug, mtime) in mtimes {
+        fs::create_dir_all(root.join(slug)).unwrap();
+        let path = root.join(slug).join("post.md");
+        fs::write(&path, "---\ntitle: Evergreen\n---\nTimeless").unwrap();
+        let modified = OffsetDateTime::parse(mtime, &Rfc3339).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+    }
+    fs::create_dir_all(root.join("dated")).unwrap();
+    fs::write(
+        root.join("dated/post.md"),
+        "---\ndate: 2022-01-01T00:00:00Z\n---\nDated",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    let error = discover_posts(&root, &config).unwrap_err();
+    assert!(error.to_string().contains("date is required"));
+
+    config.allow_undated = true;
+    let posts = discover_posts(&root, &config).unwrap();
+    let order: Vec<_> = posts
+        .iter()
+        .map(|post| (post.slug.as_str(), post.undated))
+        .collect();
+    assert_eq!(
+        order,
+        vec![("older", true), ("dated", false), ("newer", true)]
+    );
+    assert_eq!(posts[0].permalink, "/2021/06/01/older/");
+
+    config.undated_date = Some("2000-01-01T00:00:00Z".to_string());
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts[0].date.year(), 2000);
+    assert!(posts[0].undated && posts[1].undated);
+}
+
+#[test]
+fn tag_aliases_rewrite_post_tags_when_enabled() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    fs::create_dir_all(root.join("hello")).unwrap();
+    fs::write(
+        root.join("hello/post.md"),
+        "---\ndate: 2024-02-01T12:00:00Z\ntags: [RustLang, rust, notes]\n---\nBody",
+    )
+    .unwrap();
+
+    let mut config = Config::default();
+    config
+        .tag_aliases
+        .insert("rustlang".to_string(), "rust".to_string());
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts[0].tags, vec!["RustLang", "rust", "notes"]);
+
+    config.tag_aliases_rewrite = true;
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts[0].tags, vec!["rust", "notes"]);
+}
+
 #[test]
 fn reject_missing_front_matter() {
     let dir = TempDir::new().unwrap();
@@ -172,6 +457,43 @@ fn parse_comma_separated_lists() {
     );
 }
 
+#[test]
+fn attached_globs_expand_to_matching_files() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/gallery");
+    fs::create_dir_all(root.join("images")).unwrap();
+    for file in ["images/b.png", "images/a.png", "images/c.jpg", "notes.txt"] {
+        fs::write(root.join(file), "data").unwrap();
+    }
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [notes.txt, images/*.png]\n---\nBody",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert_eq!(
+        posts[0].attached,
+        vec![
+            PathBuf::from("notes.txt"),
+            PathBuf::from("images/a.png"),
+            PathBuf::from("images/b.png")
+        ]
+    );
+
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [images/*.gif]\n---\nBody",
+    )
+    .unwrap();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("attached pattern 'images/*.gif' matches no files"),
+        "{error:#}"
+    );
+}
+
 #[test]
 fn allows_empty_tags_field() {
     let dir = TempDir::new().unwrap();
@@ -244,6 +566,33 @@ fn accepts_naive_datetime_with_default_timezone() {
     assert_eq!(post.date.hour(), 9);
     assert_eq!(post.date.minute(), 30);
     assert_eq!(post.excerpt, "Body");
+
+    let strict = Config {
+        require_explicit_timezone: true,
+        ..config
+    };
+    let error = discover_posts(root.parent().unwrap(), &strict).unwrap_err();
+    assert!(format!("{error:#}").contains("has no timezone offset"));
+}
+
+#[test]
+fn accepts_date_only_as_midnight_in_default_timezone() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/date-only");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(root.join("post.md"), "---\ndate: 2024-05-06\n---\nBody").unwrap();
+
+    let config = Config {
+        default_timezone: "+02:00".to_string(),
+        ..Default::default()
+    };
+
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    let date = posts[0].date;
+    assert_eq!(date.offset(), config.default_offset().unwrap());
+    assert_eq!((date.year(), date.month() as u8, date.day()), (2024, 5, 6));
+    assert_eq!((date.hour(), date.minute(), date.second()), (0, 0, 0));
+    assert_eq!(posts[0].permalink, "/2024/05/06/date-only/");
 }
 
 #[test]
@@ -262,6 +611,39 @@ fn language_from_front_matter_is_normalized() {
     assert_eq!(posts[0].language, "el");
 }
 
+/// Fails the test if discovery asks it for a guess.
+struct NoDetection;
+
+impl LanguageDetector for NoDetection {
+    fn detect(&self, text: &str) -> Option<String> {
+        panic!("detection should be skipped, got {text:?}");
+    }
+}
+
+#[test]
+fn explicit_language_skips_detection() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/lang");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nlanguage: en\n---\nThis body would be detected as English otherwise.",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let shortcodes = Shortcodes::builtin(&config).unwrap();
+    let posts = discover_posts_with_shortcodes(
+        root.parent().unwrap(),
+        &config,
+        &shortcodes,
+        &NoDetection,
+        None,
+    )
+    .unwrap();
+    assert_eq!(posts[0].language, "en");
+}
+
 #[test]
 fn language_is_detected_when_missing() {
     let dir = TempDir::new().unwrap();
@@ -318,6 +700,109 @@ fn html_posts_are_passthrough() {
     assert_eq!(posts[0].excerpt, "Sunny");
 }
 
+#[test]
+fn excerpt_separator_ends_the_excerpt_in_both_formats() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    for (name, body) in [
+        (
+            "html/post.html",
+            "<p>Lead <b>in</b></p><p>Second.</p>\n<!-- more -->\n<p>Rest of the post.</p>",
+        ),
+        (
+            "markdown/post.md",
+            "First *part*.\n\nSecond.\n\n<!-- more -->\n\nRest of the post.",
+        ),
+        ("plain/post.html", "<p>No marker.</p><p>Rest.</p>"),
+    ] {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!("---\ndate: 2024-01-02T00:00:00Z\n---\n{body}"),
+        )
+        .unwrap();
+    }
+
+    let mut config = Config::default();
+    let excerpts = |config: &Config| -> Vec<(String, String)> {
+        discover_posts(&root, config)
+            .unwrap()
+            .into_iter()
+            .map(|post| (post.slug, post.excerpt))
+            .collect()
+    };
+    assert_eq!(
+        excerpts(&config),
+        [
+            ("html".to_string(), "Lead in Second.".to_string()),
+            ("markdown".to_string(), "First part. Second.".to_string()),
+            ("plain".to_string(), "No marker. Rest.".to_string()),
+        ]
+    );
+
+    config.excerpt_separator = String::new();
+    assert_eq!(excerpts(&config)[0].1, "Lead in Second. Rest of the post.");
+}
+
+#[test]
+fn excerpt_separator_is_dropped_from_escaped_markdown() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    let path = root.join("escaped/post.md");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(
+        &path,
+        "---\ndate: 2024-01-02T00:00:00Z\n---\nLead.\n\n<!-- more -->\n\nRest <b>here</b>.",
+    )
+    .unwrap();
+    let mut config = Config::default();
+    config.markdown.allow_raw_html = false;
+
+    let posts = discover_posts(&root, &config).unwrap();
+    assert_eq!(posts[0].excerpt, "Lead.");
+    assert_eq!(
+        posts[0].body_html,
+        "<p>Lead.</p>\n<p>Rest &lt;b&gt;here&lt;/b&gt;.</p>\n"
+    );
+}
+
+#[test]
+fn jsonld_needs_a_type() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    let post = root.join("event/post.md");
+    fs::create_dir_all(post.parent().unwrap()).unwrap();
+    let config = Config::default();
+
+    fs::write(
+        &post,
+        "---\ndate: 2024-01-02T00:00:00Z\njsonld:\n  \"@type\": Event\n  name: Meetup\n---\nBody",
+    )
+    .unwrap();
+    let posts = discover_posts(&root, &config).unwrap();
+    let jsonld = posts[0].jsonld.as_ref().unwrap();
+    assert_eq!(jsonld["name"], "Meetup");
+    assert!(!posts[0].extra.contains_key("jsonld"));
+
+    for jsonld in [
+        "  name: Meetup\n",
+        "  \"@type\": \"\"\n",
+        "  \"@type\": [Event, 3]\n",
+    ] {
+        fs::write(
+            &post,
+            format!("---\ndate: 2024-01-02T00:00:00Z\njsonld:\n{jsonld}---\nBody"),
+        )
+        .unwrap();
+        let error = format!("{:#}", discover_posts(&root, &config).unwrap_err());
+        assert!(
+            error.contains("event/post.md: jsonld needs an @type"),
+            "{error}"
+        );
+    }
+}
+
 #[test]
 fn ignores_directories_with_bcktignore() {
     let dir = TempDir::new().unwrap();
@@ -356,3 +841,234 @@ fn ignores_directories_with_bcktignore() {
     assert_eq!(posts.len(), 1);
     assert_eq!(posts[0].slug, "published");
 }
+
+#[test]
+fn bulk_directories_load_every_markdown_file() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+
+    fs::create_dir_all(root.join("regular")).unwrap();
+    fs::write(
+        root.join("regular/post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nRegular",
+    )
+    .unwrap();
+
+    let bulk = root.join("imports/microblog");
+    fs::create_dir_all(bulk.join("media/nested")).unwrap();
+    fs::write(bulk.join("_bulk.yaml"), "").unwrap();
+    fs::write(
+        bulk.join("2024-03-01-first.md"),
+        "---\ndate: 2024-03-01T00:00:00Z\nattached: [media/a.png]\n---\nFirst",
+    )
+    .unwrap();
+    fs::write(
+        bulk.join("second.md"),
+        "---\ndate: 2024-03-02T00:00:00Z\nslug: custom\n---\nSecond",
+    )
+    .unwrap();
+    fs::write(bulk.join("notes.txt"), "not a post").unwrap();
+    // Subdirectories of a bulk directory are attachments, not posts.
+    fs::write(
+        bulk.join("media/nested/post.md"),
+        "---\ndate: 2024-03-03T00:00:00Z\n---\nIgnored",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(&root, &config).unwrap();
+    let slugs: Vec<_> = posts.iter().map(|post| post.slug.as_str()).collect();
+    assert_eq!(slugs, vec!["regular", "2024-03-01-first", "custom"]);
+
+    let first = &posts[1];
+    assert_eq!(first.source_dir, bulk);
+    assert_eq!(first.content_path, bulk.join("2024-03-01-first.md"));
+    assert_eq!(first.permalink, "/2024/03/01/2024-03-01-first/");
+    assert_eq!(first.attached, vec![PathBuf::from("media/a.png")]);
+    assert_eq!(posts[2].permalink, "/2024/03/02/custom/");
+}
+
+#[test]
+fn parse_private_attachments() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/private");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: cover.jpg\nattached_private: raw/cover.dng, notes.md\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert_eq!(posts[0].attached, vec![PathBuf::from("cover.jpg")]);
+    assert_eq!(
+        posts[0].attached_private,
+        vec![PathBuf::from("raw/cover.dng"), PathBuf::from("notes.md")]
+    );
+}
+
+#[test]
+fn reject_path_both_public_and_private() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/overlap");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [a.txt]\nattached_private: [a.txt]\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(format!("{error}").contains("attached_private"));
+}
+
+#[test]
+fn parse_featured_flag() {
+    let dir = TempDir::new().unwrap();
+    let posts_dir = dir.path().join("posts");
+    for (slug, featured) in [("plain", None), ("star", Some("true"))] {
+        let root = posts_dir.join(slug);
+        fs::create_dir_all(&root).unwrap();
+        let flag = featured
+            .map(|value| format!("featured: {value}\n"))
+            .unwrap_or_default();
+        fs::write(
+            root.join("post.md"),
+            format!("---\ndate: 2024-01-01T00:00:00Z\n{flag}---\n"),
+        )
+        .unwrap();
+    }
+
+    let config = Config::default();
+    let mut posts = discover_posts(&posts_dir, &config).unwrap();
+    posts.sort_by(|a, b| a.slug.cmp(&b.slug));
+    assert!(!posts[0].featured);
+    assert!(posts[1].featured);
+    assert!(!posts[1].extra.contains_key("featured"));
+}
+
+#[test]
+fn parse_updated_date() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/revised");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nupdated: 2024-02-01 10:00:00 +02:00\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    let updated = posts[0].updated.expect("updated parsed");
+    assert_eq!(
+        updated,
+        OffsetDateTime::parse("2024-02-01T08:00:00Z", &Rfc3339).unwrap()
+    );
+    assert!(!posts[0].extra.contains_key("updated"));
+}
+
+#[test]
+fn reject_updated_before_date() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/backwards");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-02-01T00:00:00Z\nupdated: 2024-01-01T00:00:00Z\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(format!("{error}").contains("updated must not be earlier than date"));
+}
+
+#[test]
+fn reject_invalid_updated_date() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/garbled");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-02-01T00:00:00Z\nupdated: yesterday\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(format!("{error}").contains("updated must be RFC3339"));
+}
+
+#[test]
+fn parse_classes_from_lists_and_strings() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/styled");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nclasses: hero wide, dark\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(root.parent().unwrap(), &config).unwrap();
+    assert_eq!(posts[0].classes, vec!["hero", "wide", "dark"]);
+}
+
+#[test]
+fn reject_invalid_class_names() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts/styled");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        root.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nclasses: [\"x\\\"onclick\"]\n---\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let error = discover_posts(root.parent().unwrap(), &config).unwrap_err();
+    assert!(format!("{error}").contains("may only contain letters"));
+}
+
+#[test]
+fn normalizes_messy_front_matter_strings() {
+    let dir = TempDir::new().unwrap();
+    let root = dir.path().join("posts");
+    fs::create_dir_all(root.join("messy")).unwrap();
+    fs::write(
+        root.join("messy/post.md"),
+        "---\ntitle: \"  Copied\\n  Title  \"\ndate: 2024-03-04T00:00:00Z\nslug: '  tidy  '\ntags:\n  - ' rust '\n  - \"\"\nabstract: |\n  First line\n\n  second line  \n---\nBody\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("untitled")).unwrap();
+    fs::write(
+        root.join("untitled/post.md"),
+        "---\ntitle: \"\"\ndate: 2024-03-05T00:00:00Z\nabstract: \"   \"\n---\nBody\n",
+    )
+    .unwrap();
+
+    let config = Config::default();
+    let posts = discover_posts(&root, &config).unwrap();
+    let messy = posts.iter().find(|post| post.slug == "tidy").unwrap();
+    assert_eq!(messy.title.as_deref(), Some("Copied Title"));
+    assert_eq!(
+        messy.abstract_text.as_deref(),
+        Some("First line second line")
+    );
+    assert_eq!(messy.tags, vec!["rust".to_string()]);
+    let untitled = posts.iter().find(|post| post.slug == "untitled").unwrap();
+    assert_eq!(untitled.title, None);
+    assert_eq!(untitled.abstract_text, None);
+
+    fs::write(
+        root.join("untitled/post.md"),
+        "---\ntitle: \"  \\n \"\ndate: 2024-03-05T00:00:00Z\n---\nBody\n",
+    )
+    .unwrap();
+    let error = discover_posts(&root, &config).unwrap_err();
+    assert!(format!("{error:#}").contains("title is blank"), "{error:#}");
+}
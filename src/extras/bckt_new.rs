@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
 use clap::Parser;
+use deunicode::deunicode;
 use time::format_description::well_known::Rfc3339;
 use time::macros::format_description;
 use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
@@ -36,7 +37,7 @@ struct Cli {
     /// Language code to store in front matter
     #[arg(long)]
     language: Option<String>,
-    /// Destination posts directory (defaults to `<project>/posts`)
+    /// Destination posts directory (defaults to `posts_dir` from bckt.yaml, or `<project>/posts`)
     #[arg(long)]
     posts_dir: Option<PathBuf>,
     /// Run without interactive prompts (use provided flags and defaults)
@@ -58,10 +59,11 @@ fn run() -> Result<()> {
     let project_root = find_project_root(&current_dir)
         .context("run this command inside a bckt project (with bckt.yaml)")?;
 
-    let posts_root = cli
-        .posts_dir
-        .clone()
-        .unwrap_or_else(|| project_root.join("posts"));
+    let transliterate = slug_transliterate(&project_root)?;
+    let posts_root = match cli.posts_dir.clone() {
+        Some(dir) => dir,
+        None => project_root.join(configured_posts_dir(&project_root)?),
+    };
     fs::create_dir_all(&posts_root)
         .with_context(|| format!("failed to create posts directory {}", posts_root.display()))?;
 
@@ -78,14 +80,17 @@ fn run() -> Result<()> {
         cli.no_prompt,
     )?;
 
-    let mut slug_candidate = cli.slug.clone().unwrap_or_else(|| slugify(&title));
+    let mut slug_candidate = cli
+        .slug
+        .clone()
+        .unwrap_or_else(|| slugify(&title, transliterate));
     if slug_candidate.is_empty() {
         slug_candidate = generate_fallback_slug(now); // ensures non-empty even in non-interactive mode
     }
 
     let slug = loop {
         let entered = value_or_prompt("Slug", slug_candidate.clone(), false, cli.no_prompt)?;
-        let sanitized = slugify(&entered);
+        let sanitized = slugify(&entered, transliterate);
         if sanitized.is_empty() {
             if cli.no_prompt {
                 bail!("slug is required; provide a non-empty value with --slug");
@@ -319,7 +324,12 @@ fn yaml_quote(value: &str) -> String {
     format!("\"{}\"", escaped)
 }
 
-fn slugify(value: &str) -> String {
+fn slugify(value: &str, transliterate: bool) -> String {
+    let value = if transliterate {
+        deunicode(value)
+    } else {
+        value.to_string()
+    };
     let mut slug = String::new();
     let mut previous_dash = false;
 
@@ -352,6 +362,31 @@ fn find_project_root(start: &Path) -> Result<PathBuf> {
     }
 }
 
+fn read_project_config(project_root: &Path) -> Result<serde_yaml::Value> {
+    let config_path = project_root.join("bckt.yaml");
+    let raw = fs::read_to_string(&config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+    serde_yaml::from_str(&raw).with_context(|| format!("{}: invalid YAML", config_path.display()))
+}
+
+fn slug_transliterate(project_root: &Path) -> Result<bool> {
+    Ok(read_project_config(project_root)?
+        .get("slug_transliterate")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true))
+}
+
+fn configured_posts_dir(project_root: &Path) -> Result<String> {
+    let value = read_project_config(project_root)?;
+    Ok(value
+        .get("posts_dir")
+        .and_then(|dir| dir.as_str())
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or("posts")
+        .to_string())
+}
+
 fn non_empty(value: String) -> Option<String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -0,0 +1,299 @@
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anstyle::{AnsiColor, Effects, Style};
+use serde::Serialize;
+
+// Width of the longest bracketed label ("[ARCHIVE]") so colored messages line up.
+const LABEL_WIDTH: usize = 9;
+
+static COLOR_ALLOWED: AtomicBool = AtomicBool::new(true);
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Message severity, from always shown to `-vv` only.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Picks how log lines are printed for the rest of the process. Colors are
+/// disabled by `--no-color` or `NO_COLOR`, and skipped whenever the target
+/// stream is not a TTY; `--log-json` prints one JSON object per line instead.
+pub fn configure(no_color_flag: bool, json: bool) {
+    let allowed = color_allowed(no_color_flag, std::env::var_os("NO_COLOR").as_deref());
+    COLOR_ALLOWED.store(allowed, Ordering::Relaxed);
+    JSON_OUTPUT.store(json, Ordering::Relaxed);
+}
+
+fn color_allowed(no_color_flag: bool, no_color_env: Option<&OsStr>) -> bool {
+    !no_color_flag && no_color_env.is_none_or(|value| value.is_empty())
+}
+
+/// Decides which messages a command prints. Errors and warnings always go
+/// to stderr; the rest goes to stdout up to `max_level`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Logger {
+    max_level: Level,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::new(Level::Info)
+    }
+}
+
+impl Logger {
+    pub fn new(max_level: Level) -> Self {
+        Self {
+            max_level: max_level.max(Level::Warn),
+        }
+    }
+
+    /// `--quiet` keeps only warnings and errors and wins over `-v`; each `-v`
+    /// adds a level (`-v` for debug, `-vv` for trace).
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        let max_level = match (quiet, verbose) {
+            (true, _) => Level::Warn,
+            (false, 0) => Level::Info,
+            (false, 1) => Level::Debug,
+            (false, _) => Level::Trace,
+        };
+        Self::new(max_level)
+    }
+
+    pub fn enabled(self, level: Level) -> bool {
+        level <= self.max_level
+    }
+
+    pub fn log(self, level: Level, phase: &str, message: impl AsRef<str>, path: Option<&Path>) {
+        if self.enabled(level) {
+            emit(Record {
+                level,
+                phase: phase.to_string(),
+                message: message.as_ref().to_string(),
+                path: path.map(Path::to_path_buf),
+            });
+        }
+    }
+
+    pub fn error(self, phase: &str, message: impl AsRef<str>) {
+        self.log(Level::Error, phase, message, None);
+    }
+
+    pub fn warn(self, phase: &str, message: impl AsRef<str>) {
+        self.log(Level::Warn, phase, message, None);
+    }
+
+    pub fn info(self, phase: &str, message: impl AsRef<str>) {
+        self.log(Level::Info, phase, message, None);
+    }
+
+    pub fn debug(self, phase: &str, message: impl AsRef<str>) {
+        self.log(Level::Debug, phase, message, None);
+    }
+
+    pub fn trace(self, phase: &str, message: impl AsRef<str>) {
+        self.log(Level::Trace, phase, message, None);
+    }
+}
+
+/// Prints an error from code that has no [`Logger`] at hand; errors are
+/// shown at every level anyway.
+pub fn error(phase: &str, message: impl AsRef<str>) {
+    Logger::default().error(phase, message);
+}
+
+/// Like [`error`], for warnings.
+pub fn warn(phase: &str, message: impl AsRef<str>) {
+    Logger::default().warn(phase, message);
+}
+
+/// One log line, as printed by `--log-json`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Record {
+    pub level: Level,
+    pub phase: String,
+    pub message: String,
+    pub path: Option<PathBuf>,
+}
+
+fn emit(record: Record) {
+    #[cfg(test)]
+    if capture::push(&record) {
+        return;
+    }
+
+    let to_stderr = record.level <= Level::Warn;
+    let line = if JSON_OUTPUT.load(Ordering::Relaxed) {
+        format_json(&record)
+    } else {
+        let terminal = if to_stderr {
+            std::io::stderr().is_terminal()
+        } else {
+            std::io::stdout().is_terminal()
+        };
+        let color = COLOR_ALLOWED.load(Ordering::Relaxed) && terminal;
+        format_text(&record, color)
+    };
+    if to_stderr {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+fn format_json(record: &Record) -> String {
+    serde_json::to_string(record).unwrap_or_else(|_| record.message.clone())
+}
+
+/// Warnings and errors are labelled by level, everything else by phase.
+fn format_text(record: &Record, color: bool) -> String {
+    let label = match record.level {
+        Level::Error => "ERROR",
+        Level::Warn => "WARN",
+        _ => record.phase.as_str(),
+    };
+    format_status(label, &record.message, color)
+}
+
+fn format_status(label: &str, message: &str, color: bool) -> String {
+    let tag = format!("[{label}]");
+    if !color {
+        return format!("{tag} {message}");
+    }
+
+    let style = label_style(label);
+    format!(
+        "{style}{tag}{style:#}{:pad$} {message}",
+        "",
+        pad = LABEL_WIDTH.saturating_sub(tag.len())
+    )
+}
+
+fn label_style(label: &str) -> Style {
+    match label {
+        "RENDER" => AnsiColor::Green.on_default(),
+        "SKIP" => Style::new().effects(Effects::DIMMED),
+        "WARN" => AnsiColor::Yellow.on_default(),
+        "ERROR" => AnsiColor::Red.on_default(),
+        "ARCHIVE" | "TAG" => AnsiColor::Cyan.on_default(),
+        _ => Style::new().effects(Effects::BOLD),
+    }
+}
+
+/// Lets tests collect the records emitted on the current thread instead of
+/// printing them.
+#[cfg(test)]
+pub(crate) mod capture {
+    use std::cell::RefCell;
+
+    use super::Record;
+
+    thread_local! {
+        static CAPTURED: RefCell<Option<Vec<Record>>> = const { RefCell::new(None) };
+    }
+
+    pub(crate) fn records<T>(run: impl FnOnce() -> T) -> (T, Vec<Record>) {
+        CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+        let value = run();
+        let records = CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+        (value, records)
+    }
+
+    pub(super) fn push(record: &Record) -> bool {
+        CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+            Some(records) => {
+                records.push(record.clone());
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    fn record(level: Level, phase: &str, message: &str) -> Record {
+        Record {
+            level,
+            phase: phase.to_string(),
+            message: message.to_string(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn plain_status_without_color() {
+        assert_eq!(
+            format_text(&record(Level::Debug, "RENDER", "Rendered post /a/"), false),
+            "[RENDER] Rendered post /a/"
+        );
+    }
+
+    #[test]
+    fn colored_status_keeps_label_and_aligns() {
+        let line = format_status("TAG", "Rendered tag rust", true);
+        assert!(line.contains("[TAG]"));
+        assert!(line.starts_with("\u{1b}["));
+        assert!(line.ends_with("     Rendered tag rust"));
+    }
+
+    #[test]
+    fn warnings_are_labelled_by_level() {
+        assert_eq!(
+            format_text(&record(Level::Warn, "POST", "odd"), false),
+            "[WARN] odd"
+        );
+    }
+
+    #[test]
+    fn json_lines_carry_level_phase_message_and_path() {
+        let mut entry = record(Level::Warn, "POST", "missing \"x\"");
+        entry.path = Some(PathBuf::from("posts/a/post.md"));
+        let value: serde_json::Value = serde_json::from_str(&format_json(&entry)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "level": "warn",
+                "phase": "POST",
+                "message": "missing \"x\"",
+                "path": "posts/a/post.md",
+            })
+        );
+        assert!(format_json(&record(Level::Info, "DONE", "ok")).contains("\"path\":null"));
+    }
+
+    #[test]
+    fn flags_pick_the_level() {
+        assert!(!Logger::from_flags(0, false).enabled(Level::Debug));
+        assert!(Logger::from_flags(1, false).enabled(Level::Debug));
+        assert!(!Logger::from_flags(1, false).enabled(Level::Trace));
+        assert!(Logger::from_flags(2, false).enabled(Level::Trace));
+        let quiet = Logger::from_flags(2, true);
+        assert!(!quiet.enabled(Level::Info));
+        assert!(quiet.enabled(Level::Warn));
+    }
+
+    #[test]
+    fn warnings_survive_any_level() {
+        assert!(Logger::new(Level::Error).enabled(Level::Warn));
+    }
+
+    #[test]
+    fn color_respects_flag_and_environment() {
+        assert!(color_allowed(false, None));
+        assert!(color_allowed(false, Some(&OsString::new())));
+        assert!(!color_allowed(true, None));
+        assert!(!color_allowed(false, Some(&OsString::from("1"))));
+    }
+}
@@ -2,19 +2,23 @@ mod cli;
 mod commands;
 pub mod config;
 pub mod content;
+pub mod logging;
 pub mod markdown;
 pub mod render;
 pub mod search;
 pub mod template;
 pub mod theme;
+pub mod timing;
+pub mod urls;
 pub mod utils;
 
 fn main() {
     let app = cli::Cli::build();
+    logging::configure(app.no_color, app.log_json);
     let outcome = commands::run(app.command);
 
     if let Err(problem) = outcome {
-        eprintln!("{problem}");
+        logging::error("BCKT", problem.to_string());
         std::process::exit(1);
     }
 }
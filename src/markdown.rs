@@ -1,6 +1,8 @@
 use comrak::nodes::{AstNode, NodeValue};
 use comrak::{Arena, Options, format_html, parse_document};
 
+use crate::config::MarkdownConfig;
+
 const EXCERPT_LIMIT: usize = 280;
 
 pub struct MarkdownRender {
@@ -8,8 +10,8 @@ pub struct MarkdownRender {
     pub excerpt: String,
 }
 
-pub fn render_markdown(markdown: &str) -> MarkdownRender {
-    let options = options();
+pub fn render_markdown(markdown: &str, config: &MarkdownConfig) -> MarkdownRender {
+    let options = options(config);
     let arena = Arena::new();
     let root = parse_document(&arena, markdown, &options);
 
@@ -21,18 +23,20 @@ pub fn render_markdown(markdown: &str) -> MarkdownRender {
     MarkdownRender { html, excerpt }
 }
 
-fn options() -> Options<'static> {
+fn options(config: &MarkdownConfig) -> Options<'static> {
     let mut options = Options::default();
-    options.extension.table = true;
+    options.extension.table = config.tables;
     options.extension.autolink = true;
-    options.extension.tasklist = true;
-    options.extension.strikethrough = true;
-    options.extension.footnotes = true;
+    options.extension.tasklist = config.tasklists;
+    options.extension.strikethrough = config.strikethrough;
+    options.extension.footnotes = config.footnotes;
     options.extension.alerts = true;
     options.extension.shortcodes = true;
+    options.parse.smart = config.smart_punctuation;
     options.render.hardbreaks = false;
     options.render.github_pre_lang = true;
-    options.render.r#unsafe = true;
+    options.render.r#unsafe = config.allow_raw_html;
+    options.render.escape = !config.allow_raw_html;
     options.render.figure_with_caption = true;
     options.render.width = 0;
     options
@@ -74,6 +78,11 @@ fn collect<'a>(node: &'a AstNode<'a>, buf: &mut String) {
     }
 }
 
+/// The start of `text`, cut like an excerpt.
+pub fn excerpt_of(text: &str) -> String {
+    truncate(text, EXCERPT_LIMIT)
+}
+
 fn truncate(text: &str, limit: usize) -> String {
     if text.is_empty() {
         return String::new();
@@ -101,7 +110,7 @@ mod tests {
     #[test]
     fn renders_tables_and_tasks() {
         let markdown = "| h1 | h2 |\n| -- | -- |\n| a | b |\n\n- [x] done\n- [ ] todo";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert!(rendered.html.contains("<table"), "{}", rendered.html);
         assert!(
             rendered.html.contains("<input type=\"checkbox\""),
@@ -114,7 +123,7 @@ mod tests {
     fn renders_footnotes_and_code() {
         let markdown =
             "Paragraph with footnote.[^1]\n\n[^1]: Footnote text\n\n```rust\nfn main() {}\n```";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert!(
             rendered.html.contains("data-footnotes"),
             "{}",
@@ -123,17 +132,43 @@ mod tests {
         assert!(rendered.html.contains("lang=\"rust\""), "{}", rendered.html);
     }
 
+    #[test]
+    fn escapes_raw_html_when_not_allowed() {
+        let markdown = "<div class=\"note\">Hi</div>\n\nSome <b>bold</b> text";
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
+        assert!(
+            rendered.html.contains("<div class=\"note\">"),
+            "{}",
+            rendered.html
+        );
+
+        let config = MarkdownConfig {
+            allow_raw_html: false,
+            ..MarkdownConfig::default()
+        };
+        let rendered = render_markdown(markdown, &config);
+        assert!(!rendered.html.contains("<div"), "{}", rendered.html);
+        assert!(
+            rendered
+                .html
+                .contains("&lt;div class=&quot;note&quot;&gt;Hi&lt;/div&gt;"),
+            "{}",
+            rendered.html
+        );
+        assert!(rendered.html.contains("Some &lt;b&gt;bold&lt;/b&gt; text"));
+    }
+
     #[test]
     fn excerpt_prefers_first_paragraph() {
         let markdown = "First paragraph.\n\nSecond paragraph";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert_eq!(rendered.excerpt, "First paragraph.");
     }
 
     #[test]
     fn excerpt_truncates_long_text() {
         let text = "a".repeat(500);
-        let rendered = render_markdown(&text);
+        let rendered = render_markdown(&text, &MarkdownConfig::default());
         assert_eq!(rendered.excerpt.len(), EXCERPT_LIMIT + 3);
         assert!(rendered.excerpt.ends_with("..."));
     }
@@ -141,7 +176,7 @@ mod tests {
     #[test]
     fn renders_github_alerts() {
         let markdown = "> [!NOTE]\n> This is a note alert\n\n> [!WARNING]\n> This is a warning";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert!(
             rendered.html.contains("markdown-alert"),
             "{}",
@@ -162,7 +197,7 @@ mod tests {
     #[test]
     fn renders_emoji_shortcodes() {
         let markdown = "Hello :smile: and :heart: world!";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert!(rendered.html.contains("😄"), "{}", rendered.html);
         assert!(rendered.html.contains("❤"), "{}", rendered.html);
     }
@@ -170,9 +205,65 @@ mod tests {
     #[test]
     fn renders_figure_with_caption() {
         let markdown = "![alt text](https://example.com/image.png \"Image caption\")";
-        let rendered = render_markdown(markdown);
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
         assert!(rendered.html.contains("<figure>"), "{}", rendered.html);
         assert!(rendered.html.contains("<figcaption>"), "{}", rendered.html);
         assert!(rendered.html.contains("Image caption"), "{}", rendered.html);
     }
+
+    #[test]
+    fn smart_punctuation_is_opt_in() {
+        let markdown = "\"Quoted\" -- and... done";
+        let plain = render_markdown(markdown, &MarkdownConfig::default());
+        assert!(plain.html.contains("&quot;Quoted&quot;"), "{}", plain.html);
+
+        let config = MarkdownConfig {
+            smart_punctuation: true,
+            ..MarkdownConfig::default()
+        };
+        let smart = render_markdown(markdown, &config);
+        assert!(smart.html.contains("“Quoted”"), "{}", smart.html);
+        assert!(smart.html.contains("–"), "{}", smart.html);
+        assert!(smart.html.contains("…"), "{}", smart.html);
+    }
+
+    #[test]
+    fn disabled_extensions_render_as_plain_text() {
+        let config = MarkdownConfig {
+            footnotes: false,
+            tables: false,
+            tasklists: false,
+            strikethrough: false,
+            ..MarkdownConfig::default()
+        };
+
+        let table = render_markdown("| h1 | h2 |\n| -- | -- |\n| a | b |", &config);
+        assert!(!table.html.contains("<table"), "{}", table.html);
+
+        let tasks = render_markdown("- [x] done", &config);
+        assert!(!tasks.html.contains("checkbox"), "{}", tasks.html);
+
+        let strike = render_markdown("~~gone~~", &config);
+        assert!(!strike.html.contains("<del>"), "{}", strike.html);
+
+        let notes = render_markdown("Text.[^1]\n\n[^1]: Note", &config);
+        assert!(!notes.html.contains("data-footnotes"), "{}", notes.html);
+    }
+
+    #[test]
+    fn strikethrough_renders_when_enabled() {
+        let rendered = render_markdown("~~gone~~", &MarkdownConfig::default());
+        assert!(
+            rendered.html.contains("<del>gone</del>"),
+            "{}",
+            rendered.html
+        );
+    }
+
+    #[test]
+    fn excerpt_skips_footnote_references() {
+        let markdown = "First paragraph.[^1] Continues here.\n\n[^1]: Footnote text";
+        let rendered = render_markdown(markdown, &MarkdownConfig::default());
+        assert_eq!(rendered.excerpt, "First paragraph. Continues here.");
+    }
 }
@@ -6,42 +6,41 @@ use anyhow::{Context, Result, bail};
 use blake3::Hasher;
 use walkdir::WalkDir;
 
+use crate::logging::Logger;
+use crate::utils::glob_matches;
+
+use super::output::{Output, PendingCopy};
 use super::utils::normalize_path;
 
 pub(super) enum ThemeAssetCopy {
-    Copied(usize),
+    /// Paths written, relative to the html root and `/`-separated.
+    Copied(Vec<String>),
     SkippedMissing,
 }
 
-pub(super) fn compute_static_digest(root: &Path) -> Result<String> {
-    let skel_dir = root.join("skel");
-    if !skel_dir.exists() {
-        return Ok(Hasher::new().finalize().to_hex().to_string());
-    }
-
-    let mut files = Vec::new();
-    for entry in WalkDir::new(&skel_dir) {
-        let entry = entry?;
-        if entry.file_type().is_file() {
-            files.push(entry.into_path());
-        }
-    }
-    files.sort();
-
+pub(super) fn compute_static_digest(skel_dir: &Path, ignore: &[String]) -> Result<String> {
     let mut hasher = Hasher::new();
-    for path in files {
-        let relative = path.strip_prefix(&skel_dir).unwrap();
+    hash_asset_files(&mut hasher, skel_dir, ignore, "static asset")?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Hashes the path, contents, size, and modification time of every file
+/// [`asset_files`] lists below `dir`, so the digest covers exactly what is
+/// copied.
+fn hash_asset_files(hasher: &mut Hasher, dir: &Path, ignore: &[String], kind: &str) -> Result<()> {
+    for path in asset_files(dir, ignore)? {
+        let relative = path.strip_prefix(dir).unwrap();
         let normalized = normalize_path(relative);
         hasher.update(normalized.as_bytes());
-        let data = fs::read(&path)
-            .with_context(|| format!("failed to read static asset {}", path.display()))?;
+        let data =
+            fs::read(&path).with_context(|| format!("failed to read {kind} {}", path.display()))?;
         hasher.update(&data);
         let metadata = fs::metadata(&path)
-            .with_context(|| format!("failed to inspect static asset {}", path.display()))?;
+            .with_context(|| format!("failed to inspect {kind} {}", path.display()))?;
         hasher.update(&metadata.len().to_le_bytes());
         let modified = metadata.modified().with_context(|| {
             format!(
-                "failed to read modification time for static asset {}",
+                "failed to read modification time for {kind} {}",
                 path.display()
             )
         })?;
@@ -51,123 +50,154 @@ pub(super) fn compute_static_digest(root: &Path) -> Result<String> {
         hasher.update(&duration.as_secs().to_le_bytes());
         hasher.update(&duration.subsec_nanos().to_le_bytes());
     }
-
-    Ok(hasher.finalize().to_hex().to_string())
+    Ok(())
 }
 
-pub(super) fn copy_static_assets(root: &Path, html_root: &Path) -> Result<usize> {
-    let skel_dir = root.join("skel");
-    if !skel_dir.exists() {
-        return Ok(0);
-    }
-
-    let mut copied = 0usize;
-    for entry in WalkDir::new(&skel_dir) {
-        let entry = entry?;
-        if entry.file_type().is_dir() {
-            continue;
-        }
-        let relative = entry.path().strip_prefix(&skel_dir).unwrap();
-        let destination = html_root.join(relative);
-        if let Some(parent) = destination.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
-        }
-        fs::copy(entry.path(), &destination).with_context(|| {
-            format!(
-                "failed to copy static asset from {} to {}",
-                entry.path().display(),
-                destination.display()
-            )
-        })?;
-        copied += 1;
-    }
+/// Directory under `html/` that theme assets are copied into.
+pub(super) const THEME_ASSETS_DIR: &str = "assets";
 
-    Ok(copied)
-}
+/// Hidden entries that are published anyway, since web servers and hosts
+/// read them.
+const KEPT_HIDDEN: &[&str] = &[".well-known", ".htaccess", ".nojekyll"];
 
-pub(super) fn compute_theme_asset_digest(root: &Path, theme: &str) -> Result<String> {
-    let Some(assets_dir) = theme_assets_directory(root, theme)? else {
-        let mut hasher = Hasher::new();
-        hasher.update(theme.as_bytes());
-        return Ok(hasher.finalize().to_hex().to_string());
-    };
+/// Every file below `dir` (none when it is missing), sorted, leaving out
+/// hidden entries and those matching `ignore` (see [`is_ignored`]). Static and
+/// theme assets are published at the same relative path below their target.
+pub(super) fn asset_files(dir: &Path, ignore: &[String]) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
 
     let mut files = Vec::new();
-    for entry in WalkDir::new(&assets_dir) {
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !is_ignored(
+                &normalize_path(entry.path().strip_prefix(dir).unwrap()),
+                ignore,
+            )
+    });
+    for entry in walker {
         let entry = entry?;
-        if entry.file_type().is_file() {
+        if !entry.file_type().is_dir() {
             files.push(entry.into_path());
         }
     }
     files.sort();
+    Ok(files)
+}
 
+/// Whether the asset at `relative` (`/`-separated) is skipped. Hidden entries
+/// are, apart from [`KEPT_HIDDEN`]. A pattern without `/` matches an entry's
+/// name at any depth, one with `/` its whole path, and a leading `!`
+/// publishes matches again; the last matching pattern wins. An ignored
+/// directory is skipped with everything below it.
+pub(super) fn is_ignored(relative: &str, ignore: &[String]) -> bool {
+    let name = relative.rsplit('/').next().unwrap_or(relative);
+    let mut ignored = name.starts_with('.') && !KEPT_HIDDEN.contains(&name);
+    for pattern in ignore {
+        let (publish, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern.as_str()),
+        };
+        let pattern = pattern.trim_end_matches('/');
+        let matched = if pattern.contains('/') {
+            glob_matches(pattern, relative)
+        } else {
+            glob_matches(pattern, name)
+        };
+        if matched {
+            ignored = !publish;
+        }
+    }
+    ignored
+}
+
+pub(super) fn copy_static_assets(
+    skel_dir: &Path,
+    html: &Output,
+    ignore: &[String],
+    log: Logger,
+) -> Result<usize> {
+    let copies: Vec<PendingCopy> = asset_files(skel_dir, ignore)?
+        .into_iter()
+        .map(|source| PendingCopy {
+            destination: html.root().join(source.strip_prefix(skel_dir).unwrap()),
+            source,
+        })
+        .collect();
+    html.copy_all(&copies, log)?;
+
+    Ok(copies.len())
+}
+
+pub(super) fn compute_theme_asset_digest(
+    root: &Path,
+    theme: &str,
+    ignore: &[String],
+) -> Result<String> {
     let mut hasher = Hasher::new();
     hasher.update(theme.as_bytes());
-
-    for path in files {
-        let relative = path.strip_prefix(&assets_dir).unwrap();
-        let normalized = normalize_path(relative);
-        hasher.update(normalized.as_bytes());
-        let data = fs::read(&path)
-            .with_context(|| format!("failed to read theme asset {}", path.display()))?;
-        hasher.update(&data);
-        let metadata = fs::metadata(&path)
-            .with_context(|| format!("failed to inspect theme asset {}", path.display()))?;
-        hasher.update(&metadata.len().to_le_bytes());
-        let modified = metadata.modified().with_context(|| {
-            format!(
-                "failed to read modification time for theme asset {}",
-                path.display()
-            )
-        })?;
-        let duration = modified
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_else(|_| Duration::new(0, 0));
-        hasher.update(&duration.as_secs().to_le_bytes());
-        hasher.update(&duration.subsec_nanos().to_le_bytes());
+    if let Some(assets_dir) = theme_assets_directory(root, theme)? {
+        hash_asset_files(&mut hasher, &assets_dir, ignore, "theme asset")?;
     }
-
     Ok(hasher.finalize().to_hex().to_string())
 }
 
 pub(super) fn copy_theme_assets(
     root: &Path,
-    html_root: &Path,
+    html: &Output,
     theme: &str,
+    ignore: &[String],
+    log: Logger,
 ) -> Result<ThemeAssetCopy> {
     let Some(assets_dir) = theme_assets_directory(root, theme)? else {
         return Ok(ThemeAssetCopy::SkippedMissing);
     };
 
-    let destination_root = html_root.join("assets");
-    let mut copied = 0usize;
+    let destination_root = html.root().join(THEME_ASSETS_DIR);
+    let mut copied = Vec::new();
+    let mut copies = Vec::new();
+
+    for source in asset_files(&assets_dir, ignore)? {
+        let relative = source.strip_prefix(&assets_dir).unwrap();
+        copied.push(normalize_path(&Path::new(THEME_ASSETS_DIR).join(relative)));
+        copies.push(PendingCopy {
+            destination: destination_root.join(relative),
+            source,
+        });
+    }
+    html.copy_all(&copies, log)?;
+    copied.sort();
 
-    for entry in WalkDir::new(&assets_dir) {
-        let entry = entry?;
-        if entry.file_type().is_dir() {
+    Ok(ThemeAssetCopy::Copied(copied))
+}
+
+/// Deletes theme assets copied by an earlier build that the current theme no
+/// longer provides, along with any directories left empty. Files that the
+/// static directory also provides are kept.
+pub(super) fn remove_stale_theme_assets(
+    html: &Output,
+    static_dir: &Path,
+    previous: &[String],
+    current: &[String],
+) -> Result<usize> {
+    let mut removed = 0usize;
+    for relative in previous {
+        if current.contains(relative) || static_dir.join(relative).exists() {
             continue;
         }
-        let relative = entry.path().strip_prefix(&assets_dir).unwrap();
-        let destination = destination_root.join(relative);
-        if let Some(parent) = destination.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
+        let path = html.root().join(relative);
+        if !path.is_file() {
+            continue;
         }
-        fs::copy(entry.path(), &destination).with_context(|| {
-            format!(
-                "failed to copy theme asset from {} to {}",
-                entry.path().display(),
-                destination.display()
-            )
-        })?;
-        copied += 1;
+        html.remove_file(&path)?;
+        removed += 1;
+        html.prune_empty_parents(&path);
     }
-
-    Ok(ThemeAssetCopy::Copied(copied))
+    Ok(removed)
 }
 
-fn theme_assets_directory(root: &Path, theme: &str) -> Result<Option<PathBuf>> {
+pub(super) fn theme_assets_directory(root: &Path, theme: &str) -> Result<Option<PathBuf>> {
     let mut components = Path::new(theme).components();
     let first = components.next();
     if first.is_none() || components.next().is_some() {
@@ -1,13 +1,196 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::Path;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::content::{BodyPart, BodyStore, LanguageDetector};
+use crate::logging;
+use crate::utils::expand_tilde;
+use anyhow::{Context, Result, anyhow};
+use time::OffsetDateTime;
 
-pub(super) fn open_cache_db(root: &Path) -> Result<sled::Db> {
-    let cache_dir = root.join(super::CACHE_DIR);
-    fs::create_dir_all(&cache_dir)
+use super::{BODY_PREFIX, LANGUAGE_PREFIX, SEARCH_TEXT_PREFIX};
+
+/// Layout version of the cache database. Bump it whenever stored keys or
+/// values change shape; caches written with another version are reset.
+pub const CACHE_SCHEMA_VERSION: u32 = 1;
+const CACHE_SCHEMA_KEY: &str = "cache_schema_version";
+const LOCK_RETRIES: u32 = 10;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(50);
+const LOCKED_MESSAGE: &str = "failed to open cache database: it is locked by another running bckt \
+     process (stop it, or run `bckt clean --cache` if none is running)";
+
+/// Overrides `cache_dir` from the environment.
+pub const CACHE_DIR_ENV: &str = "BCKT_CACHE_DIR";
+
+/// Where the cache of the project at `root` lives: `.bckt/cache` inside it,
+/// or, when `BCKT_CACHE_DIR` or `cache_dir` names a shared parent, a
+/// directory below that parent named after the project and a hash of its
+/// path, so several projects can use the same parent.
+pub fn cache_location(root: &Path, config: &Config) -> PathBuf {
+    let env = std::env::var_os(CACHE_DIR_ENV).filter(|value| !value.is_empty());
+    resolve_cache_location(root, config.cache_dir.as_deref(), env.as_deref())
+}
+
+fn resolve_cache_location(root: &Path, configured: Option<&str>, env: Option<&OsStr>) -> PathBuf {
+    let parent = match (env, configured) {
+        (Some(dir), _) => PathBuf::from(expand_tilde(&dir.to_string_lossy())),
+        (None, Some(dir)) => root.join(expand_tilde(dir)),
+        (None, None) => return root.join(super::CACHE_DIR),
+    };
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let hash = blake3::hash(root.as_os_str().as_encoded_bytes()).to_hex();
+    let name = root
+        .file_name()
+        .map_or_else(|| "site".into(), |name| name.to_string_lossy());
+    parent.join(format!("{name}-{}", &hash[..16]))
+}
+
+/// Opens the cache stored in `cache_dir`, see [`cache_location`].
+pub(super) fn open_cache_db(cache_dir: &Path) -> Result<sled::Db> {
+    open_cache_db_with_schema(cache_dir, CACHE_SCHEMA_VERSION)
+}
+
+/// Opens a temporary copy of the cache for dry runs, which update it like a
+/// normal render while the cache on disk stays untouched.
+pub(super) fn open_cache_snapshot(cache_dir: &Path) -> Result<sled::Db> {
+    let snapshot = sled::Config::new()
+        .temporary(true)
+        .open()
+        .context("failed to open temporary cache database")?;
+    let sled_dir = cache_dir.join("sled");
+    if sled_dir.exists() {
+        let db = open_waiting_for_lock(&sled_dir).map_err(|error| {
+            let locked = is_lock_error(&error);
+            let error = anyhow!(error);
+            if locked {
+                error.context(LOCKED_MESSAGE)
+            } else {
+                error.context("failed to open cache database")
+            }
+        })?;
+        snapshot.import(db.export());
+    }
+    reset_outdated_cache(&snapshot, CACHE_SCHEMA_VERSION)?;
+    Ok(snapshot)
+}
+
+fn open_cache_db_with_schema(cache_dir: &Path, schema: u32) -> Result<sled::Db> {
+    let db = open_sled(cache_dir)?;
+    reset_outdated_cache(&db, schema)?;
+    Ok(db)
+}
+
+fn open_sled(cache_dir: &Path) -> Result<sled::Db> {
+    fs::create_dir_all(cache_dir)
         .with_context(|| format!("failed to create cache directory {}", cache_dir.display()))?;
-    sled::open(cache_dir.join("sled")).context("failed to open cache database")
+
+    match open_waiting_for_lock(&cache_dir.join("sled")) {
+        Ok(db) => Ok(db),
+        Err(error) if is_lock_error(&error) => Err(anyhow!(error)).context(LOCKED_MESSAGE),
+        Err(error) if is_corruption(&error) => {
+            let moved = quarantine_cache_dir(cache_dir)?;
+            logging::warn(
+                "CACHE",
+                format!(
+                    "Cache database was unreadable ({error}); moved it to {} and starting a full rebuild",
+                    moved.display()
+                ),
+            );
+            fs::create_dir_all(cache_dir).with_context(|| {
+                format!("failed to create cache directory {}", cache_dir.display())
+            })?;
+            sled::open(cache_dir.join("sled")).context("failed to open cache database")
+        }
+        Err(error) => Err(anyhow!(error)).context("failed to open cache database"),
+    }
+}
+
+/// Clears caches written by another schema version (or before versions were
+/// recorded), which forces a full rebuild, and records `schema` as current.
+/// Returns whether the cache was reset.
+fn reset_outdated_cache(db: &sled::Db, schema: u32) -> Result<bool> {
+    let stored = read_cached_string(db, CACHE_SCHEMA_KEY)?;
+    if stored.as_deref() == Some(schema.to_string().as_str()) {
+        return Ok(false);
+    }
+    let reset = stored.is_some() || !db.is_empty();
+    if reset {
+        logging::warn(
+            "CACHE",
+            format!(
+                "Cache was written with schema {} but this bckt uses {schema}; cleared it and starting a full rebuild",
+                stored.as_deref().unwrap_or("(unversioned)")
+            ),
+        );
+        db.clear().context("failed to reset cache database")?;
+    }
+    store_cached_string(db, CACHE_SCHEMA_KEY, &schema.to_string())?;
+    Ok(reset)
+}
+
+/// sled finishes background writes after a `Db` is dropped, so a render that
+/// follows another one in the same process can briefly see the lock held.
+fn open_waiting_for_lock(path: &Path) -> sled::Result<sled::Db> {
+    let mut attempts = 0;
+    loop {
+        match sled::open(path) {
+            Err(error) if is_lock_error(&error) && attempts < LOCK_RETRIES => {
+                attempts += 1;
+                thread::sleep(LOCK_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_lock_error(error: &sled::Error) -> bool {
+    matches!(error, sled::Error::Io(io) if io.to_string().contains("could not acquire lock"))
+}
+
+fn is_corruption(error: &sled::Error) -> bool {
+    match error {
+        sled::Error::Corruption { .. }
+        | sled::Error::Unsupported(_)
+        | sled::Error::ReportableBug(_) => true,
+        sled::Error::Io(io) => matches!(
+            io.kind(),
+            ErrorKind::InvalidData
+                | ErrorKind::UnexpectedEof
+                | ErrorKind::NotADirectory
+                | ErrorKind::IsADirectory
+        ),
+        _ => false,
+    }
+}
+
+/// Moves a broken cache aside as `cache.corrupt-<timestamp>` so it can be
+/// inspected later instead of being deleted outright.
+fn quarantine_cache_dir(cache_dir: &Path) -> Result<PathBuf> {
+    let name = cache_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("cache");
+    let timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let mut target = cache_dir.with_file_name(format!("{name}.corrupt-{timestamp}"));
+    let mut attempt = 1;
+    while target.exists() {
+        target = cache_dir.with_file_name(format!("{name}.corrupt-{timestamp}-{attempt}"));
+        attempt += 1;
+    }
+    fs::rename(cache_dir, &target).with_context(|| {
+        format!(
+            "failed to move corrupted cache {} to {}",
+            cache_dir.display(),
+            target.display()
+        )
+    })?;
+    Ok(target)
 }
 
 pub(super) fn read_cached_string(db: &sled::Db, key: &str) -> Result<Option<String>> {
@@ -28,3 +211,308 @@ pub(super) fn store_cached_string(db: &sled::Db, key: &str, value: &str) -> Resu
         .with_context(|| format!("failed to update cache key {}", key))?;
     Ok(())
 }
+
+/// Stores the guesses of `inner` under `lang:<hash of the text>`, so posts
+/// whose text did not change skip detection on the next discovery. The keys
+/// it touched are kept for [`CachedLanguageDetector::into_used_keys`], which
+/// lets the render drop guesses for text that no longer exists.
+pub(super) struct CachedLanguageDetector<'a, D> {
+    db: &'a sled::Db,
+    inner: D,
+    used: RefCell<BTreeSet<String>>,
+}
+
+impl<'a, D: LanguageDetector> CachedLanguageDetector<'a, D> {
+    pub(super) fn new(db: &'a sled::Db, inner: D) -> Self {
+        Self {
+            db,
+            inner,
+            used: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    pub(super) fn into_used_keys(self) -> BTreeSet<String> {
+        self.used.into_inner()
+    }
+}
+
+impl<D: LanguageDetector> LanguageDetector for CachedLanguageDetector<'_, D> {
+    fn detect(&self, text: &str) -> Option<String> {
+        let key = format!(
+            "{LANGUAGE_PREFIX}{}",
+            blake3::hash(text.as_bytes()).to_hex()
+        );
+        self.used.borrow_mut().insert(key.clone());
+        // An unreadable entry only costs a fresh detection. Texts without a
+        // reliable guess are stored as an empty value.
+        if let Ok(Some(guess)) = read_cached_string(self.db, &key) {
+            return (!guess.is_empty()).then_some(guess);
+        }
+        let guess = self.inner.detect(text);
+        let _ = store_cached_string(self.db, &key, guess.as_deref().unwrap_or_default());
+        guess
+    }
+}
+
+/// Keeps post bodies under `body:<content file>`, and their search text under
+/// `search_text:<content file>`, for `low_memory` builds. Like
+/// [`CachedLanguageDetector`], it remembers the keys it wrote so the render
+/// can drop the entries of posts that are gone.
+pub(super) struct CachedBodyStore<'a> {
+    db: &'a sled::Db,
+    used: RefCell<BTreeSet<String>>,
+}
+
+impl<'a> CachedBodyStore<'a> {
+    pub(super) fn new(db: &'a sled::Db) -> Self {
+        Self {
+            db,
+            used: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    pub(super) fn into_used_keys(self) -> BTreeSet<String> {
+        self.used.into_inner()
+    }
+}
+
+fn body_key(content_path: &Path, part: BodyPart) -> String {
+    let prefix = match part {
+        BodyPart::Html => BODY_PREFIX,
+        BodyPart::SearchText => SEARCH_TEXT_PREFIX,
+    };
+    format!("{prefix}{}", content_path.display())
+}
+
+impl BodyStore for CachedBodyStore<'_> {
+    fn put(&self, content_path: &Path, part: BodyPart, text: &str) -> Result<()> {
+        let key = body_key(content_path, part);
+        // Unchanged entries are not written again, which keeps sled's log short.
+        if read_cached_string(self.db, &key)?.as_deref() != Some(text) {
+            store_cached_string(self.db, &key, text)?;
+        }
+        self.used.borrow_mut().insert(key);
+        Ok(())
+    }
+
+    fn get(&self, content_path: &Path, part: BodyPart) -> Result<String> {
+        read_cached_string(self.db, &body_key(content_path, part))?.with_context(|| {
+            let what = match part {
+                BodyPart::Html => "body",
+                BodyPart::SearchText => "search text",
+            };
+            format!(
+                "{what} of {} is missing from the cache",
+                content_path.display()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempfile::TempDir;
+
+    fn read_cache_schema_version(db: &sled::Db) -> Result<Option<u32>> {
+        match read_cached_string(db, CACHE_SCHEMA_KEY)? {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .with_context(|| format!("invalid cache schema version {value:?}")),
+            None => Ok(None),
+        }
+    }
+
+    fn corrupt_cache(cache_dir: &Path) {
+        let sled_dir = cache_dir.join("sled");
+        fs::create_dir_all(&sled_dir).unwrap();
+        fs::write(sled_dir.join("conf"), b"not a sled config").unwrap();
+        fs::write(sled_dir.join("db"), b"junk").unwrap();
+    }
+
+    #[test]
+    fn corrupted_cache_is_moved_aside() {
+        let temp = TempDir::new().unwrap();
+        let cache_dir = temp.path().join("cache");
+        corrupt_cache(&cache_dir);
+
+        let db = open_cache_db(&cache_dir).unwrap();
+        store_cached_string(&db, "key", "value").unwrap();
+        assert_eq!(
+            read_cached_string(&db, "key").unwrap().as_deref(),
+            Some("value")
+        );
+
+        let quarantined: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.starts_with("cache.corrupt-"))
+            .collect();
+        assert_eq!(quarantined.len(), 1);
+    }
+
+    #[test]
+    fn cache_records_schema_version() {
+        let temp = TempDir::new().unwrap();
+        let db = open_cache_db(temp.path()).unwrap();
+        assert_eq!(
+            read_cache_schema_version(&db).unwrap(),
+            Some(CACHE_SCHEMA_VERSION)
+        );
+    }
+
+    #[test]
+    fn bumped_schema_version_clears_prior_entries() {
+        let temp = TempDir::new().unwrap();
+        {
+            let db = open_cache_db_with_schema(temp.path(), 1).unwrap();
+            store_cached_string(&db, "key", "value").unwrap();
+            db.flush().unwrap();
+        }
+        {
+            let db = open_cache_db_with_schema(temp.path(), 1).unwrap();
+            assert_eq!(
+                read_cached_string(&db, "key").unwrap().as_deref(),
+                Some("value")
+            );
+        }
+
+        let db = open_cache_db_with_schema(temp.path(), 2).unwrap();
+        assert_eq!(read_cached_string(&db, "key").unwrap(), None);
+        assert_eq!(read_cache_schema_version(&db).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn unversioned_cache_is_reset() {
+        let temp = TempDir::new().unwrap();
+        let db = open_sled(temp.path()).unwrap();
+        store_cached_string(&db, "key", "value").unwrap();
+
+        assert!(reset_outdated_cache(&db, CACHE_SCHEMA_VERSION).unwrap());
+        assert_eq!(read_cached_string(&db, "key").unwrap(), None);
+        assert!(!reset_outdated_cache(&db, CACHE_SCHEMA_VERSION).unwrap());
+    }
+
+    #[test]
+    fn snapshot_leaves_cache_on_disk_untouched() {
+        let temp = TempDir::new().unwrap();
+        {
+            let db = open_cache_db(temp.path()).unwrap();
+            store_cached_string(&db, "key", "value").unwrap();
+            db.flush().unwrap();
+        }
+
+        let snapshot = open_cache_snapshot(temp.path()).unwrap();
+        assert_eq!(
+            read_cached_string(&snapshot, "key").unwrap().as_deref(),
+            Some("value")
+        );
+        store_cached_string(&snapshot, "key", "changed").unwrap();
+        drop(snapshot);
+
+        let db = open_cache_db(temp.path()).unwrap();
+        assert_eq!(
+            read_cached_string(&db, "key").unwrap().as_deref(),
+            Some("value")
+        );
+    }
+
+    /// Answers "en" for any text and counts how often it was asked.
+    struct CountingDetector(Cell<usize>);
+
+    impl LanguageDetector for CountingDetector {
+        fn detect(&self, text: &str) -> Option<String> {
+            self.0.set(self.0.get() + 1);
+            (!text.is_empty()).then(|| "en".to_string())
+        }
+    }
+
+    #[test]
+    fn language_guesses_are_reused_for_the_same_text() {
+        let temp = TempDir::new().unwrap();
+        let db = open_cache_db(temp.path()).unwrap();
+        let first = CachedLanguageDetector::new(&db, CountingDetector(Cell::new(0)));
+        assert_eq!(first.detect("Some text").as_deref(), Some("en"));
+        assert_eq!(first.detect("").as_deref(), None);
+        assert_eq!(first.inner.0.get(), 2);
+
+        let second = CachedLanguageDetector::new(&db, CountingDetector(Cell::new(0)));
+        assert_eq!(second.detect("Some text").as_deref(), Some("en"));
+        assert_eq!(second.detect("").as_deref(), None);
+        assert_eq!(second.inner.0.get(), 0);
+        assert_eq!(second.detect("Other text").as_deref(), Some("en"));
+        assert_eq!(second.inner.0.get(), 1);
+
+        let keys = second.into_used_keys();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.iter().all(|key| key.starts_with(LANGUAGE_PREFIX)));
+    }
+
+    #[test]
+    fn locked_cache_points_at_clean() {
+        let temp = TempDir::new().unwrap();
+        let _held = open_cache_db(temp.path()).unwrap();
+
+        let error = open_cache_db(temp.path()).unwrap_err();
+        assert!(format!("{error:#}").contains("bckt clean --cache"));
+    }
+
+    #[test]
+    fn opening_waits_for_a_lock_released_shortly() {
+        let temp = TempDir::new().unwrap();
+        let held = open_cache_db(temp.path()).unwrap();
+        let release = thread::spawn(move || {
+            thread::sleep(LOCK_RETRY_DELAY * 2);
+            drop(held);
+        });
+
+        let db = open_cache_db(temp.path());
+        release.join().unwrap();
+        assert!(db.is_ok(), "{:#}", db.unwrap_err());
+    }
+
+    #[test]
+    fn environment_overrides_configured_cache_dir() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("blog");
+        fs::create_dir_all(&root).unwrap();
+
+        assert_eq!(
+            resolve_cache_location(&root, None, None),
+            root.join(".bckt/cache")
+        );
+        let configured = resolve_cache_location(&root, Some("../caches"), None);
+        assert!(configured.starts_with(root.join("../caches")));
+        let shared = temp.path().join("shared");
+        let from_env = resolve_cache_location(&root, Some("../caches"), Some(shared.as_os_str()));
+        assert_eq!(from_env.parent(), Some(shared.as_path()));
+        let name = from_env.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("blog-"), "{name}");
+    }
+
+    #[test]
+    fn projects_sharing_a_cache_parent_get_their_own_cache() {
+        let temp = TempDir::new().unwrap();
+        let shared = temp.path().join("shared");
+        let first = temp.path().join("one/blog");
+        let second = temp.path().join("two/blog");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+
+        let first_dir = resolve_cache_location(&first, None, Some(shared.as_os_str()));
+        let second_dir = resolve_cache_location(&second, None, Some(shared.as_os_str()));
+        assert_ne!(first_dir, second_dir);
+        assert_eq!(
+            resolve_cache_location(&first.join("../blog"), None, Some(shared.as_os_str())),
+            first_dir
+        );
+
+        let db = open_cache_db(&first_dir).unwrap();
+        store_cached_string(&db, "key", "first").unwrap();
+        drop(db);
+        let db = open_cache_db(&second_dir).unwrap();
+        assert_eq!(read_cached_string(&db, "key").unwrap(), None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::Path;
+
+/// Attachment formats that can carry camera metadata such as GPS positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ImageFormat {
+    Jpeg,
+    Png,
+    WebP,
+    /// Formats with metadata that [`strip_metadata`] cannot remove.
+    Unsupported,
+}
+
+impl ImageFormat {
+    /// The format of the image at `path` by its extension, or `None` for
+    /// files that carry no camera metadata.
+    pub(super) fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "webp" => Some(Self::WebP),
+            "tif" | "tiff" | "heic" | "heif" | "avif" | "dng" => Some(Self::Unsupported),
+            _ => None,
+        }
+    }
+}
+
+/// `data` without its EXIF, XMP, IPTC, and text metadata, leaving the image
+/// itself and its color profile alone. `None` when the format is unsupported
+/// or the file does not parse.
+pub(super) fn strip_metadata(format: ImageFormat, data: &[u8]) -> Option<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => strip_jpeg(data),
+        ImageFormat::Png => strip_png(data),
+        ImageFormat::WebP => strip_webp(data),
+        ImageFormat::Unsupported => None,
+    }
+}
+
+/// Drops the APP1 (EXIF, XMP), APP13 (IPTC), and comment segments ahead of
+/// the image data.
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        // Markers may be preceded by any number of 0xFF fill bytes.
+        let mut marker_pos = pos + 1;
+        while *data.get(marker_pos)? == 0xFF {
+            marker_pos += 1;
+        }
+        let marker = data[marker_pos];
+        let header_end = marker_pos + 1;
+        match marker {
+            // The scan data follows start of scan; end of image ends it all.
+            0xDA | 0xD9 => {
+                out.extend_from_slice(&data[pos..]);
+                return Some(out);
+            }
+            // Markers without a length.
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&data[pos..header_end]);
+                pos = header_end;
+                continue;
+            }
+            _ => {}
+        }
+        let length = u16::from_be_bytes([*data.get(header_end)?, *data.get(header_end + 1)?]);
+        if length < 2 {
+            return None;
+        }
+        let end = header_end + usize::from(length);
+        if end > data.len() {
+            return None;
+        }
+        if !matches!(marker, 0xE1 | 0xED | 0xFE) {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Drops the `eXIf`, text, and `tIME` chunks.
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+    loop {
+        let header = data.get(pos..pos + 8)?;
+        let length = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+        let kind = &header[4..];
+        // Length, type, data, and CRC.
+        let end = pos.checked_add(length)?.checked_add(12)?;
+        if end > data.len() {
+            return None;
+        }
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        if kind == b"IEND" {
+            return Some(out);
+        }
+        pos = end;
+    }
+}
+
+/// Bits of the `VP8X` flags byte announcing `EXIF` and `XMP ` chunks.
+const VP8X_METADATA_FLAGS: u8 = 0x08 | 0x04;
+
+/// Drops the `EXIF` and `XMP ` chunks and their flags, fixing up the RIFF
+/// size.
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(&data[..12]);
+    let mut pos = 12;
+    while pos < data.len() {
+        let header = data.get(pos..pos + 8)?;
+        let size = u32::from_le_bytes(header[4..].try_into().ok()?) as usize;
+        let kind = &header[..4];
+        // Chunks are padded to an even size.
+        let end = (pos + 8).checked_add(size)?.checked_add(size % 2)?;
+        if end > data.len() {
+            return None;
+        }
+        match kind {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" if size > 0 => {
+                let flags = out.len() + 8;
+                out.extend_from_slice(&data[pos..end]);
+                out[flags] &= !VP8X_METADATA_FLAGS;
+            }
+            _ => out.extend_from_slice(&data[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0, 0, 0, 0]);
+        chunk
+    }
+
+    fn webp_chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = kind.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    fn riff(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = chunks.concat();
+        let mut file = b"RIFF".to_vec();
+        file.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+        file.extend_from_slice(b"WEBP");
+        file.extend_from_slice(&body);
+        file
+    }
+
+    #[test]
+    fn png_loses_exif_and_text_chunks() {
+        let header = png_chunk(b"IHDR", &[0; 13]);
+        let image = png_chunk(b"IDAT", b"pixels");
+        let end = png_chunk(b"IEND", &[]);
+        let source = [
+            PNG_SIGNATURE.to_vec(),
+            header.clone(),
+            png_chunk(b"eXIf", b"GPS"),
+            png_chunk(b"tEXt", b"Author\0me"),
+            image.clone(),
+            end.clone(),
+        ]
+        .concat();
+        assert_eq!(
+            strip_metadata(ImageFormat::Png, &source).unwrap(),
+            [PNG_SIGNATURE.to_vec(), header, image, end].concat()
+        );
+        assert_eq!(strip_metadata(ImageFormat::Png, &source[..20]), None);
+    }
+
+    #[test]
+    fn webp_loses_exif_and_xmp_chunks_and_flags() {
+        let source = riff(&[
+            webp_chunk(b"VP8X", &[0x2C, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            webp_chunk(b"VP8 ", b"frame"),
+            webp_chunk(b"EXIF", b"GPS"),
+            webp_chunk(b"XMP ", b"<x/>"),
+        ]);
+        assert_eq!(
+            strip_metadata(ImageFormat::WebP, &source).unwrap(),
+            riff(&[
+                webp_chunk(b"VP8X", &[0x20, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+                webp_chunk(b"VP8 ", b"frame"),
+            ])
+        );
+    }
+
+    #[test]
+    fn formats_are_detected_by_extension() {
+        assert_eq!(
+            ImageFormat::detect(Path::new("a/IMG.JPG")),
+            Some(ImageFormat::Jpeg)
+        );
+        assert_eq!(
+            ImageFormat::detect(Path::new("photo.heic")),
+            Some(ImageFormat::Unsupported)
+        );
+        assert_eq!(ImageFormat::detect(Path::new("notes.pdf")), None);
+        assert_eq!(strip_metadata(ImageFormat::Jpeg, b"not a jpeg"), None);
+    }
+}
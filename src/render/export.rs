@@ -0,0 +1,362 @@
+use std::fs::{self, File};
+use std::io::{self, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use tempfile::NamedTempFile;
+use walkdir::WalkDir;
+use zip::ZipWriter;
+use zip::write::FileOptions;
+
+use crate::config::{self, Config};
+
+use super::CACHE_DIR;
+use super::assets::is_ignored;
+use super::cache::cache_location;
+use super::utils::normalize_path;
+
+/// Kind of archive written by [`export_site`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ExportOptions {
+    pub format: ArchiveFormat,
+    /// Only `posts/` and `pages/`.
+    pub content_only: bool,
+    /// Also the active theme's directory under `themes/`.
+    pub include_theme: bool,
+}
+
+/// Hidden files that belong to the sources, such as the markers that keep
+/// directories out of the posts.
+const KEPT_SOURCE_FILES: &[&str] = &["!.bcktignore"];
+
+/// Packs the sources of the site into `output`: the config file, posts,
+/// pages, templates, and `skel/`, never `html/` or the cache. Files are stored
+/// in path order without modification times, so exporting unchanged sources
+/// again gives the same bytes. Returns the number of files stored.
+pub fn export_site(
+    root: &Path,
+    profile: Option<&str>,
+    output: &Path,
+    options: ExportOptions,
+) -> Result<usize> {
+    let config = Config::load_with_profile(config::config_path(root), profile)?;
+    let files = export_files(root, &config, output, options)?;
+
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut temp = NamedTempFile::new_in(parent)
+        .with_context(|| format!("failed to create a file in {}", parent.display()))?;
+    match options.format {
+        ArchiveFormat::Zip => write_zip(temp.as_file_mut(), &files)?,
+        ArchiveFormat::TarGz => write_tar_gz(temp.as_file_mut(), &files)?,
+    }
+    temp.persist(output)
+        .with_context(|| format!("failed to write {}", output.display()))?;
+    Ok(files.len())
+}
+
+/// The files to export, keyed by their `/`-separated path in the archive and
+/// sorted by it.
+fn export_files(
+    root: &Path,
+    config: &Config,
+    output: &Path,
+    options: ExportOptions,
+) -> Result<Vec<(String, PathBuf)>> {
+    let excluded = [
+        root.join("html"),
+        root.join(CACHE_DIR),
+        cache_location(root, config),
+        absolute(output)?,
+    ];
+    let content_rules: Vec<String> = KEPT_SOURCE_FILES.iter().map(|s| s.to_string()).collect();
+
+    let mut dirs = vec![
+        (config.posts_path(root), content_rules.as_slice()),
+        (config.pages_path(root), content_rules.as_slice()),
+    ];
+    if !options.content_only {
+        dirs.push((config.templates_path(root), content_rules.as_slice()));
+        // skel/ and the theme skip what renders would not publish.
+        dirs.push((config.static_path(root), config.static_ignore.as_slice()));
+        if options.include_theme
+            && let Some(theme) = config.theme.as_deref()
+        {
+            let theme_dir = root.join("themes").join(theme);
+            if !theme_dir.is_dir() {
+                bail!("theme '{theme}' is not installed in themes/{theme}");
+            }
+            dirs.push((theme_dir, config.static_ignore.as_slice()));
+        }
+    }
+
+    let mut files = Vec::new();
+    if !options.content_only {
+        let config_file = config::config_path(root);
+        if config_file.is_file() {
+            files.push(archive_entry(root, config_file)?);
+        }
+    }
+    for (dir, ignore) in dirs {
+        for path in source_files(&dir, ignore, &excluded)? {
+            files.push(archive_entry(root, path)?);
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(std::env::current_dir()
+        .context("failed to read the current directory")?
+        .join(path))
+}
+
+fn archive_entry(root: &Path, path: PathBuf) -> Result<(String, PathBuf)> {
+    let Ok(relative) = path.strip_prefix(root) else {
+        bail!("{} is outside the project", path.display());
+    };
+    Ok((normalize_path(relative), path))
+}
+
+/// Every file below `dir` that the static asset `ignore` rules keep, leaving
+/// out the `excluded` paths.
+fn source_files(dir: &Path, ignore: &[String], excluded: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || (!excluded.iter().any(|path| entry.path() == path)
+                && !is_ignored(
+                    &normalize_path(entry.path().strip_prefix(dir).unwrap()),
+                    ignore,
+                ))
+    });
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+fn write_zip(file: &mut File, files: &[(String, PathBuf)]) -> Result<()> {
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+    for (name, path) in files {
+        zip.start_file(name.as_str(), options)
+            .with_context(|| format!("failed to add {name} to the archive"))?;
+        copy_into(path, &mut zip)?;
+    }
+    zip.finish().context("failed to finish the zip archive")?;
+    Ok(())
+}
+
+fn write_tar_gz(file: &mut File, files: &[(String, PathBuf)]) -> Result<()> {
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for (name, path) in files {
+        let len = fs::metadata(path)
+            .with_context(|| format!("failed to inspect {}", path.display()))?
+            .len();
+        let source =
+            File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+        tar.append_data(&mut tar_header(len), name, source)
+            .with_context(|| format!("failed to add {name} to the archive"))?;
+    }
+    tar.into_inner()
+        .and_then(GzEncoder::finish)
+        .context("failed to finish the tar.gz archive")?
+        .rewind()?;
+    Ok(())
+}
+
+fn copy_into(path: &Path, writer: &mut impl Write) -> Result<()> {
+    let mut source =
+        File::open(path).with_context(|| format!("failed to read {}", path.display()))?;
+    io::copy(&mut source, writer)
+        .with_context(|| format!("failed to archive {}", path.display()))?;
+    Ok(())
+}
+
+/// Header of a regular file owned by root, with mode 0644 and no
+/// modification time. Paths too long for the header get a GNU long-name
+/// entry when appended.
+fn tar_header(len: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(len);
+    header.set_mode(0o644);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_mtime(0);
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn site() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(root, "bckt.yaml", "title: Export\n");
+        write(root, "posts/hello/post.md", "---\ntitle: Hello\n---\nHi\n");
+        write(root, "posts/drafts/.bcktignore", "");
+        write(root, "pages/about.md", "About\n");
+        write(root, "templates/post.html", "{{ post.body }}");
+        write(root, "skel/site.css", "body {}");
+        write(root, "skel/.DS_Store", "junk");
+        write(root, "themes/bckt3/templates/post.html", "theme");
+        write(root, "html/index.html", "output");
+        write(root, ".bckt/cache/db", "cache");
+        temp
+    }
+
+    fn options(format: ArchiveFormat) -> ExportOptions {
+        ExportOptions {
+            format,
+            content_only: false,
+            include_theme: false,
+        }
+    }
+
+    fn tar_names(archive: &Path) -> Vec<String> {
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(File::open(archive).unwrap()));
+        tar.entries()
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                assert_eq!(entry.header().mode().unwrap(), 0o644);
+                assert_eq!(entry.header().mtime().unwrap(), 0);
+                entry.path().unwrap().to_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    fn zip_names(archive: &Path) -> Vec<String> {
+        let mut zip = zip::ZipArchive::new(File::open(archive).unwrap()).unwrap();
+        (0..zip.len())
+            .map(|index| zip.by_index(index).unwrap().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn exports_sources_without_output_or_cache() {
+        let temp = site();
+        let root = temp.path();
+        let archive = root.join("site.tar.gz");
+        let count = export_site(root, None, &archive, options(ArchiveFormat::TarGz)).unwrap();
+
+        let expected = [
+            "bckt.yaml",
+            "pages/about.md",
+            "posts/drafts/.bcktignore",
+            "posts/hello/post.md",
+            "skel/site.css",
+            "templates/post.html",
+        ];
+        assert_eq!(count, expected.len());
+        assert_eq!(tar_names(&archive), expected);
+
+        let archive = root.join("site.zip");
+        let theme = ExportOptions {
+            include_theme: true,
+            ..options(ArchiveFormat::Zip)
+        };
+        export_site(root, None, &archive, theme).unwrap();
+        let names = zip_names(&archive);
+        assert!(names.contains(&"themes/bckt3/templates/post.html".to_string()));
+        assert!(!names.iter().any(|name| name.starts_with("html/")));
+
+        let content = ExportOptions {
+            content_only: true,
+            ..options(ArchiveFormat::Zip)
+        };
+        export_site(root, None, &archive, content).unwrap();
+        assert_eq!(
+            zip_names(&archive),
+            [
+                "pages/about.md",
+                "posts/drafts/.bcktignore",
+                "posts/hello/post.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn output_and_cache_stay_out_when_a_source_dir_is_the_root() {
+        let temp = site();
+        let root = temp.path();
+        write(root, "bckt.yaml", "static_dir: .\n");
+        let archive = root.join("backup.zip");
+        export_site(root, None, &archive, options(ArchiveFormat::Zip)).unwrap();
+        export_site(root, None, &archive, options(ArchiveFormat::Zip)).unwrap();
+
+        let names = zip_names(&archive);
+        assert!(names.contains(&"skel/site.css".to_string()));
+        for name in &names {
+            assert!(
+                !name.starts_with("html/") && !name.starts_with(".bckt/") && name != "backup.zip",
+                "{names:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn repeated_exports_are_identical() {
+        let temp = site();
+        let root = temp.path();
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let first = root.join("first");
+            let second = root.join("second");
+            export_site(root, None, &first, options(format)).unwrap();
+            File::options()
+                .write(true)
+                .open(root.join("posts/hello/post.md"))
+                .unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(3600))
+                .unwrap();
+            export_site(root, None, &second, options(format)).unwrap();
+            assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+        }
+    }
+
+    #[test]
+    fn long_paths_are_kept_whole() {
+        let temp = site();
+        let root = temp.path();
+        let long = format!("posts/{}/post.md", "a".repeat(200));
+        write(root, &long, "---\ntitle: Long\n---\n");
+        let archive = root.join("site.tar.gz");
+        export_site(root, None, &archive, options(ArchiveFormat::TarGz)).unwrap();
+        assert!(tar_names(&archive).contains(&long));
+    }
+}
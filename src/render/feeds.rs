@@ -1,79 +1,233 @@
-use std::collections::{BTreeMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
 use std::fs;
-use std::path::Path;
 
 use anyhow::{Context, Result};
 use minijinja::Environment;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 use time::OffsetDateTime;
-
-use crate::config::Config;
-use crate::content::Post;
-use crate::utils::absolute_url;
-
-use super::listing::{page_url, tag_index_url, tag_slug};
-use super::posts::{PostSummary, att_to_absolute, build_post_summary};
+use time::format_description::well_known::Rfc3339;
+
+use crate::config::{Config, FeedContent};
+use crate::content::{Post, sanitize_html};
+use crate::urls::{HOME_URL, absolute_url};
+
+use super::cache::{read_cached_string, store_cached_string};
+use super::listing::{
+    PageLayout, by_weight, homepage_order, is_untagged, page_url, tag_index_url, tag_page_size,
+    tag_slug,
+};
+use super::output::Output;
+use super::posts::{
+    PostBodies, PostSummary, build_post_summary, cleanup_stale_keys, newest_change, thumbnail_url,
+};
 use super::templates::render_template_with_scope;
-use super::utils::{format_rfc2822, format_rfc3339, sanitize_cdata, xml_escape};
+use super::utils::{format_rfc2822, format_rfc3339, normalize_path, sanitize_cdata, xml_escape};
+use super::{BuildMode, LASTMOD_PREFIX};
+
+const UPDATES_FEED_KEY: &str = "updates_feed_hash";
+const UPDATES_FEED_FILE: &str = "updates.xml";
+
+// Characters that cannot appear unescaped in a URL path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
 
 pub(super) fn render_feeds(
     posts: &[Post],
-    html_root: &Path,
+    html: &Output,
     config: &Config,
     env: &Environment<'static>,
+    bodies: &PostBodies<'_>,
+    mode: BuildMode,
 ) -> Result<()> {
-    render_rss(posts, html_root, config, env)?;
-
-    for tag in config_tag_feeds(config) {
-        let slug = tag_slug(&tag);
+    render_rss(posts, html, config, env, bodies)?;
+
+    let mut written = HashSet::new();
+    for TagFeed {
+        tag,
+        language,
+        title: feed_title,
+    } in config_tag_feeds(config)
+    {
+        let Some(slug) = tag_slug(config, &tag) else {
+            continue;
+        };
+        if !written.insert(slug.clone()) {
+            continue;
+        }
+        // The same posts as the tag archive, which merges tags by slug.
+        let archive = tag_slug(config, config.canonical_tag(&tag));
         let tag_posts: Vec<&Post> = posts
             .iter()
             .rev()
-            .filter(|post| post.tags.iter().any(|t| t.eq(&tag)))
+            .filter(|post| {
+                post.tags
+                    .iter()
+                    .any(|t| tag_slug(config, config.canonical_tag(t)) == archive)
+            })
             .collect();
-        let output_path = html_root.join(format!("rss-{}.xml", slug));
-        let title = config.title.clone().unwrap_or_else(|| "bckt".to_string());
-        let feed_title = format!("{} · {}", tag, title);
-        let site_path = format!("/tags/{}/", slug);
+        let feed_title = feed_title.unwrap_or_else(|| {
+            let title = config.title.as_deref().unwrap_or("bckt");
+            format!("{tag} · {title}")
+        });
+        let site_path = tag_index_url(&slug);
         let feed_path = format!("/rss-{}.xml", slug);
-        render_feed(
-            tag_posts,
-            config,
-            env,
-            &site_path,
-            &feed_path,
-            &output_path,
-            Some(feed_title),
-        )?;
+        let description = config
+            .feeds
+            .tag_descriptions
+            .iter()
+            .find(|(name, _)| tag_slug(config, config.canonical_tag(name)).as_ref() == Some(&slug))
+            .map(|(_, description)| description.as_str());
+        let content = config
+            .feeds
+            .tag_content
+            .iter()
+            .find(|(name, _)| tag_slug(config, config.canonical_tag(name)).as_ref() == Some(&slug))
+            .map_or(config.feeds.content, |(_, content)| *content);
+        let feed = FeedSpec {
+            site_path: &site_path,
+            feed_path: &feed_path,
+            title: Some(feed_title),
+            description,
+            content,
+            updates: false,
+            language: language.as_deref().unwrap_or(feed_language(config)),
+        };
+        let rendered = render_feed(tag_posts, config, env, bodies, feed)?;
+        html.write(&html.root().join(format!("rss-{slug}.xml")), rendered)?;
     }
 
-    render_sitemap(posts, html_root, config)?;
+    render_updates(posts, html, config, env, bodies, mode)?;
+    render_sitemap(posts, html, config, bodies.cache_db())?;
     Ok(())
 }
 
 fn render_rss(
     posts: &[Post],
-    html_root: &Path,
+    html: &Output,
     config: &Config,
     env: &Environment<'static>,
+    bodies: &PostBodies<'_>,
 ) -> Result<()> {
-    let output_path = html_root.join("rss.xml");
     // Posts are sorted ascending, but RSS feeds should show newest first
     let posts_ref: Vec<&Post> = posts.iter().rev().collect();
-    render_feed(posts_ref, config, env, "/", "/rss.xml", &output_path, None)
+    let feed = FeedSpec {
+        site_path: HOME_URL,
+        feed_path: "/rss.xml",
+        title: None,
+        description: config.description.as_deref(),
+        content: config.feeds.content,
+        updates: false,
+        language: feed_language(config),
+    };
+    let rendered = render_feed(posts_ref, config, env, bodies, feed)?;
+    html.write(&html.root().join("rss.xml"), rendered)
+}
+
+/// Writes `/updates.xml` when `feeds.updates` is on, skipping the write when
+/// the feed is unchanged, and removes it again once the option is off.
+fn render_updates(
+    posts: &[Post],
+    html: &Output,
+    config: &Config,
+    env: &Environment<'static>,
+    bodies: &PostBodies<'_>,
+    mode: BuildMode,
+) -> Result<()> {
+    let cache_db = bodies.cache_db();
+    let path = html.root().join(UPDATES_FEED_FILE);
+    if !config.feeds.updates {
+        if read_cached_string(cache_db, UPDATES_FEED_KEY)?.is_some() {
+            html.remove_file(&path)?;
+            cache_db
+                .remove(UPDATES_FEED_KEY)
+                .context("failed to remove updates feed cache entry")?;
+        }
+        return Ok(());
+    }
+
+    let mut updated: Vec<&Post> = posts
+        .iter()
+        .filter(|post| post.updated.is_some_and(|updated| updated > post.date))
+        .collect();
+    updated.sort_by_key(|post| Reverse(post.updated));
+
+    let title = config.title.as_deref().unwrap_or("bckt");
+    let feed = FeedSpec {
+        site_path: HOME_URL,
+        feed_path: "/updates.xml",
+        title: Some(format!("Updates · {title}")),
+        description: None,
+        content: config.feeds.content,
+        updates: true,
+        language: feed_language(config),
+    };
+    let rendered = render_feed(updated, config, env, bodies, feed)?;
+    let digest = blake3::hash(rendered.as_bytes()).to_hex().to_string();
+    let cached = read_cached_string(cache_db, UPDATES_FEED_KEY)?;
+    if matches!(mode, BuildMode::Changed)
+        && cached.as_deref() == Some(digest.as_str())
+        && path.exists()
+    {
+        return Ok(());
+    }
+    html.write(&path, rendered)?;
+    store_cached_string(cache_db, UPDATES_FEED_KEY, &digest)
+}
+
+/// What sets one feed apart from the others.
+struct FeedSpec<'a> {
+    site_path: &'a str,
+    feed_path: &'a str,
+    /// The site title when `None`.
+    title: Option<String>,
+    /// The title when `None`.
+    description: Option<&'a str>,
+    content: FeedContent,
+    /// Date items by their `updated` date and mention the original one.
+    updates: bool,
+    /// The channel `<language>`.
+    language: &'a str,
+}
+
+/// `feeds.language`, or else the search default language.
+fn feed_language(config: &Config) -> &str {
+    config
+        .feeds
+        .language
+        .as_deref()
+        .unwrap_or(&config.search.default_language)
+        .trim()
 }
 
 fn render_feed(
     posts: Vec<&Post>,
     config: &Config,
     env: &Environment<'static>,
-    site_path: &str,
-    feed_path: &str,
-    output_path: &Path,
-    title: Option<String>,
-) -> Result<()> {
+    bodies: &PostBodies<'_>,
+    feed: FeedSpec<'_>,
+) -> Result<String> {
+    let FeedSpec {
+        site_path,
+        feed_path,
+        title,
+        description,
+        content,
+        updates,
+        language,
+    } = feed;
     let template = env
         .get_template("rss.xml")
         .context("rss.xml template missing")?;
@@ -82,90 +236,78 @@ fn render_feed(
     let feed_url = absolute_url(&config.base_url, feed_path);
     let resolved_title =
         title.unwrap_or_else(|| config.title.clone().unwrap_or_else(|| "bckt".to_string()));
-    let build_date = posts
-        .first()
-        .map(|post| post.date)
-        .unwrap_or_else(OffsetDateTime::now_utc);
+    // Counts the posts past the 50 listed, too.
+    let build_date = newest_change(posts.iter().copied()).unwrap_or_else(OffsetDateTime::now_utc);
     let last_build_date = format_rfc2822(&build_date)?;
 
     let items = posts
         .into_iter()
         .take(50)
-        .map(|post| build_feed_item(config, post))
+        .map(|post| build_feed_item(config, post, bodies, content, updates))
         .collect::<Result<Vec<_>>>()?;
+    let media = items
+        .iter()
+        .any(|item| item.extra.contains_key("thumbnail"));
 
     let context = FeedContext {
         title: xml_escape(&resolved_title),
         site_url: xml_escape(&site_url),
         feed_url: xml_escape(&feed_url),
-        description: xml_escape(&resolved_title),
+        description: xml_escape(description.unwrap_or(&resolved_title)),
+        language: (!language.is_empty()).then(|| xml_escape(language)),
         updated: xml_escape(&last_build_date),
+        media,
+        podcast: config.podcast.as_ref().map(|podcast| PodcastChannel {
+            author: podcast.author.as_deref().map(xml_escape),
+            image: podcast.image.as_deref().map(|image| {
+                if image.contains("://") {
+                    xml_escape(image)
+                } else {
+                    xml_escape(&absolute_url(&config.base_url, image))
+                }
+            }),
+            category: podcast.category.as_deref().map(xml_escape),
+        }),
         items,
     };
 
     let scope = format!("rendering feed {}", feed_path);
-    let rendered =
-        render_template_with_scope(&template, minijinja::context! { feed => context }, &scope)?;
-
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create {}", parent.display()))?;
-    }
-    fs::write(output_path, rendered)
-        .with_context(|| format!("failed to write {}", output_path.display()))?;
-    Ok(())
+    render_template_with_scope(&template, minijinja::context! { feed => context }, &scope)
 }
 
-fn render_sitemap(posts: &[Post], html_root: &Path, config: &Config) -> Result<()> {
-    let per_page = std::cmp::max(1, config.homepage_posts);
+fn render_sitemap(
+    posts: &[Post],
+    html: &Output,
+    config: &Config,
+    cache_db: &sled::Db,
+) -> Result<()> {
     let mut entries: Vec<SitemapEntry> = Vec::new();
+    let layout = PageLayout::new(posts.len(), config.homepage_posts);
+    let lastmods = post_lastmods(posts, cache_db)?;
 
-    // Posts are sorted ASCENDING (oldest first, newest last)
-    // Calculate pagination structure (must match listing.rs logic)
-    let remainder = posts.len() % per_page;
-    let home_page_size = if posts.len() < per_page {
-        posts.len()
-    } else if remainder == 0 {
-        per_page
-    } else if remainder < per_page {
-        remainder + per_page
-    } else {
-        per_page
-    };
-    let regular_page_count = (posts.len() - home_page_size) / per_page;
-
-    // Homepage entry (most recent posts = end of array)
-    let homepage_date = posts
-        .last()
-        .map(|post| format_rfc3339(&post.date))
-        .transpose()?;
+    // Regular page entries (page 1, 2, 3, ...), split in listing order like
+    // the pages themselves so a full page keeps its lastmod as posts arrive
+    let ordered = homepage_order(posts, config.homepage_sort);
     entries.push(SitemapEntry {
-        loc: absolute_url(&config.base_url, "/"),
-        lastmod: homepage_date,
+        loc: absolute_url(&config.base_url, HOME_URL),
+        lastmod: newest_lastmod(&ordered[layout.home_start()..], &lastmods)?,
     });
-
-    // Regular page entries (page 1, 2, 3, ...)
-    // Each page's date is the NEWEST post on that page (end of the range)
-    for page_num in 1..=regular_page_count {
-        let start = (page_num - 1) * per_page;
-        let end = start + per_page;
-        let path = page_url(page_num);
-        // The newest post on this page is at end-1 (since sorted ascending)
-        let page_date = format_rfc3339(&posts[end - 1].date)?;
+    for page_num in 1..=layout.regular_page_count {
+        let path = page_url(config, &[], page_num);
         entries.push(SitemapEntry {
             loc: absolute_url(&config.base_url, &path),
-            lastmod: Some(page_date),
+            lastmod: newest_lastmod(&ordered[layout.page_range(page_num)], &lastmods)?,
         });
     }
 
     for post in posts {
         entries.push(SitemapEntry {
             loc: absolute_url(&config.base_url, &post.permalink),
-            lastmod: Some(format_rfc3339(&post.date)?),
+            lastmod: Some(format_rfc3339(&lastmods[post.permalink.as_str()])?),
         });
     }
 
-    let tag_entries = collect_tag_sitemap_entries(posts, config)?;
+    let tag_entries = collect_tag_sitemap_entries(posts, config, &lastmods)?;
     entries.extend(tag_entries);
 
     let mut buffer = String::new();
@@ -184,23 +326,26 @@ fn render_sitemap(posts: &[Post], html_root: &Path, config: &Config) -> Result<(
     }
     writeln!(buffer, "</urlset>")?;
 
-    let output_path = html_root.join("sitemap.xml");
-    fs::write(&output_path, buffer)
-        .with_context(|| format!("failed to write {}", output_path.display()))?;
-    Ok(())
+    html.write(&html.root().join("sitemap.xml"), buffer)
 }
 
-fn collect_tag_sitemap_entries(posts: &[Post], config: &Config) -> Result<Vec<SitemapEntry>> {
+fn collect_tag_sitemap_entries(
+    posts: &[Post],
+    config: &Config,
+    lastmods: &HashMap<&str, OffsetDateTime>,
+) -> Result<Vec<SitemapEntry>> {
     let mut buckets: BTreeMap<String, TagBucket> = BTreeMap::new();
 
     for (idx, post) in posts.iter().enumerate() {
         let mut seen = HashSet::new();
         for tag in &post.tags {
-            let tag = tag.trim();
+            let tag = config.canonical_tag(tag);
             if tag.is_empty() {
                 continue;
             }
-            let slug = tag_slug(tag);
+            let Some(slug) = tag_slug(config, tag) else {
+                continue;
+            };
             if !seen.insert(slug.clone()) {
                 continue;
             }
@@ -210,6 +355,15 @@ fn collect_tag_sitemap_entries(posts: &[Post], config: &Config) -> Result<Vec<Si
             });
             bucket.indices.push(idx);
         }
+        if let Some(slug) = &config.untagged_posts_slug
+            && is_untagged(config, post)
+        {
+            let bucket = buckets.entry(slug.clone()).or_insert_with(|| TagBucket {
+                slug: slug.clone(),
+                indices: Vec::new(),
+            });
+            bucket.indices.push(idx);
+        }
     }
 
     if buckets.is_empty() {
@@ -218,68 +372,253 @@ fn collect_tag_sitemap_entries(posts: &[Post], config: &Config) -> Result<Vec<Si
 
     let mut entries = Vec::new();
 
+    let per_page = tag_page_size(config);
     for bucket in buckets.values() {
-        let first = &posts[bucket.indices[0]];
+        let ordered = by_weight(bucket.indices.iter().map(|&idx| &posts[idx]));
         entries.push(SitemapEntry {
             loc: absolute_url(&config.base_url, &tag_index_url(&bucket.slug)),
-            lastmod: Some(format_rfc3339(&first.date)?),
+            lastmod: newest_lastmod(&ordered, lastmods)?,
         });
+
+        let Some(per_page) = per_page else {
+            continue;
+        };
+        let layout = PageLayout::new(bucket.indices.len(), per_page);
+        for page_num in 1..=layout.regular_page_count {
+            entries.push(SitemapEntry {
+                loc: absolute_url(
+                    &config.base_url,
+                    &page_url(config, &["tags", &bucket.slug], page_num),
+                ),
+                lastmod: newest_lastmod(&ordered[layout.page_range(page_num)], lastmods)?,
+            });
+        }
     }
 
     Ok(entries)
 }
 
-fn build_feed_item(config: &Config, post: &Post) -> Result<PostSummary> {
-    let mut summary = build_post_summary(config, post)?;
+/// The newest lastmod among the posts a listing page shows, which is when
+/// the page last changed.
+fn newest_lastmod(
+    posts: &[&Post],
+    lastmods: &HashMap<&str, OffsetDateTime>,
+) -> Result<Option<String>> {
+    posts
+        .iter()
+        .map(|post| lastmods[post.permalink.as_str()])
+        .max()
+        .map(|modified| format_rfc3339(&modified))
+        .transpose()
+}
 
-    // Reprocess body with return_absolute=true for RSS feeds and sanitize CDATA
-    let body = att_to_absolute(
-        &post.body_html,
-        &post.permalink,
-        &config.base_url,
-        &post.attached,
-        true,
-    );
-    summary.body = sanitize_cdata(&body);
+/// When each post last changed, by permalink: its `updated` date if set.
+/// Otherwise a hash of the content file is compared with the one cached by
+/// the previous build. A post seen for the first time reports its `date`, so
+/// a fresh cache does not date every post to the checkout; an unchanged post
+/// keeps the cached lastmod, and an edited one takes the file's modification
+/// time.
+fn post_lastmods<'a>(
+    posts: &'a [Post],
+    cache_db: &sled::Db,
+) -> Result<HashMap<&'a str, OffsetDateTime>> {
+    let mut lastmods = HashMap::with_capacity(posts.len());
+    let mut keys = BTreeSet::new();
+    for post in posts {
+        let key = format!("{LASTMOD_PREFIX}{}", post.content_path.display());
+        let data = fs::read(&post.content_path)
+            .with_context(|| format!("failed to read {}", post.content_path.display()))?;
+        let hash = blake3::hash(&data).to_hex().to_string();
+        let cached = read_cached_string(cache_db, &key)?.and_then(|cached| {
+            let (cached_hash, lastmod) = cached.split_once(' ')?;
+            let lastmod = OffsetDateTime::parse(lastmod, &Rfc3339).ok()?;
+            Some((cached_hash == hash, lastmod))
+        });
+        let modified = match cached {
+            Some((true, lastmod)) => lastmod,
+            Some((false, _)) => fs::metadata(&post.content_path)
+                .and_then(|metadata| metadata.modified())
+                .map_or(post.date, |modified| {
+                    OffsetDateTime::from(modified).max(post.date)
+                }),
+            None => post.date,
+        };
+        store_cached_string(
+            cache_db,
+            &key,
+            &format!("{hash} {}", format_rfc3339(&modified)?),
+        )?;
+        keys.insert(key);
+        lastmods.insert(post.permalink.as_str(), post.updated.unwrap_or(modified));
+    }
+    cleanup_stale_keys(cache_db, LASTMOD_PREFIX, &keys)?;
+    Ok(lastmods)
+}
+
+fn build_feed_item(
+    config: &Config,
+    post: &Post,
+    bodies: &PostBodies<'_>,
+    content: FeedContent,
+    updates: bool,
+) -> Result<PostSummary> {
+    let body = match content {
+        // Feed readers need absolute URLs for attachments
+        FeedContent::Full => bodies.rewritten(config, post, true)?,
+        FeedContent::Excerpt => format!("<p>{}</p>", xml_escape(&post.excerpt)).into(),
+    };
+    let body = if config.rss_sanitize {
+        sanitize_html(&body).into()
+    } else {
+        body
+    };
+    let mut summary = build_post_summary(config, post, bodies, sanitize_cdata(&body).into())?;
+    // Private attachments stay local; feeds only describe published files.
+    summary.private_attachments.clear();
+    // The feed templates print these as they are, inside XML elements.
+    summary.title = summary.title.as_deref().map(xml_escape);
+    summary.description = xml_escape(&summary.description);
 
     // Add RSS-specific pub_date in RFC 2822 format
-    let pub_date = format_rfc2822(&post.date)?;
+    let pub_date = match post.updated {
+        Some(updated) if updates => format_rfc2822(&updated)?,
+        _ => format_rfc2822(&post.date)?,
+    };
     summary
         .extra
         .insert("pub_date".to_string(), JsonValue::String(pub_date));
+    if updates {
+        let description = format!("Originally published {}. {}", summary.date, post.excerpt);
+        summary.description = xml_escape(description.trim_end());
+    }
+    summary.extra.insert(
+        "url".to_string(),
+        JsonValue::String(xml_escape(&absolute_url(&config.base_url, &post.permalink))),
+    );
+
+    if let Some(thumbnail) = thumbnail_url(config, post) {
+        summary.extra.insert(
+            "thumbnail".to_string(),
+            JsonValue::String(xml_escape(&thumbnail)),
+        );
+    }
+
+    let enclosures = feed_enclosures(config, post, &summary);
+    summary.extra.insert(
+        "enclosures".to_string(),
+        serde_json::to_value(enclosures).context("failed to serialize feed enclosures")?,
+    );
 
     Ok(summary)
 }
 
-fn config_tag_feeds(config: &Config) -> Vec<String> {
-    fn split_list(value: &str) -> Vec<String> {
+/// Published attachments whose MIME type matches `feeds.enclosure_types`, in
+/// front matter order; only the first unless `feeds.multiple_enclosures`.
+fn feed_enclosures(config: &Config, post: &Post, summary: &PostSummary) -> Vec<Enclosure> {
+    let limit = if config.feeds.multiple_enclosures {
+        usize::MAX
+    } else {
+        1
+    };
+    post.attached
+        .iter()
+        .filter_map(|path| {
+            let relative = normalize_path(path);
+            let meta = summary.attachments.get(&relative)?;
+            config
+                .feeds
+                .is_enclosure_type(&meta.mime_type)
+                .then(|| Enclosure {
+                    url: xml_escape(&absolute_url(
+                        &config.base_url,
+                        &format!("{}{}", post.permalink, encode_path(&relative)),
+                    )),
+                    length: meta.size,
+                    mime_type: xml_escape(&meta.mime_type),
+                })
+        })
+        .take(limit)
+        .collect()
+}
+
+pub(super) fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// One `rss_tags` entry: a tag name, or `{ tag, language, title }` to give
+/// that feed its own channel language or title.
+#[derive(Debug, PartialEq)]
+pub(super) struct TagFeed {
+    pub(super) tag: String,
+    pub(super) language: Option<String>,
+    pub(super) title: Option<String>,
+}
+
+impl TagFeed {
+    fn new(tag: &str) -> Self {
+        Self {
+            tag: tag.to_string(),
+            language: None,
+            title: None,
+        }
+    }
+}
+
+/// The `rss_tags` feeds sorted by tag. A tag listed twice keeps its first
+/// entry; entries without a tag are ignored.
+pub(super) fn config_tag_feeds(config: &Config) -> Vec<TagFeed> {
+    fn split_list(value: &str) -> Vec<TagFeed> {
         value
             .split(',')
-            .map(|part| part.trim().to_string())
+            .map(str::trim)
             .filter(|part| !part.is_empty())
+            .map(TagFeed::new)
             .collect()
     }
 
-    let mut tags = Vec::new();
+    fn text(value: Option<&JsonValue>) -> Option<String> {
+        value
+            .and_then(JsonValue::as_str)
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    }
+
+    let mut feeds = Vec::new();
     if let Some(value) = config.extra.get("rss_tags") {
         match value {
-            JsonValue::String(s) => tags.extend(split_list(s)),
+            JsonValue::String(s) => feeds.extend(split_list(s)),
             JsonValue::Array(items) => {
                 for item in items {
-                    if let JsonValue::String(s) = item {
-                        let trimmed = s.trim();
-                        if !trimmed.is_empty() {
-                            tags.push(trimmed.to_string());
+                    match item {
+                        JsonValue::String(s) => {
+                            let trimmed = s.trim();
+                            if !trimmed.is_empty() {
+                                feeds.push(TagFeed::new(trimmed));
+                            }
+                        }
+                        JsonValue::Object(fields) => {
+                            if let Some(tag) = text(fields.get("tag")) {
+                                feeds.push(TagFeed {
+                                    tag,
+                                    language: text(fields.get("language")),
+                                    title: text(fields.get("title")),
+                                });
+                            }
                         }
+                        _ => {}
                     }
                 }
             }
             _ => {}
         }
     }
-    tags.sort();
-    tags.dedup();
-    tags
+    feeds.sort_by(|a, b| a.tag.cmp(&b.tag));
+    feeds.dedup_by(|later, first| later.tag == first.tag);
+    feeds
 }
 
 #[derive(Serialize)]
@@ -288,10 +627,29 @@ struct FeedContext {
     site_url: String,
     feed_url: String,
     description: String,
+    /// `None` when no language is configured at all.
+    language: Option<String>,
     updated: String,
+    /// Some item has a `thumbnail`, so the Media RSS namespace is needed.
+    media: bool,
+    podcast: Option<PodcastChannel>,
     items: Vec<PostSummary>,
 }
 
+#[derive(Serialize)]
+struct PodcastChannel {
+    author: Option<String>,
+    image: Option<String>,
+    category: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Enclosure {
+    url: String,
+    length: u64,
+    mime_type: String,
+}
+
 #[derive(Clone)]
 struct TagBucket {
     slug: String,
@@ -0,0 +1,222 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use blake3::Hasher;
+use minijinja::value::{Kwargs, Value};
+use minijinja::{Environment, Error, ErrorKind};
+
+use crate::config::Config;
+
+use super::assets::theme_assets_directory;
+use super::utils::{normalize_path, xml_escape};
+
+/// Paths passed to `inline_file` and `inline_svg` while rendering, shared
+/// with the functions registered by [`register`].
+#[derive(Clone)]
+pub(super) struct InlinedFiles {
+    /// The directories searched, in order.
+    dirs: Arc<Vec<PathBuf>>,
+    read: Arc<Mutex<BTreeSet<PathBuf>>>,
+}
+
+impl InlinedFiles {
+    /// The paths inlined so far, relative to the searched directories and
+    /// sorted.
+    pub(super) fn paths(&self) -> Vec<PathBuf> {
+        self.read.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn insert(&self, path: PathBuf) {
+        self.read.lock().unwrap().insert(path);
+    }
+
+    /// Hashes every candidate for `paths` in every searched directory, so the
+    /// site inputs hash changes when an inlined file is edited or removed, or
+    /// when a file earlier in the search order starts to shadow it.
+    pub(super) fn digest(&self, paths: &[PathBuf]) -> Result<String> {
+        let mut hasher = Hasher::new();
+        for relative in paths {
+            for dir in self.dirs.iter() {
+                let path = dir.join(relative);
+                hasher.update(normalize_path(&path).as_bytes());
+                match fs::read(&path) {
+                    Ok(data) => {
+                        hasher.update(&[1]);
+                        hasher.update(&(data.len() as u64).to_le_bytes());
+                        hasher.update(&data);
+                    }
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            std::io::ErrorKind::NotFound | std::io::ErrorKind::IsADirectory
+                        ) =>
+                    {
+                        hasher.update(&[0]);
+                    }
+                    Err(err) => {
+                        return Err(err)
+                            .with_context(|| format!("failed to read {}", path.display()));
+                    }
+                }
+            }
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+}
+
+/// Registers `inline_file(path)` and `inline_svg(path, class=, width=,
+/// height=)`, which embed a file from `skel/` or, failing that, the active
+/// theme's `assets/` directory into the page.
+pub(super) fn register(
+    env: &mut Environment<'static>,
+    root: &Path,
+    config: &Config,
+) -> Result<InlinedFiles> {
+    let mut dirs = vec![config.static_path(root)];
+    if let Some(theme) = config.theme.as_deref()
+        && let Some(assets) = theme_assets_directory(root, theme)?
+    {
+        dirs.push(assets);
+    }
+    let inliner = Arc::new(Inliner {
+        max_size: config.max_inline_size,
+        read: InlinedFiles {
+            dirs: Arc::new(dirs),
+            read: Arc::default(),
+        },
+    });
+    let read = inliner.read.clone();
+
+    let files = Arc::clone(&inliner);
+    env.add_function("inline_file", move |path: &str| -> Result<Value, Error> {
+        files.read("inline_file", path).map(Value::from_safe_string)
+    });
+    let svgs = inliner;
+    env.add_function(
+        "inline_svg",
+        move |path: &str, kwargs: Kwargs| -> Result<Value, Error> {
+            let mut attributes = Vec::new();
+            for name in ["class", "width", "height"] {
+                if let Some(value) = kwargs.get::<Option<Value>>(name)? {
+                    attributes.push((name, value.to_string()));
+                }
+            }
+            kwargs.assert_all_used()?;
+            let svg = svgs.read("inline_svg", path)?;
+            set_svg_attributes(&svg, &attributes)
+                .map(Value::from_safe_string)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidOperation,
+                        format!("inline_svg('{path}'): the file has no <svg> element"),
+                    )
+                })
+        },
+    );
+    Ok(read)
+}
+
+struct Inliner {
+    max_size: u64,
+    read: InlinedFiles,
+}
+
+impl Inliner {
+    fn read(&self, function: &str, path: &str) -> Result<String, Error> {
+        let fail = |message: String| {
+            Error::new(
+                ErrorKind::InvalidOperation,
+                format!("{function}('{path}'): {message}"),
+            )
+        };
+        let relative = Path::new(path);
+        if path.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(fail(
+                "expected a relative path inside skel/ or the theme's assets/".to_string(),
+            ));
+        }
+        let dirs = &self.read.dirs;
+        let Some(file) = dirs
+            .iter()
+            .map(|dir| dir.join(relative))
+            .find(|file| file.is_file())
+        else {
+            let searched: Vec<String> = dirs.iter().map(|dir| normalize_path(dir)).collect();
+            return Err(fail(format!("not found in {}", searched.join(" or "))));
+        };
+
+        let size = fs::metadata(&file)
+            .map_err(|err| fail(format!("failed to inspect {}: {err}", file.display())))?
+            .len();
+        if size > self.max_size {
+            return Err(fail(format!(
+                "{} is {size} bytes, more than max_inline_size ({})",
+                file.display(),
+                self.max_size
+            )));
+        }
+        let contents = fs::read_to_string(&file)
+            .map_err(|err| fail(format!("failed to read {}: {err}", file.display())))?;
+        self.read.insert(relative.to_path_buf());
+        Ok(contents)
+    }
+}
+
+/// The `<svg>` element of `svg`, without any XML declaration or doctype
+/// before it, with `attributes` set on its opening tag. `None` when there is
+/// no `<svg` tag.
+fn set_svg_attributes(svg: &str, attributes: &[(&str, String)]) -> Option<String> {
+    let start = svg.find("<svg")?;
+    let tag_end = start + svg[start..].find('>')?;
+    let self_closing = svg[..tag_end].ends_with('/');
+    let mut tag = svg[start..if self_closing { tag_end - 1 } else { tag_end }].to_string();
+    for (name, value) in attributes {
+        remove_attribute(&mut tag, name);
+        tag.push_str(&format!(" {name}=\"{}\"", xml_escape(value)));
+    }
+    let close = if self_closing { "/>" } else { ">" };
+    Some(format!("{tag}{close}{}", &svg[tag_end + 1..]))
+}
+
+/// Drops a `name="..."` or `name='...'` attribute from an opening tag.
+fn remove_attribute(tag: &mut String, name: &str) {
+    for quote in ['"', '\''] {
+        let needle = format!(" {name}={quote}");
+        if let Some(start) = tag.find(&needle)
+            && let Some(length) = tag[start + needle.len()..].find(quote)
+        {
+            tag.replace_range(start..start + needle.len() + length + 1, "");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_attributes_replace_existing_ones() {
+        let svg = "<?xml version=\"1.0\"?>\n<svg class=\"old\" viewBox=\"0 0 8 8\"><path/></svg>\n";
+        assert_eq!(
+            set_svg_attributes(
+                svg,
+                &[("class", "icon".to_string()), ("width", "16".to_string())]
+            )
+            .unwrap(),
+            "<svg viewBox=\"0 0 8 8\" class=\"icon\" width=\"16\"><path/></svg>\n"
+        );
+        assert_eq!(
+            set_svg_attributes("<svg/>", &[("height", "\"8\"".to_string())]).unwrap(),
+            "<svg height=\"&quot;8&quot;\"/>"
+        );
+        assert_eq!(set_svg_attributes("<div/>", &[]), None);
+    }
+}
@@ -1,20 +1,30 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use minijinja::Environment;
 use serde::{Deserialize, Serialize};
 
-use crate::config::Config;
+use crate::config::{Config, HomepageSort, TagSlugCollisions};
 use crate::content::Post;
+use crate::logging::Logger;
+use crate::urls::dir_url;
 
 use super::cache::{read_cached_string, store_cached_string};
-use super::posts::{PostSummary, build_post_summary, post_key};
+use super::output::Output;
+use super::posts::{
+    PostBodies, PostListEntry, PostSummary, build_post_list_entry, build_post_summary, post_key,
+    post_output_dir,
+};
 use super::templates::render_template_with_scope;
-use super::utils::{compute_cache_digest, log_status, remove_dir_if_empty, remove_file_if_exists};
+use super::utils::{compute_cache_digest, xml_escape};
+use super::validate::SiteIssue;
 use super::{
-    BuildMode, HOME_PAGES_KEY, MONTH_ARCHIVE_PREFIX, TAG_CACHE_PREFIX, YEAR_ARCHIVE_PREFIX,
+    BuildMode, CacheCounts, DAY_ARCHIVE_PREFIX, HOME_PAGES_KEY, MONTH_ARCHIVE_PREFIX,
+    TAG_CACHE_PREFIX, YEAR_ARCHIVE_PREFIX,
 };
 
 pub(super) struct HomePageCache {
@@ -40,6 +50,16 @@ impl HomePageCache {
         }
     }
 
+    /// Number of `page/N/` archives written by the last homepage render, or
+    /// `None` when nothing has been recorded yet.
+    pub(super) fn rendered_page_count(&self) -> Result<Option<usize>> {
+        let pages = self.load_pages()?;
+        if pages.is_empty() {
+            return Ok(None);
+        }
+        Ok(pages.iter().map(|page| page.page_number).max())
+    }
+
     fn store_pages(&self, pages: &[StoredPage]) -> Result<()> {
         let data = serde_json::to_vec(pages).context("failed to serialize homepage cache")?;
         self.db
@@ -50,16 +70,55 @@ impl HomePageCache {
     }
 }
 
+/// How posts (in listing order, oldest first) are split into an index page and the
+/// numbered `page/N/` pages below it. The index takes the newest `per_page`
+/// to `2 * per_page - 1` posts so every numbered page stays full and keeps its
+/// URL as new posts arrive. The homepage, tag and archive listings, cleanup,
+/// and the sitemap all use it.
+pub(super) struct PageLayout {
+    per_page: usize,
+    pub(super) regular_page_count: usize,
+}
+
+impl PageLayout {
+    pub(super) fn new(post_count: usize, per_page: usize) -> Self {
+        let per_page = std::cmp::max(1, per_page);
+        let home_page_size = if post_count < per_page {
+            post_count
+        } else {
+            per_page + post_count % per_page
+        };
+        Self {
+            per_page,
+            regular_page_count: (post_count - home_page_size) / per_page,
+        }
+    }
+
+    /// Indices of the posts on numbered page `page_number` (1-based).
+    pub(super) fn page_range(&self, page_number: usize) -> Range<usize> {
+        let start = (page_number - 1) * self.per_page;
+        start..start + self.per_page
+    }
+
+    /// Index of the oldest post shown on the index page.
+    pub(super) fn home_start(&self) -> usize {
+        self.regular_page_count * self.per_page
+    }
+}
+
 pub(super) fn render_homepage(
     posts: &[Post],
-    html_root: &Path,
+    html: &Output,
     config: &Config,
     env: &Environment<'static>,
     cache: &HomePageCache,
+    bodies: &PostBodies<'_>,
     mode: BuildMode,
 ) -> Result<()> {
+    let layout = PageLayout::new(posts.len(), config.homepage_posts);
     if posts.is_empty() {
         cache.store_pages(&[])?;
+        cleanup_numbered_pages(html, config, html.root(), layout.regular_page_count)?;
         return Ok(());
     }
 
@@ -67,52 +126,40 @@ pub(super) fn render_homepage(
         .get_template("index.html")
         .context("index.html template missing")?;
 
-    let per_page = std::cmp::max(1, config.homepage_posts);
-
-    // Posts are now sorted ASCENDING (oldest first, newest last)
-    // posts[0] = oldest, posts[len-1] = newest
-    // Page 1 gets posts[0..per_page-1], Page 2 gets posts[per_page..2*per_page-1], etc.
-    // Homepage gets the last per_page to per_page*2-1 posts (newest)
-    // Posts within each page are displayed in reverse (newest first)
-
-    let remainder = posts.len() % per_page;
-
-    // Determine homepage size: between per_page and per_page*2-1 posts
-    let home_page_size = if posts.len() < per_page {
-        posts.len()
-    } else if remainder == 0 {
-        per_page
-    } else if remainder < per_page {
-        remainder + per_page
-    } else {
-        per_page
-    };
+    // Featured posts are listed newest first on every index page
+    let featured_keys: Vec<String> = posts
+        .iter()
+        .rev()
+        .filter(|post| post.featured)
+        .map(post_key)
+        .collect();
 
-    // Number of regular pages
-    let regular_page_count = (posts.len() - home_page_size) / per_page;
-    let total_pages = regular_page_count + 1;
+    let regular_page_count = layout.regular_page_count;
+    let ordered = homepage_order(posts, config.homepage_sort);
 
     let mut new_records = Vec::new();
 
     // Regular pages (page 1, 2, 3, ...) - store in display order (reversed)
     for page_num in 1..=regular_page_count {
-        let start = (page_num - 1) * per_page;
-        let end = start + per_page;
+        let range = layout.page_range(page_num);
         // Reverse the slice to display newest first within the page
-        let page_posts: Vec<String> = posts[start..end].iter().rev().map(post_key).collect();
-        new_records.push(StoredPage {
-            page_number: page_num,
-            posts: page_posts,
-        });
+        let page_posts = &ordered[range];
+        new_records.push(StoredPage::new(
+            page_num,
+            page_posts,
+            &featured_keys,
+            &pagination_context(config, &[], page_num, regular_page_count),
+        ));
     }
 
     // Homepage gets the last posts (newest) - store in display order (reversed)
-    let home_start = regular_page_count * per_page;
-    let home_posts: Vec<String> = posts[home_start..].iter().rev().map(post_key).collect();
-    new_records.push(StoredPage {
-        page_number: 0,
-        posts: home_posts,
-    });
+    let home_posts = &ordered[layout.home_start()..];
+    new_records.push(StoredPage::new(
+        0,
+        home_posts,
+        &featured_keys,
+        &pagination_context(config, &[], 0, regular_page_count),
+    ));
 
     // Load cached pages to detect changes
     let stored_pages = cache.load_pages()?;
@@ -127,18 +174,47 @@ pub(super) fn render_homepage(
         lookup.insert(post_key(post), post);
     }
 
+    // With all_posts enabled every page lists the whole site, so any change to
+    // the set of posts invalidates all of them.
+    let all_posts = if config.homepage_all_posts {
+        let entries = ordered
+            .iter()
+            .rev()
+            .map(|post| build_post_list_entry(config, post))
+            .collect::<Result<Vec<_>>>()?;
+        Some(entries)
+    } else {
+        None
+    };
+    let post_set_changed = config.homepage_all_posts && {
+        let stored: BTreeSet<&String> = stored_pages.iter().flat_map(|p| &p.posts).collect();
+        let current: BTreeSet<&String> = new_records.iter().flat_map(|p| &p.posts).collect();
+        stored != current
+    };
+
+    let featured = featured_keys
+        .iter()
+        .filter_map(|id| lookup.get(id))
+        .map(|post| listing_summary(config, bodies, post))
+        .collect::<Result<Vec<_>>>()?;
+
     let mut plans: Vec<PagePlan> = Vec::new();
 
     for record in &new_records {
         let page_num = record.page_number;
 
         // Check if this page needs rendering
-        let mut needs_render = matches!(mode, BuildMode::Full);
+        let mut needs_render = matches!(mode, BuildMode::Full) || post_set_changed;
         if !needs_render {
             needs_render = match stored_map.get(&page_num) {
                 Some(cached) => {
-                    // Page exists in cache - check if content changed
+                    // Page exists in cache - check if content or links changed
                     cached.posts != record.posts
+                        || cached.updated != record.updated
+                        || cached.featured != record.featured
+                        || cached.prev != record.prev
+                        || cached.next != record.next
+                        || cached.total != record.total
                 }
                 None => {
                     // New page
@@ -156,49 +232,11 @@ pub(super) fn render_homepage(
             .posts
             .iter()
             .filter_map(|id| lookup.get(id))
-            .map(|post| build_post_summary(config, post))
+            .map(|post| listing_summary(config, bodies, post))
             .collect::<Result<Vec<_>>>()?;
 
-        // Build pagination links
-        let (prev, next) = if page_num == 0 {
-            // Homepage
-            let prev = if regular_page_count > 0 {
-                page_url(regular_page_count)
-            } else {
-                String::new()
-            };
-            (prev, String::new())
-        } else if page_num == 1 {
-            // Page 1
-            let next = if page_num < regular_page_count {
-                page_url(page_num + 1)
-            } else {
-                "/".to_string() // Link to homepage
-            };
-            (String::new(), next)
-        } else {
-            // Middle pages
-            let prev = page_url(page_num - 1);
-            let next = if page_num < regular_page_count {
-                page_url(page_num + 1)
-            } else {
-                "/".to_string() // Link to homepage
-            };
-            (prev, next)
-        };
-
-        let pagination = PaginationContext {
-            current: if page_num == 0 { total_pages } else { page_num },
-            total: total_pages,
-            prev,
-            next,
-        };
-
-        let output = if page_num == 0 {
-            html_root.join("index.html")
-        } else {
-            page_output_path(html_root, page_num)
-        };
+        let pagination = pagination_context(config, &[], page_num, regular_page_count);
+        let output = listing_output_path(config, html.root(), page_num);
 
         plans.push(PagePlan {
             summaries,
@@ -208,26 +246,33 @@ pub(super) fn render_homepage(
     }
 
     for plan in plans {
-        render_page(&template, plan)?;
+        render_page(
+            html,
+            config,
+            &template,
+            plan,
+            &featured,
+            all_posts.as_deref(),
+        )?;
     }
 
     cache.store_pages(&new_records)?;
 
-    // Cleanup stale page directories
-    cleanup_homepage_pages(html_root, &new_records)?;
+    cleanup_numbered_pages(html, config, html.root(), regular_page_count)?;
 
     Ok(())
 }
 
 pub(super) fn render_archives(
     posts: &[Post],
-    html_root: &Path,
+    html: &Output,
     config: &Config,
     env: &Environment<'static>,
-    cache_db: &sled::Db,
+    bodies: &PostBodies<'_>,
     mode: BuildMode,
-    verbose: bool,
-) -> Result<()> {
+    log: Logger,
+) -> Result<CacheCounts> {
+    let cache_db = bodies.cache_db();
     let year_template = env
         .get_template("archive_year.html")
         .context("archive_year.html template missing")?;
@@ -235,142 +280,223 @@ pub(super) fn render_archives(
         .get_template("archive_month.html")
         .context("archive_month.html template missing")?;
 
+    let levels = config.archive_levels;
+    let mut counts = CacheCounts::default();
     let mut year_groups: BTreeMap<i32, Vec<&Post>> = BTreeMap::new();
     let mut month_groups: BTreeMap<(i32, u8), Vec<&Post>> = BTreeMap::new();
+    let mut day_groups: BTreeMap<(i32, u8, u8), Vec<&Post>> = BTreeMap::new();
 
     for post in posts {
-        year_groups.entry(post.date.year()).or_default().push(post);
-        month_groups
-            .entry((post.date.year(), post.date.month() as u8))
-            .or_default()
-            .push(post);
+        let (year, month, day) = (post.date.year(), post.date.month() as u8, post.date.day());
+        year_groups.entry(year).or_default().push(post);
+        if levels.months() {
+            month_groups.entry((year, month)).or_default().push(post);
+        }
+        if levels.days() {
+            day_groups.entry((year, month, day)).or_default().push(post);
+        }
     }
 
     let mut year_keys: BTreeSet<String> = BTreeSet::new();
     for (year, group) in year_groups.iter().rev() {
-        let summaries = group
-            .iter()
-            .rev()
-            .map(|post| build_post_summary(config, post))
-            .collect::<Result<Vec<_>>>()?;
-        let payload = YearArchiveCachePayload {
-            year: *year,
-            posts: &summaries,
-        };
-        let digest = compute_cache_digest(&payload)?;
+        let year_segment = format!("{year:04}");
+        let pages = build_listing_pages(
+            config,
+            bodies,
+            group,
+            config.archive_per_page,
+            &[&year_segment],
+        )?;
+        let digests = listing_page_digests(&YearArchiveListing { year: *year }, &pages)?;
         let cache_key = format!("{YEAR_ARCHIVE_PREFIX}{year:04}");
         year_keys.insert(cache_key.clone());
         let cached = read_cached_string(cache_db, &cache_key)?;
-        let output = archive_year_path(html_root, *year);
-
-        let mut needs_render = matches!(mode, BuildMode::Full);
-        if !needs_render {
-            match cached.as_deref() {
-                Some(existing) if existing == digest => {
-                    if !output.exists() {
-                        needs_render = true;
-                    }
-                }
-                _ => needs_render = true,
-            }
-        }
-
-        if needs_render {
-            let scope = format!("rendering year archive {year:04}");
-            let rendered = render_template_with_scope(
-                &year_template,
-                minijinja::context! { year => year, posts => summaries },
-                &scope,
-            )?;
-
-            if let Some(parent) = output.parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("failed to create {}", parent.display()))?;
+        let dir = archive_year_dir(html.root(), *year);
+        let stale = stale_listing_pages(config, &dir, &pages, &digests, cached.as_deref(), mode);
+        counts.record(&stale);
+
+        if stale.iter().any(|&stale| stale) {
+            for (page, _) in pages.iter().zip(&stale).filter(|(_, stale)| **stale) {
+                let scope = format!(
+                    "rendering year archive {year:04} page {} of {}",
+                    page.pagination.current, page.pagination.total
+                );
+                let rendered = render_template_with_scope(
+                    &year_template,
+                    minijinja::context! {
+                        year => year,
+                        posts => page.posts,
+                        pagination => page.pagination,
+                    },
+                    &scope,
+                )?;
+                let target = listing_output_path(config, &dir, page.page_number);
+                html.write_page(config, "archive_year.html", &target, &rendered)?;
             }
-            fs::write(&output, rendered)
-                .with_context(|| format!("failed to write {}", output.display()))?;
-            store_cached_string(cache_db, &cache_key, &digest)?;
-            log_status(verbose, "ARCHIVE", format!("Rendered year {year:04}"));
+            cleanup_numbered_pages(html, config, &dir, pages.len() - 1)?;
+            store_cached_string(cache_db, &cache_key, &digests.join(","))?;
+            log.debug("ARCHIVE", format!("Rendered year {year:04}"));
         } else {
-            log_status(verbose, "ARCHIVE", format!("Year {year:04} unchanged"));
+            log.trace("ARCHIVE", format!("Year {year:04} unchanged"));
         }
     }
 
     let mut month_keys: BTreeSet<String> = BTreeSet::new();
     for ((year, month), group) in month_groups.iter().rev() {
-        let summaries = group
-            .iter()
-            .rev()
-            .map(|post| build_post_summary(config, post))
-            .collect::<Result<Vec<_>>>()?;
-        let payload = MonthArchiveCachePayload {
+        let year_segment = format!("{year:04}");
+        let month_segment = format!("{month:02}");
+        let pages = build_listing_pages(
+            config,
+            bodies,
+            group,
+            config.archive_per_page,
+            &[&year_segment, &month_segment],
+        )?;
+        let listing = MonthArchiveListing {
             year: *year,
             month: *month,
-            posts: &summaries,
         };
-        let digest = compute_cache_digest(&payload)?;
+        let digests = listing_page_digests(&listing, &pages)?;
         let cache_key = format!("{MONTH_ARCHIVE_PREFIX}{year:04}-{month:02}");
         month_keys.insert(cache_key.clone());
         let cached = read_cached_string(cache_db, &cache_key)?;
 
-        let output = archive_month_path(html_root, *year, *month);
-
-        let mut needs_render = matches!(mode, BuildMode::Full);
-        if !needs_render {
-            match cached.as_deref() {
-                Some(existing) if existing == digest.as_str() => {
-                    if !output.exists() {
-                        needs_render = true;
-                    }
-                }
-                _ => needs_render = true,
+        let dir = archive_month_dir(html.root(), *year, *month);
+        let stale = stale_listing_pages(config, &dir, &pages, &digests, cached.as_deref(), mode);
+        counts.record(&stale);
+
+        if stale.iter().any(|&stale| stale) {
+            for (page, _) in pages.iter().zip(&stale).filter(|(_, stale)| **stale) {
+                let scope = format!(
+                    "rendering month archive {year:04}-{month:02} page {} of {}",
+                    page.pagination.current, page.pagination.total
+                );
+                let rendered = render_template_with_scope(
+                    &month_template,
+                    minijinja::context! {
+                        year => year,
+                        month => month,
+                        posts => page.posts,
+                        pagination => page.pagination,
+                    },
+                    &scope,
+                )?;
+                let target = listing_output_path(config, &dir, page.page_number);
+                html.write_page(config, "archive_month.html", &target, &rendered)?;
             }
+            cleanup_numbered_pages(html, config, &dir, pages.len() - 1)?;
+            store_cached_string(cache_db, &cache_key, &digests.join(","))?;
+            log.debug("ARCHIVE", format!("Rendered month {year:04}-{month:02}"));
+        } else {
+            log.trace("ARCHIVE", format!("Month {year:04}-{month:02} unchanged"));
         }
+    }
 
-        if needs_render {
-            let scope = format!("rendering month archive {year:04}-{month:02}");
-            let rendered = render_template_with_scope(
-                &month_template,
-                minijinja::context! { year => year, month => month, posts => summaries },
-                &scope,
+    let mut day_keys: BTreeSet<String> = BTreeSet::new();
+    if levels.days() {
+        // Day archives share `/YYYY/MM/DD/` with the default post
+        // permalinks, so a post published at an archive's own path (or one of
+        // its numbered pages) would be overwritten.
+        let post_pages: HashMap<PathBuf, &str> = posts
+            .iter()
+            .map(|post| {
+                (
+                    post_output_dir(html.root(), post).join("index.html"),
+                    post.slug.as_str(),
+                )
+            })
+            .collect();
+        let day_template = env
+            .get_template("archive_day.html")
+            .or_else(|_| env.get_template("archive_month.html"))
+            .context("archive_day.html template missing")?;
+        for ((year, month, day), group) in day_groups.iter().rev() {
+            let year_segment = format!("{year:04}");
+            let month_segment = format!("{month:02}");
+            let day_segment = format!("{day:02}");
+            let pages = build_listing_pages(
+                config,
+                bodies,
+                group,
+                config.archive_per_page,
+                &[&year_segment, &month_segment, &day_segment],
             )?;
-
-            if let Some(parent) = output.parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            let dir = archive_day_dir(html.root(), *year, *month, *day);
+            for page in &pages {
+                let target = listing_output_path(config, &dir, page.page_number);
+                if let Some(slug) = post_pages.get(&target) {
+                    bail!(
+                        "day archive {year:04}-{month:02}-{day:02} would overwrite post '{slug}' at {}",
+                        target.display()
+                    );
+                }
+            }
+            let listing = DayArchiveListing {
+                year: *year,
+                month: *month,
+                day: *day,
+            };
+            let digests = listing_page_digests(&listing, &pages)?;
+            let cache_key = format!("{DAY_ARCHIVE_PREFIX}{year:04}-{month:02}-{day:02}");
+            day_keys.insert(cache_key.clone());
+            let cached = read_cached_string(cache_db, &cache_key)?;
+            let stale =
+                stale_listing_pages(config, &dir, &pages, &digests, cached.as_deref(), mode);
+            counts.record(&stale);
+
+            if stale.iter().any(|&stale| stale) {
+                for (page, _) in pages.iter().zip(&stale).filter(|(_, stale)| **stale) {
+                    let scope = format!(
+                        "rendering day archive {year:04}-{month:02}-{day:02} page {} of {}",
+                        page.pagination.current, page.pagination.total
+                    );
+                    let rendered = render_template_with_scope(
+                        &day_template,
+                        minijinja::context! {
+                            year => year,
+                            month => month,
+                            day => day,
+                            posts => page.posts,
+                            pagination => page.pagination,
+                        },
+                        &scope,
+                    )?;
+                    let target = listing_output_path(config, &dir, page.page_number);
+                    html.write_page(config, day_template.name(), &target, &rendered)?;
+                }
+                cleanup_numbered_pages(html, config, &dir, pages.len() - 1)?;
+                store_cached_string(cache_db, &cache_key, &digests.join(","))?;
+                log.debug(
+                    "ARCHIVE",
+                    format!("Rendered day {year:04}-{month:02}-{day:02}"),
+                );
+            } else {
+                log.trace(
+                    "ARCHIVE",
+                    format!("Day {year:04}-{month:02}-{day:02} unchanged"),
+                );
             }
-            fs::write(&output, rendered)
-                .with_context(|| format!("failed to write {}", output.display()))?;
-            store_cached_string(cache_db, &cache_key, &digest)?;
-            log_status(
-                verbose,
-                "ARCHIVE",
-                format!("Rendered month {year:04}-{month:02}"),
-            );
-        } else {
-            log_status(
-                verbose,
-                "ARCHIVE",
-                format!("Month {year:04}-{month:02} unchanged"),
-            );
         }
     }
 
-    cleanup_month_archives(cache_db, html_root, &month_keys)?;
-    cleanup_year_archives(cache_db, html_root, &year_keys)?;
+    cleanup_day_archives(cache_db, html, config, &day_keys)?;
+    cleanup_month_archives(cache_db, html, config, &month_keys)?;
+    cleanup_year_archives(cache_db, html, config, &year_keys)?;
 
-    Ok(())
+    Ok(counts)
 }
 
 pub(super) fn render_tag_archives(
     posts: &[Post],
-    html_root: &Path,
+    html: &Output,
     config: &Config,
     env: &Environment<'static>,
-    cache_db: &sled::Db,
+    bodies: &PostBodies<'_>,
     mode: BuildMode,
-    verbose: bool,
-) -> Result<()> {
+    log: Logger,
+) -> Result<CacheCounts> {
+    let cache_db = bodies.cache_db();
+    check_tag_slugs(config, posts)?;
     let tag_template = env
         .get_template("tag.html")
         .context("tag.html template missing")?;
@@ -379,16 +505,28 @@ pub(super) fn render_tag_archives(
     for (idx, post) in posts.iter().enumerate() {
         let mut seen = HashSet::new();
         for tag in &post.tags {
-            let tag = tag.trim();
+            let tag = config.canonical_tag(tag);
             if tag.is_empty() {
                 continue;
             }
-            let slug = tag_slug(tag);
+            let Some(slug) = tag_slug(config, tag) else {
+                continue;
+            };
             if !seen.insert(slug.clone()) {
                 continue;
             }
             let bucket = buckets.entry(slug.clone()).or_insert_with(|| TagBucket {
-                name: tag.to_string(),
+                name: tag_name(config, &slug, tag).to_string(),
+                slug: slug.clone(),
+                indices: Vec::new(),
+            });
+            bucket.indices.push(idx);
+        }
+        if let Some(slug) = &config.untagged_posts_slug
+            && is_untagged(config, post)
+        {
+            let bucket = buckets.entry(slug.clone()).or_insert_with(|| TagBucket {
+                name: slug.clone(),
                 slug: slug.clone(),
                 indices: Vec::new(),
             });
@@ -398,81 +536,214 @@ pub(super) fn render_tag_archives(
 
     if buckets.is_empty() {
         let keep_keys = BTreeSet::new();
-        cleanup_tag_cache(cache_db, html_root, &keep_keys)?;
-        return Ok(());
+        cleanup_tag_cache(cache_db, html, config, &keep_keys)?;
+        return Ok(CacheCounts::default());
     }
 
+    let per_page = tag_page_size(config);
     let mut plans = Vec::new();
     for bucket in buckets.values() {
-        let summaries = bucket
-            .indices
-            .iter()
-            .rev()
-            .map(|&idx| build_post_summary(config, &posts[idx]))
-            .collect::<Result<Vec<_>>>()?;
-        let pagination = PaginationContext {
-            current: 1,
-            total: 1,
-            prev: String::new(),
-            next: String::new(),
-        };
+        let tag_posts: Vec<&Post> = bucket.indices.iter().map(|&idx| &posts[idx]).collect();
+        let pages = build_listing_pages(
+            config,
+            bodies,
+            &tag_posts,
+            per_page,
+            &["tags", &bucket.slug],
+        )?;
         plans.push(TagPagePlan {
             tag: bucket.name.clone(),
             slug: bucket.slug.clone(),
-            summaries,
-            pagination,
-            output: tag_index_path(html_root, &bucket.slug),
+            pages,
+            dir: tag_dir(html.root(), &bucket.slug),
         });
     }
 
     let mut keep_keys: BTreeSet<String> = BTreeSet::new();
+    let mut counts = CacheCounts::default();
 
     for plan in plans {
         let cache_key = format!("{TAG_CACHE_PREFIX}{}", plan.slug);
         keep_keys.insert(cache_key.clone());
 
-        let payload = TagCachePayload {
-            tag: &plan.tag,
-            posts: &plan.summaries,
-            pagination: &plan.pagination,
-        };
-        let digest = compute_cache_digest(&payload)
+        let digests = listing_page_digests(&TagListing { tag: &plan.tag }, &plan.pages)
             .with_context(|| format!("failed to compute digest for tag {}", plan.slug))?;
         let cached = read_cached_string(cache_db, &cache_key)?;
-
-        let mut needs_render = matches!(mode, BuildMode::Full);
-        if !needs_render {
-            match cached.as_deref() {
-                Some(existing) if existing == digest.as_str() => {
-                    if !plan.output.exists() {
-                        needs_render = true;
-                    }
-                }
-                _ => needs_render = true,
-            }
-        }
+        let stale = stale_listing_pages(
+            config,
+            &plan.dir,
+            &plan.pages,
+            &digests,
+            cached.as_deref(),
+            mode,
+        );
+        counts.record(&stale);
 
         let slug = plan.slug.clone();
 
-        if needs_render {
-            render_tag_page(&tag_template, plan)?;
-            store_cached_string(cache_db, &cache_key, &digest)?;
-            log_status(verbose, "TAG", format!("Rendered tag {}", slug));
+        if stale.iter().any(|&stale| stale) {
+            render_tag_pages(html, config, &tag_template, plan, &stale)?;
+            store_cached_string(cache_db, &cache_key, &digests.join(","))?;
+            log.debug("TAG", format!("Rendered tag {}", slug));
         } else {
-            log_status(verbose, "TAG", format!("Tag {} unchanged", slug));
+            log.trace("TAG", format!("Tag {} unchanged", slug));
         }
     }
 
-    cleanup_tag_cache(cache_db, html_root, &keep_keys)?;
+    render_tag_redirects(html, config, cache_db, mode, &buckets, &mut keep_keys, log)?;
+
+    cleanup_tag_cache(cache_db, html, config, &keep_keys)?;
 
+    Ok(counts)
+}
+
+/// Writes a redirect stub at the old page of every aliased tag so links to
+/// `/tags/<alias>/` keep working. Stubs share the tag cache keys, so dropping
+/// an alias removes its stub like any other stale tag page.
+fn render_tag_redirects(
+    html: &Output,
+    config: &Config,
+    cache_db: &sled::Db,
+    mode: BuildMode,
+    buckets: &BTreeMap<String, TagBucket>,
+    keep_keys: &mut BTreeSet<String>,
+    log: Logger,
+) -> Result<()> {
+    let base_url = config.base_url.trim_end_matches('/');
+    for (alias, canonical) in &config.tag_aliases {
+        let (Some(alias_slug), Some(canonical_slug)) =
+            (tag_slug(config, alias), tag_slug(config, canonical))
+        else {
+            continue;
+        };
+        if alias_slug == canonical_slug
+            || buckets.contains_key(&alias_slug)
+            || !buckets.contains_key(&canonical_slug)
+        {
+            continue;
+        }
+
+        let cache_key = format!("{TAG_CACHE_PREFIX}{alias_slug}");
+        if !keep_keys.insert(cache_key.clone()) {
+            continue;
+        }
+        let target = format!("{base_url}{}", tag_index_url(&canonical_slug));
+        let output = tag_index_path(html.root(), &alias_slug);
+        let cached = read_cached_string(cache_db, &cache_key)?;
+        if matches!(mode, BuildMode::Changed)
+            && cached.as_deref() == Some(target.as_str())
+            && output.exists()
+        {
+            continue;
+        }
+
+        html.write(&output, redirect_stub(&target))?;
+        cleanup_numbered_pages(html, config, &tag_dir(html.root(), &alias_slug), 0)?;
+        store_cached_string(cache_db, &cache_key, &target)?;
+        log.debug(
+            "TAG",
+            format!("Redirected tag {alias_slug} to {canonical_slug}"),
+        );
+    }
     Ok(())
 }
 
-pub(super) fn page_url(page_number: usize) -> String {
-    format!("/page/{}/", page_number)
+fn redirect_stub(target: &str) -> String {
+    let target = xml_escape(target);
+    format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>Redirecting…</title>\
+         <link rel=\"canonical\" href=\"{target}\">\
+         <meta http-equiv=\"refresh\" content=\"0; url={target}\"></head>\
+         <body><a href=\"{target}\">{target}</a></body></html>\n"
+    )
+}
+
+/// Posts per tag page: `tags_per_page`, or `homepage_posts` when unset, and
+/// `None` (one page per tag) without `paginate_tags`.
+pub(super) fn tag_page_size(config: &Config) -> Option<usize> {
+    config
+        .paginate_tags
+        .then(|| config.tags_per_page.unwrap_or(config.homepage_posts))
+}
+
+/// `pagination_path` split around `{n}`: numbered page N of a listing is
+/// `<listing>/<dir>/<prefix>N<suffix>`, where a `/` suffix stands for a
+/// directory holding `index.html`. Config validation keeps `dir` non-empty.
+struct PaginationPath<'a> {
+    dir: &'a str,
+    prefix: &'a str,
+    suffix: &'a str,
+}
+
+impl<'a> PaginationPath<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self::parse(&config.pagination_path)
+    }
+
+    fn parse(pagination_path: &'a str) -> Self {
+        let path = pagination_path.trim_start_matches('/');
+        let (before, suffix) = path.split_once("{n}").unwrap_or((path, "/"));
+        let (dir, prefix) = before.rsplit_once('/').unwrap_or(("", before));
+        Self {
+            dir,
+            prefix,
+            suffix,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        self.suffix == "/"
+    }
+
+    fn entry_name(&self, page_number: usize) -> String {
+        format!(
+            "{}{page_number}{}",
+            self.prefix,
+            self.suffix.trim_end_matches('/')
+        )
+    }
+
+    fn page_number(&self, entry_name: &str) -> Option<usize> {
+        entry_name
+            .strip_prefix(self.prefix)?
+            .strip_suffix(self.suffix.trim_end_matches('/'))?
+            .parse()
+            .ok()
+    }
+}
+
+/// URL of numbered page `page_number` of the listing whose index is at
+/// `base`; an empty `base` is the homepage.
+pub(super) fn page_url(config: &Config, base: &[&str], page_number: usize) -> String {
+    let paging = PaginationPath::new(config);
+    let mut url = dir_url(base);
+    url.push_str(paging.dir);
+    url.push('/');
+    url.push_str(&paging.entry_name(page_number));
+    if paging.is_dir() {
+        url.push('/');
+    }
+    url
 }
 
-pub(super) fn tag_slug(tag: &str) -> String {
+/// Slug of the archive that lists `tag`. Tags without ASCII letters or
+/// digits share the `untagged_slug` archive, or get none when it is empty.
+pub(super) fn tag_slug(config: &Config, tag: &str) -> Option<String> {
+    if let Some(slug) = config.tag_slugs.get(&tag.trim().to_lowercase()) {
+        return Some(slug.clone());
+    }
+    let slug = plain_slug(tag);
+    if !slug.is_empty() {
+        Some(slug)
+    } else if config.untagged_slug.is_empty() {
+        None
+    } else {
+        Some(config.untagged_slug.clone())
+    }
+}
+
+/// The ASCII letters and digits of `tag`, lowercased and joined by dashes.
+fn plain_slug(tag: &str) -> String {
     let mut slug = String::new();
     let mut previous_dash = false;
 
@@ -489,81 +760,409 @@ pub(super) fn tag_slug(tag: &str) -> String {
     while slug.ends_with('-') {
         slug.pop();
     }
+    slug
+}
 
-    if slug.is_empty() {
-        "untagged".to_string()
-    } else {
-        slug
+/// The name shown for the archive at `slug`, which `tag` is listed on.
+pub(super) fn tag_name<'a>(config: &'a Config, slug: &str, tag: &'a str) -> &'a str {
+    config.tag_names.get(slug).map_or(tag, String::as_str)
+}
+
+/// Finds the tags of `posts` that have different names but the same slug,
+/// such as `C++` and `C#`, and settles them as `tag_slug_collisions` says by
+/// filling in `config.tag_names` and `config.tag_slugs`. Spellings that only
+/// differ in case are one tag and always share an archive. Tags are ranked by
+/// the number of posts using them, then alphabetically.
+pub(super) fn resolve_tag_slugs(config: &mut Config, posts: &[Post]) -> Vec<SiteIssue> {
+    let mut spellings: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+    for post in posts {
+        let mut seen = HashSet::new();
+        for tag in &post.tags {
+            let tag = config.canonical_tag(tag);
+            let slug = plain_slug(tag);
+            if !slug.is_empty() && seen.insert(tag) {
+                *spellings
+                    .entry(slug)
+                    .or_default()
+                    .entry(tag.to_string())
+                    .or_default() += 1;
+            }
+        }
     }
+
+    let mut tag_names = BTreeMap::new();
+    let mut tag_slugs = BTreeMap::new();
+    let mut issues = Vec::new();
+    for (slug, counts) in spellings.iter().filter(|(_, counts)| counts.len() > 1) {
+        let mut tags: BTreeMap<String, TagSpellings> = BTreeMap::new();
+        for (name, &count) in counts {
+            let tag = tags.entry(name.to_lowercase()).or_default();
+            tag.count += count;
+            tag.names.push((name.as_str(), count));
+        }
+        let mut tags: Vec<TagSpellings> = tags.into_values().collect();
+        for tag in &mut tags {
+            tag.names
+                .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        }
+        tags.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.names[0].0.cmp(b.names[0].0))
+        });
+
+        let first = tags[0].names[0].0;
+        if tags.len() == 1 {
+            tag_names.insert(slug.clone(), first.to_string());
+            continue;
+        }
+        let listed = tags
+            .iter()
+            .map(|tag| format!("'{}'", tag.names[0].0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let url = tag_index_url(slug);
+        match config.tag_slug_collisions {
+            TagSlugCollisions::Merge => {
+                tag_names.insert(slug.clone(), first.to_string());
+                issues.push(SiteIssue::warning(format!(
+                    "tags {listed} share the slug '{slug}'; {url} lists them all as '{first}'"
+                )));
+            }
+            TagSlugCollisions::Disambiguate => {
+                if tags[0].names.len() > 1 {
+                    tag_names.insert(slug.clone(), first.to_string());
+                }
+                let mut moved = Vec::new();
+                let mut suffix = 0;
+                for tag in &tags[1..] {
+                    let new_slug = loop {
+                        suffix += 1;
+                        let candidate = format!("{slug}-{suffix}");
+                        if !spellings.contains_key(&candidate) {
+                            break candidate;
+                        }
+                    };
+                    for (name, _) in &tag.names {
+                        tag_slugs.insert(name.to_lowercase(), new_slug.clone());
+                    }
+                    if tag.names.len() > 1 {
+                        tag_names.insert(new_slug.clone(), tag.names[0].0.to_string());
+                    }
+                    moved.push(format!(
+                        "'{}' to {}",
+                        tag.names[0].0,
+                        tag_index_url(&new_slug)
+                    ));
+                }
+                issues.push(SiteIssue::warning(format!(
+                    "tags {listed} share the slug '{slug}'; '{first}' keeps {url}, moving {}",
+                    moved.join(", ")
+                )));
+            }
+            TagSlugCollisions::Error => {
+                issues.push(SiteIssue::error(format!(
+                    "tags {listed} share the slug '{slug}'; rename one or set tag_slug_collisions"
+                )));
+            }
+        }
+    }
+    config.tag_names = tag_names;
+    config.tag_slugs = tag_slugs;
+    issues
+}
+
+/// The spellings of one tag, with the number of posts using each.
+#[derive(Default)]
+struct TagSpellings<'a> {
+    count: usize,
+    names: Vec<(&'a str, usize)>,
+}
+
+/// Whether `post` has no tags once blank ones are dropped, which lists it on
+/// the `untagged_posts_slug` archive.
+pub(super) fn is_untagged(config: &Config, post: &Post) -> bool {
+    post.tags
+        .iter()
+        .all(|tag| config.canonical_tag(tag).is_empty())
+}
+
+/// Fails when a tag would share its archive with the tags that have no slug
+/// of their own, or with the posts without tags, instead of merging them
+/// silently.
+fn check_tag_slugs(config: &Config, posts: &[Post]) -> Result<()> {
+    let mut slugless = None;
+    let mut named: HashMap<String, &str> = HashMap::new();
+    for post in posts {
+        for tag in &post.tags {
+            let tag = config.canonical_tag(tag);
+            if tag.is_empty() {
+                continue;
+            }
+            let slug = plain_slug(tag);
+            if slug.is_empty() {
+                slugless.get_or_insert(tag);
+            } else {
+                named.entry(slug).or_insert(tag);
+            }
+        }
+    }
+
+    if let Some(slugless) = slugless
+        && let Some(tag) = named.get(&config.untagged_slug)
+    {
+        bail!(
+            "tag '{tag}' and tags without ASCII letters or digits such as '{slugless}' would share /tags/{}/; set untagged_slug to another slug",
+            config.untagged_slug
+        );
+    }
+    if let Some(slug) = &config.untagged_posts_slug
+        && let Some(tag) = named.get(slug)
+    {
+        bail!(
+            "tag '{tag}' would share /tags/{slug}/ with the posts without tags; set untagged_posts_slug to another slug"
+        );
+    }
+    Ok(())
 }
 
 pub(super) fn tag_index_url(slug: &str) -> String {
-    format!("/tags/{}/", slug)
+    dir_url(&["tags", slug])
 }
 
-pub(super) fn page_output_path(html_root: &Path, page_number: usize) -> PathBuf {
-    html_root
-        .join("page")
-        .join(page_number.to_string())
-        .join("index.html")
+/// Output file of page `page_number` of the listing in `dir`; 0 is the
+/// index itself.
+fn listing_output_path(config: &Config, dir: &Path, page_number: usize) -> PathBuf {
+    if page_number == 0 {
+        return dir.join("index.html");
+    }
+    let paging = PaginationPath::new(config);
+    let path = dir.join(paging.dir).join(paging.entry_name(page_number));
+    if paging.is_dir() {
+        path.join("index.html")
+    } else {
+        path
+    }
+}
+
+/// Cache digests of `pages`, in the same order. Numbered pages leave out
+/// `pagination.total`, so a listing that grows by a page re-renders only its
+/// index and the pages whose posts or links changed.
+fn listing_page_digests(listing: &impl Serialize, pages: &[ListingPage]) -> Result<Vec<String>> {
+    pages
+        .iter()
+        .map(|page| {
+            compute_cache_digest(&ListingPageDigest {
+                listing,
+                posts: &page.posts,
+                prev: &page.pagination.prev,
+                next: &page.pagination.next,
+                total: (page.page_number == 0).then_some(page.pagination.total),
+            })
+        })
+        .collect()
+}
+
+/// Which of `pages` to write: every page in a full build, otherwise those
+/// whose digest differs from the `cached` list or whose file is missing.
+/// Numbered pages come first in both lists, so page N keeps its position as
+/// pages are added.
+fn stale_listing_pages(
+    config: &Config,
+    dir: &Path,
+    pages: &[ListingPage],
+    digests: &[String],
+    cached: Option<&str>,
+    mode: BuildMode,
+) -> Vec<bool> {
+    let cached: Vec<&str> = cached
+        .map(|value| value.split(',').collect())
+        .unwrap_or_default();
+    pages
+        .iter()
+        .zip(digests)
+        .enumerate()
+        .map(|(position, (page, digest))| {
+            matches!(mode, BuildMode::Full)
+                || cached.get(position) != Some(&digest.as_str())
+                || !listing_output_path(config, dir, page.page_number).exists()
+        })
+        .collect()
+}
+
+fn tag_dir(html_root: &Path, slug: &str) -> PathBuf {
+    html_root.join("tags").join(slug)
 }
 
 pub(super) fn tag_index_path(html_root: &Path, slug: &str) -> PathBuf {
-    html_root.join("tags").join(slug).join("index.html")
+    tag_dir(html_root, slug).join("index.html")
 }
 
-pub(super) fn archive_year_path(html_root: &Path, year: i32) -> PathBuf {
-    html_root.join(format!("{:04}", year)).join("index.html")
+fn archive_year_dir(html_root: &Path, year: i32) -> PathBuf {
+    html_root.join(format!("{:04}", year))
 }
 
-pub(super) fn archive_month_path(html_root: &Path, year: i32, month: u8) -> PathBuf {
-    html_root
-        .join(format!("{:04}", year))
-        .join(format!("{:02}", month))
-        .join("index.html")
+fn archive_month_dir(html_root: &Path, year: i32, month: u8) -> PathBuf {
+    archive_year_dir(html_root, year).join(format!("{:02}", month))
 }
 
-fn render_tag_page(template: &minijinja::Template<'_, '_>, plan: TagPagePlan) -> Result<()> {
-    let scope = format!("rendering tag page for '{}'", plan.tag);
-    let rendered = render_template_with_scope(
-        template,
-        minijinja::context! { tag => plan.tag, posts => plan.summaries, pagination => plan.pagination },
-        &scope,
-    )?;
+fn archive_day_dir(html_root: &Path, year: i32, month: u8, day: u8) -> PathBuf {
+    archive_month_dir(html_root, year, month).join(format!("{:02}", day))
+}
 
-    if let Some(parent) = plan.output.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create {}", parent.display()))?;
+/// Splits `posts` (oldest first) with [`PageLayout`] into the index of the
+/// listing at `base` and its numbered pages, each newest first. Without
+/// `per_page` every post stays on the index.
+fn build_listing_pages(
+    config: &Config,
+    bodies: &PostBodies<'_>,
+    posts: &[&Post],
+    per_page: Option<usize>,
+    base: &[&str],
+) -> Result<Vec<ListingPage>> {
+    let posts = by_weight(posts.iter().copied());
+    let layout = PageLayout::new(posts.len(), per_page.unwrap_or(posts.len()));
+    let regular_page_count = layout.regular_page_count;
+    let summarize = |range: Range<usize>| {
+        posts[range]
+            .iter()
+            .rev()
+            .map(|post| listing_summary(config, bodies, post))
+            .collect::<Result<Vec<_>>>()
+    };
+
+    let mut pages = Vec::with_capacity(regular_page_count + 1);
+    for page_number in 1..=regular_page_count {
+        pages.push(ListingPage {
+            page_number,
+            posts: summarize(layout.page_range(page_number))?,
+            pagination: pagination_context(config, base, page_number, regular_page_count),
+        });
     }
-    fs::write(&plan.output, &rendered)
-        .with_context(|| format!("failed to write {}", plan.output.display()))?;
-    Ok(())
+    pages.push(ListingPage {
+        page_number: 0,
+        posts: summarize(layout.home_start()..posts.len())?,
+        pagination: pagination_context(config, base, 0, regular_page_count),
+    });
+    Ok(pages)
+}
+
+/// The summary of `post` on a listing page, whose `body` is left empty when
+/// `listing_full_content` is off.
+fn listing_summary(config: &Config, bodies: &PostBodies<'_>, post: &Post) -> Result<PostSummary> {
+    let body = if config.listing_full_content {
+        bodies.rewritten(config, post, false)?
+    } else {
+        Arc::from("")
+    };
+    build_post_summary(config, post, bodies, body)
+}
+
+/// Links for page `page_number` (0 is the index) of the listing at `base`:
+/// `prev` leads to older posts and `next` to newer ones, ending at the index.
+fn pagination_context(
+    config: &Config,
+    base: &[&str],
+    page_number: usize,
+    regular_page_count: usize,
+) -> PaginationContext {
+    let total = regular_page_count + 1;
+    if page_number == 0 {
+        let prev = if regular_page_count > 0 {
+            page_url(config, base, regular_page_count)
+        } else {
+            String::new()
+        };
+        return PaginationContext {
+            current: total,
+            total,
+            prev,
+            next: String::new(),
+        };
+    }
+
+    let prev = if page_number > 1 {
+        page_url(config, base, page_number - 1)
+    } else {
+        String::new()
+    };
+    let next = if page_number < regular_page_count {
+        page_url(config, base, page_number + 1)
+    } else {
+        dir_url(base)
+    };
+    PaginationContext {
+        current: page_number,
+        total,
+        prev,
+        next,
+    }
+}
+
+fn render_tag_pages(
+    html: &Output,
+    config: &Config,
+    template: &minijinja::Template<'_, '_>,
+    plan: TagPagePlan,
+    stale: &[bool],
+) -> Result<()> {
+    for (page, _) in plan.pages.iter().zip(stale).filter(|(_, stale)| **stale) {
+        let scope = format!(
+            "rendering tag page {} of {} for '{}'",
+            page.pagination.current, page.pagination.total, plan.tag
+        );
+        let rendered = render_template_with_scope(
+            template,
+            minijinja::context! {
+                tag => plan.tag,
+                posts => page.posts,
+                pagination => page.pagination,
+            },
+            &scope,
+        )?;
+        let target = listing_output_path(config, &plan.dir, page.page_number);
+        html.write_page(config, template.name(), &target, &rendered)?;
+    }
+
+    cleanup_numbered_pages(html, config, &plan.dir, plan.pages.len() - 1)
 }
 
-fn render_page(template: &minijinja::Template<'_, '_>, plan: PagePlan) -> Result<()> {
+fn render_page(
+    html: &Output,
+    config: &Config,
+    template: &minijinja::Template<'_, '_>,
+    plan: PagePlan,
+    featured: &[PostSummary],
+    all_posts: Option<&[PostListEntry]>,
+) -> Result<()> {
     let scope = format!(
         "rendering homepage page {} of {}",
         plan.pagination.current, plan.pagination.total
     );
     let rendered = render_template_with_scope(
         template,
-        minijinja::context! { posts => plan.summaries, pagination => plan.pagination },
+        minijinja::context! {
+            posts => plan.summaries,
+            pagination => plan.pagination,
+            featured => featured,
+            all_posts => all_posts,
+        },
         &scope,
     )?;
 
     for output in plan.outputs {
-        if let Some(parent) = output.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
-        }
-        fs::write(&output, &rendered)
-            .with_context(|| format!("failed to write {}", output.display()))?;
+        html.write_page(config, template.name(), &output, &rendered)?;
     }
 
     Ok(())
 }
 
-fn cleanup_tag_cache(db: &sled::Db, html_root: &Path, keep: &BTreeSet<String>) -> Result<()> {
+fn cleanup_tag_cache(
+    db: &sled::Db,
+    html: &Output,
+    config: &Config,
+    keep: &BTreeSet<String>,
+) -> Result<()> {
     let mut stale: Vec<String> = Vec::new();
     for entry in db.scan_prefix(TAG_CACHE_PREFIX.as_bytes()) {
         let (key, _) = entry.context("failed to iterate tag cache entries")?;
@@ -582,18 +1181,22 @@ fn cleanup_tag_cache(db: &sled::Db, html_root: &Path, keep: &BTreeSet<String>) -
             if slug.is_empty() {
                 continue;
             }
-            let output = tag_index_path(html_root, slug);
-            remove_file_if_exists(&output)?;
-            if let Some(parent) = output.parent() {
-                remove_dir_if_empty(parent)?;
-            }
+            let dir = tag_dir(html.root(), slug);
+            html.remove_file(&dir.join("index.html"))?;
+            cleanup_numbered_pages(html, config, &dir, 0)?;
+            html.remove_dir_if_empty(&dir)?;
         }
     }
 
     Ok(())
 }
 
-fn cleanup_month_archives(db: &sled::Db, html_root: &Path, keep: &BTreeSet<String>) -> Result<()> {
+fn cleanup_month_archives(
+    db: &sled::Db,
+    html: &Output,
+    config: &Config,
+    keep: &BTreeSet<String>,
+) -> Result<()> {
     let mut stale: Vec<String> = Vec::new();
     for entry in db.scan_prefix(MONTH_ARCHIVE_PREFIX.as_bytes()) {
         let (key, _) = entry.context("failed to iterate month archive cache entries")?;
@@ -612,18 +1215,60 @@ fn cleanup_month_archives(db: &sled::Db, html_root: &Path, keep: &BTreeSet<Strin
             && let Some((year_str, month_str)) = suffix.split_once('-')
             && let (Ok(year), Ok(month)) = (year_str.parse::<i32>(), month_str.parse::<u8>())
         {
-            let output = archive_month_path(html_root, year, month);
-            remove_file_if_exists(&output)?;
-            if let Some(parent) = output.parent() {
-                remove_dir_if_empty(parent)?;
-            }
+            let dir = archive_month_dir(html.root(), year, month);
+            html.remove_file(&dir.join("index.html"))?;
+            cleanup_numbered_pages(html, config, &dir, 0)?;
+            html.remove_dir_if_empty(&dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn cleanup_day_archives(
+    db: &sled::Db,
+    html: &Output,
+    config: &Config,
+    keep: &BTreeSet<String>,
+) -> Result<()> {
+    let mut stale: Vec<String> = Vec::new();
+    for entry in db.scan_prefix(DAY_ARCHIVE_PREFIX.as_bytes()) {
+        let (key, _) = entry.context("failed to iterate day archive cache entries")?;
+        let key_str =
+            String::from_utf8(key.to_vec()).context("day archive cache key is not valid utf-8")?;
+        if !keep.contains(&key_str) {
+            stale.push(key_str);
+        }
+    }
+
+    for key in stale {
+        db.remove(key.as_bytes())
+            .context("failed to remove stale day archive cache entry")?;
+        let mut parts = key
+            .strip_prefix(DAY_ARCHIVE_PREFIX)
+            .unwrap_or_default()
+            .splitn(3, '-');
+        if let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) = (
+            parts.next().map(str::parse::<i32>),
+            parts.next().map(str::parse::<u8>),
+            parts.next().map(str::parse::<u8>),
+        ) {
+            let dir = archive_day_dir(html.root(), year, month, day);
+            html.remove_file(&dir.join("index.html"))?;
+            cleanup_numbered_pages(html, config, &dir, 0)?;
+            html.remove_dir_if_empty(&dir)?;
         }
     }
 
     Ok(())
 }
 
-fn cleanup_year_archives(db: &sled::Db, html_root: &Path, keep: &BTreeSet<String>) -> Result<()> {
+fn cleanup_year_archives(
+    db: &sled::Db,
+    html: &Output,
+    config: &Config,
+    keep: &BTreeSet<String>,
+) -> Result<()> {
     let mut stale: Vec<String> = Vec::new();
     for entry in db.scan_prefix(YEAR_ARCHIVE_PREFIX.as_bytes()) {
         let (key, _) = entry.context("failed to iterate year archive cache entries")?;
@@ -641,31 +1286,84 @@ fn cleanup_year_archives(db: &sled::Db, html_root: &Path, keep: &BTreeSet<String
         if let Some(year_str) = key.strip_prefix(YEAR_ARCHIVE_PREFIX)
             && let Ok(year) = year_str.parse::<i32>()
         {
-            let output = archive_year_path(html_root, year);
-            remove_file_if_exists(&output)?;
-            if let Some(parent) = output.parent() {
-                remove_dir_if_empty(parent)?;
-            }
+            let dir = archive_year_dir(html.root(), year);
+            html.remove_file(&dir.join("index.html"))?;
+            cleanup_numbered_pages(html, config, &dir, 0)?;
+            html.remove_dir_if_empty(&dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the numbered pages (`page/N/` by default) under the listing in
+/// `dir` beyond the current `regular_page_count`, including leftovers from a
+/// larger page count or a different layout.
+pub(super) fn cleanup_numbered_pages(
+    html: &Output,
+    config: &Config,
+    dir: &Path,
+    regular_page_count: usize,
+) -> Result<()> {
+    remove_numbered_pages(html, &PaginationPath::new(config), dir, regular_page_count)
+}
+
+/// Removes the numbered pages that `previous`, an earlier `pagination_path`,
+/// put under the homepage and under every tag and archive listing in the
+/// cache, so changing the setting leaves no pages at the old URLs.
+pub(super) fn remove_previous_pagination(
+    db: &sled::Db,
+    html: &Output,
+    previous: &str,
+) -> Result<()> {
+    let paging = PaginationPath::parse(previous);
+    let root = html.root();
+    let mut dirs = vec![root.to_path_buf()];
+    for (prefix, what) in [
+        (TAG_CACHE_PREFIX, "tag"),
+        (YEAR_ARCHIVE_PREFIX, "year archive"),
+        (MONTH_ARCHIVE_PREFIX, "month archive"),
+        (DAY_ARCHIVE_PREFIX, "day archive"),
+    ] {
+        for entry in db.scan_prefix(prefix.as_bytes()) {
+            let (key, _) =
+                entry.with_context(|| format!("failed to iterate {what} cache entries"))?;
+            let key = String::from_utf8(key.to_vec())
+                .with_context(|| format!("{what} cache key is not valid utf-8"))?;
+            let name = &key[prefix.len()..];
+            let mut parts = name.split('-').map(str::parse::<i32>);
+            let dir = match (prefix, parts.next(), parts.next(), parts.next()) {
+                (TAG_CACHE_PREFIX, ..) if !name.is_empty() => tag_dir(root, name),
+                (YEAR_ARCHIVE_PREFIX, Some(Ok(year)), None, None) => archive_year_dir(root, year),
+                (MONTH_ARCHIVE_PREFIX, Some(Ok(year)), Some(Ok(month)), None) => {
+                    archive_month_dir(root, year, month as u8)
+                }
+                (DAY_ARCHIVE_PREFIX, Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => {
+                    archive_day_dir(root, year, month as u8, day as u8)
+                }
+                _ => continue,
+            };
+            dirs.push(dir);
         }
     }
 
+    for dir in dirs {
+        remove_numbered_pages(html, &paging, &dir, 0)?;
+    }
     Ok(())
 }
 
-fn cleanup_homepage_pages(html_root: &Path, keep: &[StoredPage]) -> Result<()> {
-    let page_dir = html_root.join("page");
+fn remove_numbered_pages(
+    html: &Output,
+    paging: &PaginationPath<'_>,
+    dir: &Path,
+    regular_page_count: usize,
+) -> Result<()> {
+    let page_dir = dir.join(paging.dir);
     if !page_dir.exists() {
         return Ok(());
     }
 
-    // Build set of page numbers we want to keep (skip homepage which is page_number=0)
-    let keep_pages: HashSet<usize> = keep
-        .iter()
-        .filter(|p| p.page_number > 0)
-        .map(|p| p.page_number)
-        .collect();
-
-    // Read all subdirectories in html/page
     let entries = fs::read_dir(&page_dir)
         .with_context(|| format!("failed to read directory {}", page_dir.display()))?;
 
@@ -673,28 +1371,107 @@ fn cleanup_homepage_pages(html_root: &Path, keep: &[StoredPage]) -> Result<()> {
         let entry = entry.context("failed to read directory entry")?;
         let path = entry.path();
 
-        if !path.is_dir() {
+        if path.is_dir() != paging.is_dir() {
             continue;
         }
 
         if let Some(name) = path.file_name().and_then(|n| n.to_str())
-            && let Ok(page_num) = name.parse::<usize>()
-            && !keep_pages.contains(&page_num)
+            && let Some(page_num) = paging.page_number(name)
+            && (page_num == 0 || page_num > regular_page_count)
         {
-            // This is a stale page directory, remove it
-            fs::remove_dir_all(&path).with_context(|| {
-                format!("failed to remove stale page directory {}", path.display())
-            })?;
+            if paging.is_dir() {
+                html.remove_dir_all(&path)?;
+            } else {
+                html.remove_file(&path)?;
+            }
         }
     }
 
+    // `pagination_path` may nest the pages several directories deep.
+    let mut empty = page_dir.as_path();
+    while empty != dir {
+        html.remove_dir_if_empty(empty)?;
+        match empty.parent() {
+            Some(parent) => empty = parent,
+            None => break,
+        }
+    }
     Ok(())
 }
 
+/// Puts posts in listing order: oldest first like the discovered posts, with
+/// heavier `weight`s moved after lighter ones so they are listed first. The
+/// sort is stable, so posts of equal weight keep their date order.
+pub(super) fn by_weight<'a>(posts: impl IntoIterator<Item = &'a Post>) -> Vec<&'a Post> {
+    let mut ordered: Vec<&Post> = posts.into_iter().collect();
+    ordered.sort_by_key(|post| post.weight);
+    ordered
+}
+
+/// The posts in homepage listing order, the reverse of display order: the
+/// index page shows the last of them.
+pub(super) fn homepage_order(posts: &[Post], sort: HomepageSort) -> Vec<&Post> {
+    let mut ordered: Vec<&Post> = match sort {
+        HomepageSort::DateDesc => posts.iter().collect(),
+        HomepageSort::DateAsc => posts.iter().rev().collect(),
+        HomepageSort::Title => {
+            let mut ordered: Vec<&Post> = posts.iter().collect();
+            ordered.sort_by_cached_key(|post| {
+                post.title.as_deref().unwrap_or(&post.slug).to_lowercase()
+            });
+            ordered.reverse();
+            ordered
+        }
+    };
+    ordered.sort_by_key(|post| post.weight);
+    ordered
+}
+
+// Editing only `updated:` keeps the post keys stable, so pages also remember
+// each post's revision time to notice the change.
+fn revision_stamps<'a>(posts: impl Iterator<Item = &'a Post>) -> Vec<Option<i64>> {
+    posts
+        .map(|post| post.updated.map(|updated| updated.unix_timestamp()))
+        .collect()
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 struct StoredPage {
     page_number: usize, // 0 = homepage, 1+ = numbered pages
     posts: Vec<String>,
+    #[serde(default)]
+    updated: Vec<Option<i64>>,
+    #[serde(default)]
+    featured: Vec<String>,
+    #[serde(default)]
+    prev: String,
+    #[serde(default)]
+    next: String,
+    /// Page count, recorded for the homepage only so a new numbered page
+    /// leaves the full pages before it alone.
+    #[serde(default)]
+    total: Option<usize>,
+}
+
+impl StoredPage {
+    /// The record of page `page_number` showing `posts` (listing order,
+    /// oldest first) with the links in `pagination`.
+    fn new(
+        page_number: usize,
+        posts: &[&Post],
+        featured: &[String],
+        pagination: &PaginationContext,
+    ) -> Self {
+        Self {
+            page_number,
+            posts: posts.iter().rev().map(|post| post_key(post)).collect(),
+            updated: revision_stamps(posts.iter().rev().copied()),
+            featured: featured.to_vec(),
+            prev: pagination.prev.clone(),
+            next: pagination.next.clone(),
+            total: (page_number == 0).then_some(pagination.total),
+        }
+    }
 }
 
 struct TagBucket {
@@ -711,32 +1488,50 @@ struct PaginationContext {
     next: String,
 }
 
+/// One page of a tag or archive listing.
+struct ListingPage {
+    page_number: usize, // 0 = index, 1+ = numbered pages
+    posts: Vec<PostSummary>,
+    pagination: PaginationContext,
+}
+
 #[derive(Serialize)]
-struct TagCachePayload<'a> {
-    tag: &'a str,
+struct ListingPageDigest<'a, L: Serialize> {
+    listing: &'a L,
     posts: &'a [PostSummary],
-    pagination: &'a PaginationContext,
+    prev: &'a str,
+    next: &'a str,
+    total: Option<usize>,
 }
 
 #[derive(Serialize)]
-struct YearArchiveCachePayload<'a> {
+struct TagListing<'a> {
+    tag: &'a str,
+}
+
+#[derive(Serialize)]
+struct YearArchiveListing {
     year: i32,
-    posts: &'a [PostSummary],
 }
 
 #[derive(Serialize)]
-struct MonthArchiveCachePayload<'a> {
+struct MonthArchiveListing {
     year: i32,
     month: u8,
-    posts: &'a [PostSummary],
+}
+
+#[derive(Serialize)]
+struct DayArchiveListing {
+    year: i32,
+    month: u8,
+    day: u8,
 }
 
 struct TagPagePlan {
     tag: String,
     slug: String,
-    summaries: Vec<PostSummary>,
-    pagination: PaginationContext,
-    output: PathBuf,
+    pages: Vec<ListingPage>,
+    dir: PathBuf,
 }
 
 struct PagePlan {
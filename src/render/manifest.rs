@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use blake3::Hasher;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::config::Config;
+use crate::logging::Logger;
+
+use super::cache::{read_cached_string, store_cached_string};
+use super::output::Output;
+use super::utils::normalize_path;
+
+const MANIFEST_KEY: &str = "manifest_hash";
+const MANIFEST_FILE: &str = "manifest.json";
+/// Per-file cache of `size modified hash`, so files the render left alone are
+/// not read again.
+const MANIFEST_ENTRY_PREFIX: &str = "manifest_file:";
+
+#[derive(Debug, Serialize)]
+struct ManifestEntry {
+    hash: String,
+    size: u64,
+}
+
+/// Writes `html/manifest.json`, mapping every other file below `html/` to its
+/// blake3 hash and size, when `output_manifest` is enabled. Runs after
+/// everything else is written, only rewrites the file when an output changed,
+/// and removes it again once the option is turned off.
+pub(super) fn render_manifest(
+    html: &Output,
+    config: &Config,
+    cache_db: &sled::Db,
+    log: Logger,
+) -> Result<()> {
+    let output = html.root().join(MANIFEST_FILE);
+    if !config.output_manifest {
+        if read_cached_string(cache_db, MANIFEST_KEY)?.is_some() {
+            html.remove_file(&output)?;
+            cache_db
+                .remove(MANIFEST_KEY)
+                .context("failed to remove manifest cache entry")?;
+            clear_entries(cache_db, &HashSet::new())?;
+        }
+        return Ok(());
+    }
+
+    let mut entries = BTreeMap::new();
+    for entry in WalkDir::new(html.root()).sort_by_file_name() {
+        let entry =
+            entry.with_context(|| format!("failed to enumerate {}", html.root().display()))?;
+        if !entry.file_type().is_file() || entry.path() == output {
+            continue;
+        }
+        let relative = normalize_path(entry.path().strip_prefix(html.root()).unwrap());
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("failed to inspect {}", entry.path().display()))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_nanos());
+        let stamp = format!("{} {}", metadata.len(), modified);
+
+        let key = format!("{MANIFEST_ENTRY_PREFIX}{relative}");
+        let hash = match read_cached_string(cache_db, &key)?.and_then(|cached| {
+            cached
+                .strip_prefix(&format!("{stamp} "))
+                .map(str::to_string)
+        }) {
+            Some(hash) => hash,
+            None => {
+                let data = fs::read(entry.path())
+                    .with_context(|| format!("failed to read {}", entry.path().display()))?;
+                let hash = blake3::hash(&data).to_hex().to_string();
+                store_cached_string(cache_db, &key, &format!("{stamp} {hash}"))?;
+                hash
+            }
+        };
+        entries.insert(
+            relative,
+            ManifestEntry {
+                hash,
+                size: metadata.len(),
+            },
+        );
+    }
+    clear_entries(cache_db, &entries.keys().cloned().collect())?;
+
+    let bytes = serde_json::to_vec(&entries).context("failed to serialize manifest.json")?;
+    let mut hasher = Hasher::new();
+    hasher.update(&bytes);
+    let digest = hasher.finalize().to_hex().to_string();
+    if read_cached_string(cache_db, MANIFEST_KEY)?.as_deref() == Some(digest.as_str())
+        && output.exists()
+    {
+        log.trace("MANIFEST", "manifest.json unchanged");
+        return Ok(());
+    }
+
+    html.write(&output, bytes)?;
+    store_cached_string(cache_db, MANIFEST_KEY, &digest)?;
+    log.debug(
+        "MANIFEST",
+        format!("Wrote manifest.json ({} files)", entries.len()),
+    );
+    Ok(())
+}
+
+/// Drops the cached hashes of files that are no longer in `html/`.
+fn clear_entries(cache_db: &sled::Db, keep: &HashSet<String>) -> Result<()> {
+    for item in cache_db.scan_prefix(MANIFEST_ENTRY_PREFIX.as_bytes()) {
+        let (key, _) = item.context("failed to read manifest cache entries")?;
+        let path = String::from_utf8_lossy(&key[MANIFEST_ENTRY_PREFIX.len()..]).into_owned();
+        if !keep.contains(&path) {
+            cache_db
+                .remove(&key)
+                .context("failed to remove manifest cache entry")?;
+        }
+    }
+    Ok(())
+}
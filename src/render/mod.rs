@@ -1,55 +1,126 @@
 mod assets;
 mod cache;
+mod exif;
+mod export;
 mod feeds;
+mod inline;
 mod listing;
+mod manifest;
+mod oembed;
+mod output;
 mod pages;
+mod post_index;
 mod posts;
+mod preview;
+mod site;
+mod sources;
+mod stats;
 mod templates;
 mod utils;
+mod validate;
 
 #[cfg(test)]
 mod tests;
 
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread;
 use std::time::Instant;
 
 use anyhow::{Context, Result};
 use blake3::Hasher;
 
-use crate::config::Config;
+use crate::config::{self, Config, LintConfig, SearchFormat};
+use crate::content::Post;
+use crate::logging::{Level, Logger};
 use crate::search;
 use crate::template;
+use crate::timing;
 
 use assets::{
     ThemeAssetCopy, compute_static_digest, compute_theme_asset_digest, copy_static_assets,
-    copy_theme_assets,
+    copy_theme_assets, remove_stale_theme_assets,
 };
-use cache::{open_cache_db, read_cached_string, store_cached_string};
+pub use cache::cache_location;
+use cache::{open_cache_db, open_cache_snapshot, read_cached_string, store_cached_string};
 use feeds::render_feeds;
-use listing::{HomePageCache, render_archives, render_homepage, render_tag_archives};
-use pages::render_pages;
-use posts::render_posts;
+use listing::{
+    HomePageCache, cleanup_numbered_pages, remove_previous_pagination, render_archives,
+    render_homepage, render_tag_archives, resolve_tag_slugs,
+};
+use manifest::render_manifest;
+use output::Output;
+use pages::{pages_reference_posts, render_pages};
+use post_index::render_posts_json;
+use posts::{PostBodies, check_post_digest, discover_site_posts, render_posts};
+use site::build_site_context;
+use stats::render_stats;
 use templates::load_templates;
-use utils::log_status;
+use utils::{format_bytes, normalize_path};
+use validate::{check_base_url, report_issues};
+
+pub use cache::CACHE_SCHEMA_VERSION;
+pub use export::{ArchiveFormat, ExportOptions, export_site};
+pub use output::{ChangeCategory, ChangeKind, PlannedChange};
+pub use preview::{PostPreview, preview_post, render_preview};
+pub use sources::{SiteSources, SourceFile, SourceKind, site_sources};
+pub use validate::{SiteIssue, validate_site};
 
 pub(super) const CACHE_DIR: &str = ".bckt/cache";
 pub(super) const HOME_PAGES_KEY: &str = "home_pages";
 pub(super) const POST_HASH_PREFIX: &str = "post:";
+pub(super) const LANGUAGE_PREFIX: &str = "lang:";
+pub(super) const BODY_PREFIX: &str = "body:";
+pub(super) const SEARCH_TEXT_PREFIX: &str = "search_text:";
+pub(super) const BACKLINKS_PREFIX: &str = "backlinks:";
+pub(super) const STRIPPED_ATTACHMENT_PREFIX: &str = "stripped:";
+pub(super) const LASTMOD_PREFIX: &str = "lastmod:";
 pub(super) const TAG_CACHE_PREFIX: &str = "tag_index:";
 pub(super) const YEAR_ARCHIVE_PREFIX: &str = "archive_year:";
 pub(super) const MONTH_ARCHIVE_PREFIX: &str = "archive_month:";
+pub(super) const DAY_ARCHIVE_PREFIX: &str = "archive_day:";
 const SITE_INPUTS_KEY: &str = "site_inputs_hash";
+const INLINED_FILES_KEY: &str = "inlined_files";
+const TAG_SLUGS_KEY: &str = "tag_slugs";
 const STATIC_HASH_KEY: &str = "static_hash";
 const SEARCH_INDEX_KEY: &str = "search_index_hash";
 const THEME_ASSET_HASH_KEY: &str = "theme_asset_hash";
+const THEME_NAME_KEY: &str = "theme_name";
+const THEME_ASSET_FILES_KEY: &str = "theme_asset_files";
+const PAGINATION_PATH_KEY: &str = "pagination_path";
 
 #[derive(Clone, Copy, Debug)]
-pub struct RenderPlan {
+pub struct RenderPlan<'a> {
     pub posts: bool,
     pub static_assets: bool,
     pub mode: BuildMode,
-    pub verbose: bool,
+    /// Which progress messages to print; warnings are always shown.
+    pub log: Logger,
+    pub profile: Option<&'a str>,
+    /// Detect changes without writing `html/` or the cache.
+    pub dry_run: bool,
+    /// Overrides `jobs` from the config.
+    pub jobs: Option<usize>,
+    /// Overrides `base_url` from the config.
+    pub base_url: Option<&'a str>,
+}
+
+/// A full render of posts and static assets with the config as is.
+impl Default for RenderPlan<'_> {
+    fn default() -> Self {
+        Self {
+            posts: true,
+            static_assets: true,
+            mode: BuildMode::Full,
+            log: Logger::default(),
+            profile: None,
+            dry_run: false,
+            jobs: None,
+            base_url: None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -58,55 +129,166 @@ pub enum BuildMode {
     Changed,
 }
 
+/// Counts from a completed render, also printed as the summary line.
 #[derive(Default, Debug)]
-struct RenderStats {
-    posts_rendered: usize,
-    posts_skipped: usize,
-    pages_rendered: usize,
-    search_documents: usize,
-    static_assets_copied: usize,
-    theme_assets_copied: usize,
+pub struct RenderReport {
+    pub posts_rendered: usize,
+    pub posts_skipped: usize,
+    pub pages_rendered: usize,
+    pub search_documents: usize,
+    pub static_assets_copied: usize,
+    pub theme_assets_copied: usize,
+    pub attachments_copied: usize,
+    pub attachment_bytes_copied: u64,
+    /// What a dry run would have written or deleted; empty otherwise.
+    pub planned_changes: Vec<PlannedChange>,
+    /// Which outputs the cache let this render skip.
+    pub cache: CacheStats,
 }
 
-pub fn render_site(root: &Path, plan: RenderPlan) -> Result<()> {
+/// Outputs reused from the previous render (hits) and rebuilt (misses).
+#[derive(Clone, Copy, Default, Debug, Eq, PartialEq)]
+pub struct CacheCounts {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl CacheCounts {
+    /// Counts one listing, whose pages are rebuilt where `stale` is true.
+    pub(super) fn record(&mut self, stale: &[bool]) {
+        let misses = stale.iter().filter(|&&stale| stale).count();
+        self.misses += misses;
+        self.hits += stale.len() - misses;
+    }
+
+    pub fn total(&self) -> usize {
+        self.hits + self.misses
+    }
+
+    /// The share of hits, or `None` when there was nothing to render.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        (self.total() > 0).then(|| self.hits as f64 / self.total() as f64)
+    }
+}
+
+/// Cache hits per render step. Archives and tags count listing pages; pages
+/// are never cached, so each one is a miss.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct CacheStats {
+    /// The render ran incrementally, rather than being asked for or forced
+    /// into a full rebuild by changed config, templates, or tag slugs.
+    pub incremental: bool,
+    pub posts: CacheCounts,
+    pub archives: CacheCounts,
+    pub tags: CacheCounts,
+    pub pages: CacheCounts,
+    pub search: CacheCounts,
+}
+
+impl CacheStats {
+    pub fn categories(&self) -> [(&'static str, CacheCounts); 5] {
+        [
+            ("posts", self.posts),
+            ("archives", self.archives),
+            ("tags", self.tags),
+            ("pages", self.pages),
+            ("search", self.search),
+        ]
+    }
+
+    pub fn overall(&self) -> CacheCounts {
+        self.categories()
+            .iter()
+            .fold(CacheCounts::default(), |sum, (_, counts)| CacheCounts {
+                hits: sum.hits + counts.hits,
+                misses: sum.misses + counts.misses,
+            })
+    }
+}
+
+pub fn render_site(root: &Path, plan: RenderPlan<'_>) -> Result<RenderReport> {
+    let mut config = Config::load_with_profile(config::config_path(root), plan.profile)?;
+    if let Some(base_url) = plan.base_url {
+        config.override_base_url(base_url)?;
+    }
+    render_site_with_config(root, config, plan)
+}
+
+/// Renders with a config the caller already loaded for `plan.profile`, with
+/// `plan.base_url` applied, so it can use the same values afterwards.
+pub fn render_site_with_config(
+    root: &Path,
+    mut config: Config,
+    plan: RenderPlan<'_>,
+) -> Result<RenderReport> {
+    let _span = timing::span(|| "render".to_string());
     let started = Instant::now();
-    let mut stats = RenderStats::default();
-    let config_path = root.join("bckt.yaml");
-    let config_raw = if config_path.exists() {
-        fs::read_to_string(&config_path)
-            .with_context(|| format!("failed to read config file {}", config_path.display()))?
+    let mut stats = RenderReport::default();
+    report_issues(check_base_url(&config).as_slice(), plan.log)?;
+    // Hash the resolved config so environment variable changes invalidate too.
+    // Tag and archive page sizes are left out: the digests of those listings
+    // cover their pages, so changing one re-renders only that surface.
+    let hashed_config = Config {
+        tags_per_page: None,
+        archive_per_page: None,
+        lint: LintConfig::default(),
+        jobs: None,
+        ..config.clone()
+    };
+    let config_resolved =
+        serde_json::to_string(&hashed_config).context("failed to serialize resolved config")?;
+    let jobs = plan
+        .jobs
+        .or(config.jobs)
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+    let html = Output::new(
+        root.join("html"),
+        plan.dry_run,
+        jobs,
+        config.output_file_mode(),
+    );
+    let cache_dir = cache_location(root, &config);
+    plan.log
+        .debug("CACHE", format!("Using cache at {}", cache_dir.display()));
+    let cache_db = if plan.dry_run {
+        open_cache_snapshot(&cache_dir)?
     } else {
-        String::new()
+        fs::create_dir_all(html.root()).context("failed to ensure html directory exists")?;
+        open_cache_db(&cache_dir)?
     };
-    let config = Config::load(&config_path)?;
-    let html_root = root.join("html");
-    fs::create_dir_all(&html_root).context("failed to ensure html directory exists")?;
-
-    let cache_db = open_cache_db(root)?;
     let mut env = template::environment(&config)?;
-    let template_hash = load_templates(root, &mut env)?;
-    let site_inputs_hash = compute_site_inputs_hash(&config_raw, &template_hash);
+    let template_hash = load_templates(&config.templates_path(root), &mut env)?;
+    let inlined = inline::register(&mut env, root, &config)?;
+    // The files inlined by the last render stand in for the ones this render
+    // will inline, which are only known once it is done.
+    let previous_inlined: Vec<PathBuf> = match read_cached_string(&cache_db, INLINED_FILES_KEY)? {
+        Some(raw) => serde_json::from_str(&raw).context(
+            "cached list of inlined files is unreadable; run `bckt clean --cache` and render again",
+        )?,
+        None => Vec::new(),
+    };
+    let site_inputs_hash = compute_site_inputs_hash(
+        &config_resolved,
+        plan.profile,
+        &template_hash,
+        &inlined.digest(&previous_inlined)?,
+    );
 
     let stored_site_hash = read_cached_string(&cache_db, SITE_INPUTS_KEY)?;
     let site_changed = stored_site_hash.as_deref() != Some(site_inputs_hash.as_str());
 
-    if plan.verbose {
-        if plan.mode == BuildMode::Full {
-            log_status(true, "MODE", "Full rebuild requested");
-        } else {
-            log_status(true, "MODE", "Incremental rebuild requested");
-        }
+    let log = plan.log;
+    if plan.mode == BuildMode::Full {
+        log.debug("MODE", "Full rebuild requested");
+    } else {
+        log.debug("MODE", "Incremental rebuild requested");
     }
 
-    let effective_mode = match plan.mode {
+    let mut effective_mode = match plan.mode {
         BuildMode::Full => BuildMode::Full,
         BuildMode::Changed => {
             if site_changed {
-                log_status(
-                    plan.verbose,
-                    "MODE",
-                    "Config or templates changed; forcing full rebuild",
-                );
+                log.debug("MODE", "Config or templates changed; forcing full rebuild");
                 BuildMode::Full
             } else {
                 BuildMode::Changed
@@ -114,79 +296,113 @@ pub fn render_site(root: &Path, plan: RenderPlan) -> Result<()> {
         }
     };
 
-    if plan.verbose {
-        match effective_mode {
-            BuildMode::Full => log_status(true, "MODE", "Executing full rebuild"),
-            BuildMode::Changed => log_status(true, "MODE", "Executing incremental rebuild"),
-        }
+    match effective_mode {
+        BuildMode::Full => log.debug("MODE", "Executing full rebuild"),
+        BuildMode::Changed => log.debug("MODE", "Executing incremental rebuild"),
     }
 
     let cache = HomePageCache::new(cache_db.clone());
+    let bodies = PostBodies::new(&config, &cache_db);
 
-    let posts = if plan.posts {
-        log_status(plan.verbose, "STEP", "Rendering posts");
-        let (posts, rendered_posts, skipped_posts) = render_posts(
-            root,
-            &html_root,
-            &config,
-            &env,
-            &cache_db,
-            effective_mode,
-            plan.verbose,
-        )?;
-        log_status(
-            plan.verbose,
-            "STEP",
-            format!("Processed {} posts", posts.len()),
-        );
-        stats.posts_rendered = rendered_posts;
-        stats.posts_skipped = skipped_posts;
-        posts
+    let discovered = if plan.posts {
+        Some(discover_site_posts(root, &config, &cache_db)?)
     } else {
-        log_status(plan.verbose, "STEP", "Skipping post rendering");
-        Vec::new()
+        None
     };
+    let posts: &[Post] = discovered
+        .as_ref()
+        .map_or(&[], |discovered| &discovered.posts);
+    let tag_slugs = if plan.posts {
+        report_issues(&resolve_tag_slugs(&mut config, posts), log)?;
+        let tag_slugs = serde_json::to_string(&(&config.tag_slugs, &config.tag_names))
+            .context("failed to encode tag slugs")?;
+        // Post pages link to tag archives, so a tag changing slug or name
+        // touches pages whose posts did not change.
+        if effective_mode == BuildMode::Changed
+            && read_cached_string(&cache_db, TAG_SLUGS_KEY)?.as_deref() != Some(tag_slugs.as_str())
+        {
+            log.debug("MODE", "Tag slugs changed; forcing full rebuild");
+            effective_mode = BuildMode::Full;
+        }
+        Some(tag_slugs)
+    } else {
+        None
+    };
+    env.add_global("site", build_site_context(&config, posts)?);
 
-    if plan.posts {
-        log_status(plan.verbose, "STEP", "Rendering indexes and feeds");
-        render_homepage(&posts, &html_root, &config, &env, &cache, effective_mode)?;
-        render_tag_archives(
-            &posts,
-            &html_root,
+    if let Some(discovered) = &discovered {
+        log.debug("STEP", "Rendering posts");
+        let _span = timing::span(|| "render posts".to_string());
+        let post_stats = render_posts(
+            discovered,
+            &html,
             &config,
             &env,
-            &cache_db,
+            &bodies,
             effective_mode,
-            plan.verbose,
+            log,
         )?;
-        render_archives(
-            &posts,
-            &html_root,
-            &config,
-            &env,
-            &cache_db,
-            effective_mode,
-            plan.verbose,
-        )?;
-        render_feeds(&posts, &html_root, &config, &env)?;
+        log.debug("STEP", format!("Processed {} posts", posts.len()));
+        stats.posts_rendered = post_stats.rendered;
+        stats.posts_skipped = post_stats.skipped;
+        stats.cache.posts = CacheCounts {
+            hits: post_stats.skipped,
+            misses: post_stats.rendered,
+        };
+        stats.attachments_copied = post_stats.attachments_copied;
+        stats.attachment_bytes_copied = post_stats.attachment_bytes;
+    } else {
+        log.trace("STEP", "Skipping post rendering");
+    }
 
-        let artifact = search::build_index(&config, &posts)?;
+    if plan.posts {
+        log.debug("STEP", "Rendering indexes and feeds");
+        html.set_category(ChangeCategory::Listings);
+        if let Some(previous) = read_cached_string(&cache_db, PAGINATION_PATH_KEY)?
+            && previous != config.pagination_path
+        {
+            log.debug(
+                "STEP",
+                format!("pagination_path changed; removing pages at {previous}"),
+            );
+            remove_previous_pagination(&cache_db, &html, &previous)?;
+        }
+        store_cached_string(&cache_db, PAGINATION_PATH_KEY, &config.pagination_path)?;
+        {
+            let _span = timing::span(|| "listings".to_string());
+            render_homepage(posts, &html, &config, &env, &cache, &bodies, effective_mode)?;
+            stats.cache.tags =
+                render_tag_archives(posts, &html, &config, &env, &bodies, effective_mode, log)?;
+            stats.cache.archives =
+                render_archives(posts, &html, &config, &env, &bodies, effective_mode, log)?;
+        }
+        html.set_category(ChangeCategory::Feeds);
+        {
+            let _span = timing::span(|| "feeds".to_string());
+            render_feeds(posts, &html, &config, &env, &bodies, effective_mode)?;
+        }
+        html.set_category(ChangeCategory::Listings);
+        render_posts_json(posts, &html, &config, &cache_db, effective_mode, log)?;
+        render_stats(posts, &html, &config, &env, &bodies, effective_mode, log)?;
+
+        let search_span = timing::span(|| "search index".to_string());
+        let artifact = search::build_index(&config, posts, bodies.store())?;
         stats.search_documents = artifact.document_count;
-        let search_path = search::resolve_asset_path(&html_root, &config.search.asset_path);
+        let search_path = search::resolve_asset_path(html.root(), &config.search);
         let cached_search_hash = read_cached_string(&cache_db, SEARCH_INDEX_KEY)?;
         let needs_search = cached_search_hash.as_deref() != Some(artifact.digest.as_str())
             || !search_path.exists();
 
+        stats.cache.search = CacheCounts {
+            hits: usize::from(!needs_search),
+            misses: usize::from(needs_search),
+        };
         if needs_search {
-            if let Some(parent) = search_path.parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("failed to create {}", parent.display()))?;
-            }
-            fs::write(&search_path, &artifact.bytes).with_context(|| {
-                format!("failed to write search index to {}", search_path.display())
-            })?;
-            log_status(
-                plan.verbose,
+            html.set_category(ChangeCategory::Search);
+            html.write(&search_path, &artifact.bytes)
+                .context("failed to write search index")?;
+            remove_stale_search_indexes(&html, &config, &search_path)?;
+            log.debug(
                 "SEARCH",
                 format!(
                     "Updated search index ({} documents)",
@@ -194,75 +410,169 @@ pub fn render_site(root: &Path, plan: RenderPlan) -> Result<()> {
                 ),
             );
         } else {
-            log_status(plan.verbose, "SEARCH", "Search index unchanged");
+            log.trace("SEARCH", "Search index unchanged");
         }
 
         store_cached_string(&cache_db, SEARCH_INDEX_KEY, &artifact.digest)?;
+        drop(search_span);
+        // Incremental renders skip pages, so they only add to the files a full
+        // render found.
+        let mut inlined_paths = inlined.paths();
+        if effective_mode == BuildMode::Changed {
+            inlined_paths.extend(previous_inlined);
+            inlined_paths.sort();
+            inlined_paths.dedup();
+        }
+        let site_inputs_hash = compute_site_inputs_hash(
+            &config_resolved,
+            plan.profile,
+            &template_hash,
+            &inlined.digest(&inlined_paths)?,
+        );
         store_cached_string(&cache_db, SITE_INPUTS_KEY, &site_inputs_hash)?;
+        if let Some(tag_slugs) = &tag_slugs {
+            store_cached_string(&cache_db, TAG_SLUGS_KEY, tag_slugs)?;
+        }
+        let encoded =
+            serde_json::to_string(&inlined_paths).context("failed to encode inlined files")?;
+        store_cached_string(&cache_db, INLINED_FILES_KEY, &encoded)?;
+    } else if let Some(page_count) = cache.rendered_page_count()? {
+        html.set_category(ChangeCategory::Listings);
+        // Without posts the homepage is not re-rendered, but directories left
+        // beyond its last recorded layout are still removed.
+        cleanup_numbered_pages(&html, &config, html.root(), page_count)?;
     }
 
-    stats.pages_rendered = render_pages(root, &html_root, &env, plan.verbose)?;
+    html.set_category(ChangeCategory::Pages);
+    let pages_dir = config.pages_path(root);
+    // Posts are still needed to resolve page references when only assets
+    // are rendered.
+    let page_posts = match &discovered {
+        None if pages_reference_posts(&pages_dir, &config)? => {
+            Some(discover_site_posts(root, &config, &cache_db)?)
+        }
+        _ => None,
+    };
+    let page_posts = page_posts
+        .as_ref()
+        .map_or(posts, |discovered| &discovered.posts);
+    stats.pages_rendered = {
+        let _span = timing::span(|| "pages".to_string());
+        render_pages(&pages_dir, &html, &env, &config, page_posts, log)?
+    };
+    stats.cache.pages.misses = stats.pages_rendered;
 
     if plan.static_assets {
-        let static_hash = compute_static_digest(root)?;
+        let _span = timing::span(|| "static assets".to_string());
+        html.set_category(ChangeCategory::Assets);
+        let static_dir = config.static_path(root);
+        let static_hash = compute_static_digest(&static_dir, &config.static_ignore)?;
         let stored_static_hash = read_cached_string(&cache_db, STATIC_HASH_KEY)?;
         let static_changed = stored_static_hash.as_deref() != Some(static_hash.as_str());
         let should_copy_static = matches!(effective_mode, BuildMode::Full) || static_changed;
         if should_copy_static {
-            log_status(plan.verbose, "STATIC", "Copying static assets");
-            stats.static_assets_copied = copy_static_assets(root, &html_root)?;
+            log.debug("STATIC", "Copying static assets");
+            stats.static_assets_copied =
+                copy_static_assets(&static_dir, &html, &config.static_ignore, log)?;
         } else {
-            log_status(plan.verbose, "STATIC", "Static assets unchanged");
+            log.trace("STATIC", "Static assets unchanged");
             stats.static_assets_copied = 0;
         }
         store_cached_string(&cache_db, STATIC_HASH_KEY, &static_hash)?;
 
+        let previous_theme = read_cached_string(&cache_db, THEME_NAME_KEY)?;
+        let previous_theme_files: Vec<String> =
+            match read_cached_string(&cache_db, THEME_ASSET_FILES_KEY)? {
+                // Without the list, stale theme assets could not be removed.
+                Some(raw) => serde_json::from_str(&raw).context(
+                    "cached list of theme assets is unreadable; run `bckt clean --cache` and render again",
+                )?,
+                None => Vec::new(),
+            };
+
         if let Some(theme_name) = config.theme.as_deref() {
-            let theme_hash = compute_theme_asset_digest(root, theme_name)?;
+            let theme_hash = compute_theme_asset_digest(root, theme_name, &config.static_ignore)?;
             let stored_theme_hash = read_cached_string(&cache_db, THEME_ASSET_HASH_KEY)?;
-            let theme_changed = stored_theme_hash.as_deref() != Some(theme_hash.as_str());
+            let theme_switched = previous_theme.as_deref() != Some(theme_name);
+            let theme_changed =
+                theme_switched || stored_theme_hash.as_deref() != Some(theme_hash.as_str());
             let should_copy_theme = matches!(effective_mode, BuildMode::Full) || theme_changed;
 
             if should_copy_theme {
-                match copy_theme_assets(root, &html_root, theme_name)? {
-                    ThemeAssetCopy::Copied(count) => {
-                        stats.theme_assets_copied = count;
-                        log_status(
-                            plan.verbose,
-                            "THEME",
-                            format!("Copied {count} theme asset(s) for {theme_name}"),
-                        );
-                    }
-                    ThemeAssetCopy::SkippedMissing => {
-                        stats.theme_assets_copied = 0;
-                        log_status(
-                            plan.verbose,
-                            "THEME",
-                            format!("Theme {theme_name} has no assets directory"),
-                        );
-                    }
+                let copied =
+                    match copy_theme_assets(root, &html, theme_name, &config.static_ignore, log)? {
+                        ThemeAssetCopy::Copied(files) => {
+                            stats.theme_assets_copied = files.len();
+                            log.debug(
+                                "THEME",
+                                format!("Copied {} theme asset(s) for {theme_name}", files.len()),
+                            );
+                            files
+                        }
+                        ThemeAssetCopy::SkippedMissing => {
+                            stats.theme_assets_copied = 0;
+                            log.debug(
+                                "THEME",
+                                format!("Theme {theme_name} has no assets directory"),
+                            );
+                            Vec::new()
+                        }
+                    };
+                let removed =
+                    remove_stale_theme_assets(&html, &static_dir, &previous_theme_files, &copied)?;
+                if removed > 0 {
+                    log.debug("THEME", format!("Removed {removed} stale theme asset(s)"));
                 }
+                store_theme_asset_files(&cache_db, &copied)?;
             } else {
                 stats.theme_assets_copied = 0;
-                log_status(plan.verbose, "THEME", "Theme assets unchanged");
+                log.trace("THEME", "Theme assets unchanged");
             }
 
             store_cached_string(&cache_db, THEME_ASSET_HASH_KEY, &theme_hash)?;
+            store_cached_string(&cache_db, THEME_NAME_KEY, theme_name)?;
+        } else if previous_theme.is_some() || !previous_theme_files.is_empty() {
+            let removed =
+                remove_stale_theme_assets(&html, &static_dir, &previous_theme_files, &[])?;
+            log.debug(
+                "THEME",
+                format!("Theme removed; deleted {removed} theme asset(s)"),
+            );
+            store_theme_asset_files(&cache_db, &[])?;
+            for key in [THEME_NAME_KEY, THEME_ASSET_HASH_KEY] {
+                cache_db
+                    .remove(key)
+                    .with_context(|| format!("failed to clear cache key {key}"))?;
+            }
         }
     } else {
-        log_status(plan.verbose, "STATIC", "Skipping static assets");
+        log.trace("STATIC", "Skipping static assets");
         stats.static_assets_copied = 0;
         stats.theme_assets_copied = 0;
     }
 
+    // Nothing was written during a dry run, so there is nothing to list.
+    if !plan.dry_run {
+        render_manifest(&html, &config, &cache_db, log)?;
+    }
+
     cache_db.flush().context("failed to flush cache database")?;
+    stats.cache.incremental = effective_mode == BuildMode::Changed;
 
-    log_status(plan.verbose, "DONE", "Render complete");
+    log.debug("DONE", "Render complete");
+
+    if plan.dry_run {
+        stats.planned_changes = html.into_planned();
+        print_planned_changes(log, &stats.planned_changes);
+        return Ok(stats);
+    }
 
     let total_posts = stats.posts_rendered + stats.posts_skipped;
     let elapsed = started.elapsed();
-    println!(
-        "[SUMMARY] posts rendered: {}/{} (skipped {}); pages: {}; search docs: {}; static assets copied: {}; theme assets copied: {}; elapsed: {:.2?}",
+    log.info(
+        "SUMMARY",
+        format!(
+            "posts rendered: {}/{} (skipped {}); pages: {}; search docs: {}; static assets copied: {}; theme assets copied: {}; attachments copied: {} ({}); elapsed: {:.2?}",
         stats.posts_rendered,
         total_posts,
         stats.posts_skipped,
@@ -270,15 +580,109 @@ pub fn render_site(root: &Path, plan: RenderPlan) -> Result<()> {
         stats.search_documents,
         stats.static_assets_copied,
         stats.theme_assets_copied,
-        elapsed
+        stats.attachments_copied,
+            format_bytes(stats.attachment_bytes_copied),
+            elapsed
+        ),
+    );
+
+    Ok(stats)
+}
+
+/// Content files of the posts whose digest differs from the one stored by
+/// the last render, i.e. the posts an incremental render would rebuild for
+/// their own sake. The cache is left untouched.
+pub fn changed_post_files(root: &Path, profile: Option<&str>) -> Result<Vec<PathBuf>> {
+    let config = Config::load_with_profile(config::config_path(root), profile)?;
+    let cache_db = open_cache_snapshot(&cache_location(root, &config))?;
+    let discovered = discover_site_posts(root, &config, &cache_db)?;
+    let mut changed = Vec::new();
+    for post in &discovered.posts {
+        if check_post_digest(post, &discovered.shortcodes, &cache_db)?.changed {
+            changed.push(post.content_path.clone());
+        }
+    }
+    Ok(changed)
+}
+
+fn print_planned_changes(log: Logger, changes: &[PlannedChange]) {
+    let mut writes = 0usize;
+    let mut per_category: BTreeMap<ChangeCategory, usize> = BTreeMap::new();
+    for change in changes {
+        *per_category.entry(change.category).or_default() += 1;
+        let label = match change.kind {
+            ChangeKind::Write => {
+                writes += 1;
+                "WRITE"
+            }
+            ChangeKind::Delete => "DELETE",
+        };
+        log.log(
+            Level::Info,
+            label,
+            normalize_path(&change.path),
+            Some(&change.path),
+        );
+    }
+    log.info(
+        "DRY RUN",
+        format!(
+            "{} file(s) would be written and {} deleted{}; html/ and the cache were not changed",
+            writes,
+            changes.len() - writes,
+            category_counts(&per_category)
+        ),
     );
+}
+
+/// ` (posts: 2, feeds: 3)`, or nothing when there are no changes.
+fn category_counts(per_category: &BTreeMap<ChangeCategory, usize>) -> String {
+    if per_category.is_empty() {
+        return String::new();
+    }
+    let counts: Vec<String> = per_category
+        .iter()
+        .map(|(category, count)| format!("{}: {count}", category.as_str()))
+        .collect();
+    format!(" ({})", counts.join(", "))
+}
 
+fn store_theme_asset_files(cache_db: &sled::Db, files: &[String]) -> Result<()> {
+    let encoded = serde_json::to_string(files).context("failed to serialize theme asset list")?;
+    store_cached_string(cache_db, THEME_ASSET_FILES_KEY, &encoded)
+}
+
+/// Drops indexes left behind by a previous `search.format` so the theme never
+/// loads an outdated encoding.
+fn remove_stale_search_indexes(html: &Output, config: &Config, current: &Path) -> Result<()> {
+    for format in SearchFormat::ALL {
+        let path = html
+            .root()
+            .join(search::published_asset_path(&config.search, format));
+        if path != current {
+            html.remove_file(&path)?;
+        }
+    }
     Ok(())
 }
 
-fn compute_site_inputs_hash(config_raw: &str, template_hash: &str) -> String {
+fn compute_site_inputs_hash(
+    config_resolved: &str,
+    profile: Option<&str>,
+    template_hash: &str,
+    inlined_hash: &str,
+) -> String {
     let mut hasher = Hasher::new();
-    hasher.update(config_raw.as_bytes());
-    hasher.update(template_hash.as_bytes());
+    // Length prefixes keep input boundaries apart, so text moving from one
+    // input to the next still changes the hash.
+    for input in [
+        config_resolved,
+        profile.unwrap_or_default(),
+        template_hash,
+        inlined_hash,
+    ] {
+        hasher.update(&(input.len() as u64).to_le_bytes());
+        hasher.update(input.as_bytes());
+    }
     hasher.finalize().to_hex().to_string()
 }
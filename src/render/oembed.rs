@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::content::Post;
+use crate::urls::{HOME_URL, absolute_url};
+
+use super::posts::thumbnail_url;
+use super::utils::xml_escape;
+
+/// Written next to a post's `index.html` when `oembed` is enabled.
+pub(super) const OEMBED_FILE: &str = "oembed.json";
+
+/// Width advertised for `rich` embeds; the height follows the quoted text.
+const EMBED_WIDTH: u32 = 600;
+
+/// Absolute URL of the post's oEmbed file, or `None` when `oembed` is off.
+pub(super) fn oembed_url(config: &Config, post: &Post) -> Option<String> {
+    config.oembed.then(|| {
+        absolute_url(
+            &config.base_url,
+            &format!("{}{OEMBED_FILE}", post.permalink),
+        )
+    })
+}
+
+/// The post's oEmbed response: a `rich` embed quoting the excerpt, or a
+/// `link` when there is no text to quote.
+pub(super) fn render_oembed(config: &Config, post: &Post) -> Result<String> {
+    let url = absolute_url(&config.base_url, &post.permalink);
+    let title = post.title.clone().unwrap_or_else(|| post.slug.clone());
+    let rich = !post.excerpt.is_empty();
+    let response = OembedResponse {
+        version: "1.0",
+        kind: if rich { "rich" } else { "link" },
+        html: rich.then(|| embed_html(&url, &title, &post.excerpt)),
+        width: rich.then_some(EMBED_WIDTH),
+        title,
+        author_name: config.author.as_deref(),
+        provider_name: config.title.as_deref().unwrap_or("bckt"),
+        provider_url: absolute_url(&config.base_url, HOME_URL),
+        thumbnail_url: thumbnail_url(config, post),
+    };
+    serde_json::to_string_pretty(&response)
+        .with_context(|| format!("failed to serialize oEmbed data for {}", post.slug))
+}
+
+fn embed_html(url: &str, title: &str, excerpt: &str) -> String {
+    format!(
+        "<blockquote class=\"bckt-embed\"><p>{}</p><p><a href=\"{}\">{}</a></p></blockquote>",
+        xml_escape(excerpt),
+        xml_escape(url),
+        xml_escape(title)
+    )
+}
+
+#[derive(Serialize)]
+struct OembedResponse<'a> {
+    version: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_name: Option<&'a str>,
+    provider_name: &'a str,
+    provider_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail_url: Option<String>,
+}
@@ -0,0 +1,329 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+use walkdir::WalkDir;
+
+use super::utils::{format_bytes, normalize_path, remove_dir_if_empty, remove_file_if_exists};
+use crate::config::Config;
+use crate::logging::Logger;
+
+/// Files over this size get their own progress lines in verbose mode.
+const LARGE_COPY_BYTES: u64 = 50_000_000;
+
+/// Whether a dry run would write or delete a file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangeKind {
+    Write,
+    Delete,
+}
+
+/// The render stage a change came from, for the dry-run summary.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ChangeCategory {
+    Posts,
+    Listings,
+    Feeds,
+    Search,
+    Pages,
+    Assets,
+}
+
+impl ChangeCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Listings => "listings",
+            Self::Feeds => "feeds",
+            Self::Search => "search",
+            Self::Pages => "pages",
+            Self::Assets => "assets",
+        }
+    }
+}
+
+/// A change a dry run skipped, with `path` relative to `html/`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlannedChange {
+    pub kind: ChangeKind,
+    pub category: ChangeCategory,
+    pub path: PathBuf,
+}
+
+/// A file for [`Output::copy_all`] to copy, with the arguments of
+/// [`Output::copy`].
+pub(super) struct PendingCopy {
+    pub(super) source: PathBuf,
+    pub(super) destination: PathBuf,
+}
+
+/// The `html/` directory. Every file the renderer writes or deletes goes
+/// through here, so a dry run records the change instead of making it.
+pub(super) struct Output {
+    root: PathBuf,
+    dry_run: bool,
+    /// Most files [`Output::copy_all`] copies at once.
+    jobs: usize,
+    /// Permissions given to every written or copied file, on Unix.
+    file_mode: Option<u32>,
+    category: Cell<ChangeCategory>,
+    planned: RefCell<Vec<PlannedChange>>,
+}
+
+impl Output {
+    pub(super) fn new(root: PathBuf, dry_run: bool, jobs: usize, file_mode: Option<u32>) -> Self {
+        Self {
+            root,
+            dry_run,
+            jobs,
+            file_mode,
+            category: Cell::new(ChangeCategory::Posts),
+            planned: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(super) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Files the dry run records from now on belong to `category`.
+    pub(super) fn set_category(&self, category: ChangeCategory) {
+        self.category.set(category);
+    }
+
+    /// Writes `contents` to `path`, creating its parent directories. A dry
+    /// run leaves out files that already hold exactly `contents`.
+    pub(super) fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        if self.dry_run {
+            if fs::read(path).ok().as_deref() != Some(contents.as_ref()) {
+                self.record(ChangeKind::Write, path);
+            }
+            return Ok(());
+        }
+        create_parent(path)?;
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+        set_file_mode(path, self.file_mode)
+    }
+
+    /// Writes the page `template_name` rendered to `path`, like [`Self::write`]
+    /// after checking that it is not empty.
+    pub(super) fn write_page(
+        &self,
+        config: &Config,
+        template_name: &str,
+        path: &Path,
+        rendered: &str,
+    ) -> Result<()> {
+        ensure_page_content(config, template_name, path, rendered)?;
+        self.write(path, rendered)
+    }
+
+    /// Copies `source` to `destination`. A dry run leaves out copies whose
+    /// destination already holds the same bytes.
+    pub(super) fn copy(&self, source: &Path, destination: &Path) -> Result<()> {
+        if self.dry_run {
+            if !same_contents(source, destination) {
+                self.record(ChangeKind::Write, destination);
+            }
+            return Ok(());
+        }
+        copy_file(source, destination, self.file_mode)
+    }
+
+    /// Makes every copy in `copies`, using up to `jobs` threads. With a single
+    /// job, or during a dry run, they are made one by one in order.
+    pub(super) fn copy_all(&self, copies: &[PendingCopy], log: Logger) -> Result<()> {
+        if self.dry_run {
+            return copies
+                .iter()
+                .try_for_each(|copy| self.copy(&copy.source, &copy.destination));
+        }
+        let root = self.root.as_path();
+        let file_mode = self.file_mode;
+        let copy_one = |copy: &PendingCopy| copy_with_progress(root, copy, file_mode, log);
+        if self.jobs <= 1 || copies.len() <= 1 {
+            return copies.iter().try_for_each(copy_one);
+        }
+
+        let next = AtomicUsize::new(0);
+        let failure = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.min(copies.len()) {
+                scope.spawn(|| {
+                    while let Some(copy) = copies.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if let Err(err) = copy_one(copy) {
+                            failure.lock().unwrap().get_or_insert(err);
+                            // Leave the remaining copies undone.
+                            next.store(copies.len(), Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+        match failure.into_inner().unwrap() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Deletes `path` if it exists.
+    pub(super) fn remove_file(&self, path: &Path) -> Result<()> {
+        if self.dry_run {
+            if path.is_file() {
+                self.record(ChangeKind::Delete, path);
+            }
+            return Ok(());
+        }
+        remove_file_if_exists(path)
+    }
+
+    /// Deletes a directory and everything below it. A dry run records each
+    /// file that would go.
+    pub(super) fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        if self.dry_run {
+            for entry in WalkDir::new(path).sort_by_file_name() {
+                let entry =
+                    entry.with_context(|| format!("failed to enumerate {}", path.display()))?;
+                if entry.file_type().is_file() {
+                    self.record(ChangeKind::Delete, entry.path());
+                }
+            }
+            return Ok(());
+        }
+        fs::remove_dir_all(path)
+            .with_context(|| format!("failed to remove directory {}", path.display()))
+    }
+
+    /// Removes `path` once deletions have left it empty.
+    pub(super) fn remove_dir_if_empty(&self, path: &Path) -> Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        remove_dir_if_empty(path)
+    }
+
+    /// Removes the directories above `path` that are left empty, up to the
+    /// root.
+    pub(super) fn prune_empty_parents(&self, path: &Path) {
+        if self.dry_run {
+            return;
+        }
+        let mut parent = path.parent();
+        while let Some(dir) = parent {
+            if dir == self.root || fs::remove_dir(dir).is_err() {
+                break;
+            }
+            parent = dir.parent();
+        }
+    }
+
+    pub(super) fn into_planned(self) -> Vec<PlannedChange> {
+        self.planned.into_inner()
+    }
+
+    fn record(&self, kind: ChangeKind, path: &Path) {
+        let path = path.strip_prefix(&self.root).unwrap_or(path).to_path_buf();
+        self.planned.borrow_mut().push(PlannedChange {
+            kind,
+            category: self.category.get(),
+            path,
+        });
+    }
+}
+
+fn same_contents(source: &Path, destination: &Path) -> bool {
+    match (fs::metadata(source), fs::metadata(destination)) {
+        (Ok(source_meta), Ok(destination_meta)) if source_meta.len() == destination_meta.len() => {
+            matches!((fs::read(source), fs::read(destination)), (Ok(a), Ok(b)) if a == b)
+        }
+        _ => false,
+    }
+}
+
+/// Fails when `template_name` rendered `target` as an empty or
+/// whitespace-only document, which usually means a theme bug such as a
+/// misspelled block name, unless `allow_empty_output` lists the template.
+fn ensure_page_content(
+    config: &Config,
+    template_name: &str,
+    target: &Path,
+    rendered: &str,
+) -> Result<()> {
+    if rendered.trim().is_empty()
+        && !config
+            .allow_empty_output
+            .iter()
+            .any(|allowed| allowed == template_name)
+    {
+        bail!(
+            "template '{}' rendered {} as an empty page; check that its block names match the template it extends, or list it under allow_empty_output",
+            template_name,
+            target.display()
+        );
+    }
+    Ok(())
+}
+
+/// [`copy_file`] for [`Output::copy_all`], reporting progress on large files.
+fn copy_with_progress(
+    root: &Path,
+    copy: &PendingCopy,
+    file_mode: Option<u32>,
+    log: Logger,
+) -> Result<()> {
+    let size = fs::metadata(&copy.source).map_or(0, |metadata| metadata.len());
+    let relative = normalize_path(
+        copy.destination
+            .strip_prefix(root)
+            .unwrap_or(&copy.destination),
+    );
+    let large = size > LARGE_COPY_BYTES;
+    if large {
+        log.debug(
+            "COPY",
+            format!("Copying {relative} ({})", format_bytes(size)),
+        );
+    }
+    copy_file(&copy.source, &copy.destination, file_mode)?;
+    if large {
+        log.debug("COPY", format!("Copied {relative}"));
+    }
+    Ok(())
+}
+
+fn copy_file(source: &Path, destination: &Path, file_mode: Option<u32>) -> Result<()> {
+    create_parent(destination)?;
+    fs::copy(source, destination).with_context(|| {
+        format!(
+            "failed to copy {} to {}",
+            source.display(),
+            destination.display()
+        )
+    })?;
+    set_file_mode(destination, file_mode)
+}
+
+/// Applies `output_mode`; other platforms keep their default permissions.
+fn set_file_mode(path: &Path, file_mode: Option<u32>) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(mode) = file_mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("failed to set permissions on {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, file_mode);
+    Ok(())
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    Ok(())
+}
@@ -1,26 +1,27 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use minijinja::Environment;
 use walkdir::WalkDir;
 
+use crate::config::Config;
+use crate::content::{Post, contains_refs, resolve_page_refs};
+use crate::logging::Logger;
+
+use super::output::Output;
 use super::templates::describe_template_error;
 use super::utils::normalize_path;
 
-pub(super) fn render_pages(
-    root: &Path,
-    html_root: &Path,
-    env: &Environment<'static>,
-    verbose: bool,
-) -> Result<usize> {
-    let pages_dir = root.join("pages");
+/// The `.html` files under `pages_dir`, sorted. Each one is published at the
+/// same relative path under `html/`.
+pub(super) fn page_files(pages_dir: &Path) -> Result<Vec<PathBuf>> {
     if !pages_dir.exists() {
-        return Ok(0);
+        return Ok(Vec::new());
     }
 
     let mut files = Vec::new();
-    for entry in WalkDir::new(&pages_dir) {
+    for entry in WalkDir::new(pages_dir) {
         let entry = entry?;
         if entry.file_type().is_file() {
             let path = entry.into_path();
@@ -35,35 +36,66 @@ pub(super) fn render_pages(
     }
 
     files.sort();
+    Ok(files)
+}
 
+/// Renders every page, resolving `{{post:...}}` references against `posts`.
+pub(super) fn render_pages(
+    pages_dir: &Path,
+    html: &Output,
+    env: &Environment<'static>,
+    config: &Config,
+    posts: &[Post],
+    log: Logger,
+) -> Result<usize> {
+    let pages = &config.pages;
     let mut rendered_pages = 0usize;
-    for path in files {
-        let relative = path.strip_prefix(&pages_dir).unwrap();
-        let output_path = html_root.join(relative);
-        if let Some(parent) = output_path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    for path in page_files(pages_dir)? {
+        let relative = path.strip_prefix(pages_dir).unwrap();
+        let output_path = html.root().join(relative);
+        let template_name = normalize_path(relative);
+
+        // Exported HTML may contain `{{` in scripts or samples, so pages
+        // matching `pages.raw` are published as they are.
+        if pages.is_raw(&template_name) {
+            let contents = fs::read(&path)
+                .with_context(|| format!("failed to read page {}", path.display()))?;
+            html.write(&output_path, contents)?;
+            log.debug("PAGE", format!("Copied {template_name}"));
+            rendered_pages += 1;
+            continue;
         }
 
         let source = fs::read_to_string(&path)
             .with_context(|| format!("failed to read page template {}", path.display()))?;
+        let source = resolve_page_refs(&source, &path, posts, config)?;
 
-        let scope = format!("rendering standalone page {}", normalize_path(relative));
-        let template_name = normalize_path(relative);
+        let scope = format!("rendering standalone page {template_name}");
         let rendered = env
-            .render_str(&source, minijinja::context! {})
-            .map_err(|err| describe_template_error(&scope, &template_name, err))?;
+            .render_named_str(&template_name, &source, minijinja::context! {})
+            .map_err(|err| describe_template_error(&scope, &template_name, Some(&source), err))?;
 
-        fs::write(&output_path, rendered)
-            .with_context(|| format!("failed to write page {}", output_path.display()))?;
+        html.write_page(config, &template_name, &output_path, &rendered)?;
 
-        super::utils::log_status(
-            verbose,
-            "PAGE",
-            format!("Rendered {}", normalize_path(relative)),
-        );
+        log.debug("PAGE", format!("Rendered {template_name}"));
         rendered_pages += 1;
     }
 
     Ok(rendered_pages)
 }
+
+/// Whether any page rendered as a template references a post.
+pub(super) fn pages_reference_posts(pages_dir: &Path, config: &Config) -> Result<bool> {
+    for path in page_files(pages_dir)? {
+        let relative = path.strip_prefix(pages_dir).unwrap();
+        if config.pages.is_raw(&normalize_path(relative)) {
+            continue;
+        }
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read page template {}", path.display()))?;
+        if contains_refs(&source) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use blake3::Hasher;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::content::Post;
+use crate::logging::Logger;
+
+use super::BuildMode;
+use super::cache::{read_cached_string, store_cached_string};
+use super::output::Output;
+use super::utils::format_rfc3339;
+
+const POSTS_JSON_KEY: &str = "posts_json_hash";
+const POSTS_JSON_FILE: &str = "posts.json";
+
+#[derive(Serialize)]
+struct PostIndexEntry<'a> {
+    title: Option<&'a str>,
+    permalink: &'a str,
+    date_iso: String,
+    tags: &'a [String],
+    #[serde(rename = "type")]
+    kind: Option<&'a str>,
+    excerpt: &'a str,
+}
+
+/// Writes `html/posts.json`, every post newest first, when `posts_json` is
+/// enabled. The file is only rewritten when the listing changes, and it is
+/// removed again once the option is turned off.
+pub(super) fn render_posts_json(
+    posts: &[Post],
+    html: &Output,
+    config: &Config,
+    cache_db: &sled::Db,
+    mode: BuildMode,
+    log: Logger,
+) -> Result<()> {
+    let output = html.root().join(POSTS_JSON_FILE);
+    if !config.posts_json {
+        if read_cached_string(cache_db, POSTS_JSON_KEY)?.is_some() {
+            html.remove_file(&output)?;
+            cache_db
+                .remove(POSTS_JSON_KEY)
+                .context("failed to remove posts.json cache entry")?;
+        }
+        return Ok(());
+    }
+
+    let entries = posts
+        .iter()
+        .rev()
+        .map(|post| {
+            Ok(PostIndexEntry {
+                title: post.title.as_deref(),
+                permalink: &post.permalink,
+                date_iso: format_rfc3339(&post.date)?,
+                tags: &post.tags,
+                kind: post.post_type.as_deref(),
+                excerpt: &post.excerpt,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let bytes = serde_json::to_vec(&entries).context("failed to serialize posts.json")?;
+
+    let mut hasher = Hasher::new();
+    hasher.update(&bytes);
+    let digest = hasher.finalize().to_hex().to_string();
+    let cached = read_cached_string(cache_db, POSTS_JSON_KEY)?;
+    if matches!(mode, BuildMode::Changed)
+        && cached.as_deref() == Some(digest.as_str())
+        && output.exists()
+    {
+        log.trace("INDEX", "posts.json unchanged");
+        return Ok(());
+    }
+
+    html.write(&output, bytes)?;
+    store_cached_string(cache_db, POSTS_JSON_KEY, &digest)?;
+    log.debug(
+        "INDEX",
+        format!("Wrote posts.json ({} posts)", entries.len()),
+    );
+    Ok(())
+}
@@ -1,84 +1,245 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
 use anyhow::{Context, Result, bail};
 use blake3::Hasher;
-use minijinja::Environment;
+use minijinja::{Environment, Value};
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 use time::OffsetDateTime;
 use time::format_description;
 
 use crate::config::Config;
-use crate::content::{Post, discover_posts};
-use crate::utils::absolute_url;
-
+use crate::content::{
+    BodyPart, BodyStore, Post, Shortcodes, Whatlang, discover_posts_with_shortcodes,
+    images_without_alt,
+};
+use crate::logging::{Level, Logger};
+use crate::markdown::excerpt_of;
+use crate::timing;
+use crate::urls::{absolute_url, with_base_path};
+
+use super::cache::{
+    CachedBodyStore, CachedLanguageDetector, read_cached_string, store_cached_string,
+};
+use super::exif::{ImageFormat, strip_metadata};
+use super::feeds::encode_path;
+use super::listing::{tag_index_url, tag_name, tag_slug};
+use super::oembed::{OEMBED_FILE, oembed_url, render_oembed};
+use super::output::{Output, PendingCopy};
 use super::templates::render_template_with_scope;
-use super::utils::{log_status, normalize_path};
-use super::{BuildMode, POST_HASH_PREFIX};
+use super::utils::{format_bytes, format_rfc3339, normalize_path};
+use super::{
+    BACKLINKS_PREFIX, BODY_PREFIX, BuildMode, LANGUAGE_PREFIX, POST_HASH_PREFIX,
+    SEARCH_TEXT_PREFIX, STRIPPED_ATTACHMENT_PREFIX,
+};
+
+/// The project's posts, oldest first, the shortcodes used to expand them,
+/// and the language and body cache entries their bodies resolved to.
+pub(super) struct DiscoveredPosts {
+    pub(super) posts: Vec<Post>,
+    pub(super) shortcodes: Shortcodes,
+    pub(super) language_keys: BTreeSet<String>,
+    pub(super) body_keys: BTreeSet<String>,
+}
 
-pub(super) fn render_posts(
+pub(super) fn discover_site_posts(
     root: &Path,
-    html_root: &Path,
     config: &Config,
-    env: &Environment<'static>,
     cache_db: &sled::Db,
+) -> Result<DiscoveredPosts> {
+    let _span = timing::span(|| "discover posts".to_string());
+    let posts_dir = config.posts_path(root);
+    let shortcodes = Shortcodes::load(config, &config.templates_path(root).join("shortcodes"))?;
+    let detector = CachedLanguageDetector::new(cache_db, Whatlang);
+    let bodies = CachedBodyStore::new(cache_db);
+    let mut posts = discover_posts_with_shortcodes(
+        &posts_dir,
+        config,
+        &shortcodes,
+        &detector,
+        config.low_memory.then_some(&bodies as &dyn BodyStore),
+    )?;
+    posts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.slug.cmp(&b.slug)));
+    Ok(DiscoveredPosts {
+        posts,
+        shortcodes,
+        language_keys: detector.into_used_keys(),
+        body_keys: bodies.into_used_keys(),
+    })
+}
+
+/// Where post bodies and search text come from: the [`Post`], or for
+/// `low_memory` builds the copies discovery kept in the cache, read again for
+/// every use.
+///
+/// One is made per render and shared by post pages, listings, and feeds, so
+/// each post's body has its attachment links rewritten once instead of once
+/// per page that shows it. `low_memory` builds rewrite it again every time
+/// rather than keep the results.
+pub(super) struct PostBodies<'a> {
+    cache_db: &'a sled::Db,
+    store: Option<CachedBodyStore<'a>>,
+    rewritten: RefCell<HashMap<(PathBuf, bool), Arc<str>>>,
+}
+
+impl<'a> PostBodies<'a> {
+    pub(super) fn new(config: &Config, cache_db: &'a sled::Db) -> Self {
+        Self {
+            cache_db,
+            store: config.low_memory.then(|| CachedBodyStore::new(cache_db)),
+            rewritten: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub(super) fn cache_db(&self) -> &'a sled::Db {
+        self.cache_db
+    }
+
+    /// The store holding what posts left out, for `low_memory` builds.
+    pub(super) fn store(&self) -> Option<&dyn BodyStore> {
+        self.store.as_ref().map(|store| store as &dyn BodyStore)
+    }
+
+    pub(super) fn get<'p>(&self, post: &'p Post) -> Result<Cow<'p, str>> {
+        self.part(post, BodyPart::Html)
+    }
+
+    pub(super) fn search_text<'p>(&self, post: &'p Post) -> Result<Cow<'p, str>> {
+        self.part(post, BodyPart::SearchText)
+    }
+
+    fn part<'p>(&self, post: &'p Post, part: BodyPart) -> Result<Cow<'p, str>> {
+        match &self.store {
+            Some(store) => store.get(&post.content_path, part).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(match part {
+                BodyPart::Html => &post.body_html,
+                BodyPart::SearchText => &post.search_text,
+            })),
+        }
+    }
+
+    /// The body with links to attachments rewritten by [`att_to_absolute`]:
+    /// relative for pages, or `absolute` URLs for feeds.
+    pub(super) fn rewritten(
+        &self,
+        config: &Config,
+        post: &Post,
+        absolute: bool,
+    ) -> Result<Arc<str>> {
+        let key = (post.content_path.clone(), absolute);
+        if let Some(body) = self.rewritten.borrow().get(&key) {
+            return Ok(Arc::clone(body));
+        }
+        let body: Arc<str> = att_to_absolute(
+            &self.get(post)?,
+            &post.permalink,
+            &config.base_url,
+            &post.attached,
+            absolute,
+        )
+        .into();
+        if self.store.is_none() {
+            self.rewritten.borrow_mut().insert(key, Arc::clone(&body));
+        }
+        Ok(body)
+    }
+}
+
+/// Counts from [`render_posts`].
+#[derive(Default)]
+pub(super) struct PostRenderStats {
+    pub(super) rendered: usize,
+    pub(super) skipped: usize,
+    pub(super) attachments_copied: usize,
+    pub(super) attachment_bytes: u64,
+}
+
+pub(super) fn render_posts(
+    discovered: &DiscoveredPosts,
+    html: &Output,
+    config: &Config,
+    env: &Environment<'static>,
+    bodies: &PostBodies<'_>,
     mode: BuildMode,
-    verbose: bool,
-) -> Result<(Vec<Post>, usize, usize)> {
-    let posts_dir = root.join("posts");
-    let mut posts = discover_posts(&posts_dir, config)?;
+    log: Logger,
+) -> Result<PostRenderStats> {
+    let DiscoveredPosts {
+        posts,
+        shortcodes,
+        language_keys,
+        body_keys,
+    } = discovered;
+    let cache_db = bodies.cache_db();
+    let mut stats = PostRenderStats::default();
     if posts.is_empty() {
-        return Ok((posts, 0, 0));
+        return Ok(stats);
     }
 
-    posts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.slug.cmp(&b.slug)));
-
     let default_post_template = env
         .get_template("post.html")
         .context("post.html template missing")?;
 
     let mut cache_keys: BTreeSet<String> = BTreeSet::new();
-
-    let mut rendered_count = 0usize;
-    let mut skipped_count = 0usize;
-
-    for post in &posts {
-        let cache_key = format!("{POST_HASH_PREFIX}{}", post.permalink);
+    // Attachments are copied after every page is written, several at a time.
+    let mut copies = Vec::new();
+    let moved_links = referrers_of_moved_posts(posts, cache_db)?;
+
+    for post in posts {
+        let PostDigest {
+            cache_key,
+            digest,
+            changed,
+        } = check_post_digest(post, shortcodes, cache_db)?;
         cache_keys.insert(cache_key.clone());
-
-        let digest = compute_post_digest(post)?;
-        let cached = cache_db
-            .get(cache_key.as_bytes())
-            .with_context(|| format!("failed to read cache entry for {}", post.slug))?;
-        let digest_bytes = digest.as_bytes();
-        let needs_render = if matches!(mode, BuildMode::Full) {
-            true
-        } else if let Some(value) = cached.as_ref() {
-            value.as_ref() != digest_bytes
-        } else {
-            true
-        };
+        let needs_render =
+            matches!(mode, BuildMode::Full) || changed || moved_links.contains(&post.permalink);
 
         if needs_render {
-            rendered_count += 1;
-            let render_target = html_root.join(post.permalink.trim_start_matches('/'));
-            let output_path = render_target.join("index.html");
-            if let Some(parent) = output_path.parent() {
-                fs::create_dir_all(parent)
-                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            let _span = timing::span(|| format!("post {}", post.permalink));
+            stats.rendered += 1;
+            let render_target = post_output_dir(html.root(), post);
+            let body = bodies.get(post)?;
+            for src in images_without_alt(&body) {
+                log.log(
+                    Level::Warn,
+                    "POST",
+                    format!(
+                        "{}: image {} has no alt text",
+                        post.content_path.display(),
+                        src
+                    ),
+                    Some(&post.content_path),
+                );
+            }
+            for reference in private_references(&body, &post.attached_private) {
+                log.log(
+                    Level::Warn,
+                    "POST",
+                    format!(
+                        "{}: body links to private attachment {}; it will not be published",
+                        post.content_path.display(),
+                        reference
+                    ),
+                    Some(&post.content_path),
+                );
             }
 
-            let context = build_post_context(config, post)?;
-            let template_name = post
-                .post_type
-                .as_deref()
-                .map(|value| format!("post-{value}.html"))
-                .unwrap_or_else(|| "post.html".to_string());
+            let context = build_post_context(
+                config,
+                post,
+                Some(bodies),
+                bodies.rewritten(config, post, false)?,
+            )?;
+            let template_name = post_template_name(post);
 
             let scope = format!("rendering post {}", post.slug);
+            let mut used_template = template_name.as_str();
             let rendered = if template_name == "post.html" {
                 render_template_with_scope(
                     &default_post_template,
@@ -93,14 +254,16 @@ pub(super) fn render_posts(
                         &scope,
                     ),
                     Err(err) => {
-                        log_status(
-                            verbose,
-                            "WARN",
+                        log.log(
+                            Level::Warn,
+                            "POST",
                             format!(
                                 "{}: missing {} ({}); using post.html",
                                 post.slug, template_name, err
                             ),
+                            Some(&post.content_path),
                         );
+                        used_template = "post.html";
                         render_template_with_scope(
                             &default_post_template,
                             minijinja::context! { post => &context },
@@ -110,163 +273,474 @@ pub(super) fn render_posts(
                 }
             }?;
 
-            fs::write(&output_path, rendered)
-                .with_context(|| format!("failed to write {}", output_path.display()))?;
+            let page = render_target.join("index.html");
+            html.write_page(config, used_template, &page, &rendered)?;
+
+            let oembed_path = render_target.join(OEMBED_FILE);
+            if config.oembed {
+                html.write(&oembed_path, render_oembed(config, post)?)?;
+            } else {
+                html.remove_file(&oembed_path)?;
+            }
 
-            copy_post_assets(post, &render_target)
+            copy_post_assets(post, html, config, cache_db, log, &mut stats, &mut copies)
                 .with_context(|| format!("failed to copy assets for {}", post.slug))?;
 
-            log_status(
-                verbose,
-                "RENDER",
-                format!("Rendered post {}", post.permalink),
-            );
+            log.debug("RENDER", format!("Rendered post {}", post.permalink));
         } else {
-            skipped_count += 1;
-            log_status(
-                verbose,
-                "SKIP",
-                format!("Post {} unchanged", post.permalink),
-            );
+            stats.skipped += 1;
+            log.trace("SKIP", format!("Post {} unchanged", post.permalink));
         }
 
         cache_db
-            .insert(cache_key.as_bytes(), digest_bytes)
+            .insert(cache_key.as_bytes(), digest.as_bytes())
             .with_context(|| format!("failed to update cache entry for {}", post.slug))?;
     }
 
-    cleanup_post_hashes(cache_db, &cache_keys)?;
+    html.copy_all(&copies, log)?;
+
+    cleanup_stale_keys(cache_db, POST_HASH_PREFIX, &cache_keys)?;
+    cleanup_stale_keys(cache_db, LANGUAGE_PREFIX, language_keys)?;
+    cleanup_stale_keys(cache_db, BODY_PREFIX, body_keys)?;
+    cleanup_stale_keys(cache_db, SEARCH_TEXT_PREFIX, body_keys)?;
+    let mut stripped_keys = BTreeSet::new();
+    for post in posts {
+        let target_dir = post_output_dir(html.root(), post);
+        for relative in published_attachments(post)? {
+            stripped_keys.insert(stripped_key(html, &target_dir.join(relative)));
+        }
+    }
+    cleanup_stale_keys(cache_db, STRIPPED_ATTACHMENT_PREFIX, &stripped_keys)?;
+    store_backlinks(posts, cache_db)?;
+
+    Ok(stats)
+}
+
+fn backlinks_key(content_path: &Path) -> String {
+    format!("{BACKLINKS_PREFIX}{}", content_path.display())
+}
 
-    Ok((posts, rendered_count, skipped_count))
+/// Permalinks of the posts that link to a post whose permalink changed since
+/// the last render. Their sources are unchanged, but the URLs in their bodies
+/// are not.
+fn referrers_of_moved_posts(posts: &[Post], cache_db: &sled::Db) -> Result<HashSet<String>> {
+    let permalinks: HashMap<String, &str> = posts
+        .iter()
+        .map(|post| (backlinks_key(&post.content_path), post.permalink.as_str()))
+        .collect();
+    let mut referrers = HashSet::new();
+    for entry in cache_db.scan_prefix(BACKLINKS_PREFIX.as_bytes()) {
+        let (key, value) = entry.context("failed to iterate backlink cache entries")?;
+        let key = String::from_utf8_lossy(&key);
+        let value = String::from_utf8_lossy(&value);
+        let mut lines = value.lines();
+        let stored = lines.next().unwrap_or_default();
+        if permalinks.get(key.as_ref()) != Some(&stored) {
+            referrers.extend(lines.map(str::to_string));
+        }
+    }
+    Ok(referrers)
+}
+
+/// Stores, for every post another post links to, its permalink followed by
+/// the permalinks of the posts linking to it.
+fn store_backlinks(posts: &[Post], cache_db: &sled::Db) -> Result<()> {
+    let permalinks: HashMap<&Path, &str> = posts
+        .iter()
+        .map(|post| (post.content_path.as_path(), post.permalink.as_str()))
+        .collect();
+    let mut backlinks: BTreeMap<&Path, Vec<&str>> = BTreeMap::new();
+    for post in posts {
+        for target in &post.linked_posts {
+            backlinks
+                .entry(target.as_path())
+                .or_default()
+                .push(&post.permalink);
+        }
+    }
+
+    let mut keys = BTreeSet::new();
+    for (target, referrers) in backlinks {
+        let Some(permalink) = permalinks.get(target) else {
+            continue;
+        };
+        let key = backlinks_key(target);
+        let value = std::iter::once(*permalink)
+            .chain(referrers)
+            .collect::<Vec<_>>()
+            .join("\n");
+        cache_db
+            .insert(key.as_bytes(), value.as_bytes())
+            .with_context(|| format!("failed to update backlinks of {}", target.display()))?;
+        keys.insert(key);
+    }
+    cleanup_stale_keys(cache_db, BACKLINKS_PREFIX, &keys)
+}
+
+/// The template a post page is rendered with: the front matter `template`,
+/// else `post-<type>.html`, else `post.html`.
+pub(super) fn post_template_name(post: &Post) -> String {
+    post.template
+        .clone()
+        .or_else(|| {
+            post.post_type
+                .as_deref()
+                .map(|value| format!("post-{value}.html"))
+        })
+        .unwrap_or_else(|| "post.html".to_string())
 }
 
 pub(super) fn post_key(post: &Post) -> String {
     format!("{}-{}", post.date.unix_timestamp(), post.slug)
 }
 
-fn build_post_context(config: &Config, post: &Post) -> Result<PostTemplate> {
+/// `bodies` is where the post's text comes from, when it has one; a post
+/// without one keeps its own.
+pub(super) fn build_post_context(
+    config: &Config,
+    post: &Post,
+    bodies: Option<&PostBodies<'_>>,
+    body: Arc<str>,
+) -> Result<PostTemplate> {
     let date = format_date(config, &post.date)?;
     let date_iso = post
         .date
         .format(&time::format_description::well_known::Rfc3339)
         .context("failed to format RFC3339 date")?;
+    let (updated, updated_iso) = format_updated(config, post)?;
 
     let attached = convert_paths(&post.attached)?;
-    let body = att_to_absolute(
-        &post.body_html,
-        &post.permalink,
-        &config.base_url,
-        &post.attached,
-        false,
-    );
-
-    // Build attachments metadata map
-    let mut attachments = HashMap::new();
-    for relative_path in &post.attached {
-        let normalized = normalize_path(relative_path);
-        let asset_path = post.source_dir.join(relative_path);
-
-        if let Ok(metadata) = fs::metadata(&asset_path) {
-            let size = metadata.len();
-            let mime_type = mime_guess::from_path(&asset_path)
-                .first_or_octet_stream()
-                .to_string();
 
-            attachments.insert(normalized, AttachmentMeta { size, mime_type });
-        }
-    }
+    let attachments = attachment_metadata(post, &post.attached);
+    let private_attachments = attachment_metadata(post, &post.attached_private);
 
     Ok(PostTemplate {
         title: post.title.clone(),
         slug: post.slug.clone(),
         date,
         date_iso,
+        updated,
+        updated_iso,
         language: post.language.clone(),
+        dir: post.dir.clone(),
         tags: post.tags.clone(),
+        tag_links: tag_links(config, &post.tags),
+        featured: post.featured,
+        weight: post.weight,
+        undated: post.undated,
+        classes: post.classes.clone(),
+        body_classes: body_classes(post),
         post_type: post.post_type.clone(),
         abstract_text: post.abstract_text.clone(),
+        description: post_description(config, post, bodies)?,
         attached,
         body,
         excerpt: post.excerpt.clone(),
         permalink: post.permalink.clone(),
+        oembed_url: oembed_url(config, post),
+        jsonld: post_jsonld(config, post)?,
         attachments,
+        private_attachments,
         extra: post.extra.clone(),
     })
 }
 
-pub(super) fn build_post_summary(config: &Config, post: &Post) -> Result<PostSummary> {
+/// The post's `jsonld:` with `url`, `datePublished`, and `headline` filled in
+/// from the post where missing. `<`, `>`, and `&` are escaped so the JSON
+/// cannot close the `<script>` element holding it.
+fn post_jsonld(config: &Config, post: &Post) -> Result<Option<Value>> {
+    let Some(jsonld) = &post.jsonld else {
+        return Ok(None);
+    };
+    let mut jsonld = jsonld.clone();
+    jsonld
+        .entry("url")
+        .or_insert_with(|| absolute_url(&config.base_url, &post.permalink).into());
+    if !jsonld.contains_key("datePublished") {
+        jsonld.insert(
+            "datePublished".to_string(),
+            format_rfc3339(&post.date)?.into(),
+        );
+    }
+    if let Some(title) = &post.title {
+        jsonld
+            .entry("headline")
+            .or_insert_with(|| title.clone().into());
+    }
+    let json = serde_json::to_string(&jsonld).context("failed to serialize jsonld")?;
+    let json = json
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026");
+    Ok(Some(Value::from_safe_string(json)))
+}
+
+/// `body` is the post's body as the page should show it, e.g. from
+/// [`PostBodies::rewritten`].
+pub(super) fn build_post_summary(
+    config: &Config,
+    post: &Post,
+    bodies: &PostBodies<'_>,
+    body: Arc<str>,
+) -> Result<PostSummary> {
     let date = format_date(config, &post.date)?;
     let date_iso = post
         .date
         .format(&time::format_description::well_known::Rfc3339)
         .context("failed to format RFC3339 date")?;
+    let (updated, updated_iso) = format_updated(config, post)?;
 
-    let body = att_to_absolute(
-        &post.body_html,
-        &post.permalink,
-        &config.base_url,
-        &post.attached,
-        false,
-    );
-
-    // Build attachments metadata map
-    let mut attachments = HashMap::new();
-    for relative_path in &post.attached {
-        let normalized = normalize_path(relative_path);
-        let asset_path = post.source_dir.join(relative_path);
-
-        if let Ok(metadata) = fs::metadata(&asset_path) {
-            let size = metadata.len();
-            let mime_type = mime_guess::from_path(&asset_path)
-                .first_or_octet_stream()
-                .to_string();
-
-            attachments.insert(normalized, AttachmentMeta { size, mime_type });
-        }
-    }
+    let attachments = attachment_metadata(post, &post.attached);
+    let private_attachments = attachment_metadata(post, &post.attached_private);
 
     Ok(PostSummary {
         title: post.title.clone(),
         slug: post.slug.clone(),
         date,
         date_iso,
+        updated,
+        updated_iso,
         language: post.language.clone(),
+        dir: post.dir.clone(),
         tags: post.tags.clone(),
+        tag_links: tag_links(config, &post.tags),
+        featured: post.featured,
+        weight: post.weight,
+        undated: post.undated,
+        classes: post.classes.clone(),
+        body_classes: body_classes(post),
         post_type: post.post_type.clone(),
         abstract_text: post.abstract_text.clone(),
+        description: post_description(config, post, Some(bodies))?,
         body,
         excerpt: post.excerpt.clone(),
         permalink: post.permalink.clone(),
         attachments,
+        private_attachments,
         extra: post.extra.clone(),
     })
 }
 
+/// What describes the post in meta tags and feeds: its `abstract`, else its
+/// excerpt, else the start of its text, else the site description. A
+/// `description` set in the front matter comes first, since templates see it
+/// under the same name.
+pub(super) fn post_description(
+    config: &Config,
+    post: &Post,
+    bodies: Option<&PostBodies<'_>>,
+) -> Result<String> {
+    let front_matter = post.extra.get("description").and_then(JsonValue::as_str);
+    if let Some(text) = [
+        front_matter,
+        post.abstract_text.as_deref(),
+        Some(&post.excerpt),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .find(|text| !text.is_empty())
+    {
+        return Ok(text.to_string());
+    }
+    let search_text = match bodies {
+        Some(bodies) => bodies.search_text(post)?,
+        None => Cow::Borrowed(post.search_text.as_str()),
+    };
+    let text = excerpt_of(&search_text.split_whitespace().collect::<Vec<_>>().join(" "));
+    if !text.is_empty() {
+        return Ok(text);
+    }
+    Ok(config
+        .description
+        .as_deref()
+        .unwrap_or("")
+        .trim()
+        .to_string())
+}
+
+/// `post-<type> lang-<language>` followed by the front matter `classes`,
+/// ready for a `class` attribute.
+fn body_classes(post: &Post) -> String {
+    let mut classes: Vec<String> = Vec::new();
+    if let Some(post_type) = post.post_type.as_deref() {
+        classes.push(format!("post-{post_type}"));
+    }
+    classes.push(format!("lang-{}", post.language));
+    for class in &post.classes {
+        if !classes.contains(class) {
+            classes.push(class.clone());
+        }
+    }
+    classes.join(" ")
+}
+
+fn format_updated(config: &Config, post: &Post) -> Result<(Option<String>, Option<String>)> {
+    let Some(updated) = post.updated else {
+        return Ok((None, None));
+    };
+    let formatted = format_date(config, &updated)?;
+    let iso = updated
+        .format(&time::format_description::well_known::Rfc3339)
+        .context("failed to format RFC3339 date")?;
+    Ok((Some(formatted), Some(iso)))
+}
+
+/// Lightweight view of a post used for the optional `all_posts` listing.
+pub(super) fn build_post_list_entry(config: &Config, post: &Post) -> Result<PostListEntry> {
+    let date = format_date(config, &post.date)?;
+    let date_iso = post
+        .date
+        .format(&time::format_description::well_known::Rfc3339)
+        .context("failed to format RFC3339 date")?;
+    let (updated, updated_iso) = format_updated(config, post)?;
+
+    Ok(PostListEntry {
+        title: post.title.clone(),
+        slug: post.slug.clone(),
+        date,
+        date_iso,
+        updated,
+        updated_iso,
+        tags: post.tags.clone(),
+        permalink: post.permalink.clone(),
+    })
+}
+
 #[derive(Serialize)]
 pub(super) struct PostTemplate {
     pub(super) title: Option<String>,
     pub(super) slug: String,
     pub(super) date: String,
     pub(super) date_iso: String,
+    pub(super) updated: Option<String>,
+    pub(super) updated_iso: Option<String>,
     pub(super) language: String,
+    /// `rtl` or `ltr` from the language, unless the front matter sets `dir`.
+    pub(super) dir: String,
     pub(super) tags: Vec<String>,
+    pub(super) tag_links: Vec<TagLink>,
+    pub(super) featured: bool,
+    pub(super) weight: i64,
+    pub(super) undated: bool,
+    pub(super) classes: Vec<String>,
+    pub(super) body_classes: String,
     #[serde(rename = "type")]
     pub(super) post_type: Option<String>,
     #[serde(rename = "abstract")]
     pub(super) abstract_text: Option<String>,
+    pub(super) description: String,
     pub(super) attached: Vec<String>,
-    pub(super) body: String,
+    pub(super) body: Arc<str>,
     pub(super) excerpt: String,
     pub(super) permalink: String,
+    /// Absolute URL of `oembed.json`, set when `oembed` is enabled.
+    pub(super) oembed_url: Option<String>,
+    /// The `jsonld:` front matter as JSON for a `<script>` element.
+    pub(super) jsonld: Option<Value>,
     pub(super) attachments: HashMap<String, AttachmentMeta>,
+    pub(super) private_attachments: HashMap<String, AttachmentMeta>,
     #[serde(flatten)]
     pub(super) extra: serde_json::Map<String, JsonValue>,
 }
 
+/// Resolves tag names to the archive pages written by `render_tag_archives`,
+/// following `tag_aliases` and skipping blank tags and tags that slug to an
+/// already listed archive.
+pub(super) fn tag_links(config: &Config, tags: &[String]) -> Vec<TagLink> {
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    for tag in tags {
+        let tag = config.canonical_tag(tag);
+        if tag.is_empty() {
+            continue;
+        }
+        let Some(slug) = tag_slug(config, tag) else {
+            continue;
+        };
+        if !seen.insert(slug.clone()) {
+            continue;
+        }
+        links.push(TagLink {
+            name: tag_name(config, &slug, tag).to_string(),
+            url: with_base_path(&config.base_url, &tag_index_url(&slug)),
+            slug,
+        });
+    }
+    links
+}
+
+#[derive(Serialize)]
+pub(super) struct TagLink {
+    pub(super) name: String,
+    pub(super) slug: String,
+    pub(super) url: String,
+}
+
+/// Absolute URL of the post's first published image attachment, falling back
+/// to an `image` front matter field resolved against the permalink.
+pub(super) fn thumbnail_url(config: &Config, post: &Post) -> Option<String> {
+    let attached = post.attached.iter().find(|path| {
+        mime_guess::from_path(path)
+            .first()
+            .is_some_and(|mime| mime.type_() == mime_guess::mime::IMAGE)
+    });
+    if let Some(path) = attached {
+        let relative = encode_path(&normalize_path(path));
+        return Some(absolute_url(
+            &config.base_url,
+            &format!("{}{relative}", post.permalink),
+        ));
+    }
+
+    let image = post.extra.get("image")?.as_str()?.trim();
+    if image.is_empty() {
+        None
+    } else if image.contains("://") {
+        Some(image.to_string())
+    } else if image.starts_with('/') {
+        Some(absolute_url(&config.base_url, image))
+    } else {
+        Some(absolute_url(
+            &config.base_url,
+            &format!("{}{image}", post.permalink),
+        ))
+    }
+}
+
+fn attachment_metadata(post: &Post, paths: &[PathBuf]) -> HashMap<String, AttachmentMeta> {
+    let mut attachments = HashMap::new();
+    for relative_path in paths {
+        let normalized = normalize_path(relative_path);
+        let asset_path = post.source_dir.join(relative_path);
+
+        if let Ok(metadata) = fs::metadata(&asset_path) {
+            let size = metadata.len();
+            let mime_type = mime_guess::from_path(&asset_path)
+                .first_or_octet_stream()
+                .to_string();
+
+            let text = post.attachments_meta.get(&normalized);
+            attachments.insert(
+                normalized,
+                AttachmentMeta {
+                    size,
+                    mime_type,
+                    alt: text.and_then(|text| text.alt.clone()),
+                    caption: text.and_then(|text| text.caption.clone()),
+                },
+            );
+        }
+    }
+    attachments
+}
+
 #[derive(Serialize)]
 pub(super) struct AttachmentMeta {
     pub(super) size: u64,
     pub(super) mime_type: String,
+    pub(super) alt: Option<String>,
+    pub(super) caption: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -275,21 +749,71 @@ pub(super) struct PostSummary {
     pub(super) slug: String,
     pub(super) date: String,
     pub(super) date_iso: String,
+    pub(super) updated: Option<String>,
+    pub(super) updated_iso: Option<String>,
     pub(super) language: String,
+    /// `rtl` or `ltr` from the language, unless the front matter sets `dir`.
+    pub(super) dir: String,
     pub(super) tags: Vec<String>,
+    pub(super) tag_links: Vec<TagLink>,
+    pub(super) featured: bool,
+    pub(super) weight: i64,
+    pub(super) undated: bool,
+    pub(super) classes: Vec<String>,
+    pub(super) body_classes: String,
     #[serde(rename = "type")]
     pub(super) post_type: Option<String>,
     #[serde(rename = "abstract")]
     pub(super) abstract_text: Option<String>,
-    pub(super) body: String,
+    pub(super) description: String,
+    pub(super) body: Arc<str>,
     pub(super) excerpt: String,
     pub(super) permalink: String,
     pub(super) attachments: HashMap<String, AttachmentMeta>,
+    pub(super) private_attachments: HashMap<String, AttachmentMeta>,
     #[serde(flatten)]
     pub(super) extra: serde_json::Map<String, JsonValue>,
 }
 
-fn compute_post_digest(post: &Post) -> Result<String> {
+#[derive(Serialize)]
+pub(super) struct PostListEntry {
+    pub(super) title: Option<String>,
+    pub(super) slug: String,
+    pub(super) date: String,
+    pub(super) date_iso: String,
+    pub(super) updated: Option<String>,
+    pub(super) updated_iso: Option<String>,
+    pub(super) tags: Vec<String>,
+    pub(super) permalink: String,
+}
+
+/// A post's cache key and current digest, and whether the digest differs
+/// from the one stored by the last render.
+pub(super) struct PostDigest {
+    cache_key: String,
+    digest: String,
+    pub(super) changed: bool,
+}
+
+pub(super) fn check_post_digest(
+    post: &Post,
+    shortcodes: &Shortcodes,
+    cache_db: &sled::Db,
+) -> Result<PostDigest> {
+    let cache_key = format!("{POST_HASH_PREFIX}{}", post.permalink);
+    let digest = compute_post_digest(post, shortcodes)?;
+    let cached = cache_db
+        .get(cache_key.as_bytes())
+        .with_context(|| format!("failed to read cache entry for {}", post.slug))?;
+    let changed = cached.is_none_or(|value| value.as_ref() != digest.as_bytes());
+    Ok(PostDigest {
+        cache_key,
+        digest,
+        changed,
+    })
+}
+
+fn compute_post_digest(post: &Post, shortcodes: &Shortcodes) -> Result<String> {
     let mut hasher = Hasher::new();
     let content = fs::read(&post.content_path).with_context(|| {
         format!(
@@ -299,7 +823,21 @@ fn compute_post_digest(post: &Post) -> Result<String> {
     })?;
     hasher.update(&content);
 
-    let mut assets: Vec<PathBuf> = post.attached.clone();
+    for name in &post.shortcodes {
+        hasher.update(name.as_bytes());
+        if let Some(digest) = shortcodes.digest(name) {
+            hasher.update(digest.as_bytes());
+        }
+    }
+
+    // Private attachments are never published, but editing them should still
+    // re-render the post so the metadata exposed to templates stays current.
+    let mut assets: Vec<PathBuf> = post
+        .attached
+        .iter()
+        .chain(&post.attached_private)
+        .cloned()
+        .collect();
     assets.sort();
 
     for relative in assets {
@@ -325,9 +863,15 @@ fn compute_post_digest(post: &Post) -> Result<String> {
     Ok(hasher.finalize().to_hex().to_string())
 }
 
-fn cleanup_post_hashes(db: &sled::Db, keep: &BTreeSet<String>) -> Result<()> {
+/// Removes the post digests, language guesses, backlinks, or stripped
+/// attachment stamps under `prefix` that the current posts no longer use.
+pub(super) fn cleanup_stale_keys(
+    db: &sled::Db,
+    prefix: &str,
+    keep: &BTreeSet<String>,
+) -> Result<()> {
     let mut stale: Vec<Vec<u8>> = Vec::new();
-    for entry in db.scan_prefix(POST_HASH_PREFIX.as_bytes()) {
+    for entry in db.scan_prefix(prefix.as_bytes()) {
         let (key, _) = entry.context("failed to iterate post cache entries")?;
         let key_vec = key.to_vec();
         let key_str =
@@ -344,7 +888,15 @@ fn cleanup_post_hashes(db: &sled::Db, keep: &BTreeSet<String>) -> Result<()> {
     Ok(())
 }
 
-fn copy_post_assets(post: &Post, target_dir: &Path) -> Result<()> {
+/// Directory under `html_root` that holds a post's `index.html` and its
+/// published attachments.
+pub(super) fn post_output_dir(html_root: &Path, post: &Post) -> PathBuf {
+    html_root.join(post.permalink.trim_start_matches('/'))
+}
+
+/// The post's published attachments, relative to both its source directory
+/// and [`post_output_dir`].
+pub(super) fn published_attachments(post: &Post) -> Result<BTreeSet<PathBuf>> {
     let mut assets = BTreeSet::new();
     for entry in &post.attached {
         if entry.is_absolute() {
@@ -352,30 +904,157 @@ fn copy_post_assets(post: &Post, target_dir: &Path) -> Result<()> {
         }
         assets.insert(entry.clone());
     }
+    Ok(assets)
+}
 
-    for relative in assets {
+/// Strips camera metadata from the post's attachments as it writes them, and
+/// adds the rest to `copies`.
+fn copy_post_assets(
+    post: &Post,
+    html: &Output,
+    config: &Config,
+    cache_db: &sled::Db,
+    log: Logger,
+    stats: &mut PostRenderStats,
+    copies: &mut Vec<PendingCopy>,
+) -> Result<()> {
+    let target_dir = post_output_dir(html.root(), post);
+    for relative in published_attachments(post)? {
         let source = post.source_dir.join(&relative);
         if !source.exists() {
             bail!("missing asset {}", source.display());
         }
+        let metadata = fs::metadata(&source)
+            .with_context(|| format!("failed to inspect asset {}", source.display()))?;
+        let size = metadata.len();
+        if let Some(limit) = config.attachment_size_limit
+            && size > limit
+        {
+            bail!(
+                "{} is {}, above attachment_size_limit ({})",
+                source.display(),
+                format_bytes(size),
+                format_bytes(limit)
+            );
+        }
+        if size > config.max_attachment_size {
+            log.log(
+                Level::Warn,
+                "COPY",
+                format!(
+                    "{} is {}, above max_attachment_size ({})",
+                    source.display(),
+                    format_bytes(size),
+                    format_bytes(config.max_attachment_size)
+                ),
+                Some(&source),
+            );
+        }
+
         let destination = target_dir.join(&relative);
-        if let Some(parent) = destination.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
+        if post.strip_exif.unwrap_or(config.strip_exif)
+            && let Some(format) = ImageFormat::detect(&relative)
+        {
+            if let Some(written) = copy_stripped(
+                &source,
+                &destination,
+                format,
+                &metadata,
+                html,
+                cache_db,
+                log,
+            )? {
+                stats.attachments_copied += 1;
+                stats.attachment_bytes += written;
+            }
+            continue;
         }
-        fs::copy(&source, &destination).with_context(|| {
-            format!(
-                "failed to copy asset from {} to {}",
-                source.display(),
-                destination.display()
-            )
-        })?;
+        copies.push(PendingCopy {
+            source,
+            destination,
+        });
+        stats.attachments_copied += 1;
+        stats.attachment_bytes += size;
     }
 
     Ok(())
 }
 
-fn format_date(config: &Config, date: &OffsetDateTime) -> Result<String> {
+/// Cache key of the stamp and hash of a stripped attachment written to
+/// `destination`.
+fn stripped_key(html: &Output, destination: &Path) -> String {
+    format!(
+        "{STRIPPED_ATTACHMENT_PREFIX}{}",
+        normalize_path(destination.strip_prefix(html.root()).unwrap_or(destination))
+    )
+}
+
+/// Writes `source` to `destination` without its camera metadata, or as is
+/// with a warning when `format` cannot be stripped. The source stamp and the
+/// hash of what was written are cached, so an unchanged source whose output is
+/// still in place is skipped. Returns the bytes written, if any.
+fn copy_stripped(
+    source: &Path,
+    destination: &Path,
+    format: ImageFormat,
+    metadata: &fs::Metadata,
+    html: &Output,
+    cache_db: &sled::Db,
+    log: Logger,
+) -> Result<Option<u64>> {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+    let stamp = format!("{} {}", metadata.len(), modified);
+    let key = stripped_key(html, destination);
+    if let Some(hash) = read_cached_string(cache_db, &key)?
+        .as_deref()
+        .and_then(|cached| cached.strip_prefix(&format!("{stamp} ")))
+        && fs::read(destination)
+            .is_ok_and(|existing| blake3::hash(&existing).to_hex().as_str() == hash)
+    {
+        return Ok(None);
+    }
+
+    let data =
+        fs::read(source).with_context(|| format!("failed to read asset {}", source.display()))?;
+    let output = match strip_metadata(format, &data) {
+        Some(stripped) => stripped,
+        None => {
+            log.log(
+                Level::Warn,
+                "COPY",
+                format!(
+                    "cannot strip metadata from {}; copying it as is",
+                    source.display()
+                ),
+                Some(source),
+            );
+            data
+        }
+    };
+    html.write(destination, &output)?;
+    let hash = blake3::hash(&output).to_hex();
+    store_cached_string(cache_db, &key, &format!("{stamp} {hash}"))?;
+    Ok(Some(output.len() as u64))
+}
+
+/// The newest `date` or `updated` among `posts`, whatever their order.
+pub(super) fn newest_change<'a>(
+    posts: impl IntoIterator<Item = &'a Post>,
+) -> Option<OffsetDateTime> {
+    posts
+        .into_iter()
+        .map(|post| {
+            post.updated
+                .map_or(post.date, |updated| updated.max(post.date))
+        })
+        .max()
+}
+
+pub(super) fn format_date(config: &Config, date: &OffsetDateTime) -> Result<String> {
     if config.date_format.eq_ignore_ascii_case("RFC3339") {
         return date
             .format(&time::format_description::well_known::Rfc3339)
@@ -410,6 +1089,8 @@ pub(super) fn att_to_absolute(
     attached: &[PathBuf],
     return_absolute: bool,
 ) -> String {
+    #[cfg(test)]
+    rewrites::record(permalink, return_absolute);
     if attached.is_empty() {
         return body.to_string();
     }
@@ -481,13 +1162,41 @@ fn match_attribute(input: &str) -> Option<(char, usize)> {
     }
 }
 
-fn rewrite_if_attached(
-    value: &str,
-    permalink: &str,
-    base_url: &str,
-    attached: &HashSet<String>,
-    return_absolute: bool,
-) -> Option<String> {
+pub(super) fn private_references(body: &str, private: &[PathBuf]) -> Vec<String> {
+    let private_paths: HashSet<String> = private
+        .iter()
+        .filter(|item| !item.is_absolute())
+        .map(|item| normalize_path(item))
+        .collect();
+    if private_paths.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found = BTreeSet::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        if let Some((quote, prefix_len)) = match_attribute(rest) {
+            let value_start = &rest[prefix_len..];
+            let Some(value_len) = value_start.find(quote) else {
+                break;
+            };
+            let value = &value_start[..value_len];
+            if let Some((path_part, _)) = split_relative_reference(value)
+                && private_paths.contains(path_part)
+            {
+                found.insert(path_part.to_string());
+            }
+            rest = &value_start[value_len + quote.len_utf8()..];
+        } else {
+            let ch = rest.chars().next().unwrap();
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    found.into_iter().collect()
+}
+
+fn split_relative_reference(value: &str) -> Option<(&str, &str)> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return None;
@@ -515,10 +1224,20 @@ fn rewrite_if_attached(
         return None;
     }
 
-    let (path_part, suffix) = match relative.find(['?', '#']) {
-        Some(idx) => relative.split_at(idx),
-        None => (relative, ""),
-    };
+    match relative.find(['?', '#']) {
+        Some(idx) => Some(relative.split_at(idx)),
+        None => Some((relative, "")),
+    }
+}
+
+fn rewrite_if_attached(
+    value: &str,
+    permalink: &str,
+    base_url: &str,
+    attached: &HashSet<String>,
+    return_absolute: bool,
+) -> Option<String> {
+    let (path_part, suffix) = split_relative_reference(value)?;
 
     if !attached.contains(path_part) {
         return None;
@@ -560,3 +1279,34 @@ fn join_permalink(permalink: &str, relative: &str) -> String {
     let normalized = normalize_path(full.as_path());
     format!("/{}", normalized)
 }
+
+/// Lets tests count the [`att_to_absolute`] calls made on the current thread.
+#[cfg(test)]
+pub(super) mod rewrites {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    thread_local! {
+        static COUNTS: RefCell<Option<BTreeMap<(String, bool), usize>>> =
+            const { RefCell::new(None) };
+    }
+
+    /// Runs `run` and returns how often each permalink was rewritten, keyed by
+    /// permalink and `return_absolute`.
+    pub(in crate::render) fn count<T>(
+        run: impl FnOnce() -> T,
+    ) -> (T, BTreeMap<(String, bool), usize>) {
+        COUNTS.with(|counts| *counts.borrow_mut() = Some(BTreeMap::new()));
+        let value = run();
+        let counts = COUNTS.with(|counts| counts.borrow_mut().take().unwrap_or_default());
+        (value, counts)
+    }
+
+    pub(super) fn record(permalink: &str, absolute: bool) {
+        COUNTS.with(|counts| {
+            if let Some(counts) = counts.borrow_mut().as_mut() {
+                *counts.entry((permalink.to_string(), absolute)).or_default() += 1;
+            }
+        });
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde_json::Map as JsonMap;
+use time::OffsetDateTime;
+
+use crate::config::{Config, config_path};
+use crate::content::{Post, text_direction};
+use crate::markdown::{MarkdownRender, render_markdown};
+use crate::template;
+
+use super::cache::{cache_location, open_cache_snapshot};
+use super::inline;
+use super::listing::resolve_tag_slugs;
+use super::posts::{PostBodies, build_post_context, discover_site_posts, post_template_name};
+use super::site::build_site_context;
+use super::templates::{load_templates, render_template_with_scope};
+
+const PREVIEW_SLUG: &str = "preview";
+
+/// Renders a standalone markdown file through `post.html` as if it were a
+/// post published now, for `bckt dev`'s `/_preview` route. The file needs no
+/// front matter, and nothing is written to disk.
+pub fn render_preview(root: &Path, profile: Option<&str>, source: &Path) -> Result<String> {
+    let config = Config::load_with_profile(config_path(root), profile)?;
+    let mut env = template::environment(&config)?;
+    load_templates(&config.templates_path(root), &mut env)?;
+    inline::register(&mut env, root, &config)?;
+    env.add_global("site", build_site_context(&config, &[])?);
+
+    let markdown = fs::read_to_string(source)
+        .with_context(|| format!("failed to read {}", source.display()))?;
+    let MarkdownRender { html, excerpt } = render_markdown(&markdown, &config.markdown);
+    let post = Post {
+        title: source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned()),
+        slug: PREVIEW_SLUG.to_string(),
+        date: OffsetDateTime::now_utc(),
+        updated: None,
+        tags: Vec::new(),
+        post_type: None,
+        template: None,
+        abstract_text: None,
+        attached: Vec::new(),
+        attached_private: Vec::new(),
+        attachments_meta: BTreeMap::new(),
+        jsonld: None,
+        strip_exif: None,
+        featured: false,
+        weight: 0,
+        undated: false,
+        classes: Vec::new(),
+        shortcodes: Vec::new(),
+        post_refs: Vec::new(),
+        linked_posts: Vec::new(),
+        body_html: html,
+        excerpt,
+        language: config.search.default_language.clone(),
+        dir: text_direction(&config.search.default_language).to_string(),
+        search_text: String::new(),
+        source_dir: source.parent().unwrap_or(root).to_path_buf(),
+        content_path: source.to_path_buf(),
+        permalink: format!("/_{PREVIEW_SLUG}/"),
+        extra: JsonMap::new(),
+    };
+
+    let context = build_post_context(&config, &post, None, post.body_html.as_str().into())?;
+    let template = env
+        .get_template("post.html")
+        .context("post.html template missing")?;
+    let scope = format!("rendering preview of {}", source.display());
+    render_template_with_scope(&template, minijinja::context! { post => &context }, &scope)
+}
+
+/// What [`preview_post`] prints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostPreview {
+    /// The post page, as `bckt render` would write it.
+    Html,
+    /// The `post` and `site` values its template receives, as JSON.
+    Context,
+}
+
+/// Renders the post with `slug` through its template without writing
+/// anything, for `bckt preview`. The cache is only read.
+pub fn preview_post(
+    root: &Path,
+    profile: Option<&str>,
+    slug: &str,
+    output: PostPreview,
+) -> Result<String> {
+    let mut config = Config::load_with_profile(config_path(root), profile)?;
+    let cache_db = open_cache_snapshot(&cache_location(root, &config))?;
+    let discovered = discover_site_posts(root, &config, &cache_db)?;
+    let posts = &discovered.posts;
+    // Collisions are reported by renders; the preview only needs the slugs.
+    resolve_tag_slugs(&mut config, posts);
+    let matches: Vec<&Post> = posts.iter().filter(|post| post.slug == slug).collect();
+    let post = match matches.as_slice() {
+        [post] => *post,
+        [] => bail!("no post has the slug '{slug}'"),
+        _ => bail!(
+            "{} posts have the slug '{slug}': {}",
+            matches.len(),
+            matches
+                .iter()
+                .map(|post| post.content_path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let bodies = PostBodies::new(&config, &cache_db);
+    let context = build_post_context(
+        &config,
+        post,
+        Some(&bodies),
+        bodies.rewritten(&config, post, false)?,
+    )?;
+    let site = build_site_context(&config, posts)?;
+    if output == PostPreview::Context {
+        let context = serde_json::json!({ "post": context, "site": site });
+        return serde_json::to_string_pretty(&context).context("failed to serialize context");
+    }
+
+    let mut env = template::environment(&config)?;
+    load_templates(&config.templates_path(root), &mut env)?;
+    inline::register(&mut env, root, &config)?;
+    env.add_global("site", site);
+    let template_name = post_template_name(post);
+    let template = env
+        .get_template(&template_name)
+        .or_else(|_| env.get_template("post.html"))
+        .context("post.html template missing")?;
+    let scope = format!("rendering preview of {}", post.slug);
+    render_template_with_scope(&template, minijinja::context! { post => &context }, &scope)
+}
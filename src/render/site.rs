@@ -0,0 +1,48 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use minijinja::value::Value;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::config::Config;
+use crate::content::Post;
+
+use super::posts::{format_date, newest_change};
+use super::utils::format_rfc3339;
+
+/// Values computed from the content being built, exposed to templates as
+/// `site` so that `config` only carries user settings.
+#[derive(Serialize)]
+struct SiteContext {
+    post_count: usize,
+    latest_post_date: Option<String>,
+    latest_post_date_iso: Option<String>,
+    /// The newest post `date` or `updated`, as in the feeds.
+    updated: Option<String>,
+    updated_iso: Option<String>,
+    /// Years with at least one post, newest first.
+    years: Vec<i32>,
+    build_time: String,
+    build_time_iso: String,
+}
+
+/// Builds the `site` global from `posts`, which are sorted oldest first.
+pub(super) fn build_site_context(config: &Config, posts: &[Post]) -> Result<Value> {
+    let now = OffsetDateTime::now_utc();
+    let latest = posts.last().map(|post| post.date);
+    let updated = newest_change(posts);
+    let years: BTreeSet<i32> = posts.iter().map(|post| post.date.year()).collect();
+
+    let context = SiteContext {
+        post_count: posts.len(),
+        latest_post_date: latest.map(|date| format_date(config, &date)).transpose()?,
+        latest_post_date_iso: latest.map(|date| format_rfc3339(&date)).transpose()?,
+        updated: updated.map(|date| format_date(config, &date)).transpose()?,
+        updated_iso: updated.map(|date| format_rfc3339(&date)).transpose()?,
+        years: years.into_iter().rev().collect(),
+        build_time: format_date(config, &now)?,
+        build_time_iso: format_rfc3339(&now)?,
+    };
+    Ok(Value::from_serialize(&context))
+}
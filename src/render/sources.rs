@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::{self, Config};
+use crate::content::Post;
+use crate::urls::extract_base_path;
+
+use super::assets::{THEME_ASSETS_DIR, asset_files, theme_assets_directory};
+use super::cache::{cache_location, open_cache_snapshot};
+use super::pages::page_files;
+use super::posts::{discover_site_posts, post_output_dir, published_attachments};
+use super::utils::normalize_path;
+
+/// What publishes a file under `html/`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Post,
+    Attachment,
+    Page,
+    Static,
+    Theme,
+}
+
+impl SourceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Post => "post",
+            Self::Attachment => "attachment",
+            Self::Page => "page",
+            Self::Static => "static",
+            Self::Theme => "theme",
+        }
+    }
+}
+
+/// A file under `html/` and the project file it is rendered or copied from.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SourceFile {
+    pub kind: SourceKind,
+    /// Path below `html/`, `/`-separated.
+    pub output: String,
+    pub source: PathBuf,
+}
+
+/// The posts of a project and the output files that come from a single
+/// source file, mapped with the renderer's own path functions. Listings,
+/// feeds, and other generated files have no entry.
+pub struct SiteSources {
+    pub posts: Vec<Post>,
+    base_path: String,
+    files: BTreeMap<String, SourceFile>,
+}
+
+impl SiteSources {
+    /// The source of the output at `site_path`, which may be a root-relative
+    /// path (`/about.html`), include the `base_url` path, or be a full URL.
+    /// Directory URLs resolve to their `index.html`.
+    pub fn resolve(&self, site_path: &str) -> Option<&SourceFile> {
+        let mut path = site_path.split(['?', '#']).next().unwrap_or_default();
+        if let Some((_, after_scheme)) = path.split_once("://") {
+            path = after_scheme
+                .find('/')
+                .map_or("/", |index| &after_scheme[index..]);
+        }
+        if !self.base_path.is_empty()
+            && let Some(rest) = path.strip_prefix(&self.base_path)
+            && (rest.is_empty() || rest.starts_with('/'))
+        {
+            path = rest;
+        }
+
+        let relative = path.trim_start_matches('/');
+        if relative.is_empty() || relative.ends_with('/') {
+            return self.files.get(&format!("{relative}index.html"));
+        }
+        self.files
+            .get(relative)
+            .or_else(|| self.files.get(&format!("{relative}/index.html")))
+    }
+}
+
+/// Discovers posts, pages, and static and theme assets like `render_site`
+/// does, without writing to `html/` or the cache.
+pub fn site_sources(root: &Path, profile: Option<&str>) -> Result<SiteSources> {
+    let config = Config::load_with_profile(config::config_path(root), profile)?;
+    let cache_db = open_cache_snapshot(&cache_location(root, &config))?;
+    let discovered = discover_site_posts(root, &config, &cache_db)?;
+
+    // Later entries win, following the order in which a render writes them.
+    let mut files = BTreeMap::new();
+    let mut add = |kind: SourceKind, output: &Path, source: PathBuf| {
+        let output = normalize_path(output);
+        files.insert(
+            output.clone(),
+            SourceFile {
+                kind,
+                output,
+                source,
+            },
+        );
+    };
+
+    for post in &discovered.posts {
+        let dir = post_output_dir(Path::new(""), post);
+        add(
+            SourceKind::Post,
+            &dir.join("index.html"),
+            post.content_path.clone(),
+        );
+        for relative in published_attachments(post)? {
+            add(
+                SourceKind::Attachment,
+                &dir.join(&relative),
+                post.source_dir.join(&relative),
+            );
+        }
+    }
+
+    let pages_dir = config.pages_path(root);
+    for path in page_files(&pages_dir)? {
+        let relative = path.strip_prefix(&pages_dir).unwrap().to_path_buf();
+        add(SourceKind::Page, &relative, path);
+    }
+
+    let static_dir = config.static_path(root);
+    for path in asset_files(&static_dir, &config.static_ignore)? {
+        let relative = path.strip_prefix(&static_dir).unwrap().to_path_buf();
+        add(SourceKind::Static, &relative, path);
+    }
+
+    if let Some(theme) = config.theme.as_deref()
+        && let Some(assets_dir) = theme_assets_directory(root, theme)?
+    {
+        for path in asset_files(&assets_dir, &config.static_ignore)? {
+            let relative =
+                Path::new(THEME_ASSETS_DIR).join(path.strip_prefix(&assets_dir).unwrap());
+            add(SourceKind::Theme, &relative, path);
+        }
+    }
+
+    Ok(SiteSources {
+        posts: discovered.posts,
+        base_path: extract_base_path(&config.base_url),
+        files,
+    })
+}
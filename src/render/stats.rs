@@ -0,0 +1,180 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use blake3::Hasher;
+use minijinja::Environment;
+use serde::Serialize;
+use time::{Month, OffsetDateTime};
+
+use crate::config::Config;
+use crate::content::Post;
+use crate::logging::Logger;
+
+use super::BuildMode;
+use super::cache::{read_cached_string, store_cached_string};
+use super::output::Output;
+use super::posts::{PostBodies, tag_links};
+use super::templates::render_template_with_scope;
+
+const STATS_KEY: &str = "stats_hash";
+const STATS_TEMPLATE: &str = "stats.html";
+const STATS_DIR: &str = "stats";
+const STATS_JSON_FILE: &str = "stats.json";
+const RECENT_MONTHS: usize = 24;
+const TOP_TAGS: usize = 30;
+
+/// Aggregates over every post, passed to `stats.html` as `stats` and written
+/// to `stats/stats.json`.
+#[derive(Debug, Serialize)]
+struct SiteStats {
+    post_count: usize,
+    /// Whitespace-separated words in all post bodies.
+    word_count: usize,
+    /// Years with posts, oldest first.
+    years: Vec<YearCount>,
+    /// The last 24 months up to the build date, oldest first, including
+    /// months without posts.
+    months: Vec<MonthCount>,
+    /// The most used tags, most posts first.
+    tags: Vec<TagCount>,
+}
+
+#[derive(Debug, Serialize)]
+struct YearCount {
+    year: i32,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct MonthCount {
+    year: i32,
+    month: u8,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct TagCount {
+    name: String,
+    slug: String,
+    url: String,
+    count: usize,
+}
+
+/// Counts posts per year, per recent month, and per tag in one pass.
+fn compute_stats(
+    config: &Config,
+    posts: &[Post],
+    bodies: &PostBodies<'_>,
+    now: OffsetDateTime,
+) -> Result<SiteStats> {
+    let mut years: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut months: HashMap<(i32, u8), usize> = HashMap::new();
+    let mut tags: HashMap<String, TagCount> = HashMap::new();
+    let mut word_count = 0;
+
+    for post in posts {
+        *years.entry(post.date.year()).or_default() += 1;
+        *months
+            .entry((post.date.year(), post.date.month() as u8))
+            .or_default() += 1;
+        word_count += bodies.search_text(post)?.split_whitespace().count();
+        for link in tag_links(config, &post.tags) {
+            tags.entry(link.slug.clone())
+                .or_insert_with(|| TagCount {
+                    name: link.name,
+                    slug: link.slug,
+                    url: link.url,
+                    count: 0,
+                })
+                .count += 1;
+        }
+    }
+
+    let mut recent = Vec::with_capacity(RECENT_MONTHS);
+    let (mut year, mut month) = (now.year(), now.month());
+    for _ in 0..RECENT_MONTHS {
+        recent.push(MonthCount {
+            year,
+            month: month as u8,
+            count: months.get(&(year, month as u8)).copied().unwrap_or(0),
+        });
+        if month == Month::January {
+            year -= 1;
+        }
+        month = month.previous();
+    }
+    recent.reverse();
+
+    let mut tags: Vec<TagCount> = tags.into_values().collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    tags.truncate(TOP_TAGS);
+
+    Ok(SiteStats {
+        post_count: posts.len(),
+        word_count,
+        years: years
+            .into_iter()
+            .map(|(year, count)| YearCount { year, count })
+            .collect(),
+        months: recent,
+        tags,
+    })
+}
+
+/// Renders `stats/index.html` from the theme's optional `stats.html` and
+/// writes the same data to `stats/stats.json` for client-side charts. Both
+/// are only rewritten when the numbers change, and removed again once the
+/// template is gone.
+pub(super) fn render_stats(
+    posts: &[Post],
+    html: &Output,
+    config: &Config,
+    env: &Environment<'static>,
+    bodies: &PostBodies<'_>,
+    mode: BuildMode,
+    log: Logger,
+) -> Result<()> {
+    let cache_db = bodies.cache_db();
+    let dir = html.root().join(STATS_DIR);
+    let page = dir.join("index.html");
+    let data = dir.join(STATS_JSON_FILE);
+    let Ok(template) = env.get_template(STATS_TEMPLATE) else {
+        if read_cached_string(cache_db, STATS_KEY)?.is_some() {
+            html.remove_file(&page)?;
+            html.remove_file(&data)?;
+            cache_db
+                .remove(STATS_KEY)
+                .context("failed to remove stats cache entry")?;
+        }
+        return Ok(());
+    };
+
+    let stats = compute_stats(config, posts, bodies, OffsetDateTime::now_utc())?;
+    let bytes = serde_json::to_vec(&stats).context("failed to serialize stats.json")?;
+    let mut hasher = Hasher::new();
+    hasher.update(&bytes);
+    let digest = hasher.finalize().to_hex().to_string();
+    let cached = read_cached_string(cache_db, STATS_KEY)?;
+    if matches!(mode, BuildMode::Changed)
+        && cached.as_deref() == Some(digest.as_str())
+        && page.exists()
+        && data.exists()
+    {
+        log.trace("STATS", "Stats page unchanged");
+        return Ok(());
+    }
+
+    let rendered = render_template_with_scope(
+        &template,
+        minijinja::context! { stats => &stats },
+        "rendering stats page",
+    )?;
+    html.write_page(config, STATS_TEMPLATE, &page, &rendered)?;
+    html.write(&data, bytes)?;
+    store_cached_string(cache_db, STATS_KEY, &digest)?;
+    log.debug(
+        "STATS",
+        format!("Rendered stats page ({} posts)", stats.post_count),
+    );
+    Ok(())
+}
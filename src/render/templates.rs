@@ -18,12 +18,15 @@ pub(super) fn render_template_with_scope(
     let template_name = template.name().to_string();
     template
         .render(context)
-        .map_err(|err| describe_template_error(scope, &template_name, err))
+        .map_err(|err| describe_template_error(scope, &template_name, Some(template.source()), err))
 }
 
+/// Formats a MiniJinja error; with the `source` of `template_name`, errors
+/// inside that template also quote the offending line.
 pub(super) fn describe_template_error(
     scope: &str,
     template_name: &str,
+    source: Option<&str>,
     err: TemplateError,
 ) -> anyhow::Error {
     let actual_template = err.name().unwrap_or(template_name).to_string();
@@ -44,6 +47,13 @@ pub(super) fn describe_template_error(
         let _ = write!(&mut message, " at line {}", line_no);
     }
 
+    if actual_template == template_name
+        && let Some(line_no) = line
+        && let Some(text) = source.and_then(|source| source.lines().nth(line_no.saturating_sub(1)))
+    {
+        let _ = write!(&mut message, "\n{:>4} | {}", line_no, text.trim_end());
+    }
+
     let _ = write!(&mut message, "\nkind: {:?}", kind);
 
     let payload = detail.unwrap_or(summary);
@@ -56,14 +66,23 @@ pub(super) fn describe_template_error(
     anyhow!(message)
 }
 
-pub(super) fn load_templates(root: &Path, env: &mut Environment<'static>) -> Result<String> {
-    let templates_dir = root.join("templates");
+pub(super) fn load_templates(
+    templates_dir: &Path,
+    env: &mut Environment<'static>,
+) -> Result<String> {
     if !templates_dir.exists() {
         bail!("templates directory {} not found", templates_dir.display());
     }
 
+    // Shortcode templates are hashed per post that uses them (see
+    // render_posts), so they stay out of the site-wide template hash.
     let mut files = Vec::new();
-    for entry in WalkDir::new(&templates_dir) {
+    for entry in WalkDir::new(templates_dir)
+        .into_iter()
+        .filter_entry(|entry| {
+            !(entry.depth() == 1 && entry.file_type().is_dir() && entry.file_name() == "shortcodes")
+        })
+    {
         let entry = entry?;
         if entry.file_type().is_file() {
             files.push(entry.into_path());
@@ -76,7 +95,7 @@ pub(super) fn load_templates(root: &Path, env: &mut Environment<'static>) -> Res
     for path in files {
         let template_body = fs::read_to_string(&path)
             .with_context(|| format!("failed to read template {}", path.display()))?;
-        let relative_path = path.strip_prefix(&templates_dir).unwrap();
+        let relative_path = path.strip_prefix(templates_dir).unwrap();
         let relative_name = normalize_path(relative_path);
         hasher.update(relative_name.as_bytes());
         hasher.update(template_body.as_bytes());
@@ -1,5 +1,8 @@
 use super::*;
+use std::collections::BTreeSet;
 use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
 use std::time::UNIX_EPOCH;
 use tempfile::TempDir;
 
@@ -113,10 +116,8 @@ fn renders_markdown_post_to_expected_location() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -133,6 +134,165 @@ fn renders_markdown_post_to_expected_location() {
     assert!(homepage.contains("data-total=\"1\""));
 }
 
+#[test]
+fn dry_run_reports_changes_without_writing() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    write_markdown_post(root, "First version");
+    write_dated_post(root, "other", "2023-06-01T00:00:00Z", "Stays the same");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let post_path = root.join("html/2024/01/02/hello-world/index.html");
+    let before = fs::read_to_string(&post_path).unwrap();
+    write_markdown_post(root, "Second version");
+
+    let report = render_site(
+        root,
+        RenderPlan {
+            dry_run: true,
+            ..plan
+        },
+    )
+    .unwrap();
+    assert_eq!(report.posts_rendered, 1);
+    assert!(report.planned_changes.contains(&PlannedChange {
+        kind: ChangeKind::Write,
+        category: ChangeCategory::Posts,
+        path: PathBuf::from("2024/01/02/hello-world/index.html"),
+    }));
+    // Files whose content would not change are left out of the report.
+    assert!(
+        !report
+            .planned_changes
+            .iter()
+            .any(|change| change.path == Path::new("2023/06/01/other/index.html"))
+    );
+    assert_eq!(fs::read_to_string(&post_path).unwrap(), before);
+
+    // The cache still holds the old digest, so a real render picks up the edit.
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.posts_rendered, 1);
+    assert!(
+        fs::read_to_string(&post_path)
+            .unwrap()
+            .contains("Second version")
+    );
+}
+
+fn snapshot_tree(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .map(Result::unwrap)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| {
+            let relative = entry.path().strip_prefix(dir).unwrap().to_path_buf();
+            (relative, fs::read(entry.path()).unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn dry_run_predicts_the_next_real_render() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::create_dir_all(root.join("skel")).unwrap();
+    fs::write(root.join("skel/style.css"), "body {}").unwrap();
+    write_tagged_post(root, "alpha", "rust", "2024-01-01T00:00:00Z", "Alpha");
+    write_tagged_post(root, "beta", "go", "2024-02-01T00:00:00Z", "Beta");
+
+    let plan = RenderPlan {
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    write_tagged_post(
+        root,
+        "alpha",
+        "rust",
+        "2024-01-01T00:00:00Z",
+        "Alpha, edited",
+    );
+    fs::remove_dir_all(root.join("posts/beta")).unwrap();
+    fs::write(root.join("skel/extra.css"), "p {}").unwrap();
+
+    let html_root = root.join("html");
+    let before = snapshot_tree(&html_root);
+    let report = render_site(
+        root,
+        RenderPlan {
+            dry_run: true,
+            ..plan
+        },
+    )
+    .unwrap();
+    assert_eq!(snapshot_tree(&html_root), before);
+
+    let planned = |kind: ChangeKind| -> BTreeSet<PathBuf> {
+        report
+            .planned_changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| change.path.clone())
+            .collect()
+    };
+    let categories: BTreeSet<ChangeCategory> = report
+        .planned_changes
+        .iter()
+        .map(|change| change.category)
+        .collect();
+    assert!(categories.contains(&ChangeCategory::Posts));
+    assert!(categories.contains(&ChangeCategory::Listings));
+    assert!(categories.contains(&ChangeCategory::Assets));
+
+    render_site(root, plan).unwrap();
+    let after = snapshot_tree(&html_root);
+    let written: BTreeSet<PathBuf> = after
+        .iter()
+        .filter(|(path, contents)| before.get(*path) != Some(*contents))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let deleted: BTreeSet<PathBuf> = before
+        .keys()
+        .filter(|path| !after.contains_key(*path))
+        .cloned()
+        .collect();
+
+    assert!(written.contains(Path::new("extra.css")));
+    assert!(deleted.contains(Path::new("tags/go/index.html")));
+    assert_eq!(planned(ChangeKind::Write), written);
+    assert_eq!(planned(ChangeKind::Delete), deleted);
+}
+
+#[test]
+fn previews_markdown_file_without_writing() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(root.join("bckt.yaml"), "title: Preview\n").unwrap();
+    let notes = temp.path().join("notes");
+    fs::create_dir_all(&notes).unwrap();
+    let source = notes.join("draft.md");
+    fs::write(&source, "Some **bold** idea").unwrap();
+
+    let rendered = render_preview(root, None, &source).unwrap();
+    assert!(rendered.contains("<article>draft|"));
+    assert!(rendered.contains("<strong>bold</strong>"));
+    assert!(!root.join("html").exists());
+    assert!(!root.join(".bckt").exists());
+}
+
 #[test]
 fn copies_post_assets() {
     let temp = TempDir::new().unwrap();
@@ -152,10 +312,8 @@ fn copies_post_assets() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -166,6 +324,222 @@ fn copies_post_assets() {
     assert!(image.exists());
 }
 
+#[test]
+fn writes_oembed_files_when_enabled() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(root, "post.html", "{{ post.oembed_url | safe }}");
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Demo & Co\nauthor: Jane\nbase_url: https://example.com/blog\noembed: true\n",
+    )
+    .unwrap();
+    let post_dir = root.join("posts/embed");
+    fs::create_dir_all(post_dir.join("images")).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ntitle: Embed <me>\ndate: 2024-01-01T00:00:00Z\nattached: [notes.txt, images/my pic.png]\n---\nHello & welcome.",
+    )
+    .unwrap();
+    fs::write(post_dir.join("notes.txt"), "notes").unwrap();
+    fs::write(post_dir.join("images/my pic.png"), "image").unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let output = root.join("html/2024/01/01/embed");
+    assert_eq!(
+        fs::read_to_string(output.join("index.html")).unwrap(),
+        "https://example.com/blog/2024/01/01/embed/oembed.json"
+    );
+    let oembed: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output.join("oembed.json")).unwrap()).unwrap();
+    assert_eq!(oembed["version"], "1.0");
+    assert_eq!(oembed["type"], "rich");
+    assert_eq!(oembed["title"], "Embed <me>");
+    assert_eq!(oembed["author_name"], "Jane");
+    assert_eq!(oembed["provider_name"], "Demo & Co");
+    assert_eq!(oembed["provider_url"], "https://example.com/blog/");
+    assert_eq!(oembed["width"], 600);
+    assert_eq!(
+        oembed["thumbnail_url"],
+        "https://example.com/blog/2024/01/01/embed/images/my%20pic.png"
+    );
+    let snippet = oembed["html"].as_str().unwrap();
+    assert!(snippet.contains("<p>Hello &amp; welcome.</p>"));
+    assert!(
+        snippet.contains(
+            "<a href=\"https://example.com/blog/2024/01/01/embed/\">Embed &lt;me&gt;</a>"
+        )
+    );
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com/blog\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!output.join("oembed.json").exists());
+    assert!(
+        !fs::read_to_string(output.join("index.html"))
+            .unwrap()
+            .contains("oembed")
+    );
+}
+
+#[test]
+fn large_attachments_are_limited() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nmax_attachment_size: 1000\n",
+    )
+    .unwrap();
+    let post_dir = root.join("posts/video-post");
+    fs::create_dir_all(&post_dir).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [clip.mp4]\n---\nBody",
+    )
+    .unwrap();
+    fs::write(post_dir.join("clip.mp4"), vec![0u8; 2048]).unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.attachments_copied, 1);
+    assert_eq!(report.attachment_bytes_copied, 2048);
+    let copied = root.join("html/2024/01/01/video-post/clip.mp4");
+    assert_eq!(fs::metadata(&copied).unwrap().len(), 2048);
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nmax_attachment_size: 1000\nattachment_size_limit: 1500\n",
+    )
+    .unwrap();
+    let error = render_site(root, plan).unwrap_err();
+    assert!(format!("{error:#}").contains("above attachment_size_limit"));
+}
+
+#[test]
+fn bulk_posts_copy_only_their_own_attachments() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    let bulk = root.join("posts/microblog");
+    fs::create_dir_all(bulk.join("images")).unwrap();
+    fs::write(bulk.join("_bulk.yaml"), "").unwrap();
+    fs::write(
+        bulk.join("first.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [images/one.png]\n---\nFirst",
+    )
+    .unwrap();
+    fs::write(
+        bulk.join("second.md"),
+        "---\ndate: 2024-01-02T00:00:00Z\nattached: [images/two.png]\n---\nSecond",
+    )
+    .unwrap();
+    fs::write(bulk.join("images/one.png"), "one").unwrap();
+    fs::write(bulk.join("images/two.png"), "two").unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let first = root.join("html/2024/01/01/first");
+    let second = root.join("html/2024/01/02/second");
+    assert!(first.join("images/one.png").exists());
+    assert!(!first.join("images/two.png").exists());
+    assert!(second.join("images/two.png").exists());
+    assert!(!second.join("images/one.png").exists());
+    assert!(!first.join("second.md").exists());
+
+    // Editing one file re-renders only that post.
+    let second_html = second.join("index.html");
+    fs::write(&second_html, "stale").unwrap();
+    fs::write(
+        bulk.join("first.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [images/one.png]\n---\nFirst, edited",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(
+        fs::read_to_string(first.join("index.html"))
+            .unwrap()
+            .contains("First, edited")
+    );
+    assert_eq!(fs::read_to_string(second_html).unwrap(), "stale");
+}
+
+#[test]
+fn switching_themes_removes_previous_theme_assets() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_markdown_post(root, "Body");
+    for (theme, file) in [("first", "css/first.css"), ("second", "css/second.css")] {
+        let path = root.join("themes").join(theme).join("assets").join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, theme).unwrap();
+    }
+    let shared = root.join("themes/first/assets/js/first.js");
+    fs::create_dir_all(shared.parent().unwrap()).unwrap();
+    fs::write(shared, "first").unwrap();
+
+    let plan = RenderPlan {
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Test\nbase_url: https://example.com\ntheme: first\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(root.join("html/assets/css/first.css").exists());
+    assert!(root.join("html/assets/js/first.js").exists());
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Test\nbase_url: https://example.com\ntheme: second\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(root.join("html/assets/css/second.css").exists());
+    assert!(!root.join("html/assets/css/first.css").exists());
+    assert!(!root.join("html/assets/js").exists());
+
+    // An unchanged theme keeps its assets on the next incremental build.
+    render_site(root, plan).unwrap();
+    assert!(root.join("html/assets/css/second.css").exists());
+
+    // A damaged list of theme assets stops the render instead of leaving
+    // stale assets behind.
+    {
+        let db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+        store_cached_string(&db, THEME_ASSET_FILES_KEY, "not json").unwrap();
+        db.flush().unwrap();
+    }
+    let error = render_site(root, plan).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("cached list of theme assets is unreadable"),
+        "{error:#}"
+    );
+}
+
 #[test]
 fn renders_pages_from_pages_directory() {
     let temp = TempDir::new().unwrap();
@@ -188,8 +562,7 @@ fn renders_pages_from_pages_directory() {
         RenderPlan {
             posts: false,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -201,6 +574,45 @@ fn renders_pages_from_pages_directory() {
     assert!(about.contains("About"));
 }
 
+#[test]
+fn copies_raw_pages_verbatim() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(root.join("bckt.yaml"), "pages:\n  raw: [\"widgets/**\"]\n").unwrap();
+    fs::create_dir_all(root.join("pages/widgets")).unwrap();
+    let widget = "<div id=\"app\">{{ message }}</div>\n<script>new Vue({ template: '{{ count }}' });</script>\n";
+    fs::write(root.join("pages/widgets/clock.html"), widget).unwrap();
+
+    let plan = RenderPlan {
+        posts: false,
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("html/widgets/clock.html")).unwrap(),
+        widget
+    );
+
+    // Outside the raw patterns, a broken page names the offending line.
+    fs::write(
+        root.join("pages/broken.html"),
+        "<p>ok</p>\n<script>{{ a b }}</script>\n",
+    )
+    .unwrap();
+    let error = render_site(root, plan).unwrap_err();
+    let message = format!("{error:#}");
+    assert!(
+        message.contains("template 'broken.html' at line 2"),
+        "{message}"
+    );
+    assert!(
+        message.contains("2 | <script>{{ a b }}</script>"),
+        "{message}"
+    );
+}
+
 #[test]
 fn writes_search_index_with_posts() {
     let temp = TempDir::new().unwrap();
@@ -214,10 +626,8 @@ fn writes_search_index_with_posts() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -241,10 +651,8 @@ fn search_index_updates_when_post_changes() {
     );
 
     let full_plan = RenderPlan {
-        posts: true,
         static_assets: false,
-        mode: BuildMode::Full,
-        verbose: false,
+        ..RenderPlan::default()
     };
     render_site(root, full_plan).unwrap();
 
@@ -258,10 +666,9 @@ fn search_index_updates_when_post_changes() {
         .unwrap();
 
     let changed_plan = RenderPlan {
-        posts: true,
         static_assets: false,
         mode: BuildMode::Changed,
-        verbose: false,
+        ..RenderPlan::default()
     };
     render_site(root, changed_plan).unwrap();
 
@@ -269,6 +676,40 @@ fn search_index_updates_when_post_changes() {
     assert_ne!(original, updated);
 }
 
+#[test]
+fn switching_search_format_replaces_previous_index() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_markdown_post(root, "Body text for the compressed search index.");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    let json_path = root.join("html/assets/search/search-index.json");
+    assert!(json_path.exists());
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Test\nbase_url: https://example.com\nsearch:\n  format: json-gz\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+
+    let gz_path = root.join("html/assets/search/search-index.json.gz");
+    assert!(gz_path.exists());
+    assert!(!json_path.exists());
+    let mut data = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(gz_path).unwrap())
+        .read_to_string(&mut data)
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(payload["documents"].as_array().unwrap().len(), 1);
+}
+
 #[test]
 fn exposes_additional_front_matter_in_templates() {
     let temp = TempDir::new().unwrap();
@@ -292,10 +733,8 @@ fn exposes_additional_front_matter_in_templates() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -316,9 +755,7 @@ fn copies_static_assets() {
         root,
         RenderPlan {
             posts: false,
-            static_assets: true,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -342,10 +779,8 @@ fn paginates_homepage_with_page_numbers() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -381,10 +816,8 @@ fn paginates_homepage_with_page_numbers() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -401,6 +834,96 @@ fn paginates_homepage_with_page_numbers() {
     assert!(root.join("html/page/2/index.html").exists());
 }
 
+#[test]
+fn pagination_path_sets_numbered_page_urls_and_files() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 1\npaginate_tags: true\npagination_path: /archive/{n}.html\n",
+    )
+    .unwrap();
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
+    let plan = || RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan()).unwrap();
+
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(index.contains("data-prev=\"/archive/2.html\""));
+    let second = fs::read_to_string(root.join("html/archive/2.html")).unwrap();
+    assert!(second.contains("article data-slug=\"beta\""));
+    assert!(second.contains("data-prev=\"/archive/1.html\""));
+    assert!(root.join("html/archive/1.html").exists());
+    assert!(!root.join("html/page").exists());
+
+    let tag_index = fs::read_to_string(root.join("html/tags/shared/index.html")).unwrap();
+    assert!(tag_index.contains("data-prev=\"/tags/shared/archive/2.html\""));
+    assert!(root.join("html/tags/shared/archive/2.html").exists());
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/archive/2.html</loc>"), "{sitemap}");
+
+    fs::remove_dir_all(root.join("posts/gamma")).unwrap();
+    render_site(root, plan()).unwrap();
+    assert!(root.join("html/archive/1.html").exists());
+    assert!(!root.join("html/archive/2.html").exists());
+    assert!(!root.join("html/tags/shared/archive/2.html").exists());
+}
+
+#[test]
+fn changing_pagination_path_removes_pages_at_the_old_path() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    let config = |pagination_path: &str| {
+        format!(
+            "homepage_posts: 1\npaginate_tags: true\narchive_per_page: 1\npagination_path: {pagination_path}\n"
+        )
+    };
+    fs::write(root.join("bckt.yaml"), config("/page/{n}/")).unwrap();
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "shared", "2024-01-02T00:00:00Z", "B");
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    let old_pages = [
+        "html/page/1/index.html",
+        "html/tags/shared/page/1/index.html",
+        "html/2024/page/1/index.html",
+        "html/2024/01/page/1/index.html",
+    ];
+    for page in old_pages {
+        assert!(root.join(page).exists(), "{page}");
+    }
+
+    fs::write(root.join("bckt.yaml"), config("/archive/{n}.html")).unwrap();
+    render_site(root, plan).unwrap();
+    for page in old_pages {
+        let dir = root
+            .join(page)
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        assert!(!dir.exists(), "{}", dir.display());
+    }
+    assert!(root.join("html/archive/1.html").exists());
+    assert!(root.join("html/tags/shared/archive/1.html").exists());
+    assert!(root.join("html/2024/archive/1.html").exists());
+    assert!(root.join("html/2024/01/archive/1.html").exists());
+}
+
 #[test]
 fn renders_tag_pages_without_pagination() {
     let temp = TempDir::new().unwrap();
@@ -419,10 +942,8 @@ fn renders_tag_pages_without_pagination() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -440,7 +961,7 @@ fn renders_tag_pages_with_pagination() {
     setup_markdown_templates(root);
     fs::write(
         root.join("bckt.yaml"),
-        "homepage_posts: 1\npaginate_tags: true\n",
+        "homepage_posts: 1\npaginate_tags: true\ntags_per_page: 3\n",
     )
     .unwrap();
 
@@ -451,10 +972,8 @@ fn renders_tag_pages_with_pagination() {
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
@@ -473,547 +992,3628 @@ fn renders_tag_pages_with_pagination() {
 }
 
 #[test]
-fn generates_rss_feed_with_absolute_urls() {
+fn tag_pages_default_to_homepage_posts_per_page() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
     fs::write(
         root.join("bckt.yaml"),
-        "base_url: \"https://example.com/blog\"\n",
+        "homepage_posts: 1\npaginate_tags: true\n",
     )
     .unwrap();
 
-    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
-    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
 
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
 
-    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
-    assert!(feed.contains("<link>https://example.com/blog/</link>"));
-    assert!(feed.contains("<atom:link href=\"https://example.com/blog/rss.xml\""));
-    assert!(feed.contains("<link>https://example.com/blog/2024/02/01/beta/</link>"));
-    assert!(feed.contains("<description>Beta body"));
-    assert!(feed.contains("<content:encoded><![CDATA["));
+    let tag_index = fs::read_to_string(root.join("html/tags/shared/index.html")).unwrap();
+    assert!(tag_index.contains("article data-slug=\"gamma\""));
+    assert!(!tag_index.contains("article data-slug=\"beta\""));
+    assert!(tag_index.contains("data-total=\"3\""));
+    assert!(tag_index.contains("data-prev=\"/tags/shared/page/2/\""));
+    assert!(tag_index.contains("data-next=\"\""));
+    let oldest = fs::read_to_string(root.join("html/tags/shared/page/1/index.html")).unwrap();
+    assert!(oldest.contains("article data-slug=\"alpha\""));
 }
 
 #[test]
-fn generates_tag_rss_feeds_when_configured() {
+fn splits_tag_pages_with_tags_per_page() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
     fs::write(
         root.join("bckt.yaml"),
-        "title: Demo Site\nbase_url: \"https://example.com\"\nrss_tags:\n  - shared\n",
+        "homepage_posts: 5\ntags_per_page: 1\narchive_per_page: 1\n",
     )
     .unwrap();
 
     write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
-    write_tagged_post(root, "beta", "other", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
 
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
 
-    let feed_path = root.join("html/rss-shared.xml");
-    assert!(feed_path.exists());
-    let feed = fs::read_to_string(feed_path).unwrap();
-    assert!(feed.contains("shared · Demo Site"));
-    assert!(feed.contains("/2024/01/01/alpha/"));
-    assert!(!feed.contains("/2024/02/01/beta/"));
+    let tag_index = fs::read_to_string(root.join("html/tags/shared/index.html")).unwrap();
+    assert!(tag_index.contains("article data-slug=\"gamma\""));
+    assert!(!tag_index.contains("article data-slug=\"beta\""));
+    assert!(tag_index.contains("data-current=\"3\""));
+    assert!(tag_index.contains("data-total=\"3\""));
+    assert!(tag_index.contains("data-prev=\"/tags/shared/page/2/\""));
+
+    let first = fs::read_to_string(root.join("html/tags/shared/page/1/index.html")).unwrap();
+    assert!(first.contains("article data-slug=\"alpha\""));
+    assert!(first.contains("data-prev=\"\""));
+    assert!(first.contains("data-next=\"/tags/shared/page/2/\""));
+
+    let second = fs::read_to_string(root.join("html/tags/shared/page/2/index.html")).unwrap();
+    assert!(second.contains("article data-slug=\"beta\""));
+    assert!(second.contains("data-next=\"/tags/shared/\""));
+
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/tags/shared/page/2/</loc>"));
+
+    assert!(root.join("html/2024/page/2/index.html").exists());
+    assert!(!root.join("html/2024/01/page").exists());
 }
 
 #[test]
-fn keeps_relative_paths_in_html_and_absolute_in_feeds() {
+fn changing_tags_per_page_leaves_homepage_untouched() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
-    fs::create_dir_all(root.join("posts/media/images")).unwrap();
+    fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
-    fs::write(root.join("posts/media/images/pic.png"), "image-bytes").unwrap();
-    fs::write(root.join("posts/media/notes.txt"), "notes").unwrap();
     fs::write(
-            root.join("posts/media/post.md"),
-            "---\ndate: 2024-01-01T00:00:00Z\nattached:\n  - images/pic.png\n  - notes.txt\n---\n![Alt](images/pic.png)\n\n[Download](notes.txt)\n",
-        )
-        .unwrap();
+        root.join("bckt.yaml"),
+        "homepage_posts: 1\ntags_per_page: 3\n",
+    )
+    .unwrap();
 
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let homepage_files = [
+        root.join("html/index.html"),
+        root.join("html/page/1/index.html"),
+        root.join("html/page/2/index.html"),
+    ];
+    let before: Vec<_> = homepage_files.iter().map(|path| file_mtime(path)).collect();
+    let tag_index = root.join("html/tags/shared/index.html");
+    let tag_before = file_mtime(&tag_index);
+
+    wait_for_filesystem_tick();
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 1\ntags_per_page: 1\n",
     )
     .unwrap();
+    render_site(root, plan).unwrap();
 
-    let post_page = fs::read_to_string(root.join("html/2024/01/01/media/index.html")).unwrap();
-    // HTML pages use relative paths (works regardless of base_url)
-    assert!(post_page.contains("images/pic.png"));
-    assert!(post_page.contains("notes.txt"));
-    // Should not contain absolute paths
-    assert!(!post_page.contains("/2024/01/01/media/images/pic.png"));
-    assert!(!post_page.contains("/2024/01/01/media/notes.txt"));
+    let after: Vec<_> = homepage_files.iter().map(|path| file_mtime(path)).collect();
+    assert_eq!(before, after);
+    assert!(file_mtime(&tag_index) > tag_before);
+    assert!(root.join("html/tags/shared/page/2/index.html").exists());
 
-    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
-    // RSS feeds use absolute URLs (required for feed readers)
-    assert!(feed.contains("/2024/01/01/media/images/pic.png"));
-    assert!(feed.contains("/2024/01/01/media/notes.txt"));
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 1\ntags_per_page: 3\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!root.join("html/tags/shared/page").exists());
+    assert_eq!(
+        before,
+        homepage_files
+            .iter()
+            .map(|path| file_mtime(path))
+            .collect::<Vec<_>>()
+    );
 }
 
 #[test]
-fn generates_sitemap_with_posts_tags_and_pages() {
+fn generates_rss_feed_with_absolute_urls() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
     fs::write(
         root.join("bckt.yaml"),
-        "base_url: \"https://example.com/blog\"\nhomepage_posts: 1\npaginate_tags: true\n",
+        "base_url: \"https://example.com/blog\"\n",
     )
     .unwrap();
 
-    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
-    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
-    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
 
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
 
-    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
-    assert!(sitemap.contains("<loc>https://example.com/blog/</loc>"));
-
-    // Page-number based URLs (page 1 = oldest, page 2 = middle)
-    assert!(sitemap.contains("<loc>https://example.com/blog/page/1/</loc>"));
-    assert!(sitemap.contains("<loc>https://example.com/blog/page/2/</loc>"));
-    assert!(sitemap.contains("<loc>https://example.com/blog/tags/shared/</loc>"));
-    assert!(sitemap.contains("<loc>https://example.com/blog/2024/03/01/gamma/</loc>"));
-    assert!(sitemap.contains("<lastmod>2024-03-01T00:00:00Z</lastmod>"));
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<link>https://example.com/blog/</link>"));
+    assert!(feed.contains("<atom:link href=\"https://example.com/blog/rss.xml\""));
+    assert!(feed.contains("<link>https://example.com/blog/2024/02/01/beta/</link>"));
+    assert!(feed.contains("<description>Beta body"));
+    assert!(feed.contains("<content:encoded><![CDATA["));
 }
 
 #[test]
-fn skips_rewriting_tag_index_when_unchanged() {
+fn feed_build_date_is_the_newest_change_across_years() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
+    write_template(root, "index.html", "{{ site.updated_iso }}");
+    write_dated_post(root, "old", "2022-05-01T00:00:00Z", "Old");
+    write_dated_post(root, "newest", "2024-03-01T12:00:00Z", "Newest");
+    write_dated_post(root, "middle", "2023-12-31T23:00:00Z", "Middle");
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
 
-    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(
+        feed.contains("<lastBuildDate>Fri, 01 Mar 2024 12:00:00 +0000</lastBuildDate>"),
+        "{feed}"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("html/index.html")).unwrap(),
+        "2024-03-01T12:00:00Z"
+    );
 
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    // An edit to an old post is the newest change.
+    fs::write(
+        root.join("posts/old/post.md"),
+        "---\ntitle: old\ndate: 2022-05-01T00:00:00Z\nupdated: 2024-06-01T00:00:00Z\n---\nOld, edited",
     )
     .unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(
+        feed.contains("<lastBuildDate>Sat, 01 Jun 2024 00:00:00 +0000</lastBuildDate>"),
+        "{feed}"
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("html/index.html")).unwrap(),
+        "2024-06-01T00:00:00Z"
+    );
+}
 
-    let tag_path = root.join("html/tags/shared/index.html");
-    assert!(tag_path.exists());
-    let first_mtime = file_mtime(&tag_path);
-
-    wait_for_filesystem_tick();
-
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Changed,
-            verbose: false,
-        },
+fn write_podcast_post(root: &Path) {
+    let dir = root.join("posts/episode-1");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ntitle: Episode 1\ndate: 2024-03-01T00:00:00Z\nattached: [cover.png, audio/episode 1.mp3, episode.mp4]\n---\nShow notes",
     )
     .unwrap();
-
-    let second_mtime = file_mtime(&tag_path);
-    assert_eq!(first_mtime, second_mtime);
+    fs::write(dir.join("cover.png"), "png").unwrap();
+    fs::create_dir_all(dir.join("audio")).unwrap();
+    fs::write(dir.join("audio/episode 1.mp3"), vec![0u8; 1234]).unwrap();
+    fs::write(dir.join("episode.mp4"), vec![0u8; 99]).unwrap();
+    write_template(
+        root,
+        "rss.xml",
+        include_str!("../../themes/bckt3/templates/rss.xml"),
+    );
 }
 
 #[test]
-fn rerenders_tag_index_when_post_changes() {
+fn feed_items_get_media_thumbnails() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
-    fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
-
-    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
-
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    write_podcast_post(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com/show\n",
     )
     .unwrap();
 
-    let tag_path = root.join("html/tags/shared/index.html");
-    let first_mtime = file_mtime(&tag_path);
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
 
-    wait_for_filesystem_tick();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("xmlns:media=\"http://search.yahoo.com/mrss/\""));
+    assert!(feed.contains(
+        "<media:thumbnail url=\"https://example.com/show/2024/03/01/episode-1/cover.png\"/>"
+    ));
 
+    // Without an image attachment the front matter `image` is used instead.
     fs::write(
-            root.join("posts/alpha/post.md"),
-            "---\ntitle: Alpha Updated\ndate: 2024-01-01T00:00:00Z\nslug: alpha\ntags:\n  - shared\n---\nUpdated",
-        )
-        .unwrap();
-
-    wait_for_filesystem_tick();
-
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Changed,
-            verbose: false,
-        },
+        root.join("posts/episode-1/post.md"),
+        "---\ntitle: Episode 1\ndate: 2024-03-01T00:00:00Z\nimage: art.jpg\n---\nShow notes",
     )
     .unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains(
+        "<media:thumbnail url=\"https://example.com/show/2024/03/01/episode-1/art.jpg\"/>"
+    ));
 
-    let second_mtime = file_mtime(&tag_path);
-    assert!(second_mtime > first_mtime);
+    fs::write(
+        root.join("posts/episode-1/post.md"),
+        "---\ntitle: Episode 1\ndate: 2024-03-01T00:00:00Z\n---\nShow notes",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(!feed.contains("xmlns:media"));
+    assert!(!feed.contains("media:thumbnail"));
 }
 
 #[test]
-fn removes_tag_index_when_tag_disappears() {
+fn feed_items_get_enclosures_for_media_attachments() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
-    fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
-
-    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
-
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    write_podcast_post(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Cast\nbase_url: https://example.com/show\npodcast:\n  author: Jane & Co\n  image: /cover.jpg\n  category: Technology\n",
     )
     .unwrap();
 
-    let tag_path = root.join("html/tags/shared/index.html");
-    assert!(tag_path.exists());
-
-    wait_for_filesystem_tick();
-
-    fs::remove_dir_all(root.join("posts/alpha")).unwrap();
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
 
-    wait_for_filesystem_tick();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("xmlns:itunes=\"http://www.itunes.com/dtds/podcast-1.0.dtd\""));
+    assert!(feed.contains("<itunes:author>Jane &amp; Co</itunes:author>"));
+    assert!(feed.contains("<itunes:image href=\"https://example.com/show/cover.jpg\"/>"));
+    assert!(feed.contains("<itunes:category text=\"Technology\"/>"));
+    assert!(feed.contains(
+        "<enclosure url=\"https://example.com/show/2024/03/01/episode-1/audio/episode%201.mp3\" length=\"1234\" type=\"audio/mpeg\"/>"
+    ));
+    assert_eq!(feed.matches("<enclosure").count(), 1);
 
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Changed,
-            verbose: false,
-        },
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Cast\nbase_url: https://example.com/show\nfeeds:\n  multiple_enclosures: true\n",
     )
     .unwrap();
+    render_site(root, plan).unwrap();
 
-    assert!(!tag_path.exists());
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(!feed.contains("itunes"));
+    assert_eq!(feed.matches("<enclosure").count(), 2);
+    assert!(feed.contains("length=\"99\" type=\"video/mp4\""));
+    assert!(!feed.contains("cover.png\" length"));
 }
 
 #[test]
-fn skips_rewriting_archives_when_unchanged() {
+fn generates_tag_rss_feeds_when_configured() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
-
-    write_dated_post(root, "alpha", "2024-02-01T00:00:00Z", "A");
-
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Demo Site\nbase_url: \"https://example.com\"\nrss_tags:\n  - shared\n",
     )
     .unwrap();
 
-    let year_path = root.join("html/2024/index.html");
-    let month_path = root.join("html/2024/02/index.html");
-    let first_year_mtime = file_mtime(&year_path);
-    let first_month_mtime = file_mtime(&month_path);
-
-    wait_for_filesystem_tick();
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "other", "2024-02-01T00:00:00Z", "B");
 
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Changed,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
 
-    let second_year_mtime = file_mtime(&year_path);
-    let second_month_mtime = file_mtime(&month_path);
-
-    assert_eq!(first_year_mtime, second_year_mtime);
-    assert_eq!(first_month_mtime, second_month_mtime);
+    let feed_path = root.join("html/rss-shared.xml");
+    assert!(feed_path.exists());
+    let feed = fs::read_to_string(feed_path).unwrap();
+    assert!(feed.contains("shared · Demo Site"));
+    assert!(feed.contains("/2024/01/01/alpha/"));
+    assert!(!feed.contains("/2024/02/01/beta/"));
 }
 
-#[test]
-fn rerenders_archives_when_post_changes() {
+/// Two posts tagged `C++` and one `C#`, which both slug to `c`.
+fn render_colliding_tags(collisions: &str) -> (TempDir, Result<RenderReport>) {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
-    fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
-
-    write_dated_post(root, "alpha", "2024-03-01T00:00:00Z", "Original");
-
-    render_site(
+    write_template(
         root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+        "post.html",
+        "{% for tag in post.tag_links %}<a href=\"{{ tag.url | safe }}\">{{ tag.name }}</a>{% endfor %}",
+    );
+    write_template(
+        root,
+        "stats.html",
+        "{% for tag in stats.tags %}{{ tag.name }}={{ tag.url | safe }};{% endfor %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        format!("rss_tags: [\"C#\"]\ntag_slug_collisions: {collisions}\n"),
     )
     .unwrap();
+    write_tagged_post(root, "one", "C++", "2024-01-01T00:00:00Z", "One");
+    write_tagged_post(root, "two", "\"C#\"", "2024-01-02T00:00:00Z", "Two");
+    write_tagged_post(root, "three", "c++", "2024-01-03T00:00:00Z", "Three");
 
-    let year_path = root.join("html/2024/index.html");
-    let month_path = root.join("html/2024/03/index.html");
-    let first_year_mtime = file_mtime(&year_path);
-    let first_month_mtime = file_mtime(&month_path);
-
-    wait_for_filesystem_tick();
-
-    fs::write(
-            root.join("posts/alpha/post.md"),
-            "---\ntitle: Alpha\ndate: 2024-03-01T00:00:00Z\nslug: alpha\ntags:\n  - alpha\n---\nUpdated body",
-        )
-        .unwrap();
-
-    wait_for_filesystem_tick();
-
-    render_site(
+    let report = render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
             mode: BuildMode::Changed,
-            verbose: false,
+            ..RenderPlan::default()
         },
-    )
-    .unwrap();
+    );
+    (temp, report)
+}
 
-    let second_year_mtime = file_mtime(&year_path);
-    let second_month_mtime = file_mtime(&month_path);
+#[test]
+fn colliding_tag_slugs_merge_under_the_most_used_name() {
+    let (temp, report) = render_colliding_tags("merge");
+    report.unwrap();
+    let html = temp.path().join("html");
+
+    let archive = fs::read_to_string(html.join("tags/c/index.html")).unwrap();
+    assert!(archive.contains("data-tag=\"C++\""), "{archive}");
+    for slug in ["one", "two", "three"] {
+        assert!(archive.contains(&format!("data-slug=\"{slug}\"")));
+    }
+    let post = fs::read_to_string(html.join("2024/01/02/two/index.html")).unwrap();
+    assert_eq!(post, "<a href=\"/tags/c/\">C++</a>");
+    let feed = fs::read_to_string(html.join("rss-c.xml")).unwrap();
+    assert!(feed.contains("/2024/01/01/one/") && feed.contains("/2024/01/02/two/"));
+    let stats = fs::read_to_string(html.join("stats/index.html")).unwrap();
+    assert_eq!(stats, "C++=/tags/c/;");
+    assert!(!html.join("tags/c-1").exists());
+}
 
-    assert!(second_year_mtime > first_year_mtime);
-    assert!(second_month_mtime > first_month_mtime);
+#[test]
+fn colliding_tag_slugs_can_be_disambiguated() {
+    let (temp, report) = render_colliding_tags("disambiguate");
+    report.unwrap();
+    let html = temp.path().join("html");
+
+    let archive = fs::read_to_string(html.join("tags/c/index.html")).unwrap();
+    assert!(archive.contains("data-tag=\"C++\""), "{archive}");
+    assert!(!archive.contains("data-slug=\"two\""));
+    let archive = fs::read_to_string(html.join("tags/c-1/index.html")).unwrap();
+    assert!(archive.contains("data-tag=\"C#\""), "{archive}");
+    assert!(archive.contains("data-slug=\"two\""));
+
+    let post = fs::read_to_string(html.join("2024/01/02/two/index.html")).unwrap();
+    assert_eq!(post, "<a href=\"/tags/c-1/\">C#</a>");
+    let post = fs::read_to_string(html.join("2024/01/03/three/index.html")).unwrap();
+    assert_eq!(post, "<a href=\"/tags/c/\">C++</a>");
+    let feed = fs::read_to_string(html.join("rss-c-1.xml")).unwrap();
+    assert!(feed.contains("/2024/01/02/two/") && !feed.contains("/2024/01/01/one/"));
+    assert!(!html.join("rss-c.xml").exists());
+    let sitemap = fs::read_to_string(html.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/tags/c/") && sitemap.contains("/tags/c-1/"));
+    let stats = fs::read_to_string(html.join("stats/index.html")).unwrap();
+    assert_eq!(stats, "C++=/tags/c/;C#=/tags/c-1/;");
 }
 
 #[test]
-fn removes_archives_when_posts_are_removed() {
+fn colliding_tag_slugs_can_fail_the_render() {
+    let (temp, report) = render_colliding_tags("error");
+    assert!(report.is_err());
+    assert!(!temp.path().join("html/tags").exists());
+
+    let issues = validate_site(temp.path(), None).unwrap();
+    assert!(issues.iter().any(|issue| {
+        issue.level == Level::Error
+            && issue
+                .message
+                .starts_with("tags 'C++', 'C#' share the slug 'c'")
+    }));
+}
+
+#[test]
+fn feeds_use_configured_descriptions() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
+    write_tagged_post(root, "alpha", "Shared", "2024-01-01T00:00:00Z", "A");
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
 
-    write_dated_post(root, "alpha", "2024-04-01T00:00:00Z", "Body");
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Demo Site\nrss_tags: [Shared]\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<description>Demo Site</description>"));
 
-    render_site(
-        root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
-        },
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Demo Site\ndescription: Notes & links\nrss_tags: [Shared]\nfeeds:\n  tag_descriptions:\n    shared: Everything shared\n",
     )
     .unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<title>Demo Site</title>"));
+    assert!(feed.contains("<description>Notes &amp; links</description>"));
+    let tag_feed = fs::read_to_string(root.join("html/rss-shared.xml")).unwrap();
+    assert!(tag_feed.contains("<title>Shared · Demo Site</title>"));
+    assert!(tag_feed.contains("<description>Everything shared</description>"));
+}
 
-    let year_path = root.join("html/2024/index.html");
-    let month_path = root.join("html/2024/04/index.html");
-    assert!(year_path.exists());
-    assert!(month_path.exists());
+#[test]
+fn unchanged_rebuild_hits_the_cache_for_every_post() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(root.join("bckt.yaml"), "title: Demo Site\n").unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "A");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "B");
+    let mut plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
 
-    wait_for_filesystem_tick();
+    let first = render_site(root, plan).unwrap().cache;
+    assert!(!first.incremental);
+    assert_eq!(first.posts, CacheCounts { hits: 0, misses: 2 });
+
+    plan.mode = BuildMode::Changed;
+    let cache = render_site(root, plan).unwrap().cache;
+    assert!(cache.incremental);
+    assert_eq!(cache.posts, CacheCounts { hits: 2, misses: 0 });
+    assert_eq!(cache.posts.hit_ratio(), Some(1.0));
+    assert_eq!(cache.tags.misses, 0);
+    assert!(cache.tags.hits >= 2);
+    assert_eq!(cache.archives.misses, 0);
+    // The index records when it was generated, so it is rewritten each time.
+    assert_eq!(cache.search.total(), 1);
+
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "B, edited");
+    let cache = render_site(root, plan).unwrap().cache;
+    assert_eq!(cache.posts, CacheCounts { hits: 1, misses: 1 });
+}
 
-    fs::remove_dir_all(root.join("posts/alpha")).unwrap();
+#[test]
+fn rss_tags_accept_names_and_objects() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("bckt.yaml");
+    fs::write(&path, "rss_tags: \"rust, web\"\n").unwrap();
+    let config = Config::load(&path).unwrap();
+    let tags: Vec<String> = feeds::config_tag_feeds(&config)
+        .into_iter()
+        .map(|feed| feed.tag)
+        .collect();
+    assert_eq!(tags, ["rust", "web"]);
 
-    wait_for_filesystem_tick();
+    fs::write(
+        &path,
+        "rss_tags:\n  - web\n  - tag: ελληνικά\n    language: el\n    title: Ελληνικά\n  - tag: web\n    language: fr\n  - language: de\n",
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+    assert_eq!(
+        feeds::config_tag_feeds(&config),
+        [
+            feeds::TagFeed {
+                tag: "web".to_string(),
+                language: None,
+                title: None,
+            },
+            feeds::TagFeed {
+                tag: "ελληνικά".to_string(),
+                language: Some("el".to_string()),
+                title: Some("Ελληνικά".to_string()),
+            },
+        ]
+    );
+}
 
-    render_site(
+#[test]
+fn feeds_declare_their_language() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
         root,
-        RenderPlan {
-            posts: true,
-            static_assets: false,
-            mode: BuildMode::Changed,
-            verbose: false,
-        },
+        "rss.xml",
+        include_str!("../../themes/bckt3/templates/rss.xml"),
+    );
+    write_tagged_post(root, "alpha", "ελληνικά", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "rust", "2024-02-01T00:00:00Z", "B");
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+
+    fs::write(root.join("bckt.yaml"), "title: Demo Site\n").unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<language>en</language>"), "{feed}");
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Demo Site\nfeeds:\n  language: en-GB\nrss_tags:\n  - rust\n  - tag: ελληνικά\n    language: el\n    title: Ελληνικά & more\n",
     )
     .unwrap();
-
-    assert!(!year_path.exists());
-    assert!(!month_path.exists());
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<language>en-GB</language>"));
+    let rust = fs::read_to_string(root.join("html/rss-rust.xml")).unwrap();
+    assert!(rust.contains("<language>en-GB</language>"));
+    assert!(rust.contains("<title>rust · Demo Site</title>"));
+    let greek = fs::read_dir(root.join("html"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            name.starts_with("rss-") && name != "rss-rust.xml"
+        })
+        .unwrap();
+    let greek = fs::read_to_string(greek).unwrap();
+    assert!(greek.contains("<language>el</language>"), "{greek}");
+    assert!(greek.contains("<title>Ελληνικά &amp; more</title>"));
 }
 
 #[test]
-fn renders_year_and_month_archives() {
+fn keeps_relative_paths_in_html_and_absolute_in_feeds() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
-    fs::create_dir_all(root.join("posts")).unwrap();
+    fs::create_dir_all(root.join("posts/media/images")).unwrap();
     setup_markdown_templates(root);
-
-    write_dated_post(root, "jan", "2023-01-01T00:00:00Z", "Old");
-    write_dated_post(root, "feb", "2024-02-01T00:00:00Z", "Mid");
-    write_dated_post(root, "mar", "2024-03-01T00:00:00Z", "New");
+    fs::write(root.join("posts/media/images/pic.png"), "image-bytes").unwrap();
+    fs::write(root.join("posts/media/notes.txt"), "notes").unwrap();
+    fs::write(
+            root.join("posts/media/post.md"),
+            "---\ndate: 2024-01-01T00:00:00Z\nattached:\n  - images/pic.png\n  - notes.txt\n---\n![Alt](images/pic.png)\n\n[Download](notes.txt)\n",
+        )
+        .unwrap();
 
     render_site(
         root,
         RenderPlan {
-            posts: true,
             static_assets: false,
-            mode: BuildMode::Full,
-            verbose: false,
+            ..RenderPlan::default()
         },
     )
     .unwrap();
 
-    assert!(root.join("html/2024/index.html").exists());
-    assert!(root.join("html/2024/03/index.html").exists());
-    assert!(root.join("html/2023/index.html").exists());
+    let post_page = fs::read_to_string(root.join("html/2024/01/01/media/index.html")).unwrap();
+    // HTML pages use relative paths (works regardless of base_url)
+    assert!(post_page.contains("images/pic.png"));
+    assert!(post_page.contains("notes.txt"));
+    // Should not contain absolute paths
+    assert!(!post_page.contains("/2024/01/01/media/images/pic.png"));
+    assert!(!post_page.contains("/2024/01/01/media/notes.txt"));
+
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    // RSS feeds use absolute URLs (required for feed readers)
+    assert!(feed.contains("/2024/01/01/media/images/pic.png"));
+    assert!(feed.contains("/2024/01/01/media/notes.txt"));
 }
 
 #[test]
-fn incremental_rebuilds_only_changed_post() {
+fn rewrites_each_post_body_once_per_build() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
     setup_markdown_templates(root);
+    write_template(
+        root,
+        "index.html",
+        "{% for post in posts %}<article>{{ post.body | safe }}</article>{% endfor %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 1
+rss_tags: [shared]
+",
+    )
+    .unwrap();
+    for slug in ["first", "second"] {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("pic.png"), "image-bytes").unwrap();
+        fs::write(
+            dir.join("post.md"),
+            format!(
+                "---\ndate: 2024-01-0{}T00:00:00Z\nslug: {slug}\ntags: [shared]\nfeatured: true\nattached: [pic.png]\n---\n![Alt](pic.png)\n",
+                if slug == "first" { 1 } else { 2 }
+            ),
+        )
+        .unwrap();
+    }
+    let plan = |listing_full_content| {
+        let config = if listing_full_content {
+            "homepage_posts: 1\nrss_tags: [shared]\n"
+        } else {
+            "homepage_posts: 1\nrss_tags: [shared]\nlisting_full_content: false\n"
+        };
+        fs::write(root.join("bckt.yaml"), config).unwrap();
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        }
+    };
 
-    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
-    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
+    // Each post is on its page, a homepage page, its tag and archive pages,
+    // the featured list, and two feeds.
+    let (report, counts) = posts::rewrites::count(|| render_site(root, plan(true)));
+    report.unwrap();
+    assert_eq!(counts.len(), 4, "{counts:?}");
+    assert!(counts.values().all(|&count| count == 1), "{counts:?}");
+    let homepage = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(homepage.contains("<img src=\"pic.png\""));
 
-    let alpha_output = root.join("html/2024/01/01/alpha/index.html");
-    let beta_output = root.join("html/2024/02/01/beta/index.html");
+    let (report, counts) = posts::rewrites::count(|| render_site(root, plan(false)));
+    report.unwrap();
+    assert!(counts.values().all(|&count| count == 1), "{counts:?}");
+    let homepage = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert_eq!(homepage, "<article></article>");
+    let feed = fs::read_to_string(root.join("html/rss-shared.xml")).unwrap();
+    assert!(feed.contains("/2024/01/02/second/pic.png"));
+}
 
-    let full_plan = RenderPlan {
-        posts: true,
+#[test]
+fn feed_item_titles_and_descriptions_are_xml_escaped() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "rss.xml",
+        include_str!("../../themes/bckt3/templates/rss.xml"),
+    );
+    fs::write(root.join("bckt.yaml"), "title: Demo Site\n").unwrap();
+    let dir = root.join("posts/fish");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ntitle: Fish & chips\ndate: 2024-01-01T00:00:00Z\nabstract: \"Fish & <chips>\"\n---\nBody\n",
+    )
+    .unwrap();
+    let plan = RenderPlan {
         static_assets: false,
-        mode: BuildMode::Full,
-        verbose: false,
+        ..RenderPlan::default()
     };
-    let changed_plan = RenderPlan {
-        posts: true,
+    render_site(root, plan).unwrap();
+
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    let document = roxmltree::Document::parse(&feed).unwrap();
+    let item = document
+        .descendants()
+        .find(|node| node.has_tag_name("item"))
+        .unwrap();
+    let text = |name: &str| {
+        item.children()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(text("title"), "Fish & chips");
+    assert_eq!(text("description"), "Fish & <chips>");
+}
+
+#[test]
+fn descriptions_match_across_meta_tags_and_feeds() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "<meta property=\"og:description\" content=\"{{ post.description }}\">",
+    );
+    write_template(
+        root,
+        "rss.xml",
+        "{% for item in feed.items %}<description>{{ item.description }}</description>{% endfor %}",
+    );
+    fs::write(root.join("bckt.yaml"), "description: About the site\n").unwrap();
+    let dir = root.join("posts/plain");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\nJust a body, with no abstract.\n",
+    )
+    .unwrap();
+    let plan = RenderPlan {
         static_assets: false,
-        mode: BuildMode::Changed,
-        verbose: false,
+        ..RenderPlan::default()
+    };
+    let descriptions = || {
+        let page = fs::read_to_string(root.join("html/2024/01/01/plain/index.html")).unwrap();
+        let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+        let meta = page
+            .strip_prefix("<meta property=\"og:description\" content=\"")
+            .and_then(|rest| rest.strip_suffix("\">"))
+            .unwrap()
+            .to_string();
+        let item = feed
+            .strip_prefix("<description>")
+            .and_then(|rest| rest.strip_suffix("</description>"))
+            .unwrap()
+            .to_string();
+        (meta, item)
     };
 
-    render_site(root, full_plan).unwrap();
+    render_site(root, plan).unwrap();
+    let (meta, item) = descriptions();
+    assert_eq!(meta, "Just a body, with no abstract.");
+    assert_eq!(meta, item);
 
-    let alpha_first = file_mtime(&alpha_output);
-    let beta_first = file_mtime(&beta_output);
+    // A body without text falls back to the site description.
+    fs::write(
+        dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\n---\n<img src=\"cover.jpg\">\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        descriptions(),
+        ("About the site".to_string(), "About the site".to_string())
+    );
 
-    wait_for_filesystem_tick();
-    render_site(root, changed_plan).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nabstract: The abstract\n---\nBody\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        descriptions(),
+        ("The abstract".to_string(), "The abstract".to_string())
+    );
+}
 
-    let alpha_second = file_mtime(&alpha_output);
-    let beta_second = file_mtime(&beta_output);
-    assert_eq!(alpha_first, alpha_second);
-    assert_eq!(beta_first, beta_second);
+#[test]
+fn generates_sitemap_with_posts_tags_and_pages() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: \"https://example.com/blog\"\nhomepage_posts: 1\npaginate_tags: true\n",
+    )
+    .unwrap();
 
-    wait_for_filesystem_tick();
-    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha updated");
-    render_site(root, changed_plan).unwrap();
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "beta", "shared", "2024-02-01T00:00:00Z", "B");
+    write_tagged_post(root, "gamma", "shared", "2024-03-01T00:00:00Z", "C");
 
-    let alpha_third = file_mtime(&alpha_output);
-    let beta_third = file_mtime(&beta_output);
-    assert!(alpha_third > alpha_second);
-    assert_eq!(beta_second, beta_third);
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("<loc>https://example.com/blog/</loc>"));
+
+    // Page-number based URLs (page 1 = oldest, page 2 = middle)
+    assert!(sitemap.contains("<loc>https://example.com/blog/page/1/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/blog/page/2/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/blog/tags/shared/</loc>"));
+    assert!(sitemap.contains("<loc>https://example.com/blog/2024/03/01/gamma/</loc>"));
+    assert!(sitemap.contains("<lastmod>2024-03-01T00:00:00Z</lastmod>"));
 }
 
 #[test]
-fn template_change_triggers_full_rebuild() {
+fn skips_rewriting_tag_index_when_unchanged() {
     let temp = TempDir::new().unwrap();
     let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
     setup_markdown_templates(root);
 
-    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
-    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
-
-    let alpha_output = root.join("html/2024/01/01/alpha/index.html");
-    let beta_output = root.join("html/2024/02/01/beta/index.html");
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
 
-    let full_plan = RenderPlan {
-        posts: true,
-        static_assets: false,
-        mode: BuildMode::Full,
-        verbose: false,
-    };
-    let changed_plan = RenderPlan {
-        posts: true,
-        static_assets: false,
-        mode: BuildMode::Changed,
-        verbose: false,
-    };
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
 
-    render_site(root, full_plan).unwrap();
-    let alpha_initial = file_mtime(&alpha_output);
-    let beta_initial = file_mtime(&beta_output);
+    let tag_path = root.join("html/tags/shared/index.html");
+    assert!(tag_path.exists());
+    let first_mtime = file_mtime(&tag_path);
 
     wait_for_filesystem_tick();
-    render_site(root, changed_plan).unwrap();
-    let alpha_after_changed = file_mtime(&alpha_output);
-    let beta_after_changed = file_mtime(&beta_output);
-    assert_eq!(alpha_initial, alpha_after_changed);
-    assert_eq!(beta_initial, beta_after_changed);
 
-    wait_for_filesystem_tick();
-    write_template(
+    render_site(
         root,
-        "base.html",
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let second_mtime = file_mtime(&tag_path);
+    assert_eq!(first_mtime, second_mtime);
+}
+
+#[test]
+fn rerenders_tag_index_when_post_changes() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let tag_path = root.join("html/tags/shared/index.html");
+    let first_mtime = file_mtime(&tag_path);
+
+    wait_for_filesystem_tick();
+
+    fs::write(
+            root.join("posts/alpha/post.md"),
+            "---\ntitle: Alpha Updated\ndate: 2024-01-01T00:00:00Z\nslug: alpha\ntags:\n  - shared\n---\nUpdated",
+        )
+        .unwrap();
+
+    wait_for_filesystem_tick();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let second_mtime = file_mtime(&tag_path);
+    assert!(second_mtime > first_mtime);
+}
+
+#[test]
+fn tag_aliases_merge_pages_and_redirect_old_urls() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    let aliased =
+        "base_url: https://example.com\ntag_aliases:\n  rustlang: rust\n  rust-lang: rust\n";
+    fs::write(root.join("bckt.yaml"), aliased).unwrap();
+
+    write_tagged_post(root, "one", "rustlang", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "two", "rust-lang", "2024-01-02T00:00:00Z", "B");
+    write_tagged_post(root, "three", "Rust", "2024-01-03T00:00:00Z", "C");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let merged = fs::read_to_string(root.join("html/tags/rust/index.html")).unwrap();
+    assert_eq!(merged.matches("<article data-slug=").count(), 3);
+    for alias in ["rustlang", "rust-lang"] {
+        let stub = fs::read_to_string(root.join(format!("html/tags/{alias}/index.html"))).unwrap();
+        assert!(
+            stub.contains(
+                "http-equiv=\"refresh\" content=\"0; url=https://example.com/tags/rust/\""
+            ),
+            "{stub}"
+        );
+    }
+
+    // Dropping an alias turns its stub back into a regular tag page.
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\ntag_aliases:\n  rust-lang: rust\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+
+    let restored = fs::read_to_string(root.join("html/tags/rustlang/index.html")).unwrap();
+    assert!(restored.contains("<article data-slug=\"one\">"));
+    let merged = fs::read_to_string(root.join("html/tags/rust/index.html")).unwrap();
+    assert_eq!(merged.matches("<article data-slug=").count(), 2);
+}
+
+#[test]
+fn posts_json_lists_visible_posts() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nposts_json: true\n",
+    )
+    .unwrap();
+    write_tagged_post(root, "older", "rust", "2024-01-01T00:00:00Z", "First body");
+    write_tagged_post(
+        root,
+        "newer",
+        "notes",
+        "2024-02-01T00:00:00Z",
+        "Second body",
+    );
+    write_tagged_post(
+        root,
+        "drafts/hidden",
+        "rust",
+        "2024-03-01T00:00:00Z",
+        "Hidden",
+    );
+    fs::write(root.join("posts/drafts/.bcktignore"), "").unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let output = root.join("html/posts.json");
+    let listing: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(
+        listing,
+        serde_json::json!([
+            {
+                "title": "newer",
+                "permalink": "/2024/02/01/newer/",
+                "date_iso": "2024-02-01T00:00:00Z",
+                "tags": ["notes"],
+                "type": null,
+                "excerpt": "Second body"
+            },
+            {
+                "title": "older",
+                "permalink": "/2024/01/01/older/",
+                "date_iso": "2024-01-01T00:00:00Z",
+                "tags": ["rust"],
+                "type": null,
+                "excerpt": "First body"
+            }
+        ])
+    );
+
+    fs::write(root.join("bckt.yaml"), "base_url: https://example.com\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!output.exists());
+}
+
+#[test]
+fn shrinking_page_count_removes_stale_page_directories() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nhomepage_posts: 2\n",
+    )
+    .unwrap();
+    for day in 1..=7 {
+        write_dated_post(
+            root,
+            &format!("post-{day}"),
+            &format!("2024-01-0{day}T00:00:00Z"),
+            "Body",
+        );
+    }
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    assert!(root.join("html/page/1/index.html").exists());
+    assert!(root.join("html/page/2/index.html").exists());
+
+    // Seven posts fit on a homepage of 5 to 9, so no numbered pages remain.
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nhomepage_posts: 5\n",
+    )
+    .unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!root.join("html/page").exists());
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(!sitemap.contains("/page/"), "{sitemap}");
+
+    // Static-only renders also prune directories the homepage does not link.
+    fs::create_dir_all(root.join("html/page/3")).unwrap();
+    fs::write(root.join("html/page/3/index.html"), "stale").unwrap();
+    render_site(
+        root,
+        RenderPlan {
+            posts: false,
+            static_assets: true,
+            ..plan
+        },
+    )
+    .unwrap();
+    assert!(!root.join("html/page/3").exists());
+}
+
+/// The homepage, tag, and archive listings of `new_posts_leave_full_listing_pages_alone`.
+const LISTING_DIRS: [&str; 4] = ["html", "html/tags/shared", "html/2024", "html/2024/01"];
+
+/// Replaces the index and numbered pages of the listing in `dir` with a
+/// marker, so a later render shows which of them it wrote.
+fn freeze_listing_pages(dir: &Path) -> Vec<PathBuf> {
+    let mut frozen = vec![dir.join("index.html")];
+    for entry in fs::read_dir(dir.join("page")).unwrap() {
+        frozen.push(entry.unwrap().path().join("index.html"));
+    }
+    for path in &frozen {
+        fs::write(path, "frozen").unwrap();
+    }
+    frozen
+}
+
+fn rewritten(frozen: &[PathBuf]) -> Vec<&PathBuf> {
+    frozen
+        .iter()
+        .filter(|path| fs::read_to_string(path).unwrap() != "frozen")
+        .collect()
+}
+
+#[test]
+fn new_posts_leave_full_listing_pages_alone() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 2\npaginate_tags: true\narchive_per_page: 2\n",
+    )
+    .unwrap();
+    for day in 1..=6 {
+        let slug = format!("post-{day}");
+        let date = format!("2024-01-0{day}T00:00:00Z");
+        write_tagged_post(root, &slug, "shared", &date, "Body");
+    }
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    // Six posts: pages 1 and 2 are full and the index holds two posts, so a
+    // seventh post only changes each index.
+    let frozen: Vec<_> = LISTING_DIRS
+        .iter()
+        .map(|dir| freeze_listing_pages(&root.join(dir)))
+        .collect();
+    write_tagged_post(root, "post-7", "shared", "2024-01-07T00:00:00Z", "Body");
+    render_site(root, plan).unwrap();
+    for pages in &frozen {
+        assert_eq!(pages.len(), 3);
+        assert_eq!(rewritten(pages), [&pages[0]]);
+    }
+
+    // The eighth post fills a new page 3: page 2 gains a link to it and
+    // page 1 stays as it was.
+    let frozen: Vec<_> = LISTING_DIRS
+        .iter()
+        .map(|dir| freeze_listing_pages(&root.join(dir)))
+        .collect();
+    write_tagged_post(root, "post-8", "shared", "2024-01-08T00:00:00Z", "Body");
+    render_site(root, plan).unwrap();
+    for (dir, pages) in LISTING_DIRS.iter().zip(&frozen) {
+        let dir = root.join(dir);
+        assert!(dir.join("page/3/index.html").exists());
+        let written = rewritten(pages);
+        assert_eq!(written.len(), 2, "{written:?}");
+        assert!(!written.contains(&&dir.join("page/1/index.html")));
+    }
+    let second = fs::read_to_string(root.join("html/page/2/index.html")).unwrap();
+    assert!(second.contains("data-next=\"/page/3/\""), "{second}");
+    let third = fs::read_to_string(root.join("html/page/3/index.html")).unwrap();
+    assert!(third.contains("article data-slug=\"post-6\""));
+    assert!(third.contains("data-next=\"/\""));
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(index.contains("data-prev=\"/page/3/\""));
+    assert!(index.contains("data-total=\"4\""));
+
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(
+        sitemap.contains(
+            "<loc>https://example.com/page/3/</loc>\n    <lastmod>2024-01-06T00:00:00Z</lastmod>"
+        ),
+        "{sitemap}"
+    );
+}
+
+#[test]
+fn removes_tag_index_when_tag_disappears() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_tagged_post(root, "alpha", "shared", "2024-01-01T00:00:00Z", "A");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let tag_path = root.join("html/tags/shared/index.html");
+    assert!(tag_path.exists());
+
+    wait_for_filesystem_tick();
+
+    fs::remove_dir_all(root.join("posts/alpha")).unwrap();
+
+    wait_for_filesystem_tick();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!tag_path.exists());
+}
+
+#[test]
+fn skips_rewriting_archives_when_unchanged() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "alpha", "2024-02-01T00:00:00Z", "A");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let year_path = root.join("html/2024/index.html");
+    let month_path = root.join("html/2024/02/index.html");
+    let first_year_mtime = file_mtime(&year_path);
+    let first_month_mtime = file_mtime(&month_path);
+
+    wait_for_filesystem_tick();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let second_year_mtime = file_mtime(&year_path);
+    let second_month_mtime = file_mtime(&month_path);
+
+    assert_eq!(first_year_mtime, second_year_mtime);
+    assert_eq!(first_month_mtime, second_month_mtime);
+}
+
+#[test]
+fn archive_levels_choose_the_date_archives() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    write_dated_post(root, "alpha", "2024-02-01T00:00:00Z", "A");
+    let plan = || RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+
+    render_site(root, plan()).unwrap();
+    assert!(root.join("html/2024/02/index.html").exists());
+    assert!(!root.join("html/2024/02/01/index.html").exists());
+
+    fs::write(root.join("bckt.yaml"), "archive_levels: year\n").unwrap();
+    render_site(root, plan()).unwrap();
+    assert!(root.join("html/2024/index.html").exists());
+    assert!(!root.join("html/2024/02/index.html").exists());
+    assert!(root.join("html/2024/02/01/alpha/index.html").exists());
+
+    fs::write(root.join("bckt.yaml"), "archive_levels: year_month_day\n").unwrap();
+    render_site(root, plan()).unwrap();
+    let day = fs::read_to_string(root.join("html/2024/02/01/index.html")).unwrap();
+    assert!(day.contains("data-month=\"2\""));
+    assert!(day.contains("data-slug=\"alpha\""));
+    assert!(root.join("html/2024/02/index.html").exists());
+}
+
+#[test]
+fn rerenders_archives_when_post_changes() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "alpha", "2024-03-01T00:00:00Z", "Original");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let year_path = root.join("html/2024/index.html");
+    let month_path = root.join("html/2024/03/index.html");
+    let first_year_mtime = file_mtime(&year_path);
+    let first_month_mtime = file_mtime(&month_path);
+
+    wait_for_filesystem_tick();
+
+    fs::write(
+            root.join("posts/alpha/post.md"),
+            "---\ntitle: Alpha\ndate: 2024-03-01T00:00:00Z\nslug: alpha\ntags:\n  - alpha\n---\nUpdated body",
+        )
+        .unwrap();
+
+    wait_for_filesystem_tick();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let second_year_mtime = file_mtime(&year_path);
+    let second_month_mtime = file_mtime(&month_path);
+
+    assert!(second_year_mtime > first_year_mtime);
+    assert!(second_month_mtime > first_month_mtime);
+}
+
+#[test]
+fn removes_archives_when_posts_are_removed() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "alpha", "2024-04-01T00:00:00Z", "Body");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let year_path = root.join("html/2024/index.html");
+    let month_path = root.join("html/2024/04/index.html");
+    assert!(year_path.exists());
+    assert!(month_path.exists());
+
+    wait_for_filesystem_tick();
+
+    fs::remove_dir_all(root.join("posts/alpha")).unwrap();
+
+    wait_for_filesystem_tick();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    assert!(!year_path.exists());
+    assert!(!month_path.exists());
+}
+
+#[test]
+fn renders_year_and_month_archives() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "jan", "2023-01-01T00:00:00Z", "Old");
+    write_dated_post(root, "feb", "2024-02-01T00:00:00Z", "Mid");
+    write_dated_post(root, "mar", "2024-03-01T00:00:00Z", "New");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    assert!(root.join("html/2024/index.html").exists());
+    assert!(root.join("html/2024/03/index.html").exists());
+    assert!(root.join("html/2023/index.html").exists());
+}
+
+#[test]
+fn incremental_rebuilds_only_changed_post() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
+
+    let alpha_output = root.join("html/2024/01/01/alpha/index.html");
+    let beta_output = root.join("html/2024/02/01/beta/index.html");
+
+    let full_plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    let changed_plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+
+    render_site(root, full_plan).unwrap();
+
+    let alpha_first = file_mtime(&alpha_output);
+    let beta_first = file_mtime(&beta_output);
+
+    wait_for_filesystem_tick();
+    render_site(root, changed_plan).unwrap();
+
+    let alpha_second = file_mtime(&alpha_output);
+    let beta_second = file_mtime(&beta_output);
+    assert_eq!(alpha_first, alpha_second);
+    assert_eq!(beta_first, beta_second);
+
+    wait_for_filesystem_tick();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha updated");
+    render_site(root, changed_plan).unwrap();
+
+    let alpha_third = file_mtime(&alpha_output);
+    let beta_third = file_mtime(&beta_output);
+    assert!(alpha_third > alpha_second);
+    assert_eq!(beta_second, beta_third);
+}
+
+#[test]
+fn template_change_triggers_full_rebuild() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Beta body");
+
+    let alpha_output = root.join("html/2024/01/01/alpha/index.html");
+    let beta_output = root.join("html/2024/02/01/beta/index.html");
+
+    let full_plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    let changed_plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+
+    render_site(root, full_plan).unwrap();
+    let alpha_initial = file_mtime(&alpha_output);
+    let beta_initial = file_mtime(&beta_output);
+
+    wait_for_filesystem_tick();
+    render_site(root, changed_plan).unwrap();
+    let alpha_after_changed = file_mtime(&alpha_output);
+    let beta_after_changed = file_mtime(&beta_output);
+    assert_eq!(alpha_initial, alpha_after_changed);
+    assert_eq!(beta_initial, beta_after_changed);
+
+    wait_for_filesystem_tick();
+    write_template(
+        root,
+        "base.html",
         "<!doctype html><html><body data-version=\"v2\">{% block content %}{% endblock %}</body></html>",
     );
-    render_site(root, changed_plan).unwrap();
+    render_site(root, changed_plan).unwrap();
+
+    let alpha_after_template = file_mtime(&alpha_output);
+    let beta_after_template = file_mtime(&beta_output);
+    assert!(alpha_after_template > alpha_after_changed);
+    assert!(beta_after_template > beta_after_changed);
+}
+
+#[test]
+fn markdown_option_change_triggers_full_rebuild() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(root.join("bckt.yaml"), "title: Smart\n").unwrap();
+
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "\"Quoted\" text");
+    let alpha_output = root.join("html/2024/01/01/alpha/index.html");
+
+    let changed_plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+
+    render_site(root, changed_plan).unwrap();
+    let plain = fs::read_to_string(&alpha_output).unwrap();
+    assert!(plain.contains("&quot;Quoted&quot;"), "{plain}");
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Smart\nmarkdown:\n  smart_punctuation: true\n",
+    )
+    .unwrap();
+    render_site(root, changed_plan).unwrap();
+    let smart = fs::read_to_string(&alpha_output).unwrap();
+    assert!(smart.contains("“Quoted”"), "{smart}");
+}
+
+#[test]
+fn quiet_render_still_writes_output() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_markdown_post(root, "Quiet body");
+
+    render_site(
+        root,
+        RenderPlan {
+            log: Logger::new(Level::Warn),
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    assert!(root.join("html/2024/01/02/hello-world/index.html").exists());
+    assert!(root.join("html/index.html").exists());
+    assert!(root.join("html/rss.xml").exists());
+}
+
+#[test]
+fn private_attachments_are_not_published() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "{% for name, meta in post.private_attachments | items %}<span data-private=\"{{ name | safe }}\" data-size=\"{{ meta.size }}\"></span>{% endfor %}",
+    );
+    let post_dir = root.join("posts/private-post");
+    fs::create_dir_all(post_dir.join("raw")).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [cover.jpg]\nattached_private: [raw/cover.dng]\n---\n![cover](cover.jpg) [raw](raw/cover.dng)",
+    )
+    .unwrap();
+    fs::write(post_dir.join("cover.jpg"), "jpeg").unwrap();
+    fs::write(post_dir.join("raw/cover.dng"), "raw-bytes").unwrap();
+
+    let (result, records) = crate::logging::capture::records(|| {
+        render_site(
+            root,
+            RenderPlan {
+                static_assets: false,
+                ..RenderPlan::default()
+            },
+        )
+    });
+    result.unwrap();
+    let warnings: Vec<_> = records
+        .iter()
+        .filter(|record| record.message.contains("private attachment"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{records:?}");
+    assert!(
+        warnings[0]
+            .message
+            .contains("body links to private attachment raw/cover.dng")
+    );
+
+    let output_dir = root.join("html/2024/01/01/private-post");
+    assert!(output_dir.join("cover.jpg").exists());
+    assert!(!output_dir.join("raw/cover.dng").exists());
+    assert!(!output_dir.join("raw").exists());
+
+    let rendered = fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(
+        rendered.contains("data-private=\"raw/cover.dng\""),
+        "{rendered}"
+    );
+    assert!(rendered.contains("data-size=\"9\""), "{rendered}");
+}
+
+#[test]
+fn detects_body_links_to_private_attachments() {
+    let private = vec![PathBuf::from("raw/cover.dng"), PathBuf::from("notes.md")];
+    let body = "<p><a href=\"./raw/cover.dng?dl=1\">raw</a><img src=\"cover.jpg\"><a href='https://example.com/notes.md'>x</a></p>";
+    let references = posts::private_references(body, &private);
+    assert_eq!(references, vec!["raw/cover.dng".to_string()]);
+
+    assert!(posts::private_references(body, &[]).is_empty());
+}
+
+#[test]
+fn attachments_meta_fills_alt_text_and_missing_alts_are_reported() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "{{ post.body | safe }}{% for name, meta in post.attachments | items %}<figcaption data-for=\"{{ name | safe }}\">{{ meta.caption }}</figcaption>{% endfor %}",
+    );
+    let post_dir = root.join("posts/photos");
+    fs::create_dir_all(post_dir.join("images")).unwrap();
+    fs::write(post_dir.join("images/pic.png"), b"png").unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\nattached: [images/pic.png]\nattachments_meta:\n  ./images/pic.png:\n    alt: A red barn\n    caption: Taken at dawn\n---\n![](images/pic.png)\n\n![](https://example.org/remote.jpg)\n",
+    )
+    .unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let (result, records) = crate::logging::capture::records(|| render_site(root, plan));
+    result.unwrap();
+
+    let page = fs::read_to_string(root.join("html/2024/01/01/photos/index.html")).unwrap();
+    assert!(
+        page.contains("<img src=\"images/pic.png\" alt=\"A red barn\">"),
+        "{page}"
+    );
+    assert!(page.contains("<figcaption data-for=\"images/pic.png\">Taken at dawn</figcaption>"));
+
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert_eq!(records[0].level, Level::Warn);
+    assert!(
+        records[0]
+            .message
+            .ends_with("image https://example.org/remote.jpg has no alt text"),
+        "{records:?}"
+    );
+    assert_eq!(
+        records[0].path.as_deref(),
+        Some(post_dir.join("post.md").as_path())
+    );
+}
+
+#[test]
+fn jsonld_is_completed_from_the_post_and_safe_in_a_script() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "<script type=\"application/ld+json\">{{ post.jsonld }}</script>",
+    );
+    let post_dir = root.join("posts/pie");
+    fs::create_dir_all(&post_dir).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ntitle: Apple Pie\ndate: 2024-01-02T03:04:05Z\njsonld:\n  \"@type\": Recipe\n  recipeYield: 8 slices\n  description: \"Bake </script><script>alert(1) & enjoy\"\n  recipeIngredient: [apples, flour]\n---\nBody",
+    )
+    .unwrap();
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let page = fs::read_to_string(root.join("html/2024/01/02/pie/index.html")).unwrap();
+    let json = page
+        .strip_prefix("<script type=\"application/ld+json\">")
+        .and_then(|rest| rest.strip_suffix("</script>"))
+        .unwrap_or_else(|| panic!("{page}"));
+    assert!(!json.contains('<') && !json.contains('&'), "{json}");
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "@type": "Recipe",
+            "recipeYield": "8 slices",
+            "description": "Bake </script><script>alert(1) & enjoy",
+            "recipeIngredient": ["apples", "flour"],
+            "url": "https://example.com/2024/01/02/pie/",
+            "datePublished": "2024-01-02T03:04:05Z",
+            "headline": "Apple Pie",
+        })
+    );
+}
+
+#[test]
+fn right_to_left_languages_get_dir_rtl() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "base.html",
+        include_str!("../../themes/bckt3/templates/base.html"),
+    );
+    write_template(
+        root,
+        "post.html",
+        "{% extends \"base.html\" %}{% block content %}<p data-dir=\"{{ post.dir }}\"></p>{% endblock %}",
+    );
+    for (slug, front_matter) in [
+        ("shalom", "language: he\n"),
+        ("hello", "language: en\n"),
+        ("mixed", "language: ar\ndir: ltr\n"),
+    ] {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("post.md"),
+            format!("---\ndate: 2024-01-01T00:00:00Z\n{front_matter}---\nBody"),
+        )
+        .unwrap();
+    }
+    let plan = RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    for (slug, lang, dir) in [
+        ("shalom", "he", "rtl"),
+        ("hello", "en", "ltr"),
+        ("mixed", "ar", "ltr"),
+    ] {
+        let page =
+            fs::read_to_string(root.join(format!("html/2024/01/01/{slug}/index.html"))).unwrap();
+        assert!(
+            page.contains(&format!("<html lang=\"{lang}\" dir=\"{dir}\">")),
+            "{page}"
+        );
+        assert!(page.contains(&format!("<p data-dir=\"{dir}\">")), "{page}");
+    }
+
+    fs::write(
+        root.join("posts/mixed/post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\ndir: sideways\n---\nBody",
+    )
+    .unwrap();
+    let error = format!("{:#}", render_site(root, plan).unwrap_err());
+    assert!(
+        error.contains("dir must be ltr, rtl, or auto, not 'sideways'"),
+        "{error}"
+    );
+}
+
+#[test]
+fn warnings_are_logged_even_when_quiet() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    let post_dir = root.join("posts/gallery");
+    fs::create_dir_all(&post_dir).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\ntype: gallery\n---\nPhotos.",
+    )
+    .unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let (result, records) = crate::logging::capture::records(|| render_site(root, plan));
+    result.unwrap();
+
+    assert_eq!(records.len(), 1, "{records:?}");
+    let warning = &records[0];
+    assert_eq!(warning.level, Level::Warn);
+    assert_eq!(warning.phase, "POST");
+    assert!(warning.message.contains("missing post-gallery.html"));
+    assert_eq!(
+        warning.path.as_deref(),
+        Some(post_dir.join("post.md").as_path())
+    );
+
+    let (result, records) = crate::logging::capture::records(|| {
+        render_site(
+            root,
+            RenderPlan {
+                log: Logger::default(),
+                ..plan
+            },
+        )
+    });
+    result.unwrap();
+    let phases: Vec<&str> = records.iter().map(|record| record.phase.as_str()).collect();
+    assert_eq!(phases, ["POST", "SUMMARY"]);
+}
+
+#[test]
+fn switching_profiles_triggers_full_rebuild() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(root, "post.html", "{{ base_url }}");
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nprofiles:\n  dev:\n    base_url: http://localhost:8000\n",
+    )
+    .unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
+    let output = root.join("html/2024/01/01/alpha/index.html");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "https://example.com");
+
+    render_site(
+        root,
+        RenderPlan {
+            profile: Some("dev"),
+            dry_run: false,
+            ..plan
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "http://localhost:8000"
+    );
+
+    render_site(root, plan).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "https://example.com");
+}
+
+#[test]
+fn environment_changes_invalidate_rendered_posts() {
+    const VAR: &str = "BCKT_TEST_RENDER_BASE_URL";
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(root, "post.html", "{{ base_url }}");
+    fs::write(
+        root.join("bckt.yaml"),
+        format!("base_url: ${{{VAR}:-https://default.example}}\n"),
+    )
+    .unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha body");
+    let output = root.join("html/2024/01/01/alpha/index.html");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    let config = Config::load_with_env(root.join("bckt.yaml"), None, &|_| None).unwrap();
+    render_site_with_config(root, config, plan).unwrap();
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "https://default.example"
+    );
+
+    let config = Config::load_with_env(root.join("bckt.yaml"), None, &|name| {
+        (name == VAR).then(|| "https://prod.example".to_string())
+    })
+    .unwrap();
+    render_site_with_config(root, config, plan).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "https://prod.example");
+}
+
+#[test]
+fn site_global_exposes_computed_values() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "{{ site.post_count }}|{{ site.latest_post_date }}|{{ site.years | join(\",\") }}|{{ site.build_time_iso is string }}",
+    );
+    write_dated_post(root, "alpha", "2023-05-01T00:00:00Z", "A");
+    write_dated_post(root, "beta", "2024-01-01T00:00:00Z", "B");
+    write_dated_post(root, "gamma", "2024-02-01T00:00:00Z", "C");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let rendered = fs::read_to_string(root.join("html/2023/05/01/alpha/index.html")).unwrap();
+    assert_eq!(rendered, "3|2024-02-01|2024,2023|true");
+}
+
+#[test]
+fn index_template_can_list_all_posts() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "index.html",
+        "{% for post in posts %}[page:{{ post.slug }}]{% endfor %}{% for post in all_posts %}[all:{{ post.slug }}|{{ post.permalink | safe }}]{% endfor %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 1\nhomepage_all_posts: true\n",
+    )
+    .unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "A");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "B");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(index.contains("[page:beta]"));
+    assert!(!index.contains("[page:alpha]"));
+    assert!(index.contains("[all:beta|/2024/02/01/beta/][all:alpha|/2024/01/01/alpha/]"));
+
+    write_dated_post(root, "gamma", "2024-03-01T00:00:00Z", "C");
+    render_site(root, plan).unwrap();
+    let first = fs::read_to_string(root.join("html/page/1/index.html")).unwrap();
+    assert!(first.contains("[all:gamma|"));
+}
+
+#[test]
+fn all_posts_is_absent_by_default() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "index.html",
+        "{% if all_posts is defined and all_posts is not none %}listed{% else %}absent{% endif %}",
+    );
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "A");
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert_eq!(index, "absent");
+}
+
+#[test]
+fn tag_links_point_at_rendered_tag_archives() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "{% for tag in post.tag_links %}[{{ tag.name }}|{{ tag.slug }}|{{ tag.url | safe }}]{% endfor %}",
+    );
+    write_template(
+        root,
+        "index.html",
+        "{% for post in posts %}{% for tag in post.tag_links %}<{{ tag.url | safe }}>{% endfor %}{% endfor %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: \"https://example.com/blog\"\n",
+    )
+    .unwrap();
+    write_tagged_post(
+        root,
+        "alpha",
+        "Δοκιμή test!",
+        "2024-01-01T00:00:00Z",
+        "Body",
+    );
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let post = fs::read_to_string(root.join("html/2024/01/01/alpha/index.html")).unwrap();
+    assert_eq!(post, "[Δοκιμή test!|test|/blog/tags/test/]");
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert_eq!(index, "</blog/tags/test/>");
+
+    let url = "/blog/tags/test/";
+    let site_path = url.strip_prefix("/blog").unwrap();
+    assert!(
+        root.join("html")
+            .join(site_path.trim_start_matches('/'))
+            .join("index.html")
+            .exists()
+    );
+}
+
+fn write_featured_post(root: &Path, slug: &str, date: &str, featured: bool) {
+    let dir = root.join("posts").join(slug);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        format!("---\ntitle: {slug}\ndate: {date}\nslug: {slug}\nfeatured: {featured}\n---\nBody"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn featured_posts_are_exposed_to_homepage() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "index.html",
+        "{% for post in featured %}[featured:{{ post.slug }}]{% endfor %}{% for post in posts %}[post:{{ post.slug }}]{% endfor %}",
+    );
+    write_featured_post(root, "alpha", "2024-01-01T00:00:00Z", true);
+    write_featured_post(root, "beta", "2024-02-01T00:00:00Z", false);
+    write_featured_post(root, "gamma", "2024-03-01T00:00:00Z", true);
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert_eq!(
+        index,
+        "[featured:gamma][featured:alpha][post:gamma][post:beta][post:alpha]"
+    );
+
+    write_featured_post(root, "alpha", "2024-01-01T00:00:00Z", false);
+    render_site(root, plan).unwrap();
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(index.starts_with("[featured:gamma][post:"));
+}
+
+#[test]
+fn weighted_posts_are_listed_first() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(root.join("bckt.yaml"), "homepage_posts: 2\n").unwrap();
+    write_template(
+        root,
+        "index.html",
+        "{% for post in posts %}[{{ post.slug }}]{% endfor %}|{{ pagination.total }}",
+    );
+    let dir = root.join("posts/notice");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ntitle: notice\ndate: 2023-06-01T00:00:00Z\nslug: notice\ntags: [alpha]\nweight: 10\n---\nPinned",
+    )
+    .unwrap();
+    for (slug, month) in [("alpha", 1), ("beta", 2), ("gamma", 3), ("delta", 4)] {
+        write_dated_post(root, slug, &format!("2024-0{month}-01T00:00:00Z"), "Body");
+    }
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert_eq!(index, "[notice][delta][gamma]|2");
+    let page = fs::read_to_string(root.join("html/page/1/index.html")).unwrap();
+    assert_eq!(page, "[beta][alpha]|2");
+
+    let tag = fs::read_to_string(root.join("html/tags/alpha/index.html")).unwrap();
+    let notice = tag.find("data-slug=\"notice\"").unwrap();
+    assert!(notice < tag.find("data-slug=\"alpha\"").unwrap(), "{tag}");
+}
+
+#[test]
+fn render_recovers_from_corrupted_cache() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "A");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let sled_dir = root.join(CACHE_DIR).join("sled");
+    fs::write(sled_dir.join("conf"), b"not a sled config").unwrap();
+    let output = root.join("html/2024/01/01/alpha/index.html");
+    fs::remove_file(&output).unwrap();
+
+    render_site(root, plan).unwrap();
+    assert!(output.exists());
+}
+
+#[test]
+fn custom_content_directories_are_used() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::rename(root.join("templates"), root.join("layouts")).unwrap();
+    fs::write(
+        root.join("bckt.yaml"),
+        "posts_dir: content\nstatic_dir: static\npages_dir: extra\ntemplates_dir: layouts\n",
+    )
+    .unwrap();
+
+    let post_dir = root.join("content/hello");
+    fs::create_dir_all(&post_dir).unwrap();
+    fs::write(
+        post_dir.join("post.md"),
+        "---\ntitle: Hello\ndate: 2024-01-01T00:00:00Z\n---\nBody",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("static/css")).unwrap();
+    fs::write(root.join("static/css/site.css"), "body{}").unwrap();
+    fs::create_dir_all(root.join("extra")).unwrap();
+    fs::write(root.join("extra/about.html"), "About").unwrap();
+
+    render_site(
+        root,
+        RenderPlan {
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    assert!(root.join("html/2024/01/01/hello/index.html").exists());
+    assert!(root.join("html/css/site.css").exists());
+    assert!(root.join("html/about.html").exists());
+}
+
+fn write_revised_post(root: &Path, slug: &str, date: &str, updated: Option<&str>) {
+    let dir = root.join("posts").join(slug);
+    fs::create_dir_all(&dir).unwrap();
+    let updated = updated
+        .map(|value| format!("updated: {value}\n"))
+        .unwrap_or_default();
+    fs::write(
+        dir.join("post.md"),
+        format!("---\ntitle: {slug}\ndate: {date}\nslug: {slug}\n{updated}---\nBody"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn unicode_permalinks_are_percent_encoded_in_feeds_and_sitemap() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "rss.xml",
+        include_str!("../../themes/bckt3/templates/rss.xml"),
+    );
+    write_dated_post(root, "greek", "2024-01-02T00:00:00Z", "Γεια");
+
+    // Slugs are ASCII today, so give the post a Greek one after discovery.
+    let config = Config::load(root.join("bckt.yaml")).unwrap();
+    let cache_db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+    let mut discovered = discover_site_posts(root, &config, &cache_db).unwrap();
+    discovered.posts[0].slug = "δοκιμή".to_string();
+    discovered.posts[0].permalink = "/2024/01/02/δοκιμή/".to_string();
+    let mut env = template::environment(&config).unwrap();
+    load_templates(&config.templates_path(root), &mut env).unwrap();
+    env.add_global(
+        "site",
+        build_site_context(&config, &discovered.posts).unwrap(),
+    );
+    let html = Output::new(root.join("html"), false, 1, None);
+    let bodies = PostBodies::new(&config, &cache_db);
+    render_posts(
+        &discovered,
+        &html,
+        &config,
+        &env,
+        &bodies,
+        BuildMode::Full,
+        Logger::default(),
+    )
+    .unwrap();
+    render_feeds(
+        &discovered.posts,
+        &html,
+        &config,
+        &env,
+        &bodies,
+        BuildMode::Full,
+    )
+    .unwrap();
+
+    assert!(root.join("html/2024/01/02/δοκιμή/index.html").is_file());
+    let encoded = "https://example.com/2024/01/02/%CE%B4%CE%BF%CE%BA%CE%B9%CE%BC%CE%AE/";
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains(&format!("<link>{encoded}</link>")), "{feed}");
+    assert!(feed.contains(&format!("<guid isPermaLink=\"true\">{encoded}</guid>")));
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(
+        sitemap.contains(&format!("<loc>{encoded}</loc>")),
+        "{sitemap}"
+    );
+    for document in [&feed, &sitemap] {
+        for line in document.lines().filter(|line| line.contains("://")) {
+            if !line.contains("CDATA") {
+                assert!(line.is_ascii(), "{line}");
+            }
+        }
+    }
+}
+
+#[test]
+fn updated_date_is_exposed_and_used_for_sitemap() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "{{ post.date_iso }}|{{ post.updated_iso }}|{{ post.updated }}",
+    );
+    write_template(
+        root,
+        "index.html",
+        "{% for post in posts %}[{{ post.slug }}:{{ post.updated_iso }}]{% endfor %}",
+    );
+    write_revised_post(root, "alpha", "2024-01-01T00:00:00Z", None);
+    write_revised_post(
+        root,
+        "beta",
+        "2024-02-01T00:00:00Z",
+        Some("2024-03-05T06:07:08Z"),
+    );
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let beta = fs::read_to_string(root.join("html/2024/02/01/beta/index.html")).unwrap();
+    assert_eq!(beta, "2024-02-01T00:00:00Z|2024-03-05T06:07:08Z|2024-03-05");
+    let alpha = fs::read_to_string(root.join("html/2024/01/01/alpha/index.html")).unwrap();
+    assert_eq!(alpha, "2024-01-01T00:00:00Z|none|none");
+
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(sitemap.contains(
+        "<loc>https://example.com/2024/02/01/beta/</loc>\n    <lastmod>2024-03-05T06:07:08Z</lastmod>"
+    ));
+    assert!(sitemap.contains(
+        "<loc>https://example.com/2024/01/01/alpha/</loc>\n    <lastmod>2024-01-01T00:00:00Z</lastmod>"
+    ));
+
+    // Editing only `updated:` must refresh listings that show the post.
+    write_revised_post(
+        root,
+        "alpha",
+        "2024-01-01T00:00:00Z",
+        Some("2024-04-01T00:00:00Z"),
+    );
+    render_site(root, plan).unwrap();
+    let index = fs::read_to_string(root.join("html/index.html")).unwrap();
+    assert!(index.contains("[alpha:2024-04-01T00:00:00Z]"));
+}
+
+fn sitemap_lastmod(root: &Path, loc: &str) -> String {
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    let entry = format!("<loc>https://example.com{loc}</loc>\n    <lastmod>");
+    let start = sitemap.find(&entry).unwrap_or_else(|| panic!("{sitemap}")) + entry.len();
+    let end = start + sitemap[start..].find("</lastmod>").unwrap();
+    sitemap[start..end].to_string()
+}
+
+#[test]
+fn editing_a_post_bumps_its_sitemap_lastmod() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "First draft");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Unchanged");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        sitemap_lastmod(root, "/2024/01/01/alpha/"),
+        "2024-01-01T00:00:00Z"
+    );
+
+    // Touching a file without changing it keeps the lastmod stable.
+    wait_for_filesystem_tick();
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Unchanged");
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        sitemap_lastmod(root, "/2024/02/01/beta/"),
+        "2024-02-01T00:00:00Z"
+    );
+    assert_eq!(
+        sitemap_lastmod(root, "/2024/01/01/alpha/"),
+        "2024-01-01T00:00:00Z"
+    );
+
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Fixed a typo");
+    render_site(root, plan).unwrap();
+    let edited = sitemap_lastmod(root, "/2024/01/01/alpha/");
+    assert!(edited.as_str() > "2025", "{edited}");
+    assert_eq!(sitemap_lastmod(root, "/tags/alpha/"), edited);
+    assert_eq!(sitemap_lastmod(root, "/"), edited);
+    assert_eq!(
+        sitemap_lastmod(root, "/2024/02/01/beta/"),
+        "2024-02-01T00:00:00Z"
+    );
+
+    render_site(root, plan).unwrap();
+    assert_eq!(sitemap_lastmod(root, "/2024/01/01/alpha/"), edited);
+}
+
+#[test]
+fn shortcode_template_changes_invalidate_posts_using_them() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "shortcodes/note.html",
+        "<aside>v1 {{ args[0] }}</aside>",
+    );
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "{{< note hi >}}");
+    write_dated_post(root, "beta", "2024-02-01T00:00:00Z", "Plain body");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let alpha = root.join("html/2024/01/01/alpha/index.html");
+    let beta = root.join("html/2024/02/01/beta/index.html");
+    assert!(
+        fs::read_to_string(&alpha)
+            .unwrap()
+            .contains("<aside>v1 hi</aside>")
+    );
+    let beta_before = file_mtime(&beta);
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    write_template(
+        root,
+        "shortcodes/note.html",
+        "<aside>v2 {{ args[0] }}</aside>",
+    );
+    render_site(root, plan).unwrap();
+
+    assert!(
+        fs::read_to_string(&alpha)
+            .unwrap()
+            .contains("<aside>v2 hi</aside>")
+    );
+    assert_eq!(file_mtime(&beta), beta_before);
+}
+
+#[test]
+fn unknown_shortcode_fails_render_with_location() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_dated_post(
+        root,
+        "alpha",
+        "2024-01-01T00:00:00Z",
+        "Intro\n{{< missing >}}",
+    );
+
+    let error = render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap_err();
+    let message = format!("{error:#}");
+    assert!(message.contains("alpha/post.md:9: unknown shortcode 'missing'"));
+}
+
+#[test]
+fn body_classes_combine_type_language_and_custom_classes() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "<article class=\"{{ post.body_classes }}\">{{ post.classes | join(\",\") }}</article>",
+    );
+    let dir = root.join("posts/sunset");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ndate: 2024-01-01T00:00:00Z\ntype: photo\nlanguage: en\nclasses: [wide, dark]\n---\nA sunset.",
+    )
+    .unwrap();
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let html = fs::read_to_string(root.join("html/2024/01/01/sunset/index.html")).unwrap();
+    assert_eq!(
+        html,
+        "<article class=\"post-photo lang-en wide dark\">wide,dark</article>"
+    );
+}
+
+#[test]
+fn cache_dir_moves_the_cache_out_of_the_project() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("blog");
+    setup_markdown_templates(&root);
+    fs::write(root.join("bckt.yaml"), "cache_dir: ../caches\n").unwrap();
+    write_tagged_post(&root, "alpha", "rust", "2024-01-01T00:00:00Z", "A");
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(&root, plan).unwrap();
+
+    assert!(!root.join(".bckt").exists());
+    let caches: Vec<_> = fs::read_dir(temp.path().join("caches"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    let config = Config::load(root.join("bckt.yaml")).unwrap();
+    assert_eq!(caches.len(), 1);
+    assert_eq!(
+        fs::canonicalize(cache_location(&root, &config)).unwrap(),
+        fs::canonicalize(&caches[0]).unwrap()
+    );
+    assert!(caches[0].join("sled").exists());
+
+    let output = root.join("html/2024/01/01/alpha/index.html");
+    fs::write(&output, "kept").unwrap();
+    render_site(&root, plan).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "kept");
+}
+
+#[test]
+fn changed_post_files_follow_the_render_cache() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_tagged_post(root, "first", "rust", "2024-01-01T00:00:00Z", "One");
+    write_tagged_post(root, "second", "rust", "2024-01-02T00:00:00Z", "Two");
+    assert_eq!(changed_post_files(root, None).unwrap().len(), 2);
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            mode: BuildMode::Changed,
+            log: Logger::new(Level::Warn),
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+    assert!(changed_post_files(root, None).unwrap().is_empty());
+
+    write_tagged_post(
+        root,
+        "second",
+        "rust",
+        "2024-01-02T00:00:00Z",
+        "Two, edited",
+    );
+    assert_eq!(
+        changed_post_files(root, None).unwrap(),
+        [root.join("posts/second/post.md")]
+    );
+}
+
+#[test]
+fn language_cache_drops_guesses_for_edited_posts() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    let body = "This is a long enough English sentence for the language detector.";
+    write_tagged_post(root, "first", "rust", "2024-01-01T00:00:00Z", body);
+    let plan = || RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let language_keys = || {
+        let db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+        db.scan_prefix(LANGUAGE_PREFIX.as_bytes())
+            .map(|entry| entry.unwrap().0.to_vec())
+            .collect::<Vec<_>>()
+    };
+
+    render_site(root, plan()).unwrap();
+    let before = language_keys();
+    assert_eq!(before.len(), 1);
+
+    write_tagged_post(
+        root,
+        "first",
+        "rust",
+        "2024-01-01T00:00:00Z",
+        "Ceci est une phrase française assez longue pour le détecteur de langue.",
+    );
+    render_site(root, plan()).unwrap();
+    let after = language_keys();
+    assert_eq!(after.len(), 1);
+    assert_ne!(after, before);
+}
+
+#[test]
+fn post_references_resolve_and_follow_their_target() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com/blog\n",
+    )
+    .unwrap();
+    write_dated_post(root, "target", "2024-01-01T00:00:00Z", "Target");
+    write_dated_post(
+        root,
+        "source",
+        "2024-02-01T00:00:00Z",
+        "See [the target]({{post:target}}).",
+    );
+    write_dated_post(root, "bystander", "2024-03-01T00:00:00Z", "Unrelated");
+    fs::create_dir_all(root.join("pages")).unwrap();
+    fs::write(
+        root.join("pages/about.html"),
+        "<a href=\"{{ post:target }}\">{{ site.title }}</a>",
+    )
+    .unwrap();
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let source = root.join("html/2024/02/01/source/index.html");
+    assert!(
+        fs::read_to_string(&source)
+            .unwrap()
+            .contains("<a href=\"/blog/2024/01/01/target/\">the target</a>")
+    );
+    assert!(
+        fs::read_to_string(root.join("html/about.html"))
+            .unwrap()
+            .starts_with("<a href=\"/blog/2024/01/01/target/\">")
+    );
+
+    // Moving the target re-renders the post linking to it, and only that.
+    let bystander = root.join("html/2024/03/01/bystander/index.html");
+    fs::write(&bystander, "kept").unwrap();
+    write_dated_post(root, "target", "2024-01-05T00:00:00Z", "Target");
+    render_site(root, plan).unwrap();
+    assert!(
+        fs::read_to_string(&source)
+            .unwrap()
+            .contains("<a href=\"/blog/2024/01/05/target/\">")
+    );
+    assert!(
+        fs::read_to_string(root.join("html/about.html"))
+            .unwrap()
+            .contains("/blog/2024/01/05/target/")
+    );
+    assert_eq!(fs::read_to_string(&bystander).unwrap(), "kept");
+
+    // Once the link is current, the post is left alone again.
+    fs::write(&source, "kept").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(fs::read_to_string(&source).unwrap(), "kept");
+}
+
+#[test]
+fn unresolved_post_references_name_their_location() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_dated_post(
+        root,
+        "source",
+        "2024-02-01T00:00:00Z",
+        "Intro\n\n[x]({{post:missing}})",
+    );
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let error = format!("{:#}", render_site(root, plan).unwrap_err());
+    assert!(
+        error.contains("posts/source/post.md:10: post:missing does not match any post"),
+        "{error}"
+    );
+
+    write_dated_post(root, "twin", "2024-01-01T00:00:00Z", "One");
+    let dir = root.join("posts/twin-again");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("post.md"),
+        "---\ndate: 2024-01-02T00:00:00Z\nslug: twin\n---\nTwo",
+    )
+    .unwrap();
+    write_dated_post(root, "source", "2024-02-01T00:00:00Z", "[x]({{post:twin}})");
+    let error = format!("{:#}", render_site(root, plan).unwrap_err());
+    assert!(
+        error.contains(
+            "post:twin matches several posts (/2024/01/01/twin/, /2024/01/02/twin/); use a dated reference such as post:2024/01/01/twin"
+        ),
+        "{error}"
+    );
+
+    write_dated_post(
+        root,
+        "source",
+        "2024-02-01T00:00:00Z",
+        "[x]({{post:2024/01/02/twin}})",
+    );
+    render_site(root, plan).unwrap();
+    assert!(
+        fs::read_to_string(root.join("html/2024/02/01/source/index.html"))
+            .unwrap()
+            .contains("href=\"/2024/01/02/twin/\"")
+    );
+}
+
+#[test]
+fn homepage_sort_title_lists_posts_alphabetically() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "homepage_posts: 2\nhomepage_sort: title\n",
+    )
+    .unwrap();
+    for (day, slug) in ["delta", "alpha", "echo", "charlie", "bravo"]
+        .iter()
+        .enumerate()
+    {
+        let date = format!("2024-01-0{}T00:00:00Z", day + 1);
+        write_tagged_post(root, slug, "words", &date, "Body");
+    }
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let slugs = |path: &str| -> Vec<String> {
+        let html = fs::read_to_string(root.join(path)).unwrap();
+        html.split("data-slug=\"")
+            .skip(1)
+            .map(|rest| rest[..rest.find('"').unwrap()].to_string())
+            .collect()
+    };
+    assert_eq!(slugs("html/index.html"), ["alpha", "bravo", "charlie"]);
+    assert_eq!(slugs("html/page/1/index.html"), ["delta", "echo"]);
+}
+
+#[test]
+fn theme_default_changes_force_a_full_rebuild() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "<article style=\"color: {{ config.accent_color }}\">{{ post.body | safe }}</article>",
+    );
+    fs::write(root.join("bckt.yaml"), "theme: mine\n").unwrap();
+    let defaults = root.join("themes/mine/defaults.yaml");
+    fs::create_dir_all(defaults.parent().unwrap()).unwrap();
+    fs::write(&defaults, "accent_color: red\n").unwrap();
+    write_tagged_post(root, "alpha", "rust", "2024-01-01T00:00:00Z", "A");
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    let output = root.join("html/2024/01/01/alpha/index.html");
+    assert!(fs::read_to_string(&output).unwrap().contains("color: red"));
+
+    fs::write(&defaults, "accent_color: green\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(
+        fs::read_to_string(&output)
+            .unwrap()
+            .contains("color: green")
+    );
+
+    // A value set in bckt.yaml wins, so the theme default no longer matters.
+    fs::write(root.join("bckt.yaml"), "theme: mine\naccent_color: blue\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(fs::read_to_string(&output).unwrap().contains("color: blue"));
+    fs::write(&output, "kept").unwrap();
+    fs::write(&defaults, "accent_color: black\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(fs::read_to_string(&output).unwrap(), "kept");
+}
+
+#[test]
+fn stats_page_aggregates_posts() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    write_tagged_post(
+        root,
+        "first",
+        "rust",
+        "2019-05-01T00:00:00Z",
+        "one two three",
+    );
+    write_tagged_post(root, "second", "Rust", "2019-06-01T00:00:00Z", "four five");
+    write_tagged_post(root, "third", "web", "2021-01-01T00:00:00Z", "six");
+    let now = time::OffsetDateTime::now_utc();
+    let this_month = format!("{:04}-{:02}-01T00:00:00Z", now.year(), now.month() as u8);
+    write_tagged_post(root, "fourth", "web", &this_month, "seven eight");
+
+    // Without a stats.html template nothing is written.
+    render_site(root, plan).unwrap();
+    assert!(!root.join("html/stats").exists());
+
+    write_template(
+        root,
+        "stats.html",
+        "{{ stats.post_count }}|{{ stats.word_count }}|{% for tag in stats.tags %}{{ tag.name }}={{ tag.count }} {% endfor %}",
+    );
+    render_site(root, plan).unwrap();
+    // `rust` and `Rust` are as common, so the first alphabetically names the tag.
+    assert_eq!(
+        fs::read_to_string(root.join("html/stats/index.html")).unwrap(),
+        "4|8|Rust=2 web=2 "
+    );
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(root.join("html/stats/stats.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        stats["years"],
+        serde_json::json!([
+            {"year": 2019, "count": 2},
+            {"year": 2021, "count": 1},
+            {"year": now.year(), "count": 1},
+        ])
+    );
+    let months = stats["months"].as_array().unwrap();
+    assert_eq!(months.len(), 24);
+    assert_eq!(
+        months[23],
+        serde_json::json!({"year": now.year(), "month": now.month() as u8, "count": 1})
+    );
+    assert!(months[..23].iter().all(|month| month["count"] == 0));
+    assert_eq!(stats["tags"][0]["url"], "/tags/rust/");
+
+    // Unchanged numbers leave the page alone; removing the template removes it.
+    fs::write(root.join("html/stats/index.html"), "kept").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        fs::read_to_string(root.join("html/stats/index.html")).unwrap(),
+        "kept"
+    );
+    fs::remove_file(root.join("templates/stats.html")).unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!root.join("html/stats/index.html").exists());
+    assert!(!root.join("html/stats/stats.json").exists());
+}
+
+#[test]
+fn front_matter_template_overrides_the_type_template() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(root, "special.html", "special:{{ post.body | safe }}");
+    write_template(root, "post-note.html", "note:{{ post.body | safe }}");
+    for (slug, template) in [("chosen", "special.html"), ("missing", "gone.html")] {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("post.md"),
+            format!("---\ndate: 2024-01-01T00:00:00Z\ntype: note\ntemplate: {template}\n---\nHi"),
+        )
+        .unwrap();
+    }
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let (result, records) = crate::logging::capture::records(|| render_site(root, plan));
+    result.unwrap();
+
+    assert_eq!(
+        fs::read_to_string(root.join("html/2024/01/01/chosen/index.html")).unwrap(),
+        "special:<p>Hi</p>\n"
+    );
+    assert!(
+        fs::read_to_string(root.join("html/2024/01/01/missing/index.html"))
+            .unwrap()
+            .starts_with("<!doctype html>")
+    );
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert!(records[0].message.contains("missing gone.html"));
+}
+
+#[test]
+fn ignored_static_files_are_neither_hashed_nor_copied() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::create_dir_all(root.join("posts")).unwrap();
+    fs::write(
+        root.join("bckt.yaml"),
+        "theme: mine\nstatic_ignore: [node_modules, \"*.map\", \"!keep.map\"]\n",
+    )
+    .unwrap();
+    for path in [
+        "skel/css/site.css",
+        "skel/.DS_Store",
+        "skel/.well-known/security.txt",
+        "skel/node_modules/widget/index.js",
+        "skel/js/app.js.map",
+        "skel/js/keep.map",
+        "themes/mine/assets/theme.css",
+        "themes/mine/assets/.DS_Store",
+        "themes/mine/assets/node_modules/lib.js",
+    ] {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "data").unwrap();
+    }
+    let plan = RenderPlan {
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let html = root.join("html");
+    for published in [
+        "css/site.css",
+        ".well-known/security.txt",
+        "js/keep.map",
+        "assets/theme.css",
+    ] {
+        assert!(html.join(published).exists(), "{published}");
+    }
+    for skipped in [
+        ".DS_Store",
+        "node_modules",
+        "js/app.js.map",
+        "assets/.DS_Store",
+        "assets/node_modules",
+    ] {
+        assert!(!html.join(skipped).exists(), "{skipped}");
+    }
+
+    // Editing ignored files does not count as a change.
+    fs::write(html.join("css/site.css"), "kept").unwrap();
+    fs::write(html.join("assets/theme.css"), "kept").unwrap();
+    wait_for_filesystem_tick();
+    fs::write(root.join("skel/node_modules/widget/index.js"), "new").unwrap();
+    fs::write(root.join("skel/.DS_Store"), "new").unwrap();
+    fs::write(root.join("themes/mine/assets/node_modules/lib.js"), "new").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        fs::read_to_string(html.join("css/site.css")).unwrap(),
+        "kept"
+    );
+    assert_eq!(
+        fs::read_to_string(html.join("assets/theme.css")).unwrap(),
+        "kept"
+    );
 
-    let alpha_after_template = file_mtime(&alpha_output);
-    let beta_after_template = file_mtime(&beta_output);
-    assert!(alpha_after_template > alpha_after_changed);
-    assert!(beta_after_template > beta_after_changed);
+    fs::write(root.join("skel/js/keep.map"), "new").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(
+        fs::read_to_string(html.join("css/site.css")).unwrap(),
+        "data"
+    );
+}
+
+#[test]
+fn output_manifest_lists_every_file_with_its_hash() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\noutput_manifest: true\n",
+    )
+    .unwrap();
+    write_dated_post(root, "hello", "2024-01-01T00:00:00Z", "Hello body");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let output = root.join("html/manifest.json");
+    let read_manifest = || -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap()
+    };
+    let manifest = read_manifest();
+    let index = fs::read(root.join("html/index.html")).unwrap();
+    assert_eq!(
+        manifest["index.html"],
+        serde_json::json!({
+            "hash": blake3::hash(&index).to_hex().to_string(),
+            "size": index.len(),
+        })
+    );
+    assert!(manifest.get("2024/01/01/hello/index.html").is_some());
+    assert!(manifest.get("manifest.json").is_none());
+
+    write_dated_post(root, "hello", "2024-01-01T00:00:00Z", "Changed body");
+    render_site(root, plan).unwrap();
+    let post = fs::read(root.join("html/2024/01/01/hello/index.html")).unwrap();
+    assert_eq!(
+        read_manifest()["2024/01/01/hello/index.html"]["hash"],
+        blake3::hash(&post).to_hex().to_string()
+    );
+
+    fs::write(root.join("bckt.yaml"), "base_url: https://example.com\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!output.exists());
+}
+
+/// A JPEG with JFIF, EXIF (holding `GPS`), and ICC segments ahead of the scan.
+fn jpeg_with_exif() -> Vec<u8> {
+    let segment = |marker: u8, payload: &[u8]| {
+        let mut bytes = vec![0xFF, marker];
+        bytes.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    };
+    [
+        vec![0xFF, 0xD8],
+        segment(0xE0, b"JFIF\0\x01\x01"),
+        segment(0xE1, b"Exif\0\0GPS 37.97N 23.72E"),
+        segment(0xE2, b"ICC_PROFILE\0"),
+        segment(0xDA, b"\x01\x01\0"),
+        b"scan data".to_vec(),
+        vec![0xFF, 0xD9],
+    ]
+    .concat()
+}
+
+#[test]
+fn strip_exif_removes_metadata_from_copied_images() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nstrip_exif: true\n",
+    )
+    .unwrap();
+    let jpeg = jpeg_with_exif();
+    for (slug, front) in [("trip", ""), ("original", "strip_exif: false\n")] {
+        let post_dir = root.join("posts").join(slug);
+        fs::create_dir_all(&post_dir).unwrap();
+        fs::write(
+            post_dir.join("post.md"),
+            format!(
+                "---\ndate: 2024-01-01T00:00:00Z\nslug: {slug}\n{front}attached: [photo.jpg, scan.heic]\n---\nBody"
+            ),
+        )
+        .unwrap();
+        fs::write(post_dir.join("photo.jpg"), &jpeg).unwrap();
+        fs::write(post_dir.join("scan.heic"), "heic").unwrap();
+    }
+
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let (result, records) = crate::logging::capture::records(|| render_site(root, plan));
+    let report = result.unwrap();
+    assert_eq!(report.attachments_copied, 4);
+    assert_eq!(records.len(), 1, "{records:?}");
+    assert!(records[0].message.contains("cannot strip metadata from"));
+    assert!(records[0].message.contains("scan.heic"));
+
+    let contains = |haystack: &[u8], needle: &[u8]| {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    };
+    let stripped = fs::read(root.join("html/2024/01/01/trip/photo.jpg")).unwrap();
+    assert!(!contains(&stripped, b"Exif"));
+    assert!(!contains(&stripped, b"GPS"));
+    assert!(contains(&stripped, b"JFIF"));
+    assert!(contains(&stripped, b"ICC_PROFILE"));
+    assert!(stripped.ends_with(b"scan data\xFF\xD9"));
+    assert_eq!(fs::read(root.join("posts/trip/photo.jpg")).unwrap(), jpeg);
+    assert_eq!(
+        fs::read(root.join("html/2024/01/01/original/photo.jpg")).unwrap(),
+        jpeg
+    );
+
+    // The stripped copies are recognised as up to date; plain ones are copied.
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.posts_rendered, 2);
+    assert_eq!(report.attachments_copied, 2);
+
+    // An output replaced behind bckt's back is written again.
+    fs::write(root.join("html/2024/01/01/trip/photo.jpg"), &jpeg).unwrap();
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.attachments_copied, 3);
+    assert!(!contains(
+        &fs::read(root.join("html/2024/01/01/trip/photo.jpg")).unwrap(),
+        b"Exif"
+    ));
+
+    // Stamps of attachments that are gone are dropped from the cache.
+    let stripped_keys = || {
+        let db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+        db.scan_prefix(STRIPPED_ATTACHMENT_PREFIX.as_bytes())
+            .map(|entry| String::from_utf8(entry.unwrap().0.to_vec()).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        stripped_keys(),
+        [
+            "stripped:2024/01/01/trip/photo.jpg",
+            "stripped:2024/01/01/trip/scan.heic"
+        ]
+    );
+    fs::remove_dir_all(root.join("posts/trip")).unwrap();
+    render_site(root, plan).unwrap();
+    assert!(stripped_keys().is_empty());
+}
+
+#[test]
+fn rss_sanitize_strips_scripts_from_feed_content() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nrss_tags: [rust]\nrss_sanitize: true\nfeeds:\n  tag_content:\n    rust: excerpt\n",
+    )
+    .unwrap();
+    let post_dir = root.join("posts/raw");
+    fs::create_dir_all(&post_dir).unwrap();
+    fs::write(
+        post_dir.join("post.html"),
+        "---\ndate: 2024-01-01T00:00:00Z\ntags: [rust]\n---\n<p onclick=\"steal()\">Full text</p><script>alert(1)</script><p>More text</p>",
+    )
+    .unwrap();
+
+    let plan = RenderPlan {
+        static_assets: false,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    let encoded = feed
+        .split("<content:encoded><![CDATA[")
+        .nth(1)
+        .and_then(|rest| rest.split("]]>").next())
+        .unwrap();
+    assert_eq!(encoded, "<p>Full text</p><p>More text</p>");
+    // The page itself keeps the markup; only the feed is sanitized.
+    let page = fs::read_to_string(root.join("html/2024/01/01/raw/index.html")).unwrap();
+    assert!(page.contains("<script>alert(1)</script>"));
+
+    let tag_feed = fs::read_to_string(root.join("html/rss-rust.xml")).unwrap();
+    assert!(tag_feed.contains("<content:encoded><![CDATA[<p>Full text alert(1) More text</p>]]>"));
+
+    fs::write(root.join("bckt.yaml"), "base_url: https://example.com\n").unwrap();
+    render_site(root, plan).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<script>alert(1)</script>"));
+}
+
+#[test]
+fn untagged_slug_renames_or_suppresses_the_untagged_archive() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    write_tagged_post(root, "symbols", "日本語", "2024-01-01T00:00:00Z", "A");
+    write_tagged_post(root, "named", "rust", "2024-01-02T00:00:00Z", "B");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    let untagged = root.join("html/tags/untagged/index.html");
+    assert!(untagged.exists());
+
+    fs::write(root.join("bckt.yaml"), "untagged_slug: misc\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!untagged.exists());
+    assert!(root.join("html/tags/misc/index.html").exists());
+
+    fs::write(root.join("bckt.yaml"), "untagged_slug: \"\"\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert!(!root.join("html/tags/untagged").exists());
+    assert!(!root.join("html/tags/misc").exists());
+    assert!(root.join("html/tags/rust/index.html").exists());
+    assert!(
+        root.join("html/2024/01/01/symbols/index.html").exists(),
+        "the post itself is still rendered"
+    );
+}
+
+#[test]
+fn posts_without_tags_get_their_own_archive_when_configured() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    write_tagged_post(root, "symbols", "\"!!!\"", "2024-01-01T00:00:00Z", "A");
+    fs::create_dir_all(root.join("posts/bare")).unwrap();
+    fs::write(
+        root.join("posts/bare/post.md"),
+        "---\ntitle: bare\ndate: 2024-01-02T00:00:00Z\nslug: bare\n---\nB",
+    )
+    .unwrap();
+    write_tagged_post(root, "named", "rust", "2024-01-03T00:00:00Z", "C");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    let listed = |slug: &str| -> Vec<String> {
+        let page = fs::read_to_string(root.join("html/tags").join(slug).join("index.html"))
+            .unwrap_or_default();
+        page.split("data-slug=\"")
+            .skip(1)
+            .map(|rest| rest.split('"').next().unwrap().to_string())
+            .collect()
+    };
+
+    render_site(root, plan).unwrap();
+    assert_eq!(listed("untagged"), ["symbols"]);
+
+    fs::write(root.join("bckt.yaml"), "untagged_posts_slug: no-tags\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(listed("untagged"), ["symbols"]);
+    assert_eq!(listed("no-tags"), ["bare"]);
+    let sitemap = fs::read_to_string(root.join("html/sitemap.xml")).unwrap();
+    assert!(sitemap.contains("/tags/no-tags/"));
+
+    fs::write(root.join("bckt.yaml"), "untagged_posts_slug: untagged\n").unwrap();
+    render_site(root, plan).unwrap();
+    assert_eq!(listed("untagged"), ["bare", "symbols"]);
+    assert!(!root.join("html/tags/no-tags").exists());
+
+    // A real tag never silently shares a page with the slugless ones.
+    fs::write(root.join("bckt.yaml"), "").unwrap();
+    write_tagged_post(root, "named", "Untagged", "2024-01-03T00:00:00Z", "C");
+    let error = render_site(root, plan).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("tag 'Untagged' and tags without ASCII letters or digits such as '!!!' would share /tags/untagged/"),
+        "{error:#}"
+    );
+}
+
+#[test]
+fn low_memory_renders_keep_bodies_out_of_posts() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "tag.html",
+        "{% for post in posts %}[{{ post.body | safe }}]{% endfor %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\nlow_memory: true\n",
+    )
+    .unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Alpha **body**");
+    write_dated_post(
+        root,
+        "beta",
+        "2024-02-01T00:00:00Z",
+        "Beta links to [alpha]({{post:alpha}})",
+    );
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+
+    let beta = fs::read_to_string(root.join("html/2024/02/01/beta/index.html")).unwrap();
+    assert!(
+        beta.contains(r#"<a href="/2024/01/01/alpha/">alpha</a>"#),
+        "{beta}"
+    );
+    let tag = fs::read_to_string(root.join("html/tags/alpha/index.html")).unwrap();
+    assert_eq!(tag, "[<p>Alpha <strong>body</strong></p>\n]");
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("Alpha <strong>body</strong>"), "{feed}");
+    let index = fs::read_to_string(root.join("html/assets/search/search-index.json")).unwrap();
+    let index: serde_json::Value = serde_json::from_str(&index).unwrap();
+    let alpha = index["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|document| document["url"] == "/2024/01/01/alpha/")
+        .unwrap();
+    assert!(alpha["content"].to_string().contains("body"), "{alpha}");
+
+    let config = Config::load(root.join("bckt.yaml")).unwrap();
+    let cache_db = open_cache_db(&cache_location(root, &config)).unwrap();
+    let discovered = discover_site_posts(root, &config, &cache_db).unwrap();
+    assert!(
+        discovered
+            .posts
+            .iter()
+            .all(|post| post.body_html.is_empty() && post.search_text.is_empty())
+    );
+    let bodies = posts::PostBodies::new(&config, &cache_db);
+    assert_eq!(
+        bodies.get(&discovered.posts[0]).unwrap(),
+        "<p>Alpha <strong>body</strong></p>\n"
+    );
+    assert_eq!(
+        bodies.search_text(&discovered.posts[1]).unwrap().trim(),
+        "Beta links to alpha"
+    );
+}
+
+#[test]
+fn inlined_files_come_from_skel_before_the_theme_and_trigger_rebuilds() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "base.html",
+        "<style>{{ inline_file('css/critical.css') }}</style>{{ inline_svg('icons/rss.svg', class='icon', width=16) }}{% block content %}{% endblock %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "base_url: https://example.com\ntheme: mine\nmax_inline_size: 200\n",
+    )
+    .unwrap();
+    let theme_assets = root.join("themes/mine/assets");
+    fs::create_dir_all(theme_assets.join("css")).unwrap();
+    fs::create_dir_all(theme_assets.join("icons")).unwrap();
+    fs::write(theme_assets.join("css/critical.css"), "theme{}").unwrap();
+    fs::write(
+        theme_assets.join("icons/rss.svg"),
+        "<?xml version=\"1.0\"?>\n<svg viewBox=\"0 0 8 8\"><path/></svg>",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("skel/css")).unwrap();
+    fs::write(root.join("skel/css/critical.css"), "skel{}").unwrap();
+    write_dated_post(root, "alpha", "2024-01-01T00:00:00Z", "Body");
+
+    let plan = RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        log: Logger::new(Level::Warn),
+        ..RenderPlan::default()
+    };
+    render_site(root, plan).unwrap();
+    let post_path = root.join("html/2024/01/01/alpha/index.html");
+    let post = fs::read_to_string(&post_path).unwrap();
+    assert!(
+        post.starts_with(
+            "<style>skel{}</style><svg viewBox=\"0 0 8 8\" class=\"icon\" width=\"16\"><path/></svg>"
+        ),
+        "{post}"
+    );
+
+    // An unchanged site skips the post; editing an inlined file does not.
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.posts_rendered, 0);
+    fs::write(root.join("skel/css/critical.css"), "edited{}").unwrap();
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.posts_rendered, 1);
+    assert!(
+        fs::read_to_string(&post_path)
+            .unwrap()
+            .starts_with("<style>edited{}</style>")
+    );
+
+    fs::remove_file(root.join("skel/css/critical.css")).unwrap();
+    render_site(root, plan).unwrap();
+    assert!(
+        fs::read_to_string(&post_path)
+            .unwrap()
+            .starts_with("<style>theme{}</style>")
+    );
+
+    // A new skel/ file shadows the theme's copy.
+    fs::create_dir_all(root.join("skel/icons")).unwrap();
+    fs::write(root.join("skel/icons/rss.svg"), "<svg><circle/></svg>").unwrap();
+    let report = render_site(root, plan).unwrap();
+    assert_eq!(report.posts_rendered, 1);
+    assert!(
+        fs::read_to_string(&post_path)
+            .unwrap()
+            .contains("<svg class=\"icon\" width=\"16\"><circle/></svg>")
+    );
+
+    // A damaged list of inlined files stops the render instead of missing
+    // their edits.
+    {
+        let db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+        let list = read_cached_string(&db, INLINED_FILES_KEY).unwrap().unwrap();
+        store_cached_string(&db, INLINED_FILES_KEY, "not json").unwrap();
+        db.flush().unwrap();
+        drop(db);
+        let error = render_site(root, plan).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("cached list of inlined files is unreadable"),
+            "{error:#}"
+        );
+        let db = open_cache_db(&root.join(CACHE_DIR)).unwrap();
+        store_cached_string(&db, INLINED_FILES_KEY, &list).unwrap();
+        db.flush().unwrap();
+    }
+
+    fs::write(theme_assets.join("css/critical.css"), "x".repeat(201)).unwrap();
+    let error = render_site(root, plan).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("more than max_inline_size (200)"),
+        "{error:#}"
+    );
+}
+
+#[test]
+fn empty_post_page_fails_unless_allowed() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    setup_markdown_templates(root);
+    write_template(root, "base.html", "{% block content %}{% endblock %}\n");
+    write_template(
+        root,
+        "post.html",
+        "{% extends \"base.html\" %}{% block contnet %}<article>{{ post.title }}</article>{% endblock %}",
+    );
+    write_markdown_post(root, "Hello");
+
+    let plan = || RenderPlan {
+        static_assets: false,
+        ..RenderPlan::default()
+    };
+
+    let err = format!("{:#}", render_site(root, plan()).unwrap_err());
+    assert!(err.contains("template 'post.html'"), "{err}");
+    assert!(
+        err.contains(
+            &root
+                .join("html/2024/01/02/hello-world/index.html")
+                .display()
+                .to_string()
+        ),
+        "{err}"
+    );
+
+    fs::write(
+        root.join("bckt.yaml"),
+        "allow_empty_output:\n  - post.html\n",
+    )
+    .unwrap();
+    render_site(root, plan()).unwrap();
+    assert!(root.join("html/2024/01/02/hello-world/index.html").exists());
+}
+
+#[test]
+fn updates_feed_lists_edited_posts_by_updated_date() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "rss.xml",
+        "{% autoescape false %}<title>{{ feed.title }}</title>{% for item in feed.items %}<item data-slug=\"{{ item.slug }}\"><pubDate>{{ item.pub_date }}</pubDate><description>{{ item.description }}</description></item>{% endfor %}{% endautoescape %}",
+    );
+    fs::write(
+        root.join("bckt.yaml"),
+        "title: Site\nfeeds:\n  updates: true\n",
+    )
+    .unwrap();
+    for (slug, date, updated) in [
+        ("old", "2023-01-01T00:00:00Z", Some("2024-06-01T00:00:00Z")),
+        (
+            "newer",
+            "2023-05-01T00:00:00Z",
+            Some("2024-03-01T00:00:00Z"),
+        ),
+        ("unedited", "2024-01-01T00:00:00Z", None),
+        ("same", "2024-02-01T00:00:00Z", Some("2024-02-01T00:00:00Z")),
+    ] {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        let updated = updated.map_or(String::new(), |updated| format!("updated: {updated}\n"));
+        fs::write(
+            dir.join("post.md"),
+            format!("---\ntitle: {slug}\nslug: {slug}\ndate: {date}\n{updated}---\nBody\n"),
+        )
+        .unwrap();
+    }
+
+    render_site(
+        root,
+        RenderPlan {
+            static_assets: false,
+            ..RenderPlan::default()
+        },
+    )
+    .unwrap();
+
+    let feed = fs::read_to_string(root.join("html/updates.xml")).unwrap();
+    assert!(feed.contains("<title>Updates · Site</title>"), "{feed}");
+    let old = feed.find("data-slug=\"old\"").unwrap();
+    let newer = feed.find("data-slug=\"newer\"").unwrap();
+    assert!(old < newer, "{feed}");
+    assert!(!feed.contains("data-slug=\"unedited\""));
+    assert!(!feed.contains("data-slug=\"same\""));
+    assert!(feed.contains("<pubDate>Sat, 01 Jun 2024 00:00:00 +0000</pubDate>"));
+    assert!(feed.contains("<description>Originally published 2023-01-01."));
+
+    let main = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(main.contains("data-slug=\"unedited\""));
+}
+
+#[test]
+fn base_url_override_replaces_the_configured_url() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_template(
+        root,
+        "post.html",
+        "<link rel=\"canonical\" href=\"{{ base_url }}{{ post.permalink | safe }}\">",
+    );
+    fs::write(root.join("bckt.yaml"), "base_url: https://example.com\n").unwrap();
+    write_markdown_post(root, "Hello");
+    let plan = |base_url| RenderPlan {
+        static_assets: false,
+        mode: BuildMode::Changed,
+        base_url,
+        ..RenderPlan::default()
+    };
+    let post = root.join("html/2024/01/02/hello-world/index.html");
+
+    for base_url in [
+        "https://preview-123.example.dev",
+        "https://preview-456.example.dev",
+    ] {
+        render_site(root, plan(Some(base_url))).unwrap();
+        let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+        assert!(
+            feed.contains(&format!("<link>{base_url}/</link>")),
+            "{feed}"
+        );
+        assert!(feed.contains(&format!("{base_url}/2024/01/02/hello-world/")));
+        assert!(!feed.contains("https://example.com"));
+        let page = fs::read_to_string(&post).unwrap();
+        assert!(
+            page.contains(&format!("href=\"{base_url}/2024/01/02/hello-world/\"")),
+            "{page}"
+        );
+    }
+
+    render_site(root, plan(None)).unwrap();
+    let feed = fs::read_to_string(root.join("html/rss.xml")).unwrap();
+    assert!(feed.contains("<link>https://example.com/</link>"), "{feed}");
+
+    let error = render_site(root, plan(Some("ftp://example.com"))).unwrap_err();
+    assert!(format!("{error:#}").contains("--base-url: base_url must use http or https"));
+}
+
+#[cfg(unix)]
+#[test]
+fn output_mode_sets_permissions_on_written_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    setup_markdown_templates(root);
+    write_markdown_post(root, "Hello");
+    fs::create_dir_all(root.join("skel")).unwrap();
+    fs::write(root.join("skel/robots.txt"), "User-agent: *\n").unwrap();
+    fs::set_permissions(
+        root.join("skel/robots.txt"),
+        fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    fs::write(root.join("bckt.yaml"), "output_mode: \"604\"\n").unwrap();
+
+    render_site(root, RenderPlan::default()).unwrap();
+
+    let mode = |path: &str| fs::metadata(root.join(path)).unwrap().permissions().mode() & 0o7777;
+    assert_eq!(mode("html/2024/01/02/hello-world/index.html"), 0o604);
+    assert_eq!(mode("html/rss.xml"), 0o604);
+    assert_eq!(mode("html/robots.txt"), 0o604);
+}
+
+#[test]
+fn site_inputs_hash_keeps_inputs_apart() {
+    assert_ne!(
+        compute_site_inputs_hash("title: a", Some("dev"), "t", "i"),
+        compute_site_inputs_hash("title: ad", Some("ev"), "t", "i")
+    );
+    assert_ne!(
+        compute_site_inputs_hash("c", None, "ab", ""),
+        compute_site_inputs_hash("c", None, "a", "b")
+    );
 }
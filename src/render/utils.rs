@@ -8,10 +8,22 @@ use serde::Serialize;
 use time::OffsetDateTime;
 use time::format_description::well_known::{Rfc2822, Rfc3339};
 
-pub(super) fn log_status(enabled: bool, label: &str, message: impl AsRef<str>) {
-    if enabled {
-        println!("[{}] {}", label, message.as_ref());
+/// Human-readable size with decimal units, e.g. `700.0 MB`.
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} B");
     }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
 }
 
 pub(super) fn compute_cache_digest<T: Serialize>(value: &T) -> Result<String> {
@@ -82,3 +94,15 @@ pub(super) fn xml_escape(value: &str) -> String {
     }
     escaped
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_byte_counts() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1_500), "1.5 KB");
+        assert_eq!(format_bytes(700_000_000), "700.0 MB");
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use crate::config::{self, Config};
+use crate::content::Post;
+use crate::logging::{Level, Logger};
+use crate::urls::extract_base_path;
+
+use super::cache::{cache_location, open_cache_snapshot};
+use super::feeds::{TagFeed, config_tag_feeds};
+use super::listing::resolve_tag_slugs;
+use super::posts::discover_site_posts;
+
+/// A problem found by [`validate_site`]. Errors break the site; warnings are
+/// worth a look.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiteIssue {
+    pub level: Level,
+    pub message: String,
+    /// The post the issue was found in, if any.
+    pub path: Option<PathBuf>,
+}
+
+impl SiteIssue {
+    pub(super) fn error(message: String) -> Self {
+        Self {
+            level: Level::Error,
+            message,
+            path: None,
+        }
+    }
+
+    pub(super) fn warning(message: String) -> Self {
+        Self {
+            level: Level::Warn,
+            message,
+            path: None,
+        }
+    }
+}
+
+/// Checks that the config agrees with the rest of the site: the theme is
+/// installed, post templates exist, `rss_tags` names used tags, search covers
+/// the post languages, tag slugs do not collide, and `base_url` does not end
+/// in a generated directory.
+pub fn validate_site(root: &Path, profile: Option<&str>) -> Result<Vec<SiteIssue>> {
+    let mut config = Config::load_with_profile(config::config_path(root), profile)?;
+    let mut issues = Vec::new();
+    // Renders work without the theme, since templates/ holds the templates,
+    // so only validation insists on it.
+    if let Some(theme) = config.theme.as_deref()
+        && !root.join("themes").join(theme).is_dir()
+    {
+        issues.push(SiteIssue::error(format!(
+            "theme '{theme}' is not installed in themes/{theme}"
+        )));
+    }
+    issues.extend(check_base_url(&config));
+    let cache_db = open_cache_snapshot(&cache_location(root, &config))?;
+    let discovered = discover_site_posts(root, &config, &cache_db)?;
+    issues.extend(resolve_tag_slugs(&mut config, &discovered.posts));
+    issues.extend(check_posts(root, &config, &discovered.posts));
+    Ok(issues)
+}
+
+/// The check of [`validate_site`] that every render runs first: `base_url`
+/// must not end in the name of a directory bckt generates.
+pub(super) fn check_base_url(config: &Config) -> Option<SiteIssue> {
+    let base_path = extract_base_path(&config.base_url);
+    let last = base_path.rsplit('/').next()?;
+    is_generated_directory(config, last).then(|| {
+        SiteIssue::error(format!(
+            "base_url ends in /{last}, the name of a directory bckt generates"
+        ))
+    })
+}
+
+/// Logs the warnings among `issues`, and fails with the errors.
+pub(super) fn report_issues(issues: &[SiteIssue], log: Logger) -> Result<()> {
+    for issue in issues {
+        log.log(issue.level, "CONFIG", &issue.message, issue.path.as_deref());
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.level == Level::Error)
+        .count();
+    if errors > 0 {
+        bail!("the site configuration has {errors} error(s)");
+    }
+    Ok(())
+}
+
+/// Whether `segment` names a top-level directory of `html/`: tag pages, a
+/// year archive, or the numbered homepage pages.
+fn is_generated_directory(config: &Config, segment: &str) -> bool {
+    segment == "tags"
+        || (segment.len() == 4 && segment.bytes().all(|byte| byte.is_ascii_digit()))
+        || config
+            .pagination_path
+            .trim_start_matches('/')
+            .split('/')
+            .next()
+            == Some(segment)
+}
+
+fn check_posts(root: &Path, config: &Config, posts: &[Post]) -> Vec<SiteIssue> {
+    let mut issues = Vec::new();
+
+    let templates_dir = config.templates_path(root);
+    let mut missing_templates: BTreeMap<String, Vec<&Post>> = BTreeMap::new();
+    for post in posts {
+        let template = post.template.clone().or_else(|| {
+            post.post_type
+                .as_deref()
+                .map(|value| format!("post-{value}.html"))
+        });
+        if let Some(template) = template
+            && !templates_dir.join(&template).is_file()
+        {
+            missing_templates.entry(template).or_default().push(post);
+        }
+    }
+    for (template, users) in missing_templates {
+        issues.push(SiteIssue {
+            path: Some(users[0].content_path.clone()),
+            ..SiteIssue::error(format!(
+                "template {template} is missing but {} post(s) use it, e.g. {}",
+                users.len(),
+                users[0].content_path.display()
+            ))
+        });
+    }
+
+    let used_tags: BTreeSet<&str> = posts
+        .iter()
+        .flat_map(|post| &post.tags)
+        .map(|tag| config.canonical_tag(tag))
+        .collect();
+    for TagFeed { tag, .. } in config_tag_feeds(config) {
+        if !used_tags.contains(config.canonical_tag(&tag)) {
+            issues.push(SiteIssue::error(format!(
+                "rss_tags lists '{tag}', but no post has that tag"
+            )));
+        }
+    }
+
+    let searched: BTreeSet<&str> = config
+        .search
+        .languages
+        .iter()
+        .map(|language| language.id.as_str())
+        .collect();
+    let mut uncovered: BTreeMap<&str, usize> = BTreeMap::new();
+    for post in posts {
+        if !searched.contains(post.language.as_str()) {
+            *uncovered.entry(post.language.as_str()).or_default() += 1;
+        }
+    }
+    for (language, count) in uncovered {
+        issues.push(SiteIssue::warning(format!(
+            "{count} post(s) are in '{language}', which search.languages does not list"
+        )));
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn site(config: &str) -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("templates")).unwrap();
+        fs::create_dir_all(root.join("posts")).unwrap();
+        fs::write(root.join("templates/post.html"), "{{ post.body }}").unwrap();
+        // The default theme.
+        fs::create_dir_all(root.join("themes/bckt3")).unwrap();
+        fs::write(root.join("bckt.yaml"), config).unwrap();
+        temp
+    }
+
+    fn write_post(root: &Path, slug: &str, front_matter: &str) {
+        let dir = root.join("posts").join(slug);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("post.md"),
+            format!("---\ntitle: {slug}\ndate: 2024-01-01T00:00:00Z\n{front_matter}---\nBody\n"),
+        )
+        .unwrap();
+    }
+
+    fn messages(root: &Path, level: Level) -> Vec<String> {
+        validate_site(root, None)
+            .unwrap()
+            .into_iter()
+            .filter(|issue| issue.level == level)
+            .map(|issue| issue.message)
+            .collect()
+    }
+
+    #[test]
+    fn missing_theme_is_an_error() {
+        let temp = site("theme: mine\n");
+        let root = temp.path();
+        assert_eq!(
+            messages(root, Level::Error),
+            ["theme 'mine' is not installed in themes/mine"]
+        );
+
+        fs::create_dir_all(root.join("themes/mine")).unwrap();
+        assert!(validate_site(root, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn base_url_may_not_end_in_a_generated_directory() {
+        for (base_url, collides) in [
+            ("https://example.com/tags/", true),
+            ("https://example.com/blog/2024", true),
+            ("https://example.com/page", true),
+            ("https://example.com/blog/", false),
+            ("https://example.com", false),
+        ] {
+            let temp = site(&format!("base_url: {base_url}\n"));
+            let errors = messages(temp.path(), Level::Error);
+            assert_eq!(
+                errors.len(),
+                usize::from(collides),
+                "{base_url}: {errors:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn post_types_need_their_template() {
+        let temp = site("");
+        let root = temp.path();
+        write_post(root, "photo", "type: photo\n");
+        write_post(root, "custom", "template: special.html\n");
+        write_post(root, "plain", "");
+
+        let errors = messages(root, Level::Error);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].starts_with("template post-photo.html is missing but 1 post(s) use it"));
+        assert!(errors[1].starts_with("template special.html is missing"));
+
+        fs::write(root.join("templates/post-photo.html"), "").unwrap();
+        fs::write(root.join("templates/special.html"), "").unwrap();
+        assert!(messages(root, Level::Error).is_empty());
+    }
+
+    #[test]
+    fn rss_tags_must_be_used_by_a_post() {
+        let temp = site("rss_tags: [rust, golang, python]\ntag_aliases:\n  py: python\n");
+        let root = temp.path();
+        write_post(root, "one", "tags: [rust, py]\n");
+
+        assert_eq!(
+            messages(root, Level::Error),
+            ["rss_tags lists 'golang', but no post has that tag"]
+        );
+    }
+
+    #[test]
+    fn uncovered_post_languages_are_a_warning() {
+        let temp = site("");
+        let root = temp.path();
+        write_post(root, "english", "language: en\n");
+        write_post(root, "french", "language: fr\n");
+        write_post(root, "french-too", "language: fr\n");
+
+        let issues = validate_site(root, None).unwrap();
+        assert_eq!(
+            issues,
+            [SiteIssue::warning(
+                "2 post(s) are in 'fr', which search.languages does not list".to_string()
+            )]
+        );
+        assert!(report_issues(&issues, Logger::new(Level::Warn)).is_ok());
+        assert!(
+            report_issues(
+                &[SiteIssue::error("x".to_string())],
+                Logger::new(Level::Warn)
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,8 +1,12 @@
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use blake3::Hasher;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use isolang::Language;
 use serde::Serialize;
 use serde_json::{Map as JsonMap, Value as JsonValue};
@@ -10,12 +14,13 @@ use time::OffsetDateTime;
 use time::format_description;
 use time::format_description::well_known::Rfc3339;
 
-use crate::config::{Config, SearchLanguageConfig};
-use crate::content::Post;
+use crate::config::{Config, SearchConfig, SearchField, SearchFormat, SearchLanguageConfig};
+use crate::content::{BodyPart, BodyStore, Post};
 
 #[derive(Debug)]
 pub struct SearchIndexArtifact {
     pub bytes: Vec<u8>,
+    pub format: SearchFormat,
     pub digest: String,
     pub document_count: usize,
 }
@@ -37,20 +42,28 @@ struct SearchLanguageMeta {
     stopwords: Vec<String>,
 }
 
+/// One post in the index. The text fields left out by `search.fields` are
+/// omitted; `terms` stands in for `content` when `body` is.
 #[derive(Serialize)]
 struct SearchDocument {
     id: String,
-    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
     url: String,
     language: String,
-    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
     #[serde(rename = "type")]
     kind: Option<String>,
     date_display: String,
     date_iso: String,
     timestamp: i64,
-    excerpt: String,
-    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terms: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     payload: Option<JsonMap<String, JsonValue>>,
 }
@@ -63,7 +76,13 @@ struct SearchFacets {
     years: Vec<i32>,
 }
 
-pub fn build_index(config: &Config, posts: &[Post]) -> Result<SearchIndexArtifact> {
+/// Builds the index of `posts`. Their text comes from `bodies` when posts
+/// were discovered with one, as in `low_memory` builds.
+pub fn build_index(
+    config: &Config,
+    posts: &[Post],
+    bodies: Option<&dyn BodyStore>,
+) -> Result<SearchIndexArtifact> {
     let now = OffsetDateTime::now_utc();
     let generated_at = now
         .format(&Rfc3339)
@@ -99,9 +118,10 @@ pub fn build_index(config: &Config, posts: &[Post]) -> Result<SearchIndexArtifac
         // More efficient tag processing - avoid cloning unless necessary
         let mut tag_list = Vec::with_capacity(post.tags.len());
         for tag in &post.tags {
+            let tag = config.canonical_tag(tag);
             if !tag.is_empty() {
-                tag_list.push(tag.clone());
-                tags.insert(tag.clone());
+                tag_list.push(tag.to_string());
+                tags.insert(tag.to_string());
             }
         }
         tag_list.sort_unstable();
@@ -159,18 +179,26 @@ pub fn build_index(config: &Config, posts: &[Post]) -> Result<SearchIndexArtifac
             }
         }
 
+        let fields = &config.search.fields;
+        let min_length = config.search.min_token_length;
+        let has_body = fields.contains(&SearchField::Body);
+        let search_text = match bodies {
+            Some(bodies) => Cow::Owned(bodies.get(&post.content_path, BodyPart::SearchText)?),
+            None => Cow::Borrowed(post.search_text.as_str()),
+        };
         documents.push(SearchDocument {
             id: post.permalink.clone(),
-            title,
+            title: fields.contains(&SearchField::Title).then_some(title),
             url: post.permalink.clone(),
             language,
-            tags: tag_list,
+            tags: fields.contains(&SearchField::Tags).then_some(tag_list),
             kind: post.post_type.clone(),
             date_display,
             date_iso,
             timestamp: post.date.unix_timestamp(),
-            excerpt,
-            content: post.search_text.clone(),
+            excerpt: fields.contains(&SearchField::Excerpt).then_some(excerpt),
+            content: has_body.then(|| index_terms(&search_text, min_length)),
+            terms: (!has_body).then(|| distinct_terms(&search_text, min_length)),
             payload: if payload_map.is_empty() {
                 None
             } else {
@@ -186,7 +214,7 @@ pub fn build_index(config: &Config, posts: &[Post]) -> Result<SearchIndexArtifac
         .map(|entry| SearchLanguageMeta {
             id: entry.id.clone(),
             name: entry.name.clone(),
-            stopwords: normalize_stopwords(&entry.stopwords),
+            stopwords: normalize_stopwords(&entry.stopwords()),
         })
         .collect();
 
@@ -203,21 +231,95 @@ pub fn build_index(config: &Config, posts: &[Post]) -> Result<SearchIndexArtifac
         },
     };
 
-    let bytes = serde_json::to_vec(&index).context("failed to serialize search index")?;
+    let json = serde_json::to_vec(&index).context("failed to serialize search index")?;
+    let format = config.search.format;
     let mut hasher = Hasher::new();
-    hasher.update(&bytes);
+    hasher.update(format.as_str().as_bytes());
+    hasher.update(&json);
     let digest = hasher.finalize().to_hex().to_string();
 
+    let bytes = match format {
+        SearchFormat::Json => json,
+        SearchFormat::JsonGz => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder
+                .write_all(&json)
+                .context("failed to compress search index")?;
+            encoder
+                .finish()
+                .context("failed to compress search index")?
+        }
+        SearchFormat::Msgpack => {
+            rmp_serde::to_vec_named(&index).context("failed to serialize search index")?
+        }
+    };
+
     Ok(SearchIndexArtifact {
         digest,
         bytes,
+        format,
         document_count: index.documents.len(),
     })
 }
 
-pub fn resolve_asset_path(html_root: &Path, asset_path: &str) -> PathBuf {
-    let trimmed = asset_path.trim_start_matches('/');
-    html_root.join(trimmed)
+/// Location of the published index under `html_root`, including the suffix
+/// implied by `search.format`.
+pub fn resolve_asset_path(html_root: &Path, search: &SearchConfig) -> PathBuf {
+    html_root.join(published_asset_path(search, search.format))
+}
+
+/// Site-relative path (without a leading slash) of the index as written in
+/// `format`. Templates link to it through the `search_index_url` global.
+pub fn published_asset_path(search: &SearchConfig, format: SearchFormat) -> String {
+    format
+        .asset_path(&search.asset_path)
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// `text` without the words shorter than `min_length` characters, ignoring
+/// surrounding punctuation. Words in CJK scripts are kept whatever their
+/// length, since a single character there is already a word.
+fn index_terms(text: &str, min_length: usize) -> String {
+    if min_length <= 1 {
+        return text.to_string();
+    }
+    text.split_whitespace()
+        .filter(|word| long_enough(word_core(word), min_length))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The distinct lowercase words of `text`, split at spaces and punctuation
+/// like the client tokenizer does, in order of first appearance and with the
+/// `min_length` rule of [`index_terms`].
+fn distinct_terms(text: &str, min_length: usize) -> String {
+    let mut seen = BTreeSet::new();
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && long_enough(word, min_length))
+        .filter(|word| seen.insert(word.clone()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn word_core(word: &str) -> &str {
+    word.trim_matches(|ch: char| !ch.is_alphanumeric())
+}
+
+fn long_enough(word: &str, min_length: usize) -> bool {
+    word.chars().count() >= min_length || word.chars().any(is_cjk)
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
 }
 
 fn normalize_stopwords(stopwords: &[String]) -> Vec<String> {
@@ -295,6 +397,7 @@ mod tests {
     use super::*;
     use crate::content::Post;
     use serde_json::{Value as JsonValue, json};
+    use std::io::Read;
     use std::path::PathBuf;
 
     fn build_post(slug: &str, language: &str, tags: &[&str]) -> Post {
@@ -303,13 +406,27 @@ mod tests {
             title: Some("Example".to_string()),
             slug: slug.to_string(),
             date,
+            updated: None,
             tags: tags.iter().map(|tag| tag.to_string()).collect(),
             post_type: Some("note".to_string()),
+            template: None,
             abstract_text: Some("Summary".to_string()),
             attached: Vec::new(),
+            attached_private: Vec::new(),
+            attachments_meta: BTreeMap::new(),
+            jsonld: None,
+            strip_exif: None,
+            featured: false,
+            weight: 0,
+            undated: false,
+            classes: Vec::new(),
+            shortcodes: Vec::new(),
+            post_refs: Vec::new(),
+            linked_posts: Vec::new(),
             body_html: "<p>Example body</p>".to_string(),
             excerpt: "Example body".to_string(),
             language: language.to_string(),
+            dir: "ltr".to_string(),
             search_text: "Example body for search indexing".to_string(),
             source_dir: PathBuf::from("posts/example"),
             content_path: PathBuf::from("posts/example/post.md"),
@@ -322,7 +439,7 @@ mod tests {
     fn build_index_serializes_documents() {
         let config = Config::default();
         let posts = vec![build_post("alpha", "en", &["rust", "notes"])];
-        let artifact = build_index(&config, &posts).unwrap();
+        let artifact = build_index(&config, &posts, None).unwrap();
         let payload: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
 
         let documents = payload["documents"].as_array().unwrap();
@@ -336,11 +453,69 @@ mod tests {
         assert!(tags.iter().any(|value| value == "rust"));
     }
 
+    #[test]
+    fn short_words_are_left_out_of_content() {
+        let mut config = Config::default();
+        config.search.min_token_length = 3;
+        let mut post = build_post("alpha", "en", &[]);
+        post.search_text = "Go is fun, so is Rust (v1) 東京 に".to_string();
+        let artifact = build_index(&config, &[post], None).unwrap();
+        let payload: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
+        assert_eq!(payload["documents"][0]["content"], "fun, Rust 東京 に");
+        assert_eq!(index_terms("a  b", 1), "a  b");
+    }
+
+    #[test]
+    fn dropping_body_keeps_its_terms_searchable() {
+        let mut post = build_post("alpha", "en", &["rust"]);
+        post.search_text =
+            "Sled keeps the cache. The cache survives restarts, sled says.".repeat(20);
+        let full = build_index(&Config::default(), std::slice::from_ref(&post), None).unwrap();
+
+        let mut config = Config::default();
+        config.search.fields = vec![SearchField::Title, SearchField::Excerpt];
+        let trimmed = build_index(&config, &[post], None).unwrap();
+
+        let document_size = |artifact: &SearchIndexArtifact| {
+            let payload: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
+            payload["documents"][0].to_string().len()
+        };
+        assert!(document_size(&trimmed) * 4 < document_size(&full));
+        let payload: JsonValue = serde_json::from_slice(&trimmed.bytes).unwrap();
+        let document = payload["documents"][0].as_object().unwrap();
+        assert_eq!(document["title"], "Example");
+        assert_eq!(document["excerpt"], "Summary");
+        assert!(!document.contains_key("content"));
+        assert!(!document.contains_key("tags"));
+        assert_eq!(
+            document["terms"],
+            "sled keeps the cache survives restarts says"
+        );
+        assert_eq!(payload["facets"]["tags"], json!(["rust"]));
+    }
+
+    #[test]
+    fn tag_aliases_merge_search_tags() {
+        let mut config = Config::default();
+        config
+            .tag_aliases
+            .insert("rustlang".to_string(), "rust".to_string());
+        let posts = vec![
+            build_post("alpha", "en", &["rustlang"]),
+            build_post("beta", "en", &["rust"]),
+        ];
+        let artifact = build_index(&config, &posts, None).unwrap();
+        let payload: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
+
+        assert_eq!(payload["documents"][0]["tags"], serde_json::json!(["rust"]));
+        assert_eq!(payload["facets"]["tags"], serde_json::json!(["rust"]));
+    }
+
     #[test]
     fn language_aliases_map_to_configured_ids() {
         let config = Config::default();
         let posts = vec![build_post("beta", "eng", &[])];
-        let artifact = build_index(&config, &posts).unwrap();
+        let artifact = build_index(&config, &posts, None).unwrap();
         let payload: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
         let document_language = payload["documents"][0]["language"].as_str().unwrap();
         assert_eq!(document_language, "en");
@@ -355,7 +530,7 @@ mod tests {
             .insert("image".into(), json!("/static/img/cover.jpg"));
         post.extra.insert("duration".into(), json!(128));
         post.extra.insert("ignored".into(), json!("value"));
-        let artifact = build_index(&config, &[post]).unwrap();
+        let artifact = build_index(&config, &[post], None).unwrap();
         let root: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
         let payload = root["documents"][0]["payload"].as_object().unwrap();
         assert_eq!(
@@ -382,8 +557,74 @@ mod tests {
             }),
         );
 
-        let artifact = build_index(&config, &[post]).unwrap();
+        let artifact = build_index(&config, &[post], None).unwrap();
         let root: JsonValue = serde_json::from_slice(&artifact.bytes).unwrap();
         assert!(root["documents"][0]["payload"].is_null());
     }
+
+    #[test]
+    fn json_gz_round_trips_to_same_documents() {
+        let posts = vec![
+            build_post("alpha", "en", &["rust"]),
+            build_post("beta", "el", &["notes"]),
+        ];
+        let plain = build_index(&Config::default(), &posts, None).unwrap();
+
+        let mut config = Config::default();
+        config.search.format = SearchFormat::JsonGz;
+        let compressed = build_index(&config, &posts, None).unwrap();
+        assert_eq!(compressed.format, SearchFormat::JsonGz);
+        assert_eq!(&compressed.bytes[..2], &[0x1f, 0x8b]);
+        assert_ne!(compressed.digest, plain.digest);
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(compressed.bytes.as_slice())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        let decoded: JsonValue = serde_json::from_str(&decoded).unwrap();
+        let plain: JsonValue = serde_json::from_slice(&plain.bytes).unwrap();
+        assert_eq!(decoded["documents"], plain["documents"]);
+        assert_eq!(decoded["facets"], plain["facets"]);
+    }
+
+    #[test]
+    fn msgpack_decodes_to_same_documents() {
+        let posts = vec![
+            build_post("alpha", "en", &["rust"]),
+            build_post("beta", "el", &["notes"]),
+        ];
+        let plain = build_index(&Config::default(), &posts, None).unwrap();
+
+        let mut config = Config::default();
+        config.search.format = SearchFormat::Msgpack;
+        let packed = build_index(&config, &posts, None).unwrap();
+        assert_eq!(packed.format, SearchFormat::Msgpack);
+        assert_ne!(packed.digest, plain.digest);
+
+        let decoded: JsonValue = rmp_serde::from_slice(&packed.bytes).unwrap();
+        let plain: JsonValue = serde_json::from_slice(&plain.bytes).unwrap();
+        assert_eq!(decoded["version"], 1);
+        assert_eq!(decoded["documents"], plain["documents"]);
+        assert_eq!(decoded["facets"], plain["facets"]);
+    }
+
+    #[test]
+    fn asset_path_follows_format() {
+        let mut search = SearchConfig::default();
+        let html = Path::new("html");
+        assert_eq!(
+            resolve_asset_path(html, &search),
+            html.join("assets/search/search-index.json")
+        );
+        search.format = SearchFormat::JsonGz;
+        assert_eq!(
+            resolve_asset_path(html, &search),
+            html.join("assets/search/search-index.json.gz")
+        );
+        search.format = SearchFormat::Msgpack;
+        assert_eq!(
+            published_asset_path(&search, search.format),
+            "assets/search/search-index.msgpack"
+        );
+    }
 }
@@ -7,6 +7,8 @@ use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 
 use crate::config::Config;
+use crate::search;
+use crate::urls::extract_base_path;
 
 pub fn environment(config: &Config) -> Result<Environment<'static>> {
     let mut env = Environment::new();
@@ -19,6 +21,14 @@ pub fn environment(config: &Config) -> Result<Environment<'static>> {
         "base_path",
         Value::from_safe_string(extract_base_path(&config.base_url)),
     );
+    env.add_global(
+        "search_index_url",
+        Value::from_safe_string(format!(
+            "{}/{}",
+            extract_base_path(&config.base_url),
+            search::published_asset_path(&config.search, config.search.format)
+        )),
+    );
 
     let default_format = config.date_format.clone();
     env.add_function(
@@ -66,28 +76,6 @@ fn normalize_base_url(value: &str) -> String {
     trimmed.trim_end_matches('/').to_string()
 }
 
-fn extract_base_path(base_url: &str) -> String {
-    // Extract path component from base_url
-    // Examples:
-    //   "https://vrypan.net/blog/" -> "/blog"
-    //   "https://vrypan.net/" -> ""
-    //   "https://example.com" -> ""
-
-    if let Some(idx) = base_url.find("://") {
-        let after_scheme = &base_url[idx + 3..];
-        if let Some(slash_idx) = after_scheme.find('/') {
-            let path = &after_scheme[slash_idx..];
-            // Remove trailing slash
-            path.trim_end_matches('/').to_string()
-        } else {
-            String::new()
-        }
-    } else {
-        // No scheme, treat as path
-        base_url.trim_end_matches('/').to_string()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +159,20 @@ mod tests {
         assert_eq!(rendered, "/blog");
     }
 
+    #[test]
+    fn search_index_url_matches_published_file() {
+        let mut config = Config {
+            base_url: "https://vrypan.net/blog/".to_string(),
+            ..Default::default()
+        };
+        config.search.format = crate::config::SearchFormat::JsonGz;
+        let mut env = environment(&config).unwrap();
+        env.add_template("url", "{{ search_index_url }}").unwrap();
+
+        let rendered = env.get_template("url").unwrap().render(()).unwrap();
+        assert_eq!(rendered, "/blog/assets/search/search-index.json.gz");
+    }
+
     #[test]
     fn base_path_empty_for_root_url() {
         let config = Config {
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::time::Instant;
+
+use serde::Serialize;
+
+thread_local! {
+    static COLLECTOR: RefCell<Option<Collector>> = const { RefCell::new(None) };
+}
+
+struct Collector {
+    started: Instant,
+    spans: Vec<Span>,
+}
+
+/// A finished span, with times in microseconds since recording started.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub name: String,
+    pub start: u64,
+    pub duration: u64,
+}
+
+/// Runs `run` and returns the spans it closed on this thread, in the order
+/// they ended.
+pub fn record<T>(run: impl FnOnce() -> T) -> (T, Vec<Span>) {
+    COLLECTOR.with(|collector| {
+        *collector.borrow_mut() = Some(Collector {
+            started: Instant::now(),
+            spans: Vec::new(),
+        })
+    });
+    let value = run();
+    let spans = COLLECTOR.with(|collector| {
+        collector
+            .borrow_mut()
+            .take()
+            .map(|collector| collector.spans)
+            .unwrap_or_default()
+    });
+    (value, spans)
+}
+
+/// Times the scope the returned guard lives in. Outside [`record`] this does
+/// nothing, and `name` is only built while recording.
+pub fn span(name: impl FnOnce() -> String) -> SpanGuard {
+    let recording = COLLECTOR.with(|collector| collector.borrow().is_some());
+    SpanGuard {
+        open: recording.then(|| (name(), Instant::now())),
+    }
+}
+
+pub struct SpanGuard {
+    open: Option<(String, Instant)>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let Some((name, opened)) = self.open.take() else {
+            return;
+        };
+        COLLECTOR.with(|collector| {
+            if let Some(collector) = collector.borrow_mut().as_mut() {
+                let start = opened.saturating_duration_since(collector.started);
+                collector.spans.push(Span {
+                    name,
+                    start: start.as_micros() as u64,
+                    duration: opened.elapsed().as_micros() as u64,
+                });
+            }
+        });
+    }
+}
+
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'static str,
+    ph: &'static str,
+    ts: u64,
+    dur: u64,
+    pid: u32,
+    tid: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: Vec<TraceEvent<'a>>,
+    display_time_unit: &'static str,
+}
+
+/// The spans as a Chrome tracing file, which `chrome://tracing`, Perfetto, and
+/// speedscope open as a flame chart.
+pub fn chrome_trace(spans: &[Span]) -> serde_json::Result<Vec<u8>> {
+    let mut trace_events: Vec<TraceEvent> = spans
+        .iter()
+        .map(|span| TraceEvent {
+            name: &span.name,
+            cat: "bckt",
+            ph: "X",
+            ts: span.start,
+            dur: span.duration,
+            pid: 1,
+            tid: 1,
+        })
+        .collect();
+    // Parents first, so viewers nest spans that start at the same time.
+    trace_events.sort_by(|a, b| a.ts.cmp(&b.ts).then_with(|| b.dur.cmp(&a.dur)));
+    serde_json::to_vec(&Trace {
+        trace_events,
+        display_time_unit: "ms",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_are_only_recorded_inside_record() {
+        drop(span(|| unreachable!("names are not built outside record")));
+
+        let ((), spans) = record(|| {
+            let _outer = span(|| "outer".to_string());
+            let _inner = span(|| "inner".to_string());
+        });
+        let names: Vec<&str> = spans.iter().map(|span| span.name.as_str()).collect();
+        assert_eq!(names, ["inner", "outer"]);
+        assert!(spans[1].start <= spans[0].start);
+
+        let trace: serde_json::Value =
+            serde_json::from_slice(&chrome_trace(&spans).unwrap()).unwrap();
+        assert_eq!(trace["traceEvents"][0]["name"], "outer");
+        assert_eq!(trace["traceEvents"][0]["ph"], "X");
+    }
+}
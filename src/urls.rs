@@ -0,0 +1,162 @@
+//! Construction of the site URLs bckt emits. Directory-style pages (the
+//! homepage, `page/N/`, `tags/<slug>/`) always end in a slash so one page has
+//! exactly one URL.
+
+use percent_encoding::{AsciiSet, CONTROLS, NON_ALPHANUMERIC, utf8_percent_encode};
+
+// Everything except unreserved characters is encoded inside a segment.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_');
+
+// What may not appear raw in a URL. `%` is not encoded, so paths that are
+// already percent-encoded pass through unchanged.
+const UNSAFE: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Root-relative URL of the homepage.
+pub const HOME_URL: &str = "/";
+
+/// Joins `path` onto `base`, keeping exactly one slash between them.
+/// Non-ASCII characters and spaces in `path` are percent-encoded, so feeds,
+/// the sitemap, and other XML or JSON outputs get valid URLs even for
+/// Unicode slugs.
+pub fn absolute_url(base: &str, path: &str) -> String {
+    let trimmed_base = base.trim_end_matches('/');
+    let trimmed_path = utf8_percent_encode(path.trim_start_matches('/'), UNSAFE).to_string();
+
+    if trimmed_path.is_empty() {
+        format!("{}/", trimmed_base)
+    } else {
+        format!("{}/{trimmed_path}", trimmed_base)
+    }
+}
+
+/// Root-relative URL of a directory page, e.g. `["tags", "rust"]` becomes
+/// `/tags/rust/`. Segments are percent-encoded and empty ones are skipped, so
+/// no segments yields `/`.
+pub fn dir_url<S: AsRef<str>>(segments: &[S]) -> String {
+    let mut url = String::from("/");
+    for segment in segments {
+        let segment = segment.as_ref().trim_matches('/');
+        if segment.is_empty() {
+            continue;
+        }
+        url.extend(utf8_percent_encode(segment, SEGMENT));
+        url.push('/');
+    }
+    url
+}
+
+/// Path component of `base_url` without a trailing slash: `/blog` for
+/// `https://example.com/blog/`, empty for root deployments.
+pub fn extract_base_path(base_url: &str) -> String {
+    if let Some(idx) = base_url.find("://") {
+        let after_scheme = &base_url[idx + 3..];
+        if let Some(slash_idx) = after_scheme.find('/') {
+            let path = &after_scheme[slash_idx..];
+            path.trim_end_matches('/').to_string()
+        } else {
+            String::new()
+        }
+    } else {
+        // No scheme, treat as path
+        base_url.trim_end_matches('/').to_string()
+    }
+}
+
+/// Prefixes a root-relative `path` with the base path of `base_url`.
+pub fn with_base_path(base_url: &str, path: &str) -> String {
+    format!("{}{}", extract_base_path(base_url), path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_root_path() {
+        let url = absolute_url("https://example.com", "/");
+        assert_eq!(url, "https://example.com/");
+    }
+
+    #[test]
+    fn joins_nested_path() {
+        let url = absolute_url("https://example.com/blog", "/rss.xml");
+        assert_eq!(url, "https://example.com/blog/rss.xml");
+    }
+
+    #[test]
+    fn trims_trailing_slash() {
+        let url = absolute_url("https://example.com/", "/page/2/");
+        assert_eq!(url, "https://example.com/page/2/");
+    }
+
+    #[test]
+    fn encodes_unicode_paths_once() {
+        assert_eq!(
+            absolute_url("https://example.com/blog/", "/2024/01/02/δοκιμή/"),
+            "https://example.com/blog/2024/01/02/%CE%B4%CE%BF%CE%BA%CE%B9%CE%BC%CE%AE/"
+        );
+        assert_eq!(
+            absolute_url("https://example.com", "/tags/%CE%B4/a%20b.png?x=1#top"),
+            "https://example.com/tags/%CE%B4/a%20b.png?x=1#top"
+        );
+        assert_eq!(
+            absolute_url("https://example.com", "/files/a b.png"),
+            "https://example.com/files/a%20b.png"
+        );
+    }
+
+    #[test]
+    fn dir_urls_are_canonical() {
+        assert_eq!(dir_url::<&str>(&[]), "/");
+        assert_eq!(dir_url(&["tags", "rust"]), "/tags/rust/");
+        assert_eq!(dir_url(&["/page/", "2"]), "/page/2/");
+        assert_eq!(dir_url(&["tags", "", "rust"]), "/tags/rust/");
+        assert_eq!(
+            dir_url(&["tags", "καλημέρα"]),
+            "/tags/%CE%BA%CE%B1%CE%BB%CE%B7%CE%BC%CE%AD%CF%81%CE%B1/"
+        );
+        assert_eq!(dir_url(&["tags", "a b?"]), "/tags/a%20b%3F/");
+    }
+
+    #[test]
+    fn extract_base_path_from_full_url() {
+        assert_eq!(extract_base_path("https://vrypan.net/blog/"), "/blog");
+        assert_eq!(extract_base_path("https://vrypan.net/blog"), "/blog");
+        assert_eq!(
+            extract_base_path("https://example.com/foo/bar/"),
+            "/foo/bar"
+        );
+    }
+
+    #[test]
+    fn extract_base_path_from_root_url() {
+        assert_eq!(extract_base_path("https://vrypan.net/"), "");
+        assert_eq!(extract_base_path("https://vrypan.net"), "");
+        assert_eq!(extract_base_path("http://example.com"), "");
+    }
+
+    #[test]
+    fn extract_base_path_from_path_only() {
+        assert_eq!(extract_base_path("/blog/"), "/blog");
+        assert_eq!(extract_base_path("/blog"), "/blog");
+    }
+
+    #[test]
+    fn base_path_prefixes_root_relative_urls() {
+        assert_eq!(
+            with_base_path("https://example.com/blog/", "/tags/rust/"),
+            "/blog/tags/rust/"
+        );
+        assert_eq!(
+            with_base_path("https://example.com", "/tags/rust/"),
+            "/tags/rust/"
+        );
+    }
+}
@@ -1,17 +1,7 @@
 use anyhow::Result;
 use std::env;
 use std::path::{Path, PathBuf};
-
-pub fn absolute_url(base: &str, path: &str) -> String {
-    let trimmed_base = base.trim_end_matches('/');
-    let trimmed_path = path.trim_start_matches('/');
-
-    if trimmed_path.is_empty() {
-        format!("{}/", trimmed_base)
-    } else {
-        format!("{}/{trimmed_path}", trimmed_base)
-    }
-}
+use std::process::Command;
 
 /// Resolves a root path, expanding tilde and converting to absolute path.
 /// If root_opt is None, returns the current working directory.
@@ -29,7 +19,7 @@ pub fn resolve_root(root_opt: Option<&str>) -> Result<PathBuf> {
 }
 
 /// Expands ~ to the user's home directory
-fn expand_tilde(path: &str) -> String {
+pub fn expand_tilde(path: &str) -> String {
     if path.starts_with("~/")
         && let Some(home) = env::var_os("HOME")
     {
@@ -39,27 +29,69 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Runs `command` through the platform shell (`sh -c` or `cmd /C`).
+pub fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
 
-    #[test]
-    fn joins_root_path() {
-        let url = absolute_url("https://example.com", "/");
-        assert_eq!(url, "https://example.com/");
+/// Quotes `value` as a single argument for the shell used by
+/// [`shell_command`].
+pub fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{value}\"")
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
     }
+}
 
-    #[test]
-    fn joins_nested_path() {
-        let url = absolute_url("https://example.com/blog", "/rss.xml");
-        assert_eq!(url, "https://example.com/blog/rss.xml");
+/// Matches a `/`-separated path against a glob supporting `*`, `?` and `**`
+/// (any number of directories).
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &path)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, remaining)) => {
+                let segment: Vec<char> = segment.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                match_segment(&segment, &name) && match_segments(rest, remaining)
+            }
+            None => false,
+        },
     }
+}
 
-    #[test]
-    fn trims_trailing_slash() {
-        let url = absolute_url("https://example.com/", "/page/2/");
-        assert_eq!(url, "https://example.com/page/2/");
+fn match_segment(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some(('*', rest)), _) => {
+            match_segment(rest, name) || (!name.is_empty() && match_segment(pattern, &name[1..]))
+        }
+        (Some(('?', rest)), Some((_, name_rest))) => match_segment(rest, name_rest),
+        (Some((expected, rest)), Some((actual, name_rest))) => {
+            expected == actual && match_segment(rest, name_rest)
+        }
+        _ => false,
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn resolve_root_handles_none() {
@@ -85,4 +117,15 @@ mod tests {
         let expanded = expand_tilde("/absolute/path");
         assert_eq!(expanded, "/absolute/path");
     }
+
+    #[test]
+    fn glob_supports_wildcards() {
+        assert!(glob_matches("styles/*.css", "styles/site.css"));
+        assert!(!glob_matches("styles/*.css", "styles/nested/site.css"));
+        assert!(glob_matches("styles/**/*.css", "styles/site.css"));
+        assert!(glob_matches("styles/**/*.css", "styles/a/b/site.css"));
+        assert!(glob_matches("./src/?.js", "src/a.js"));
+        assert!(!glob_matches("src/?.js", "src/ab.js"));
+        assert!(glob_matches("**", "anything/at/all"));
+    }
 }
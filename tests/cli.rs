@@ -0,0 +1,400 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+fn write(root: &Path, relative: &str, contents: &str) {
+    let path = root.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, contents).unwrap();
+}
+
+fn scaffold_project(root: &Path) {
+    write(
+        root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com\n",
+    );
+    for name in [
+        "post.html",
+        "index.html",
+        "tag.html",
+        "archive_year.html",
+        "archive_month.html",
+        "rss.xml",
+    ] {
+        write(root, &format!("templates/{name}"), "ok");
+    }
+    write(
+        root,
+        "posts/hello/post.md",
+        "---\ntitle: Hello\ndate: 2024-01-01T00:00:00Z\n---\nBody",
+    );
+}
+
+fn bckt(root: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_bckt"))
+        .args(args)
+        .arg("--root")
+        .arg(root)
+        .output()
+        .expect("failed to run bckt")
+}
+
+#[test]
+fn quiet_render_prints_nothing_to_stdout() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+
+    let output = bckt(root, &["render", "--quiet", "--verbose"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stdout.is_empty(), "{output:?}");
+    assert!(root.join("html/2024/01/01/hello/index.html").exists());
+}
+
+#[test]
+fn render_errors_go_to_stderr() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    fs::remove_file(root.join("templates/post.html")).unwrap();
+
+    let output = bckt(root, &["render", "--quiet"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "{output:?}");
+    assert!(!output.stderr.is_empty());
+}
+
+#[test]
+fn render_trace_records_spans() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    let trace_path = root.join("trace.json");
+
+    let output = bckt(
+        root,
+        &["render", "--quiet", "--trace", trace_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let trace: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&trace_path).unwrap()).unwrap();
+    let events = trace["traceEvents"].as_array().unwrap();
+    let names: Vec<&str> = events
+        .iter()
+        .map(|event| event["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names[0], "render", "{names:?}");
+    assert!(names.contains(&"discover posts"), "{names:?}");
+    assert!(names.contains(&"post /2024/01/01/hello/"), "{names:?}");
+    assert!(
+        names.iter().any(|name| name.starts_with("markdown ")),
+        "{names:?}"
+    );
+    assert!(events[0]["dur"].as_u64().is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn post_render_hook_receives_build_details() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    write(
+        root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com\nhooks:\n  post_render:\n    - 'echo \"$BCKT_POSTS_RENDERED $BCKT_OUTPUT_DIR\" > marker.txt && cat \"$BCKT_CHANGED_FILES\" >> marker.txt'\n",
+    );
+
+    let output = bckt(root, &["render", "--quiet"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let marker = fs::read_to_string(root.join("marker.txt")).unwrap();
+    let mut lines = marker.lines();
+    let first = lines.next().unwrap();
+    assert!(first.starts_with("1 "), "{marker}");
+    assert!(first.ends_with("html"), "{marker}");
+    assert!(
+        lines.any(|line| line == "2024/01/01/hello/index.html"),
+        "{marker}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn post_render_hook_sees_the_base_url_override() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    write(
+        root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com\nhooks:\n  post_render:\n    - 'echo \"$BCKT_BASE_URL\" > marker.txt'\n",
+    );
+
+    let output = bckt(
+        root,
+        &[
+            "render",
+            "--quiet",
+            "--base-url",
+            "https://preview.example.dev/",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let marker = fs::read_to_string(root.join("marker.txt")).unwrap();
+    assert_eq!(marker.trim(), "https://preview.example.dev/");
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_post_render_hook_fails_the_render() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    write(
+        root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com\nhooks:\n  post_render:\n    - exit 3\n    - touch after.txt\n",
+    );
+
+    let output = bckt(root, &["render", "--quiet"]);
+    assert!(!output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("exit 3"), "{stderr}");
+    assert!(root.join("html/2024/01/01/hello/index.html").exists());
+    assert!(root.join("after.txt").exists());
+
+    let output = bckt(root, &["render", "--quiet", "--no-hooks"]);
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn clean_dry_run_lists_files_and_keeps_them() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    assert!(bckt(root, &["render", "--quiet"]).status.success());
+    let post = root.join("html/2024/01/01/hello/index.html");
+
+    let output = bckt(root, &["clean", "--dry-run"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains(&format!("Would remove {}\n", root.join("html").display())),
+        "{stdout}"
+    );
+    assert!(stdout.contains(&format!("Would remove {}\n", post.display())));
+    assert!(stdout.contains(&root.join(".bckt").display().to_string()));
+    assert!(post.exists());
+
+    // Without a terminal to ask, deleting needs --yes.
+    let output = bckt(root, &["clean"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--yes"));
+    assert!(post.exists());
+
+    assert!(bckt(root, &["clean", "--yes"]).status.success());
+    assert!(!post.exists());
+    assert!(root.join("html").is_dir());
+}
+
+#[test]
+fn clean_cache_follows_profiles_and_survives_a_broken_config() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path().join("site");
+    scaffold_project(&root);
+    write(
+        &root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com\nprofiles:\n  shared:\n    cache_dir: ../caches\n",
+    );
+    let shared = temp.path().join("caches");
+    assert!(
+        bckt(&root, &["render", "--quiet", "--profile", "shared"])
+            .status
+            .success()
+    );
+    assert_eq!(fs::read_dir(&shared).unwrap().count(), 1);
+
+    let output = bckt(&root, &["clean", "--cache", "--yes", "--profile", "shared"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read_dir(&shared).unwrap().count(), 0);
+
+    assert!(bckt(&root, &["render", "--quiet"]).status.success());
+    assert!(root.join(".bckt").exists());
+    write(
+        &root,
+        "bckt.yaml",
+        "base_url: ${BCKT_TEST_UNSET_BASE_URL}\n",
+    );
+    let output = bckt(&root, &["clean", "--cache", "--yes"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("cleaning the default cache location"),
+        "{output:?}"
+    );
+    assert!(!root.join(".bckt").exists());
+}
+
+#[test]
+fn bench_renders_a_synthetic_site() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bckt"))
+        .args(["bench", "--posts", "5", "--attachments", "1"])
+        .output()
+        .expect("failed to run bckt");
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let full = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("full render: "))
+        .unwrap_or_else(|| panic!("{stdout}"));
+    assert!(full.starts_with(|c: char| c.is_ascii_digit()), "{stdout}");
+    let millis: f64 = full.split(' ').next().unwrap().parse().unwrap();
+    assert!(millis > 0.0, "{stdout}");
+    assert!(
+        full.ends_with(" ms (5 posts rendered, 0 skipped)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("one post edited: ") && stdout.contains("(1 posts rendered, 4 skipped)"),
+        "{stdout}"
+    );
+}
+
+fn read_tree(dir: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let entry = entry.unwrap();
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.file_type().unwrap().is_dir() {
+            read_tree(&entry.path(), &format!("{name}/"), files);
+        } else {
+            files.push((name, fs::read(entry.path()).unwrap()));
+        }
+    }
+}
+
+#[test]
+fn single_job_render_matches_the_default() {
+    let mut trees = Vec::new();
+    for jobs in [None, Some("1")] {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        scaffold_project(root);
+        write(
+            root,
+            "templates/post.html",
+            "{{ post.title }}{{ post.body }}",
+        );
+        for index in 0..8 {
+            write(
+                root,
+                &format!("posts/p{index}/post.md"),
+                &format!(
+                    "---\ntitle: Post {index}\ndate: 2024-02-0{}T00:00:00Z\nattached: [a.txt, b.txt]\n---\nBody {index}",
+                    index + 1
+                ),
+            );
+            for file in ["a.txt", "b.txt"] {
+                write(
+                    root,
+                    &format!("posts/p{index}/{file}"),
+                    &format!("{file} of {index}"),
+                );
+            }
+            write(
+                root,
+                &format!("skel/css/{index}.css"),
+                &format!("/* {index} */"),
+            );
+        }
+
+        let mut args = vec!["render", "--quiet"];
+        if let Some(jobs) = jobs {
+            args.extend(["--jobs", jobs]);
+        }
+        let output = bckt(root, &args);
+        assert!(output.status.success(), "{output:?}");
+        let mut files = Vec::new();
+        read_tree(&root.join("html"), "", &mut files);
+        // The search index records when it was generated.
+        files.retain(|(path, _)| !path.starts_with("assets/search/"));
+        files.sort();
+        trees.push(files);
+    }
+
+    assert!(trees[0].iter().any(|(path, _)| path.ends_with("p7/b.txt")));
+    assert!(trees[0].iter().any(|(path, _)| path == "css/7.css"));
+    assert_eq!(trees[0], trees[1]);
+}
+
+#[test]
+fn config_validate_fails_on_errors() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    let validate = || {
+        Command::new(env!("CARGO_BIN_EXE_bckt"))
+            .arg("config")
+            .arg("--root")
+            .arg(root)
+            .arg("validate")
+            .output()
+            .expect("failed to run bckt")
+    };
+
+    let output = validate();
+    assert!(!output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("theme 'bckt3' is not installed"),
+        "{stderr}"
+    );
+
+    fs::create_dir_all(root.join("themes/bckt3")).unwrap();
+    let output = validate();
+    assert!(output.status.success(), "{output:?}");
+
+    // Renders run the base_url check too.
+    write(
+        root,
+        "bckt.yaml",
+        "title: CLI\nbase_url: https://example.com/tags/\n",
+    );
+    let output = bckt(root, &["render", "--quiet"]);
+    assert!(!output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("base_url ends in /tags"), "{stderr}");
+}
+
+#[test]
+fn preview_prints_one_post_without_writing() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    scaffold_project(root);
+    write(
+        root,
+        "templates/post.html",
+        "<h1>{{ post.title }}</h1>{{ post.body }}",
+    );
+
+    let output = bckt(root, &["preview", "hello"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("<h1>Hello</h1>"), "{stdout}");
+    assert!(!root.join("html").exists());
+
+    let output = bckt(root, &["preview", "--context", "hello"]);
+    assert!(output.status.success(), "{output:?}");
+    let context: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(context["post"]["title"], "Hello");
+    assert_eq!(context["site"]["post_count"], 1);
+
+    let output = bckt(root, &["preview", "missing"]);
+    assert!(!output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no post has the slug 'missing'"));
+}
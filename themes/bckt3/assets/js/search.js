@@ -35,8 +35,9 @@
       if (!response.ok) {
         throw new Error('Failed to load search index');
       }
-      return response.json();
+      return response.arrayBuffer();
     })
+    .then((buffer) => decodeIndex(new Uint8Array(buffer)))
     .then((payload) => {
       documents = (payload.documents || []).map((doc) => (
         Object.assign({}, doc, {
@@ -46,7 +47,7 @@
         })
       ));
       miniSearch = new MiniSearch({
-        fields: ['title', 'content', 'excerpt', 'tags_text'],
+        fields: ['title', 'content', 'terms', 'excerpt', 'tags_text'],
         prefix: true,
       });
       miniSearch.addAll(documents);
@@ -531,6 +532,85 @@
     return highlighted;
   }
 
+  // The index may be plain JSON, gzip-compressed JSON (search.format: json-gz,
+  // unless the server already decoded it) or MessagePack (search.format: msgpack).
+  function decodeIndex(bytes) {
+    if (bytes[0] === 0x1f && bytes[1] === 0x8b) {
+      const stream = new Blob([bytes]).stream().pipeThrough(new DecompressionStream('gzip'));
+      return new Response(stream).arrayBuffer().then((buffer) => decodeIndex(new Uint8Array(buffer)));
+    }
+    if (/\.msgpack(\?|#|$)/.test(indexUrl)) {
+      return decodeMsgpack(bytes);
+    }
+    return JSON.parse(new TextDecoder().decode(bytes));
+  }
+
+  function decodeMsgpack(bytes) {
+    const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    const text = new TextDecoder();
+    let offset = 0;
+
+    function str(length) {
+      const value = text.decode(bytes.subarray(offset, offset + length));
+      offset += length;
+      return value;
+    }
+    function array(length) {
+      const items = [];
+      for (let i = 0; i < length; i += 1) {
+        items.push(read());
+      }
+      return items;
+    }
+    function map(length) {
+      const object = {};
+      for (let i = 0; i < length; i += 1) {
+        const key = read();
+        object[key] = read();
+      }
+      return object;
+    }
+    function take(size, getter) {
+      const value = getter.call(view, offset);
+      offset += size;
+      return value;
+    }
+    function read() {
+      const byte = bytes[offset];
+      offset += 1;
+      if (byte < 0x80) return byte;
+      if (byte < 0x90) return map(byte & 0x0f);
+      if (byte < 0xa0) return array(byte & 0x0f);
+      if (byte < 0xc0) return str(byte & 0x1f);
+      if (byte >= 0xe0) return byte - 0x100;
+      switch (byte) {
+        case 0xc0: return null;
+        case 0xc2: return false;
+        case 0xc3: return true;
+        case 0xca: return take(4, view.getFloat32);
+        case 0xcb: return take(8, view.getFloat64);
+        case 0xcc: return take(1, view.getUint8);
+        case 0xcd: return take(2, view.getUint16);
+        case 0xce: return take(4, view.getUint32);
+        case 0xcf: return Number(take(8, view.getBigUint64));
+        case 0xd0: return take(1, view.getInt8);
+        case 0xd1: return take(2, view.getInt16);
+        case 0xd2: return take(4, view.getInt32);
+        case 0xd3: return Number(take(8, view.getBigInt64));
+        case 0xd9: return str(take(1, view.getUint8));
+        case 0xda: return str(take(2, view.getUint16));
+        case 0xdb: return str(take(4, view.getUint32));
+        case 0xdc: return array(take(2, view.getUint16));
+        case 0xdd: return array(take(4, view.getUint32));
+        case 0xde: return map(take(2, view.getUint16));
+        case 0xdf: return map(take(4, view.getUint32));
+        default: throw new Error('Unsupported MessagePack type 0x' + byte.toString(16));
+      }
+    }
+
+    return read();
+  }
+
   function escapeRegex(value) {
     return value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
   }